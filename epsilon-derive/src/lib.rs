@@ -0,0 +1,99 @@
+//! Derive macros for [`epsilon`](https://docs.rs/epsilon), re-exported by it
+//! with the `derive` feature.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Implement `epsilon::params::DualParams` for a struct of named `f64`
+/// fields
+///
+/// For a struct `Params`, this generates the dual type `ParamsDual` using
+/// `make_dual`, with one component per field named after it, and the struct
+/// `ParamsDuals` with the same fields holding `ParamsDual` values.
+#[proc_macro_derive(DualParams)]
+pub fn derive_dual_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "DualParams can not be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "DualParams requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "DualParams can only be derived for structs",
+            ))
+        }
+    };
+    if fields.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "DualParams requires at least one field",
+        ));
+    }
+    for field in fields {
+        let ty = &field.ty;
+        if quote!(#ty).to_string() != "f64" {
+            return Err(Error::new_spanned(ty, "DualParams fields must be f64"));
+        }
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let dual = format_ident!("{}Dual", name);
+    let duals = format_ident!("{}Duals", name);
+    let names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let eps: Vec<_> = names.iter().map(|n| format_ident!("eps_{}", n)).collect();
+    let duals_doc = format!("The fields of [`{name}`] as [`{dual}`] values");
+
+    Ok(quote! {
+        ::epsilon::make_dual! { #dual, #(#names),* }
+
+        #[doc = #duals_doc]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #vis struct #duals {
+            #(pub #names: #dual,)*
+        }
+
+        impl ::epsilon::params::DualParams for #name {
+            type Dual = #dual;
+            type Duals = #duals;
+
+            fn seed(&self) -> #duals {
+                #duals {
+                    #(#names: #dual::#names(self.#names),)*
+                }
+            }
+
+            fn from_dual(duals: &#duals) -> Self {
+                #name {
+                    #(#names: duals.#names.real,)*
+                }
+            }
+
+            fn gradient(value: &#dual) -> Self {
+                #name {
+                    #(#names: value.#eps,)*
+                }
+            }
+        }
+    })
+}
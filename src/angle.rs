@@ -0,0 +1,124 @@
+//! # Angles
+//!
+//! Helpers for angles in radians, generic over [`Scalar`]. Wrapping an angle
+//! only shifts it by a multiple of `2 pi`, a constant, so the derivatives
+//! pass through unchanged, including across the wrap boundary.
+//!
+//! ```
+//! use epsilon::angle::angle_diff;
+//! use epsilon::Dual;
+//! // Heading error across the +-pi boundary
+//! let [heading, target] = Dual::<2>::variables([3.1, -3.1]);
+//! let error = angle_diff(heading, target);
+//!
+//! assert!((error.real - (6.2 - 2. * std::f64::consts::PI)).abs() < 1e-15);
+//! assert_eq!(error.eps, [1., -1.]);
+//! ```
+
+use crate::Scalar;
+use std::f64::consts::{PI, TAU};
+
+/// `a` wrapped into `(-pi, pi]`, with the derivatives of `a`
+pub fn wrap_to_pi<T: Scalar>(a: T) -> T {
+    let a0 = a.to_f64();
+    let mut wrapped = a0 - TAU * (a0 / TAU).round();
+    if wrapped <= -PI {
+        wrapped += TAU;
+    } else if wrapped > PI {
+        wrapped -= TAU;
+    }
+    // Subtract the shift rather than computing it on `T`, to keep it exact
+    a - T::from_f64(a0 - wrapped)
+}
+
+/// Signed difference `a - b` wrapped into `(-pi, pi]`, i.e. the rotation
+/// taking `b` to `a` by the shortest path
+pub fn angle_diff<T: Scalar>(a: T, b: T) -> T {
+    wrap_to_pi(a - b)
+}
+
+/// The angle of the point `(x, y)` from the positive x-axis, in `(-pi, pi]`
+///
+/// Scalar types have no inverse trigonometric functions, so the angle is
+/// computed in `f64` and then corrected on `T` by the arctangent series of
+/// the remaining angle, whose real part is zero, giving exact derivatives of
+/// all orders that nested dual numbers need. `NaN` at the origin.
+pub fn atan2<T: Scalar>(y: T, x: T) -> T {
+    const TERMS: usize = 12;
+    let theta = y.to_f64().atan2(x.to_f64());
+    // (x, y) rotated by -theta, along the positive x-axis
+    let (s, c) = theta.sin_cos();
+    let (s, c) = (T::from_f64(s), T::from_f64(c));
+    let t = (y * c - x * s) / (x * c + y * s);
+    // atan(t) = t - t^3 / 3 + t^5 / 5 - ...
+    let t2 = t * t;
+    let mut power = t;
+    let mut delta = T::ZERO;
+    for k in 0..TERMS {
+        let term = power / T::from_f64((2 * k + 1) as f64);
+        delta += if k % 2 == 0 { term } else { -term };
+        power *= t2;
+    }
+    T::from_f64(theta) + delta
+}
+
+/// Signed angle from `a` to `b` in the plane, in `(-pi, pi]`, positive for a
+/// counterclockwise rotation
+pub fn angle_between_2d<T: Scalar>(a: [T; 2], b: [T; 2]) -> T {
+    let cross = a[0] * b[1] - a[1] * b[0];
+    let dot = a[0] * b[0] + a[1] * b[1];
+    atan2(cross, dot)
+}
+
+/// Unsigned angle between `a` and `b` in space, in `[0, pi]`
+///
+/// The derivatives are not finite for parallel vectors, where the angle has
+/// a kink.
+pub fn angle_between_3d<T: Scalar>(a: [T; 3], b: [T; 3]) -> T {
+    let cross = [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ];
+    let sin = cross
+        .iter()
+        .fold(T::ZERO, |sum, &c| sum + c * c)
+        .powf(T::from_f64(0.5));
+    let cos = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    atan2(sin, cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hessian, Dual};
+
+    #[test]
+    fn test_wrap() {
+        assert!((wrap_to_pi(7f64) - (7. - TAU)).abs() < 1e-15);
+        assert!((wrap_to_pi(-4f64) - (TAU - 4.)).abs() < 1e-15);
+        assert_eq!(wrap_to_pi(PI), PI);
+        assert_eq!(wrap_to_pi(-PI), PI);
+        assert_eq!(wrap_to_pi(Dual::<1>::variable(100., 0)).eps, [1.]);
+        assert!((angle_diff(0.1f64, TAU - 0.1) - 0.2).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_atan2() {
+        for (y, x) in [(1., 1.), (0.5, -2.), (-3., -0.1), (0., -1.)] {
+            let [dy, dx] = Dual::<2>::variables([y, x]);
+            let a = atan2(dy, dx);
+            let r2 = x * x + y * y;
+            assert!((a.real - f64::atan2(y, x)).abs() < 1e-15);
+            assert!((a.eps[0] - x / r2).abs() < 1e-15 && (a.eps[1] + y / r2).abs() < 1e-15);
+        }
+        // d^2/dy^2 atan2(y, x) = -2 x y / r^4
+        let h = hessian(|[y, x]| atan2(y, x), [0.5, 2.]);
+        assert!((h[0][0] + 2. * 2. * 0.5 / 4.25f64.powi(2)).abs() < 1e-15);
+
+        let a = angle_between_2d([1., 0.], [-1., -1f64]);
+        assert!((a + 0.75 * PI).abs() < 1e-15);
+        let a = angle_between_3d([1., 0., 0.], [1., 1., 0f64]);
+        assert!((a - 0.25 * PI).abs() < 1e-15);
+    }
+}
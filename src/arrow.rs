@@ -0,0 +1,109 @@
+//! # Arrow and Parquet export
+//!
+//! With the `arrow` feature, slices of dual numbers can be converted into
+//! Arrow [`RecordBatch`]es, with one `Float64` column for the real values
+//! followed by one column per dual component. The columns are named `value`
+//! and `d_d<component>`, matching the generated accessor methods, so the
+//! schema only depends on the dual type.
+//!
+//! With the `parquet` feature, the record batches can also be written directly
+//! to a Parquet file using [`write_parquet`].
+//!
+//! ```
+//! use epsilon::{make_dual, arrow};
+//! make_dual! { ADual, x, y }
+//!
+//! let sweep: Vec<ADual> = (0..4).map(|i| ADual::x(i as f64).powf(2.)).collect();
+//! let batch = arrow::to_record_batch(&sweep).unwrap();
+//!
+//! assert_eq!(batch.num_rows(), 4);
+//! assert_eq!(batch.schema().field(1).name(), "d_dx");
+//! ```
+
+use crate::DualNumber;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use std::sync::Arc;
+
+/// The schema used for record batches of the dual type `D`
+pub fn schema<D: DualNumber>() -> Schema {
+    let fields = std::iter::once("value".to_string())
+        .chain(D::NAMES.iter().map(|name| format!("d_d{name}")))
+        .map(|name| Field::new(name, DataType::Float64, false));
+    Schema::new(fields.collect::<Vec<_>>())
+}
+
+/// Convert a slice of duals into a record batch with the schema [`schema`]
+pub fn to_record_batch<D: DualNumber>(duals: &[D]) -> Result<RecordBatch, ArrowError> {
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(1 + D::COMPONENTS);
+    columns.push(Arc::new(Float64Array::from_iter_values(
+        duals.iter().map(|d| d.real()),
+    )));
+    for i in 0..D::COMPONENTS {
+        columns.push(Arc::new(Float64Array::from_iter_values(
+            duals.iter().map(|d| d.eps(i)),
+        )));
+    }
+    RecordBatch::try_new(Arc::new(schema::<D>()), columns)
+}
+
+/// Write a slice of duals to `writer` as a Parquet file containing a single
+/// record batch
+#[cfg(feature = "parquet")]
+pub fn write_parquet<D: DualNumber, W: std::io::Write + Send>(
+    duals: &[D],
+    writer: W,
+) -> Result<(), parquet::errors::ParquetError> {
+    let batch = to_record_batch(duals)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample::SampleXYZ;
+    use arrow_array::{cast::AsArray, types::Float64Type};
+
+    #[test]
+    fn test_record_batch() {
+        let duals = [SampleXYZ::x(1.), SampleXYZ::eps_z(2., -3.)];
+        let batch = super::to_record_batch(&duals).unwrap();
+
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, ["value", "d_dx", "d_dy", "d_dz"]);
+        assert_eq!(
+            batch.column(0).as_primitive::<Float64Type>().values(),
+            &[1., 2.]
+        );
+        assert_eq!(
+            batch.column(3).as_primitive::<Float64Type>().values(),
+            &[0., -3.]
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let duals = [SampleXYZ::y(4.), SampleXYZ::z(5.)];
+        let name = format!("epsilon_test_parquet_{}.parquet", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        super::write_parquet(&duals, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches, [super::to_record_batch(&duals).unwrap()]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! # Derivative helpers
+//!
+//! Functions taking a closure written against [`Dual`], which handle seeding
+//! the variables and extracting the derivatives.
+//!
+//! ```
+//! use epsilon::{grad, value_and_grad};
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7
+//! let g = grad(|[x, y]| x * x + y * y.sin(), [5., 7.]);
+//! assert_eq!(g, [10., 5.934302379121921]);
+//!
+//! let (z, _) = value_and_grad(|[x, y]| x * x + y * y.sin(), [5., 7.]);
+//! assert_eq!(z, 25. + 7. * 7f64.sin());
+//! ```
+
+use crate::{Dual, Dual1, HyperDual};
+
+/// Gradient of `f` at `x`
+pub fn grad<const N: usize>(f: impl FnOnce([Dual<N>; N]) -> Dual<N>, x: [f64; N]) -> [f64; N] {
+    value_and_grad(f, x).1
+}
+
+/// Value and gradient of `f` at `x`
+pub fn value_and_grad<const N: usize>(
+    f: impl FnOnce([Dual<N>; N]) -> Dual<N>,
+    x: [f64; N],
+) -> (f64, [f64; N]) {
+    let y = f(Dual::variables(x));
+    (y.real, y.eps)
+}
+
+/// Jacobian of `f` at `x`, where row `i` is the gradient of the `i`th output
+pub fn jacobian<const N: usize, const M: usize>(
+    f: impl FnOnce([Dual<N>; N]) -> [Dual<N>; M],
+    x: [f64; N],
+) -> [[f64; N]; M] {
+    f(Dual::variables(x)).map(|y| y.eps)
+}
+
+/// Jacobian-vector product `J v` of `f` at `x`, the directional derivative
+/// of `f` along `v`
+///
+/// `f` is evaluated once on dual numbers with a single component, seeded
+/// with `v`, so the Jacobian is never formed. This makes it suitable for
+/// large systems, e.g. as a [`LinearOperator`](crate::krylov::LinearOperator)
+/// in iterative solvers.
+///
+/// # Panics
+///
+/// If `x` and `v` have different lengths.
+///
+/// ```
+/// use epsilon::jvp;
+/// // f = (x y, y + z), along (1, 0, 2)
+/// let jv = jvp(|x| vec![x[0] * x[1], x[1] + x[2]], &[1., 2., 3.], &[1., 0., 2.]);
+/// assert_eq!(jv, [2., 2.]);
+/// ```
+pub fn jvp(f: impl FnOnce(&[Dual1]) -> Vec<Dual1>, x: &[f64], v: &[f64]) -> Vec<f64> {
+    assert_eq!(x.len(), v.len(), "x and v must have the same length");
+    let seeded: Vec<Dual1> = x.iter().zip(v).map(|(&x, &v)| Dual1::new(x, [v])).collect();
+    f(&seeded).iter().map(|y| y.eps[0]).collect()
+}
+
+/// Hessian of `f` at `x`
+///
+/// `f` is evaluated once on nested dual numbers, with the variable `x[i]`
+/// seeded in component `i` of both the inner and the outer dual part. The
+/// second derivatives are read from the upper triangle and mirrored, so the
+/// result is exactly symmetric.
+pub fn hessian<const N: usize>(
+    f: impl FnOnce([HyperDual<N>; N]) -> HyperDual<N>,
+    x: [f64; N],
+) -> [[f64; N]; N] {
+    value_grad_hessian(f, x).2
+}
+
+/// Value, gradient and [`hessian`] of `f` at `x`, from the same evaluation
+pub(crate) fn value_grad_hessian<const N: usize>(
+    f: impl FnOnce([HyperDual<N>; N]) -> HyperDual<N>,
+    x: [f64; N],
+) -> (f64, [f64; N], [[f64; N]; N]) {
+    let vars = std::array::from_fn(|i| Dual::variable(Dual::variable(x[i], i), i));
+    let y = f(vars);
+    let hessian = std::array::from_fn(|i| std::array::from_fn(|j| y.eps[i.min(j)].eps[i.max(j)]));
+    (y.real.real, y.eps.map(|e| e.real), hessian)
+}
+
+/// Laplacian of `f` at `x`, the trace of its Hessian
+pub fn laplacian<const N: usize>(
+    f: impl FnOnce([HyperDual<N>; N]) -> HyperDual<N>,
+    x: [f64; N],
+) -> f64 {
+    let h = hessian(f, x);
+    (0..N).map(|i| h[i][i]).sum()
+}
+
+/// Divergence of the vector field `f` at `x`, the trace of its Jacobian
+pub fn divergence<const N: usize>(
+    f: impl FnOnce([Dual<N>; N]) -> [Dual<N>; N],
+    x: [f64; N],
+) -> f64 {
+    let j = jacobian(f, x);
+    (0..N).map(|i| j[i][i]).sum()
+}
+
+/// Curl of the three-dimensional vector field `f` at `x`
+pub fn curl(f: impl FnOnce([Dual<3>; 3]) -> [Dual<3>; 3], x: [f64; 3]) -> [f64; 3] {
+    let j = jacobian(f, x);
+    [j[2][1] - j[1][2], j[0][2] - j[2][0], j[1][0] - j[0][1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grad() {
+        assert_eq!(grad(|[x]| x.powf(3.), [2.]), [12.]);
+        assert_eq!(
+            value_and_grad(|[x, y, z]| x * y - z / 2., [1., 2., 3.]),
+            (0.5, [2., 1., -0.5])
+        );
+    }
+
+    #[test]
+    fn test_hessian() {
+        // f = x^2 y + sin(y)
+        let h = hessian(|[x, y]| x * x * y + y.sin(), [3., 0.5]);
+        assert_eq!(h, [[1., 6.], [6., -0.5f64.sin()]]);
+
+        // A single evaluation for the whole Hessian
+        let calls = std::cell::Cell::new(0);
+        let h = hessian(
+            |[x, y, z]| {
+                calls.set(calls.get() + 1);
+                x * y * z
+            },
+            [1., 2., 3.],
+        );
+        assert_eq!(h, [[0., 3., 2.], [3., 0., 1.], [2., 1., 0.]]);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_vector_calculus() {
+        // f = x^2 + 3 y^2 - z^2
+        assert_eq!(
+            laplacian(|[x, y, z]| x * x + 3. * y * y - z * z, [1., 2., 3.]),
+            6.
+        );
+
+        // F = (x y, y z, z x)
+        fn field<T: crate::Numerical>([x, y, z]: [T; 3]) -> [T; 3] {
+            [x * y, y * z, z * x]
+        }
+        assert_eq!(
+            jacobian(field, [1., 2., 3.]),
+            [[2., 1., 0.], [0., 3., 2.], [3., 0., 1.]]
+        );
+        assert_eq!(divergence(field, [1., 2., 3.]), 6.);
+        assert_eq!(curl(field, [1., 2., 3.]), [-2., -3., -1.]);
+
+        // J v agrees with the full Jacobian
+        let v = [0.5, -1., 2.];
+        let j = jacobian(field, [1., 2., 3.]);
+        let jv = jvp(|x| field([x[0], x[1], x[2]]).to_vec(), &[1., 2., 3.], &v);
+        for (row, jv) in j.iter().zip(jv) {
+            assert_eq!(row.iter().zip(v).map(|(a, b)| a * b).sum::<f64>(), jv);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! # Integer casts
+//!
+//! Conversions of the real part of a number to integers, e.g. for indexing a
+//! table inside otherwise differentiable code. An integer has no dual part,
+//! so the derivatives are dropped. This is exact almost everywhere, as the
+//! rounding functions are piecewise constant, but the jumps at the integers
+//! are invisible to the derivatives. [`IntegerCast::floor_split`] also keeps
+//! the fractional remainder, which carries the dual part unchanged.
+//!
+//! ```
+//! use epsilon::cast::IntegerCast;
+//! use epsilon::Dual;
+//! // Linear interpolation in a table at a position depending on x
+//! let table = [1., 4., 9., 16.];
+//! let [x] = Dual::<1>::variables([0.6]);
+//! let (i, frac) = (x * 3.).floor_split();
+//! let i = i as usize;
+//! let y = frac * (table[i + 1] - table[i]) + table[i];
+//!
+//! assert_eq!(i, 1);
+//! assert!((y.real - 8.).abs() < 1e-14);
+//! // The slope of the segment times d(3x)/dx
+//! assert!((y.eps[0] - 15.).abs() < 1e-14);
+//! ```
+
+use crate::Numerical;
+
+/// Conversions of the real part to integers, implemented for all
+/// [`Numerical`] types
+///
+/// Like the `as` operator, the conversions saturate at the bounds of the
+/// integer type and give zero for NaN.
+pub trait IntegerCast: Numerical {
+    /// The real part rounded towards zero
+    fn to_i64_real(&self) -> i64 {
+        self.real() as i64
+    }
+
+    /// The real part rounded towards zero, with negative values giving zero
+    fn to_usize_real(&self) -> usize {
+        self.real() as usize
+    }
+
+    /// The largest integer not greater than the real part
+    fn floor_to_i64(&self) -> i64 {
+        self.real().floor() as i64
+    }
+
+    /// The smallest integer not less than the real part
+    fn ceil_to_i64(&self) -> i64 {
+        self.real().ceil() as i64
+    }
+
+    /// The integer closest to the real part, rounding half-way cases away
+    /// from zero
+    fn round_to_i64(&self) -> i64 {
+        self.real().round() as i64
+    }
+
+    /// The integer part by [`floor_to_i64`](IntegerCast::floor_to_i64), and
+    /// the remainder `self - floor(self)` in `[0, 1)`, whose derivatives are
+    /// those of `self`
+    fn floor_split(self) -> (i64, Self) {
+        let floor = self.real().floor();
+        (floor as i64, self - Self::constant(floor))
+    }
+}
+
+impl<T: Numerical> IntegerCast for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::IntegerCast;
+    use crate::Dual;
+
+    #[test]
+    fn test_casts() {
+        let x = Dual::<2>::new(-2.5, [1., 3.]);
+        assert_eq!(x.to_i64_real(), -2);
+        assert_eq!(x.to_usize_real(), 0);
+        assert_eq!(x.floor_to_i64(), -3);
+        assert_eq!(x.ceil_to_i64(), -2);
+        assert_eq!(x.round_to_i64(), -3);
+        assert_eq!(f64::NAN.floor_to_i64(), 0);
+        assert_eq!(1e300.round_to_i64(), i64::MAX);
+
+        let (i, frac) = x.floor_split();
+        assert_eq!(i, -3);
+        assert_eq!(frac, Dual::new(0.5, [1., 3.]));
+    }
+}
@@ -0,0 +1,716 @@
+//! # Derivative checks
+//!
+//! Utilities verifying the derivatives computed using dual numbers against
+//! independent methods. The function under test is given as a [`NumericalFn`],
+//! which can be evaluated both on dual numbers and on the number types used by
+//! the reference method.
+//!
+//! Functions with hand-written derivatives, taking `Dual<2>` to `Dual<2>`, can
+//! also be checked against the derivative laws at a set of sample points with
+//! [`check_linearity`], [`check_product_rule`] and [`check_chain_rule`].
+//!
+//! ```
+//! use epsilon::{make_dual, Numerical};
+//! use epsilon::check::{self, FiniteDifferences, NumericalFn, Tolerance};
+//!
+//! make_dual! { CheckDual, x, y }
+//!
+//! // z = x^2 + y sin(y)
+//! struct F;
+//! impl NumericalFn for F {
+//!     fn eval<T: Numerical>(&self, v: &[T]) -> T {
+//!         v[0].powf(2.) + v[1] * v[1].sin()
+//!     }
+//! }
+//!
+//! let report = check::gradient::<CheckDual, _>(&F, &[5., 7.], FiniteDifferences::default());
+//! assert!(report.passed(), "{}", report);
+//!
+//! # #[cfg(feature = "complex")] {
+//! let report = check::complex_step::<CheckDual, _>(&F, &[5., 7.], Tolerance::default());
+//! assert!(report.passed(), "{}", report);
+//! # }
+//! ```
+
+use crate::{Dual, DualNumber, Numerical, Scalar};
+
+/// A function of several variables which can be evaluated on any
+/// [`Numerical`] type
+///
+/// As closures cannot be generic, functions to be checked are written as an
+/// implementation of this trait.
+pub trait NumericalFn {
+    fn eval<T: Numerical>(&self, x: &[T]) -> T;
+}
+
+/// Tolerance when comparing derivatives
+/// A derivative is accepted if either the absolute or the relative error is
+/// within the tolerance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub abs: f64,
+    pub rel: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            abs: 1e-12,
+            rel: 1e-10,
+        }
+    }
+}
+
+/// Comparison of a single derivative against its reference value
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentCheck {
+    /// Name of the dual component
+    pub name: &'static str,
+    /// Derivative computed using dual numbers
+    pub dual: f64,
+    /// Derivative computed by the reference method
+    pub reference: f64,
+    pub abs_error: f64,
+    pub rel_error: f64,
+    /// Whether the error is within the tolerance
+    pub passed: bool,
+}
+
+impl ComponentCheck {
+    fn new(name: &'static str, dual: f64, reference: f64, tolerance: Tolerance) -> Self {
+        let abs_error = (dual - reference).abs();
+        let rel_error = abs_error / reference.abs().max(f64::MIN_POSITIVE);
+        ComponentCheck {
+            name,
+            dual,
+            reference,
+            abs_error,
+            rel_error,
+            passed: abs_error <= tolerance.abs || rel_error <= tolerance.rel,
+        }
+    }
+}
+
+/// Result of checking all derivatives of a function at a point
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckReport {
+    /// Value of the function
+    pub value: f64,
+    pub components: Vec<ComponentCheck>,
+}
+
+impl CheckReport {
+    /// Build a report comparing the derivatives in `value` to reference values,
+    /// e.g. derivatives obtained analytically
+    pub fn new<D: DualNumber>(value: D, reference: &[f64], tolerance: Tolerance) -> Self {
+        let components = D::NAMES
+            .iter()
+            .zip(reference)
+            .enumerate()
+            .map(|(i, (&name, &reference))| {
+                ComponentCheck::new(name, value.eps(i), reference, tolerance)
+            })
+            .collect();
+        CheckReport {
+            value: value.real(),
+            components,
+        }
+    }
+
+    /// Whether all derivatives are within the tolerance
+    pub fn passed(&self) -> bool {
+        self.components.iter().all(|c| c.passed)
+    }
+}
+
+impl std::fmt::Display for CheckReport {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(fmt, "value: {}", self.value)?;
+        for c in &self.components {
+            writeln!(
+                fmt,
+                "d/d{}: dual {:e}, reference {:e}, abs error {:e}, rel error {:e}{}",
+                c.name,
+                c.dual,
+                c.reference,
+                c.abs_error,
+                c.rel_error,
+                if c.passed { "" } else { " FAILED" },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluate `f` at `x` on dual numbers, seeding one variable per component
+pub fn eval_dual<D: DualNumber, F: NumericalFn>(f: &F, x: &[f64]) -> D {
+    assert_eq!(
+        x.len(),
+        D::COMPONENTS,
+        "number of variables must match the number of dual components"
+    );
+    let vars: Vec<D> = x
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| D::variable(x, i))
+        .collect();
+    f.eval(&vars)
+}
+
+/// Options for [`gradient`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FiniteDifferences {
+    pub tolerance: Tolerance,
+    /// Largest step tried, relative to `max(|x_i|, 1)`
+    pub initial_step: f64,
+    /// Number of times the step is divided by 4
+    pub refinements: usize,
+}
+
+impl Default for FiniteDifferences {
+    fn default() -> Self {
+        FiniteDifferences {
+            // Central differences are accurate to about eps^(2/3)
+            tolerance: Tolerance {
+                abs: 1e-8,
+                rel: 1e-6,
+            },
+            initial_step: 1e-2,
+            refinements: 8,
+        }
+    }
+}
+
+/// Check the derivatives of `f` at `x` against central differences
+///
+/// For each variable, central differences `(f(x + h) - f(x - h)) / 2h` are
+/// computed for a decreasing sequence of steps `h`. Truncation error dominates
+/// for large steps and rounding error for small ones, so the estimate which
+/// changes least from the previous step is used as the reference.
+pub fn gradient<D: DualNumber, F: NumericalFn>(
+    f: &F,
+    x: &[f64],
+    options: FiniteDifferences,
+) -> CheckReport {
+    let mut y = x.to_vec();
+    let reference: Vec<f64> = (0..x.len())
+        .map(|i| {
+            let mut diff = |h: f64| {
+                y[i] = x[i] + h;
+                let plus = f.eval(&y);
+                y[i] = x[i] - h;
+                let minus = f.eval(&y);
+                y[i] = x[i];
+                (plus - minus) / (2. * h)
+            };
+            let mut h = options.initial_step * x[i].abs().max(1.);
+            let mut prev = diff(h);
+            let (mut best, mut best_change) = (prev, f64::INFINITY);
+            for _ in 0..options.refinements {
+                h /= 4.;
+                let d = diff(h);
+                let change = (d - prev).abs();
+                if change < best_change {
+                    (best, best_change) = (d, change);
+                }
+                prev = d;
+            }
+            best
+        })
+        .collect();
+    CheckReport::new(eval_dual::<D, F>(f, x), &reference, options.tolerance)
+}
+
+/// Check the derivatives of `f` at `x` against the complex-step method
+///
+/// The complex-step derivative `Im(f(x + ih)) / h` has no subtractive
+/// cancellation, so it is accurate to machine precision with a tiny step `h`
+/// (`1e-20` is used). It requires `f` to be real-analytic, so it should not
+/// branch on or take the absolute value of its arguments.
+#[cfg(feature = "complex")]
+pub fn complex_step<D: DualNumber, F: NumericalFn>(
+    f: &F,
+    x: &[f64],
+    tolerance: Tolerance,
+) -> CheckReport {
+    use num_complex::Complex;
+    const STEP: f64 = 1e-20;
+
+    let mut z: Vec<Complex<f64>> = x.iter().map(|&x| Complex::new(x, 0.)).collect();
+    let reference: Vec<f64> = (0..x.len())
+        .map(|i| {
+            z[i].im = STEP;
+            let d = f.eval(&z).im / STEP;
+            z[i].im = 0.;
+            d
+        })
+        .collect();
+    CheckReport::new(eval_dual::<D, F>(f, x), &reference, tolerance)
+}
+
+/// Number of tangent directions checked at each sample point by the
+/// derivative law checks
+const DIRECTIONS: usize = 4;
+
+/// Directions of the two dual components of the input in the derivative law
+/// checks, drawn by SplitMix64 from a fixed seed so that the checks are
+/// reproducible
+///
+/// The components have random signs and magnitudes in `[0.5, 2)`, so that
+/// functions which assume a unit dual part, or mix up the components, are
+/// caught.
+fn tangents() -> impl Iterator<Item = [f64; 2]> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let t = 0.5 + 1.5 * ((z >> 11) as f64 / (1u64 << 53) as f64);
+        if z & 1 == 0 {
+            t
+        } else {
+            -t
+        }
+    };
+    (0..DIRECTIONS).map(move |_| [next(), next()])
+}
+
+/// Result of a derivative law check, at the sample point and tangent
+/// direction with the largest error relative to the tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct LawReport {
+    /// Sample point of the worst check
+    pub x: f64,
+    /// Dual part of the input of the worst check
+    pub tangent: [f64; 2],
+    /// Number of sample points and tangent directions checked
+    pub checks: usize,
+    pub worst: CheckReport,
+}
+
+impl LawReport {
+    /// Whether the law holds within the tolerance in all checks
+    pub fn passed(&self) -> bool {
+        self.worst.passed()
+    }
+}
+
+impl std::fmt::Display for LawReport {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            fmt,
+            "worst of {} checks at x = {}, tangent {:?}",
+            self.checks, self.x, self.tangent
+        )?;
+        write!(fmt, "{}", self.worst)
+    }
+}
+
+/// Largest error of the components of `report` in units of the tolerance,
+/// more than one for a failed check
+fn severity(report: &CheckReport, tolerance: Tolerance) -> f64 {
+    (report.components.iter())
+        .map(|c| {
+            let error = (c.abs_error / tolerance.abs).min(c.rel_error / tolerance.rel);
+            if error.is_nan() {
+                f64::INFINITY
+            } else {
+                error
+            }
+        })
+        .fold(0., f64::max)
+}
+
+/// Run `check` at every point of `points` in every direction of
+/// [`tangents`], keeping the worst report
+fn check_law(
+    points: &[f64],
+    tolerance: Tolerance,
+    check: impl Fn(f64, [f64; 2]) -> CheckReport,
+) -> LawReport {
+    assert!(!points.is_empty(), "no sample points");
+    let (mut worst, mut worst_error) = (None, -1.);
+    let mut checks = 0;
+    for &x in points {
+        for tangent in tangents() {
+            let report = check(x, tangent);
+            let error = severity(&report, tolerance);
+            checks += 1;
+            if error > worst_error {
+                worst_error = error;
+                worst = Some((x, tangent, report));
+            }
+        }
+    }
+    let (x, tangent, worst) = worst.expect("checks");
+    LawReport {
+        x,
+        tangent,
+        checks,
+        worst,
+    }
+}
+
+/// Value and derivative of `f` at `x`, seeding only the first component
+fn value_and_slope(f: &impl Fn(Dual<2>) -> Dual<2>, x: f64) -> (f64, f64) {
+    let y = f(Dual::new(x, [1., 0.]));
+    (y.real, y.eps[0])
+}
+
+/// Compare both dual components of `value` to the derivative `slope` along
+/// `tangent`
+fn law_report(value: Dual<2>, slope: f64, tangent: [f64; 2], tolerance: Tolerance) -> CheckReport {
+    let components = ["eps_0", "eps_1"]
+        .into_iter()
+        .zip(value.eps.into_iter().zip(tangent))
+        .map(|(name, (dual, t))| ComponentCheck::new(name, dual, slope * t, tolerance))
+        .collect();
+    CheckReport {
+        value: value.real,
+        components,
+    }
+}
+
+/// Check that `f` propagates derivatives linearly at each of `points`
+///
+/// A function with a hand-written derivative, taking a dual number `x` to
+/// `f(x.real) + f'(x.real) x.eps`, must scale every dual component of its
+/// argument by the same derivative. `f` is evaluated on arguments whose two
+/// components are random unequal multiples of the derivative direction, and
+/// the results compared to the derivative obtained by seeding only the first
+/// component.
+pub fn check_linearity(
+    f: impl Fn(Dual<2>) -> Dual<2>,
+    points: &[f64],
+    tolerance: Tolerance,
+) -> LawReport {
+    check_law(points, tolerance, |x, tangent| {
+        let (_, slope) = value_and_slope(&f, x);
+        law_report(f(Dual::new(x, tangent)), slope, tangent, tolerance)
+    })
+}
+
+/// Check the product rule `(f g)' = f' g + f g'` at each of `points`
+///
+/// The derivative of the product of `f` and `g` evaluated on dual numbers is
+/// compared to the combination of their separately computed values and
+/// derivatives.
+pub fn check_product_rule(
+    f: impl Fn(Dual<2>) -> Dual<2>,
+    g: impl Fn(Dual<2>) -> Dual<2>,
+    points: &[f64],
+    tolerance: Tolerance,
+) -> LawReport {
+    check_law(points, tolerance, |x, tangent| {
+        let (fx, df) = value_and_slope(&f, x);
+        let (gx, dg) = value_and_slope(&g, x);
+        let arg = Dual::new(x, tangent);
+        law_report(f(arg) * g(arg), df * gx + fx * dg, tangent, tolerance)
+    })
+}
+
+/// Check the chain rule `(f o g)' = f'(g) g'` at each of `points`
+///
+/// The derivative of the composition `f(g(x))` evaluated on dual numbers is
+/// compared to the product of the derivative of `f` at `g(x)` and that of
+/// `g` at `x`. As the dual part of `g(x)` is in general not one, this catches
+/// functions `f` which ignore the dual part of their argument.
+pub fn check_chain_rule(
+    f: impl Fn(Dual<2>) -> Dual<2>,
+    g: impl Fn(Dual<2>) -> Dual<2>,
+    points: &[f64],
+    tolerance: Tolerance,
+) -> LawReport {
+    check_law(points, tolerance, |x, tangent| {
+        let (gx, dg) = value_and_slope(&g, x);
+        let (_, df) = value_and_slope(&f, gx);
+        law_report(f(g(Dual::new(x, tangent))), df * dg, tangent, tolerance)
+    })
+}
+
+/// Access to the parts of a dual number by index, implemented for the types
+/// generated by [`make_dual`](crate::make_dual) and for [`Dual`]
+///
+/// Used by [`assert_grad_eq!`], [`assert_dual_eq!`],
+/// [`sensitivity`](crate::sensitivity), [`dbg_dual!`](crate::dbg_dual) and
+/// the [`text`](crate::text) format.
+pub trait Components {
+    /// The real part
+    fn value(&self) -> f64;
+    /// Number of dual components
+    fn components(&self) -> usize;
+    /// The `i`th dual component
+    fn component(&self, i: usize) -> f64;
+    /// Name of the `i`th component, as used in the derivative `d/d{name}`
+    fn name(&self, i: usize) -> String;
+
+    /// Name of the field holding the `i`th component, `eps_{name}`
+    fn field(&self, i: usize) -> String {
+        format!("eps_{}", self.name(i))
+    }
+}
+
+impl<D: DualNumber> Components for D {
+    fn value(&self) -> f64 {
+        self.real()
+    }
+
+    fn components(&self) -> usize {
+        D::COMPONENTS
+    }
+
+    fn component(&self, i: usize) -> f64 {
+        self.eps(i)
+    }
+
+    fn name(&self, i: usize) -> String {
+        D::NAMES[i].to_owned()
+    }
+}
+
+impl<const N: usize, F: Scalar> Components for Dual<N, F> {
+    fn value(&self) -> f64 {
+        self.real.to_f64()
+    }
+
+    fn components(&self) -> usize {
+        N
+    }
+
+    fn component(&self, i: usize) -> f64 {
+        self.eps[i].to_f64()
+    }
+
+    fn name(&self, i: usize) -> String {
+        format!("eps_{i}")
+    }
+
+    fn field(&self, i: usize) -> String {
+        self.name(i)
+    }
+}
+
+/// Whether `actual` is within `tol` of `expected`, relative to
+/// `max(|expected|, 1)`
+fn close(actual: f64, expected: f64, tol: f64) -> bool {
+    (actual - expected).abs() <= tol * expected.abs().max(1.)
+}
+
+/// Line of a failure message comparing a single number
+fn mismatch_line(label: &str, actual: f64, expected: f64, tol: f64) -> String {
+    format!(
+        "  {label}: actual {actual:e}, expected {expected:e}, error {:e}{}\n",
+        (actual - expected).abs(),
+        if close(actual, expected, tol) {
+            ""
+        } else {
+            "  <--"
+        },
+    )
+}
+
+/// Implementation of [`assert_grad_eq!`], returning the failure message
+#[doc(hidden)]
+pub fn __compare_grad<D: Components>(actual: &D, expected: &[f64], tol: f64) -> Result<(), String> {
+    let same_len = actual.components() == expected.len();
+    let passed = (0..actual.components())
+        .zip(expected)
+        .all(|(i, &e)| close(actual.component(i), e, tol));
+    if same_len && passed {
+        return Ok(());
+    }
+    let mut msg = format!("assertion `grad == expected` failed (tolerance {tol:e})\n");
+    if !same_len {
+        msg += &format!(
+            "  {} dual components, but {} expected derivatives\n",
+            actual.components(),
+            expected.len()
+        );
+    }
+    for (i, &e) in expected.iter().enumerate().take(actual.components()) {
+        msg += &mismatch_line(
+            &format!("d/d{}", actual.name(i)),
+            actual.component(i),
+            e,
+            tol,
+        );
+    }
+    Err(msg)
+}
+
+/// Implementation of [`assert_dual_eq!`], returning the failure message
+#[doc(hidden)]
+pub fn __compare_dual<D: Components>(actual: &D, expected: &D, tol: f64) -> Result<(), String> {
+    let passed = close(actual.value(), expected.value(), tol)
+        && (0..actual.components()).all(|i| close(actual.component(i), expected.component(i), tol));
+    if passed {
+        return Ok(());
+    }
+    let mut msg = format!("assertion `left == right` failed (tolerance {tol:e})\n");
+    msg += &mismatch_line("value", actual.value(), expected.value(), tol);
+    for i in 0..actual.components() {
+        let label = format!("d/d{}", actual.name(i));
+        msg += &mismatch_line(&label, actual.component(i), expected.component(i), tol);
+    }
+    Err(msg)
+}
+
+/// Assert that the dual part of a dual number matches the expected gradient
+///
+/// Each derivative must be within `tol` of the expected value, relative to
+/// `max(|expected|, 1)`. On failure, the message lists the actual and expected
+/// value of every derivative, marking the ones which differ. Works with the
+/// types generated by [`make_dual`](crate::make_dual) and with
+/// [`Dual`](crate::Dual). An optional format string and arguments are added to
+/// the message, as for [`assert!`].
+///
+/// ```
+/// use epsilon::{assert_grad_eq, Dual};
+/// let [x, y] = Dual::<2>::variables([5., 7.]);
+/// assert_grad_eq!(x * x + y.sin(), [10., 7f64.cos()], 1e-12);
+/// ```
+#[macro_export]
+macro_rules! assert_grad_eq {
+    ($dual:expr, $expected:expr, $tol:expr $(,)?) => {
+        if let ::core::result::Result::Err(msg) =
+            $crate::check::__compare_grad(&$dual, &$expected, $tol)
+        {
+            ::core::panic!("{}", msg);
+        }
+    };
+    ($dual:expr, $expected:expr, $tol:expr, $($arg:tt)+) => {
+        if let ::core::result::Result::Err(msg) =
+            $crate::check::__compare_grad(&$dual, &$expected, $tol)
+        {
+            ::core::panic!("{}: {}", ::core::format_args!($($arg)+), msg);
+        }
+    };
+}
+
+/// Assert that two dual numbers have the same real and dual parts
+///
+/// Like [`assert_grad_eq!`], comparing the real parts as well.
+///
+/// ```
+/// use epsilon::{assert_dual_eq, Dual};
+/// let x = Dual::<1>::variable(0.5, 0);
+/// assert_dual_eq!(x.sin() * x.sin(), 0.5 - 0.5 * (2. * x).cos(), 1e-15);
+/// ```
+#[macro_export]
+macro_rules! assert_dual_eq {
+    ($left:expr, $right:expr, $tol:expr $(,)?) => {
+        if let ::core::result::Result::Err(msg) =
+            $crate::check::__compare_dual(&$left, &$right, $tol)
+        {
+            ::core::panic!("{}", msg);
+        }
+    };
+    ($left:expr, $right:expr, $tol:expr, $($arg:tt)+) => {
+        if let ::core::result::Result::Err(msg) =
+            $crate::check::__compare_dual(&$left, &$right, $tol)
+        {
+            ::core::panic!("{}: {}", ::core::format_args!($($arg)+), msg);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::SampleXYZ;
+
+    struct Poly;
+    impl NumericalFn for Poly {
+        fn eval<T: Numerical>(&self, v: &[T]) -> T {
+            v[0] * v[1] * T::constant(3.) + v[2].tan() / v[0]
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let value = SampleXYZ::from_parts(1., &[2., 3., 4.]);
+        let report = CheckReport::new(value, &[2., 3.5, 4. + 1e-14], Tolerance::default());
+        assert!(report.components[0].passed);
+        assert!(!report.components[1].passed);
+        assert!(report.components[2].passed);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_eval_dual() {
+        let v: SampleXYZ = eval_dual(&Poly, &[1., 2., 0.]);
+        assert_eq!(v, SampleXYZ::from_parts(6., &[6., 3., 1.]));
+    }
+
+    #[test]
+    fn test_gradient() {
+        let options = FiniteDifferences::default();
+        let report = gradient::<SampleXYZ, _>(&Poly, &[1.5, -2., 0.3], options);
+        assert!(report.passed(), "{}", report);
+        // Steps relative to the magnitude of the variables
+        let report = gradient::<SampleXYZ, _>(&Poly, &[1e6, -2e-3, 1.5], options);
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    fn test_assert_macros() {
+        let v = SampleXYZ::from_parts(1., &[2., 3., 4.]);
+        assert_grad_eq!(v, [2., 3., 4. + 1e-14], 1e-12);
+        assert_dual_eq!(v, SampleXYZ::from_parts(1., &[2., 3., 4.]), 0.);
+
+        assert_eq!(
+            __compare_grad(&v, &[2., 3.5, 4.], 1e-12).unwrap_err(),
+            "assertion `grad == expected` failed (tolerance 1e-12)\n  \
+             d/dx: actual 2e0, expected 2e0, error 0e0\n  \
+             d/dy: actual 3e0, expected 3.5e0, error 5e-1  <--\n  \
+             d/dz: actual 4e0, expected 4e0, error 0e0\n"
+        );
+        let [x] = Dual::<1>::variables([2.]);
+        assert!(__compare_grad(&x, &[1., 0.], 0.)
+            .unwrap_err()
+            .contains("1 dual components, but 2 expected derivatives"));
+        assert!(__compare_dual(&x, &Dual::new(2.5, [1.]), 0.)
+            .unwrap_err()
+            .contains("value: actual 2e0, expected 2.5e0, error 5e-1  <--"));
+    }
+
+    #[test]
+    fn test_derivative_laws() {
+        // Hand-written derivatives
+        let sinh = |x: Dual<2>| {
+            let (s, c) = (x.real.sinh(), x.real.cosh());
+            Dual::new(s, x.eps.map(|e| e * c))
+        };
+        let square = |x: Dual<2>| x * x;
+        // Wrong for arguments whose dual part is not a unit seed
+        let broken = |x: Dual<2>| Dual::new(x.real.exp(), [x.real.exp(), 0.]);
+
+        let points = [-1.3, 0.2, 2.5];
+        let tol = Tolerance::default();
+        assert!(check_linearity(sinh, &points, tol).passed());
+        assert!(check_product_rule(sinh, square, &points, tol).passed());
+        assert!(check_chain_rule(sinh, square, &points, tol).passed());
+        assert!(check_chain_rule(square, sinh, &points, tol).passed());
+
+        assert!(!check_linearity(broken, &points, tol).passed());
+        assert!(!check_product_rule(broken, square, &points, tol).passed());
+        assert!(!check_chain_rule(broken, sinh, &points, tol).passed());
+
+        // Broken only for positive arguments, which the worst check shows
+        let partly = |x: Dual<2>| if x.real > 0. { broken(x) } else { sinh(x) };
+        let report = check_linearity(partly, &points, tol);
+        assert!(!report.passed());
+        assert_eq!(report.checks, 3 * DIRECTIONS);
+        assert!(report.x > 0. && report.tangent[1] != 0.);
+        assert!(report.to_string().starts_with("worst of 12 checks at x = "));
+    }
+
+    #[cfg(feature = "complex")]
+    #[test]
+    fn test_complex_step() {
+        let report = complex_step::<SampleXYZ, _>(&Poly, &[1.5, -2., 0.3], Tolerance::default());
+        assert!(report.passed(), "{}", report);
+    }
+}
@@ -0,0 +1,151 @@
+//! # Complex-valued dual numbers
+//!
+//! With the `complex` feature, [`Complex<f32>`] and [`Complex<f64>`] implement
+//! [`Scalar`], and can be used as the inner type of
+//! [`make_dual`](crate::make_dual). All math functions are holomorphic, so the
+//! dual parts propagate as complex derivatives, e.g. the derivative of a
+//! transfer function with respect to real parameters.
+//!
+//! ```
+//! use epsilon::make_dual;
+//! use num_complex::Complex;
+//!
+//! make_dual! { CDual: Complex<f64>, tau }
+//!
+//! // H(jw, tau) = 1 / (1 + jw tau), dH/dtau = -jw / (1 + jw tau)^2
+//! let jw = Complex::new(0., 2.);
+//! let tau = CDual::tau(Complex::new(0.5, 0.));
+//! let h = (tau * jw + Complex::new(1., 0.)).invert();
+//!
+//! let expected = -jw / ((jw * 0.5 + 1.) * (jw * 0.5 + 1.));
+//! assert!((h.d_dtau() - expected).norm() < 1e-12);
+//! ```
+//!
+//! Complex numbers are only ordered when both lie on the real axis, so
+//! comparisons between complex duals with non-zero imaginary parts are always
+//! false.
+
+use crate::{Numerical, Scalar};
+use num_complex::Complex;
+
+macro_rules! impl_scalar_complex {
+    ($t:ident) => {
+        impl Scalar for Complex<$t> {
+            const ZERO: Self = Complex::new(0., 0.);
+            const ONE: Self = Complex::new(1., 0.);
+
+            fn from_f64(value: f64) -> Self {
+                Complex::new(value as $t, 0.)
+            }
+
+            fn to_f64(self) -> f64 {
+                self.re as f64
+            }
+
+            fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+                if self.im == 0. && other.im == 0. {
+                    self.re.partial_cmp(&other.re)
+                } else {
+                    None
+                }
+            }
+
+            fn powf(self, pow: Self) -> Self {
+                self.powc(pow)
+            }
+
+            fn exp(self) -> Self {
+                Complex::exp(self)
+            }
+
+            fn ln(self) -> Self {
+                Complex::ln(self)
+            }
+
+            fn sin(self) -> Self {
+                Complex::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                Complex::cos(self)
+            }
+
+            fn trunc(self) -> Self {
+                Complex::new(self.re.trunc(), self.im.trunc())
+            }
+        }
+    };
+}
+
+impl_scalar_complex! {f32}
+impl_scalar_complex! {f64}
+
+/// Complex numbers can be used in code generic over [`Numerical`], which
+/// allows evaluating such code at complex arguments, e.g. for the complex-step
+/// derivative check in [`check::complex_step`](crate::check::complex_step)
+impl Numerical for Complex<f64> {
+    fn constant(real: f64) -> Self {
+        Complex::new(real, 0.)
+    }
+
+    fn real(&self) -> f64 {
+        self.re
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        Complex::powf(self, pow)
+    }
+
+    fn invert(self) -> Self {
+        self.inv()
+    }
+
+    fn sin(self) -> Self {
+        Complex::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Complex::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        Complex::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+
+    crate::make_dual! { ComplexXY: Complex<f64>, x, y }
+
+    #[test]
+    fn test_holomorphic() {
+        let z = Complex::new(1., 2.);
+        let x = ComplexXY::x(z);
+        let y = ComplexXY::y(Complex::new(3., 0.));
+
+        // d/dx [x^2 y] = 2 x y, d/dy [x^2 y] = x^2
+        let f = x * x * y;
+        assert_eq!(f.d_dx(), z * 6.);
+        assert_eq!(f.d_dy(), z * z);
+
+        let s = x.sin();
+        assert!((s.d_dx() - z.cos()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let a = ComplexXY::from(Complex::new(1., 0.));
+        let b = ComplexXY::from(Complex::new(2., 0.));
+        assert!(a < b);
+        assert_eq!(a.partial_cmp(&ComplexXY::from(Complex::new(2., 1.))), None);
+        assert_eq!(
+            format!(
+                "{}",
+                ComplexXY::eps_x(Complex::new(1., 0.), Complex::new(0., -1.))
+            ),
+            "1+0i + 0-1i eps_x"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! # Constrained parameters
+//!
+//! Invertible transforms from unconstrained real numbers to parameters with
+//! bounds, such as scales, which must be positive, or probabilities, in
+//! `(0, 1)`. Optimizers and samplers such as [`optim`](crate::optim) and
+//! [`hmc`](crate::hmc) then work on the unconstrained values, and never step
+//! outside the bounds.
+//!
+//! A density of the constrained parameters becomes a density of the
+//! unconstrained ones by adding the logarithm of the absolute derivative of
+//! the transform, [`Constraint::log_jacobian`]. [`constrain`] applies one
+//! constraint per parameter and sums these corrections.
+//!
+//! ```
+//! use epsilon::constraint::{constrain, Constraint};
+//! use epsilon::distributions::{beta_logpdf, exponential_logpdf};
+//! use epsilon::Dual;
+//! // Log-density of a rate with an exponential prior and a probability with
+//! // a beta prior, in terms of unconstrained parameters
+//! let log_density = |x: [Dual<2>; 2]| {
+//!     let ([rate, p], log_jacobian) = constrain([Constraint::Positive, Constraint::UnitInterval], x);
+//!     let one = Dual::constant(1.);
+//!     exponential_logpdf(rate, one) + beta_logpdf(p, one * 2., one * 2.) + log_jacobian
+//! };
+//!
+//! // rate = exp(x0), with density exp(-rate) exp(x0), has its mode at x0 = 0
+//! let x0 = Constraint::Positive.unconstrain(1.);
+//! let x1 = Constraint::UnitInterval.unconstrain(0.5);
+//! let y = log_density(Dual::variables([x0, x1]));
+//! assert!(y.eps[0].abs() < 1e-15 && y.eps[1].abs() < 1e-15);
+//! ```
+
+use crate::special::{logistic, softplus};
+use crate::Scalar;
+
+/// Bounds of a parameter, and the transform mapping the real numbers onto
+/// them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Constraint {
+    /// No bounds, by the identity
+    Real,
+    /// `(0, inf)`, by `exp(x)`
+    Positive,
+    /// `(low, inf)`, by `low + exp(x)`
+    Lower(f64),
+    /// `(-inf, high)`, by `high - exp(x)`
+    Upper(f64),
+    /// `(0, 1)`, by the logistic function `1 / (1 + exp(-x))`
+    UnitInterval,
+    /// `(low, high)`, by `low + (high - low) / (1 + exp(-x))`
+    Interval(f64, f64),
+}
+
+impl Constraint {
+    /// The constrained parameter at the unconstrained `x`
+    pub fn constrain<T: Scalar>(self, x: T) -> T {
+        match self {
+            Constraint::Real => x,
+            Constraint::Positive => x.exp(),
+            Constraint::Lower(low) => T::from_f64(low) + x.exp(),
+            Constraint::Upper(high) => T::from_f64(high) - x.exp(),
+            Constraint::UnitInterval => logistic(x),
+            Constraint::Interval(low, high) => {
+                T::from_f64(low) + T::from_f64(high - low) * logistic(x)
+            }
+        }
+    }
+
+    /// The unconstrained value of the parameter `y`, the inverse of
+    /// [`constrain`](Constraint::constrain)
+    ///
+    /// `NaN` outside the bounds, and infinite on them.
+    pub fn unconstrain<T: Scalar>(self, y: T) -> T {
+        let logit = |p: T| (p / (T::ONE - p)).ln();
+        match self {
+            Constraint::Real => y,
+            Constraint::Positive => y.ln(),
+            Constraint::Lower(low) => (y - T::from_f64(low)).ln(),
+            Constraint::Upper(high) => (T::from_f64(high) - y).ln(),
+            Constraint::UnitInterval => logit(y),
+            Constraint::Interval(low, high) => {
+                logit((y - T::from_f64(low)) / T::from_f64(high - low))
+            }
+        }
+    }
+
+    /// `ln |dy/dx|` for the constrained `y` at the unconstrained `x`, the
+    /// term to add to a log-density of `y` to give one of `x`
+    pub fn log_jacobian<T: Scalar>(self, x: T) -> T {
+        match self {
+            Constraint::Real => T::ZERO,
+            Constraint::Positive | Constraint::Lower(_) | Constraint::Upper(_) => x,
+            // ln(s (1 - s)) for the logistic function s
+            Constraint::UnitInterval => -softplus(x) - softplus(-x),
+            Constraint::Interval(low, high) => {
+                T::from_f64((high - low).ln()) - softplus(x) - softplus(-x)
+            }
+        }
+    }
+}
+
+/// Apply `constraints` to the unconstrained `x`, giving the constrained
+/// parameters and the sum of their [`log_jacobian`](Constraint::log_jacobian)
+pub fn constrain<T: Scalar, const N: usize>(
+    constraints: [Constraint; N],
+    x: [T; N],
+) -> ([T; N], T) {
+    let y = std::array::from_fn(|i| constraints[i].constrain(x[i]));
+    let log_jacobian =
+        (constraints.iter().zip(x)).fold(T::ZERO, |sum, (c, x)| sum + c.log_jacobian(x));
+    (y, log_jacobian)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constrain, Constraint};
+    use crate::Dual;
+
+    const ALL: [Constraint; 6] = [
+        Constraint::Real,
+        Constraint::Positive,
+        Constraint::Lower(-2.),
+        Constraint::Upper(3.),
+        Constraint::UnitInterval,
+        Constraint::Interval(1., 5.),
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        for c in ALL {
+            for x in [-8., -1.5, 0., 0.7, 8.] {
+                let y: f64 = c.constrain(x);
+                assert!(
+                    (c.unconstrain(y) - x).abs() < 1e-9 * (1. + x.abs()),
+                    "{c:?} {x}"
+                );
+            }
+        }
+        assert!(Constraint::Positive.unconstrain(-1f64).is_nan());
+        assert!(Constraint::Interval(1., 5.).unconstrain(6f64).is_nan());
+        assert_eq!(Constraint::UnitInterval.constrain(-800f64), 0.);
+        assert_eq!(Constraint::UnitInterval.constrain(800f64), 1.);
+    }
+
+    #[test]
+    fn test_log_jacobian() {
+        for c in ALL {
+            for x in [-3., 0.2, 4.] {
+                let dy = c.constrain(Dual::<1>::variable(x, 0)).eps[0];
+                assert!(
+                    (c.log_jacobian(x) - dy.abs().ln()).abs() < 1e-14,
+                    "{c:?} {x}"
+                );
+            }
+        }
+        let (y, log_jacobian) = constrain(ALL, [0.; 6]);
+        assert_eq!(y, [0., 1., -1., 2., 0.5, 3.]);
+        let expected = 4f64.ln() + 2. * 0.25f64.ln();
+        assert!((log_jacobian - expected).abs() < 1e-15);
+    }
+}
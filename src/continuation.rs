@@ -0,0 +1,256 @@
+//! # Numerical continuation
+//!
+//! Tracking a branch of solutions of `f(x, lambda) = 0` as the parameter
+//! `lambda` varies, by pseudo-arclength continuation. The tangent of the
+//! branch follows from the Jacobian of `f` with respect to both `x` and
+//! `lambda`, computed with dual numbers, so `dx/dlambda` is available at every
+//! point, and the branch can be followed around folds where `lambda` turns
+//! back.
+//!
+//! ```
+//! use epsilon::continuation::{self, ArcLength};
+//! // x^3 - x + lambda = 0, which folds at lambda = 2 / (3 sqrt(3))
+//! let f = |[x]: [epsilon::Dual<2>; 1], lambda| [x * x * x - x + lambda];
+//! let options = ArcLength { bounds: (-1., 1.), ..ArcLength::default() };
+//! let branch = continuation::track(f, [1.2], 1.2 - 1.728, options);
+//!
+//! assert!(branch.completed);
+//! let folds = branch.folds();
+//! assert_eq!(folds.len(), 2);
+//! let lambda = 2. / (3. * 3f64.sqrt());
+//! assert!((branch.points[folds[0]].lambda - lambda).abs() < 1e-2);
+//! ```
+
+use crate::solve::{LinearSolver, Lu};
+use crate::Dual;
+
+/// Step size control and stopping criteria of [`track`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArcLength {
+    /// Initial arclength step
+    pub step: f64,
+    /// Smallest step tried before giving up
+    pub min_step: f64,
+    /// Largest step taken
+    pub max_step: f64,
+    /// Largest number of points on the branch
+    pub max_points: usize,
+    /// Tolerance on the Euclidean norm of the residual of the corrector
+    pub tol: f64,
+    /// Largest number of corrector iterations per step
+    pub max_iter: usize,
+    /// Tracking stops once `lambda` leaves this range
+    pub bounds: (f64, f64),
+}
+
+impl Default for ArcLength {
+    fn default() -> Self {
+        ArcLength {
+            step: 1e-2,
+            min_step: 1e-8,
+            max_step: 1e-1,
+            max_points: 1000,
+            tol: 1e-10,
+            max_iter: 10,
+            bounds: (f64::NEG_INFINITY, f64::INFINITY),
+        }
+    }
+}
+
+/// A point on a solution branch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point<const N: usize> {
+    pub x: [f64; N],
+    pub lambda: f64,
+    /// Tangent of the branch as `dx/dlambda`, infinite at a fold
+    pub dx_dlambda: [f64; N],
+}
+
+/// Points along a solution branch, in the order they were tracked
+#[derive(Clone, Debug, PartialEq)]
+pub struct Branch<const N: usize> {
+    pub points: Vec<Point<N>>,
+    /// Whether tracking stopped by leaving the bounds or reaching the
+    /// largest number of points, rather than failing to correct a step
+    pub completed: bool,
+}
+
+impl<const N: usize> Branch<N> {
+    /// Indices of the points closest to folds, where `lambda` turns back
+    pub fn folds(&self) -> Vec<usize> {
+        let lambdas: Vec<f64> = self.points.iter().map(|p| p.lambda).collect();
+        (1..lambdas.len().saturating_sub(1))
+            .filter(|&i| (lambdas[i] - lambdas[i - 1]) * (lambdas[i + 1] - lambdas[i]) < 0.)
+            .collect()
+    }
+}
+
+/// Residual and Jacobian with respect to `(x, lambda)` at `z = (x, lambda)`
+fn linearize<const N: usize, const M: usize>(
+    f: &impl Fn([Dual<M>; N], Dual<M>) -> [Dual<M>; N],
+    z: [f64; M],
+) -> ([f64; N], [[f64; M]; N]) {
+    let x = Dual::variables_at(std::array::from_fn(|i| z[i]), 0);
+    let y = f(x, Dual::variable(z[N], N));
+    (y.map(|y| y.real), y.map(|y| y.eps))
+}
+
+/// Unit tangent `t` of the branch with `J t = 0`, oriented by `t . orient > 0`
+fn tangent<const N: usize, const M: usize>(
+    jac: [[f64; M]; N],
+    orient: [f64; M],
+) -> Option<[f64; M]> {
+    let a = std::array::from_fn(|i| if i < N { jac[i] } else { orient });
+    let b = std::array::from_fn(|i| if i < N { 0. } else { 1. });
+    let t = Lu.solve(a, b)?;
+    let norm = t.iter().map(|t| t * t).sum::<f64>().sqrt();
+    Some(t.map(|t| t / norm))
+}
+
+/// Newton's method for `f(z) = 0` restricted to the hyperplane through
+/// `predicted` normal to `t`, returning the solution and its Jacobian
+fn correct<const N: usize, const M: usize>(
+    f: &impl Fn([Dual<M>; N], Dual<M>) -> [Dual<M>; N],
+    predicted: [f64; M],
+    t: [f64; M],
+    options: &ArcLength,
+) -> Option<([f64; M], [[f64; M]; N], usize)> {
+    let mut z = predicted;
+    for iteration in 0..=options.max_iter {
+        let (y, jac) = linearize(f, z);
+        let offset: f64 = t
+            .iter()
+            .zip(z.iter().zip(&predicted))
+            .map(|(t, (z, p))| t * (z - p))
+            .sum();
+        let residual = (y.iter().map(|y| y * y).sum::<f64>() + offset * offset).sqrt();
+        if !residual.is_finite() {
+            return None;
+        }
+        if residual <= options.tol {
+            return Some((z, jac, iteration));
+        }
+        let a = std::array::from_fn(|i| if i < N { jac[i] } else { t });
+        let b = std::array::from_fn(|i| if i < N { -y[i] } else { -offset });
+        let step = Lu.solve(a, b)?;
+        for (z, s) in z.iter_mut().zip(step) {
+            *z += s;
+        }
+    }
+    None
+}
+
+/// Track the branch of solutions of `f(x, lambda) = 0` through
+/// `(x0, lambda0)`, in the direction of increasing `lambda`
+///
+/// `x0` is first corrected to a solution at `lambda0`. Each step predicts the
+/// next point along the tangent of the branch and corrects it by Newton's
+/// method, keeping the arclength step `t . (z - z_predicted) = 0` fixed. The
+/// step is halved whenever the corrector fails, and grows after corrections
+/// needing few iterations. `f` is evaluated on dual numbers with
+/// `M = N + 1` components, the first `N` seeded for `x` and the last for
+/// `lambda`.
+pub fn track<const N: usize, const M: usize>(
+    f: impl Fn([Dual<M>; N], Dual<M>) -> [Dual<M>; N],
+    x0: [f64; N],
+    lambda0: f64,
+    options: ArcLength,
+) -> Branch<N> {
+    const { assert!(M == N + 1, "number of dual components must be N + 1") };
+    let mut branch = Branch {
+        points: Vec::new(),
+        completed: false,
+    };
+
+    // Correct at fixed lambda, then orient the tangent towards increasing lambda
+    let lambda_axis: [f64; M] = std::array::from_fn(|i| if i == N { 1. } else { 0. });
+    let start = std::array::from_fn(|i| if i < N { x0[i] } else { lambda0 });
+    let Some((mut z, jac, _)) = correct(&f, start, lambda_axis, &options) else {
+        return branch;
+    };
+    let Some(mut t) = tangent(jac, lambda_axis) else {
+        return branch;
+    };
+
+    let mut step = options.step;
+    loop {
+        branch.points.push(Point {
+            x: std::array::from_fn(|i| z[i]),
+            lambda: z[N],
+            dx_dlambda: std::array::from_fn(|i| t[i] / t[N]),
+        });
+        let (lo, hi) = options.bounds;
+        if branch.points.len() >= options.max_points || !(lo..=hi).contains(&z[N]) {
+            branch.completed = true;
+            return branch;
+        }
+
+        let next = loop {
+            let predicted = std::array::from_fn(|i| z[i] + step * t[i]);
+            let corrected = correct(&f, predicted, t, &options)
+                .and_then(|(z, jac, iterations)| Some((z, tangent(jac, t)?, iterations)));
+            match corrected {
+                Some(next) => break Some(next),
+                None if step / 2. >= options.min_step => step /= 2.,
+                None => break None,
+            }
+        };
+        let Some((z_next, t_next, iterations)) = next else {
+            return branch;
+        };
+        if iterations <= 3 {
+            step = (step * 1.5).min(options.max_step);
+        }
+        (z, t) = (z_next, t_next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{track, ArcLength};
+    use crate::Dual;
+
+    #[test]
+    fn test_circle() {
+        // x^2 + lambda^2 = 1 from an inexact start at lambda = 0, around the
+        // fold at lambda = 1
+        let f = |[x]: [Dual<2>; 1], lambda: Dual<2>| [x * x + lambda * lambda - 1.];
+        let options = ArcLength {
+            max_points: 30,
+            ..ArcLength::default()
+        };
+        let branch = track(f, [1.1], 0., options);
+        assert!(branch.completed);
+        assert_eq!(branch.points.len(), 30);
+        assert!((branch.points[0].x[0] - 1.).abs() < 1e-10);
+
+        for p in &branch.points[1..] {
+            assert!((p.x[0] * p.x[0] + p.lambda * p.lambda - 1.).abs() < 1e-10);
+            assert!((p.dx_dlambda[0] + p.lambda / p.x[0]).abs() < 1e-6);
+        }
+        let folds = branch.folds();
+        assert_eq!(folds.len(), 1);
+        let fold = branch.points[folds[0]];
+        assert!(fold.lambda > 0.99 && fold.x[0].abs() < 0.15);
+        // Past the fold, lambda decreases along the lower half of the circle
+        let last = branch.points.last().unwrap();
+        assert!(last.x[0] < 0. && last.lambda < fold.lambda);
+    }
+
+    #[test]
+    fn test_system() {
+        // Equilibria of a two-dimensional system, x = lambda y, y = sin(x) / 2
+        let f = |[x, y]: [Dual<3>; 2], lambda: Dual<3>| [x - lambda * y, y - x.sin() * 0.5];
+        let options = ArcLength {
+            bounds: (0., 1.5),
+            ..ArcLength::default()
+        };
+        let branch = track(f, [0., 0.], 0.5, options);
+        assert!(branch.completed);
+        // The trivial solution remains a solution for all lambda
+        let last = branch.points.last().unwrap();
+        assert!(last.lambda > 1.5);
+        assert!(last.x.iter().all(|x| x.abs() < 1e-10));
+        assert!(last.dx_dlambda.iter().all(|d| d.abs() < 1e-10));
+    }
+}
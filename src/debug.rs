@@ -0,0 +1,82 @@
+//! # Debugging dual numbers
+//!
+//! [`dbg_dual!`](crate::dbg_dual) works like [`dbg!`], but prints the real
+//! part and a table of the dual components instead of the `Debug` output,
+//! which for types with many components is hard to read.
+//!
+//! ```
+//! use epsilon::{dbg_dual, make_dual};
+//! make_dual! { Pos, x, y }
+//!
+//! let (x, y) = (Pos::x(2.), Pos::y(3.));
+//! // Prints to stderr:
+//! // [src/main.rs:6:9] x * y = 6
+//! //     eps_x  3
+//! //     eps_y  2
+//! let z = dbg_dual!(x * y) + 1.;
+//! assert_eq!(z.d_dx(), 3.);
+//! ```
+
+use crate::check::Components;
+
+/// The output of [`dbg_dual!`](crate::dbg_dual), without a trailing newline
+#[doc(hidden)]
+pub fn format(location: &str, expr: &str, value: &impl Components) -> String {
+    let names: Vec<String> = (0..value.components()).map(|i| value.field(i)).collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let mut out = format!("[{location}] {expr} = {}", value.value());
+    for (i, name) in names.iter().enumerate() {
+        out += &format!("\n    {name:width$}  {}", value.component(i));
+    }
+    out
+}
+
+/// Print the source location, the expression, its real part and its dual
+/// components to stderr, and return the value of the expression
+///
+/// Like [`dbg!`], takes ownership of the value, and accepts several
+/// expressions, returning a tuple of their values.
+#[macro_export]
+macro_rules! dbg_dual {
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                let location = concat!(file!(), ":", line!(), ":", column!());
+                eprintln!("{}", $crate::debug::format(location, stringify!($val), &tmp));
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_dual!($val)),+,)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+    use crate::sample::SampleXYZ;
+    use crate::{Dual, DualNumber};
+
+    #[test]
+    fn test_format() {
+        let v = SampleXYZ::from_parts(1.5, &[1., -2., 0.]);
+        assert_eq!(
+            format("a.rs:1:1", "v", &v),
+            "[a.rs:1:1] v = 1.5\n    eps_x  1\n    eps_y  -2\n    eps_z  0"
+        );
+        let eps: Vec<f64> = (0..11).map(f64::from).collect();
+        let v = Dual::<11>::new(0., eps.try_into().unwrap());
+        let out = format("a.rs:1:1", "v", &v);
+        assert!(out.contains("\n    eps_1   1\n") && out.ends_with("\n    eps_10  10"));
+    }
+
+    #[test]
+    fn test_macro() {
+        let x = SampleXYZ::x(2.);
+        let y = crate::dbg_dual!(x * x);
+        assert_eq!(y, x * x);
+        let (a, b) = crate::dbg_dual!(Dual::<1>::variable(1., 0), x);
+        assert_eq!((a.real, b), (1., x));
+    }
+}
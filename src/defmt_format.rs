@@ -0,0 +1,57 @@
+//! # Embedded logging
+//!
+//! With the `defmt` feature, all types generated by
+//! [`make_dual`](crate::make_dual) implement [`defmt::Format`], so values and
+//! their gradients can be logged from microcontrollers, e.g. over RTT:
+//!
+//! ```ignore
+//! defmt::info!("position: {}", pos); // position: 1.5 + 2 eps_x - 0.5 eps_y
+//! ```
+//!
+//! The output has the same format as the `Display` implementation, with all
+//! parts converted to `f64` using [`Scalar::to_f64`](crate::Scalar::to_f64).
+
+#[doc(hidden)]
+pub use defmt;
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_defmt {
+    ($name:ident, $inner:ty, $($comp:ident,)+) => { $crate::paste! {
+        const _: () = {
+            // The defmt macros refer to the crate as `defmt`
+            use $crate::defmt_format::defmt;
+
+            impl defmt::Format for $name {
+                fn format(&self, fmt: defmt::Formatter) {
+                    defmt::write!(fmt, "{=f64}", $crate::Scalar::to_f64(self.real));
+                    $(
+                        let v = self.[< eps_ $comp >];
+                        match $crate::Scalar::compare(v, <$inner as $crate::Scalar>::ZERO) {
+                            Some(core::cmp::Ordering::Less) => {
+                                defmt::write!(fmt, " - {=f64} eps_{=str}", $crate::Scalar::to_f64(-v), stringify!($comp))
+                            }
+                            Some(core::cmp::Ordering::Equal) => {}
+                            _ => defmt::write!(fmt, " + {=f64} eps_{=str}", $crate::Scalar::to_f64(v), stringify!($comp)),
+                        }
+                    )+
+                }
+            }
+        };
+    } };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample::SampleXYZ;
+
+    crate::make_dual! { SmallXY: f32, x, y }
+
+    #[test]
+    fn test_format() {
+        // Formatting needs a global logger, which only the firmware provides,
+        // so this only checks that the implementations compile
+        let _: fn(&SampleXYZ, defmt::Formatter) = <SampleXYZ as defmt::Format>::format;
+        let _: fn(&SmallXY, defmt::Formatter) = <SmallXY as defmt::Format>::format;
+    }
+}
@@ -0,0 +1,167 @@
+//! # Derivative-based global sensitivity measures
+//!
+//! Screening of the inputs of a model by its partial derivatives sampled over
+//! the whole input space, rather than at a single point as in
+//! [`sensitivity`](crate::sensitivity). The gradient at each sample costs a
+//! single evaluation on dual numbers, so this is much cheaper than variance
+//! based Sobol indices, while the mean squared partial derivative `nu_i`
+//! still bounds the total Sobol index of input `i` from above. Inputs with a
+//! small bound can safely be fixed.
+//!
+//! With the `rand` feature, [`sample`] draws the points from the [`Input`]
+//! distributions, otherwise [`measures`] takes points sampled by any method,
+//! e.g. a quasi-random sequence.
+//!
+//! ```
+//! # #[cfg(feature = "rand")] {
+//! use epsilon::dgsm::{self, Input};
+//! use rand::SeedableRng;
+//! // y = x0 + 0.1 x1^2, where x2 has no effect
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+//! let inputs = [Input::Uniform(-1., 1.); 3];
+//! let [x0, x1, x2] = dgsm::sample(|[x0, x1, _]| x0 + x1 * x1 * 0.1, inputs, 10_000, &mut rng);
+//!
+//! assert!((x0.mean_squared - 1.).abs() < 1e-12);
+//! assert!(x1.total_bound.unwrap() < 0.05 && x2.mean_squared == 0.);
+//! # }
+//! ```
+
+use crate::Dual;
+
+/// Distribution of an input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    /// Uniform on `[low, high]`
+    Uniform(f64, f64),
+    /// Normal with the mean and standard deviation
+    Normal(f64, f64),
+}
+
+impl Input {
+    /// The Poincaré constant of the distribution, bounding its variance of
+    /// any function by this times its mean squared derivative
+    fn poincare(self) -> f64 {
+        match self {
+            Input::Uniform(low, high) => (high - low).powi(2) / std::f64::consts::PI.powi(2),
+            Input::Normal(_, sigma) => sigma * sigma,
+        }
+    }
+}
+
+/// Sensitivity measures of one input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measure {
+    /// Mean absolute partial derivative
+    pub mean_abs: f64,
+    /// Mean squared partial derivative, `nu_i`
+    pub mean_squared: f64,
+    /// Sample variance of the squared partial derivative, to judge whether
+    /// enough samples were taken
+    pub variance_squared: f64,
+    /// Upper bound of the total Sobol index, `C_i nu_i / Var(f)` for the
+    /// Poincaré constant `C_i` of the input distribution, if it is known and
+    /// the sampled output has non-zero variance
+    pub total_bound: Option<f64>,
+}
+
+/// The measures of each input of `f` from its gradients at `points`
+///
+/// The total Sobol index bounds are only computed if `inputs` gives the
+/// distributions the points are drawn from.
+pub fn measures<const N: usize>(
+    f: impl Fn([Dual<N>; N]) -> Dual<N>,
+    points: &[[f64; N]],
+    inputs: Option<[Input; N]>,
+) -> [Measure; N] {
+    let n = points.len() as f64;
+    let (mut sum, mut sum_squares) = (0., 0.);
+    let mut abs = [0.; N];
+    let mut squared = [0.; N];
+    let mut fourth = [0.; N];
+    for &x in points {
+        let y = f(Dual::variables(x));
+        sum += y.real;
+        sum_squares += y.real * y.real;
+        for (i, d) in y.eps.iter().enumerate() {
+            abs[i] += d.abs();
+            squared[i] += d * d;
+            fourth[i] += d * d * d * d;
+        }
+    }
+    let variance = (sum_squares - sum * sum / n) / (n - 1.);
+
+    std::array::from_fn(|i| {
+        let mean_squared = squared[i] / n;
+        Measure {
+            mean_abs: abs[i] / n,
+            mean_squared,
+            variance_squared: (fourth[i] - n * mean_squared * mean_squared) / (n - 1.),
+            total_bound: inputs
+                .filter(|_| variance > 0.)
+                .map(|inputs| inputs[i].poincare() * mean_squared / variance),
+        }
+    })
+}
+
+/// The measures of each input of `f`, from its gradients at `samples` points
+/// drawn independently from the `inputs` distributions
+#[cfg(feature = "rand")]
+pub fn sample<const N: usize, R: rand::Rng + ?Sized>(
+    f: impl Fn([Dual<N>; N]) -> Dual<N>,
+    inputs: [Input; N],
+    samples: usize,
+    rng: &mut R,
+) -> [Measure; N] {
+    let points: Vec<[f64; N]> = (0..samples)
+        .map(|_| {
+            inputs.map(|input| match input {
+                Input::Uniform(low, high) => low + (high - low) * rng.random::<f64>(),
+                Input::Normal(mu, sigma) => mu + sigma * crate::reparam::standard_normal(rng),
+            })
+        })
+        .collect();
+    measures(f, &points, Some(inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{measures, Input};
+
+    #[test]
+    fn test_measures() {
+        // f = x0 x1 on a grid over [0, 1]^2, df/dx0 = x1
+        let grid: Vec<[f64; 2]> = (0..4)
+            .flat_map(|i| (0..4).map(move |j| [i as f64 / 3., j as f64 / 3.]))
+            .collect();
+        let [m0, m1] = measures(|[x, y]| x * y, &grid, Some([Input::Uniform(0., 1.); 2]));
+        assert_eq!(m0, m1);
+        assert!((m0.mean_abs - 0.5).abs() < 1e-15);
+        assert!((m0.mean_squared - 7. / 18.).abs() < 1e-15);
+        assert!(m0.variance_squared > 0.);
+        let bound = m0.total_bound.unwrap();
+        assert!(bound > 0. && bound < 1.);
+
+        // Without distributions or variance, there is no bound
+        let [m] = measures(
+            |[x]| x * 0. + 1.,
+            &[[0.], [1.]],
+            Some([Input::Normal(0., 1.)]),
+        );
+        assert_eq!(m.total_bound, None);
+        let [m] = measures(|[x]| x, &[[0.], [1.]], None);
+        assert_eq!((m.mean_squared, m.total_bound), (1., None));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sample() {
+        use rand::SeedableRng;
+        // Linear model with normal inputs, where the bound is the exact index
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let inputs = [Input::Normal(1., 2.), Input::Normal(0., 1.)];
+        let [a, b] = super::sample(|[x, y]| x + y * 2., inputs, 20_000, &mut rng);
+        assert!((a.total_bound.unwrap() - 0.5).abs() < 0.02);
+        assert!((b.total_bound.unwrap() - 0.5).abs() < 0.02);
+        assert_eq!(a.variance_squared, 0.);
+    }
+}
@@ -0,0 +1,154 @@
+//! # Probability distributions
+//!
+//! Logarithms of probability densities and mass functions, generic over
+//! [`Scalar`]. Evaluated on dual numbers, they give the gradients of
+//! log-likelihoods with respect to the parameters, as needed for maximum
+//! likelihood or MAP estimation with the [`optim`](crate::optim) module.
+//! Outside the support of a distribution, the log-density is `-inf`. At the
+//! boundary of the parameters, terms `0 ln(0)` are taken to be zero, so that
+//! e.g. a Poisson distribution with mean zero has all its mass at zero.
+//!
+//! ```
+//! use epsilon::distributions::normal_logpdf;
+//! use epsilon::Dual;
+//! // Gradient of a normal log-likelihood with respect to mu and sigma
+//! let data = [1.2, 0.7, 2.1];
+//! let [mu, sigma] = Dual::<2>::variables([1., 0.5]);
+//! let ll = data.iter().fold(Dual::constant(0.), |ll, &x| {
+//!     ll + normal_logpdf(Dual::constant(x), mu, sigma)
+//! });
+//!
+//! // d/dmu = sum(x - mu) / sigma^2
+//! assert!((ll.eps[0] - 1. / 0.25).abs() < 1e-12);
+//! ```
+
+use crate::special::{ln_beta, ln_gamma};
+use crate::Scalar;
+
+fn outside<T: Scalar>() -> T {
+    T::from_f64(f64::NEG_INFINITY)
+}
+
+/// `x ln(y)`, or zero if `x` is zero, even where `ln(y)` is not finite
+fn xlogy<T: Scalar>(x: T, y: T) -> T {
+    if x.to_f64() == 0. {
+        return T::ZERO;
+    }
+    x * y.ln()
+}
+
+/// Normal distribution with mean `mu` and standard deviation `sigma`
+pub fn normal_logpdf<T: Scalar>(x: T, mu: T, sigma: T) -> T {
+    let z = (x - mu) / sigma;
+    T::from_f64(-0.5 * (2. * std::f64::consts::PI).ln()) - sigma.ln() - T::from_f64(0.5) * z * z
+}
+
+/// Log-normal distribution, with `ln(x)` normally distributed with mean `mu`
+/// and standard deviation `sigma`
+pub fn lognormal_logpdf<T: Scalar>(x: T, mu: T, sigma: T) -> T {
+    if x.to_f64() <= 0. {
+        return outside();
+    }
+    normal_logpdf(x.ln(), mu, sigma) - x.ln()
+}
+
+/// Exponential distribution with rate `rate`
+pub fn exponential_logpdf<T: Scalar>(x: T, rate: T) -> T {
+    if x.to_f64() < 0. {
+        return outside();
+    }
+    rate.ln() - rate * x
+}
+
+/// Gamma distribution with shape `shape` and rate `rate`
+pub fn gamma_logpdf<T: Scalar>(x: T, shape: T, rate: T) -> T {
+    if x.to_f64() <= 0. {
+        return outside();
+    }
+    shape * rate.ln() + (shape - T::ONE) * x.ln() - rate * x - ln_gamma(shape)
+}
+
+/// Beta distribution with shape parameters `a` and `b`
+pub fn beta_logpdf<T: Scalar>(x: T, a: T, b: T) -> T {
+    if !(0. ..=1.).contains(&x.to_f64()) {
+        return outside();
+    }
+    xlogy(a - T::ONE, x) + xlogy(b - T::ONE, T::ONE - x) - ln_beta(a, b)
+}
+
+/// Poisson distribution with mean `lambda`, at the count `k`
+pub fn poisson_logpmf<T: Scalar>(k: u64, lambda: T) -> T {
+    let k = T::from_f64(k as f64);
+    xlogy(k, lambda) - lambda - ln_gamma(k + T::ONE)
+}
+
+/// Binomial distribution of the number of successes `k` out of `n` trials
+/// with success probability `p`
+pub fn binomial_logpmf<T: Scalar>(k: u64, n: u64, p: T) -> T {
+    if k > n {
+        return outside();
+    }
+    let (k, n) = (T::from_f64(k as f64), T::from_f64(n as f64));
+    let ln_choose = ln_gamma(n + T::ONE) - ln_gamma(k + T::ONE) - ln_gamma(n - k + T::ONE);
+    ln_choose + xlogy(k, p) + xlogy(n - k, T::ONE - p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::special::digamma;
+    use crate::Dual;
+
+    #[test]
+    fn test_values() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-13;
+        assert!(close(
+            normal_logpdf(0., 0., 1.),
+            -0.5 * (2. * std::f64::consts::PI).ln()
+        ));
+        assert!(close(
+            lognormal_logpdf(1., 0., 1.),
+            normal_logpdf(0., 0., 1.)
+        ));
+        assert!(close(exponential_logpdf(2., 3.), 3f64.ln() - 6.));
+        // Gamma(1, rate) is exponential
+        assert!(close(gamma_logpdf(2., 1., 3.), exponential_logpdf(2., 3.)));
+        // Beta(2, 2) has density 6 x (1 - x)
+        assert!(close(beta_logpdf(0.25, 2., 2.), (6. * 0.25 * 0.75f64).ln()));
+        assert!(close(poisson_logpmf(3, 2.), (8. * (-2f64).exp() / 6.).ln()));
+        assert!(close(binomial_logpmf(2, 4, 0.5), (6. / 16f64).ln()));
+
+        assert_eq!(gamma_logpdf(-1., 2., 1.), f64::NEG_INFINITY);
+        assert_eq!(beta_logpdf(1.5, 2., 2.), f64::NEG_INFINITY);
+        assert_eq!(binomial_logpmf(5, 4, 0.5), f64::NEG_INFINITY);
+
+        // Boundaries of the parameters
+        assert!(close(poisson_logpmf(0, 0.), 0.));
+        assert_eq!(poisson_logpmf(1, 0.), f64::NEG_INFINITY);
+        assert!(close(binomial_logpmf(0, 5, 0.), 0.));
+        assert!(close(binomial_logpmf(5, 5, 1.), 0.));
+        assert!(close(beta_logpdf(0., 1., 3.), 3f64.ln()));
+        assert!(close(beta_logpdf(1., 3., 1.), 3f64.ln()));
+    }
+
+    #[test]
+    fn test_gradients() {
+        // d/dshape = ln(rate) + ln(x) - digamma(shape), d/drate = shape / rate - x
+        let [shape, rate] = Dual::<2>::variables([2.5, 1.5]);
+        let lp = gamma_logpdf(Dual::constant(0.8), shape, rate);
+        assert!((lp.eps[0] - (1.5f64.ln() + 0.8f64.ln() - digamma(2.5))).abs() < 1e-13);
+        assert!((lp.eps[1] - (2.5 / 1.5 - 0.8)).abs() < 1e-13);
+
+        // Poisson maximum likelihood at the sample mean
+        let [lambda] = Dual::<1>::variables([3.]);
+        let ll = [2, 3, 4]
+            .iter()
+            .fold(Dual::constant(0.), |ll, &k| ll + poisson_logpmf(k, lambda));
+        assert!(ll.eps[0].abs() < 1e-15);
+
+        // Binomial, d/dp = k / p - (n - k) / (1 - p)
+        let [p] = Dual::<1>::variables([0.3]);
+        let lp = binomial_logpmf(2, 10, p);
+        assert!((lp.eps[0] - (2. / 0.3 - 8. / 0.7)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,616 @@
+//! # Dual numbers with a const-generic number of components
+//!
+//! [`Dual<N>`] stores its `N` dual components in an array, indexed rather
+//! than named. It is useful when the number of variables follows from the
+//! problem, such as the length of a parameter array, and is the type used by
+//! the closure-based helpers such as [`grad`](crate::grad).
+//!
+//! ```
+//! use epsilon::Dual;
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7
+//! let [x, y] = Dual::<2>::variables([5., 7.]);
+//!
+//! let z = x.powf(2.) + y * y.sin();
+//!
+//! assert_eq!(z.d_d(0), 10.);
+//! assert_eq!(z.d_d(1), 5.934302379121921);
+//! ```
+
+use crate::Scalar;
+
+/// Dual number with `N` dual components, backed by `F`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Dual<const N: usize, F = f64> {
+    /// The real value of the dual number
+    pub real: F,
+    /// The dual components
+    pub eps: [F; N],
+}
+
+/// Dual number with a single component, for functions of one variable
+pub type Dual1 = Dual<1>;
+
+/// Nested dual number, whose dual components of dual components are second
+/// derivatives
+///
+/// [`Dual`] implements [`Scalar`], so it can be nested in itself. See
+/// [`hessian`](crate::hessian) for the seeding.
+pub type HyperDual<const N: usize> = Dual<N, Dual<N>>;
+
+impl<const N: usize, F: Scalar> Dual<N, F> {
+    /// Create instance with specified real and dual parts
+    pub fn new(real: F, eps: [F; N]) -> Self {
+        Dual { real, eps }
+    }
+
+    /// Create instance with specified real part and zero dual part
+    pub fn constant(real: F) -> Self {
+        Dual {
+            real,
+            eps: [F::ZERO; N],
+        }
+    }
+
+    /// Create instance with specified real part and a unit dual part in
+    /// component `index`
+    pub fn variable(real: F, index: usize) -> Self {
+        let mut v = Self::constant(real);
+        v.eps[index] = F::ONE;
+        v
+    }
+
+    /// Create one variable per component, the `i`th having the real part
+    /// `values[i]` and a unit dual part in component `i`
+    pub fn variables(values: [F; N]) -> [Self; N] {
+        Self::variables_at(values, 0)
+    }
+
+    /// Create variables in the components from `offset` onwards, the `i`th
+    /// having the real part `values[i]` and a unit dual part in component
+    /// `offset + i`
+    ///
+    /// This allows seeding groups of variables separately, e.g. the initial
+    /// state and the parameters of an ODE.
+    pub fn variables_at<const K: usize>(values: [F; K], offset: usize) -> [Self; K] {
+        let mut i = offset;
+        values.map(|real| {
+            i += 1;
+            Self::variable(real, i - 1)
+        })
+    }
+
+    /// Derivative with respect to the `index`th component
+    pub fn d_d(&self, index: usize) -> F {
+        self.eps[index]
+    }
+
+    /// Elasticity `(x / z) dz/dx` with respect to the `index`th component,
+    /// given the value `x` of that variable
+    pub fn d_d_rel(&self, index: usize, x: F) -> F {
+        x / self.real * self.eps[index]
+    }
+
+    /// Multiply each dual component by the corresponding entry of `scales`,
+    /// e.g. for preconditioning or converting the units of the variables
+    pub fn scale_eps_by(mut self, scales: [F; N]) -> Self {
+        for (e, s) in self.eps.iter_mut().zip(scales) {
+            *e *= s;
+        }
+        self
+    }
+
+    /// Euclidean norm of the dual part, i.e. of the gradient
+    pub fn grad_norm(&self) -> F {
+        let squared = self.eps.iter().fold(F::ZERO, |sum, &e| sum + e * e);
+        squared.powf(F::from_f64(0.5))
+    }
+
+    /// `self` with the dual part scaled to unit norm, unchanged if the dual
+    /// part is zero
+    pub fn normalize_grad(self) -> Self {
+        let norm = self.grad_norm();
+        if norm == F::ZERO {
+            return self;
+        }
+        Dual::new(self.real, self.eps.map(|e| e / norm))
+    }
+
+    /// `self` with the dual part scaled down to norm `max_norm` if its norm
+    /// is larger, keeping its direction
+    pub fn clamp_grad(self, max_norm: F) -> Self {
+        let norm = self.grad_norm();
+        if norm.to_f64() <= max_norm.to_f64() {
+            return self;
+        }
+        Dual::new(self.real, self.eps.map(|e| e * (max_norm / norm)))
+    }
+
+    /// `self` with each dual component clipped to `[-limit, limit]`
+    pub fn clamp_grad_per_component(self, limit: F) -> Self {
+        Dual::new(self.real, self.eps.map(|e| clip(e, limit)))
+    }
+
+    /// Derivative in the direction `direction`, i.e. the dot product with
+    /// the gradient
+    pub fn directional_derivative(&self, direction: [F; N]) -> F {
+        self.eps
+            .iter()
+            .zip(direction)
+            .fold(F::ZERO, |sum, (&e, d)| sum + e * d)
+    }
+
+    /// `value` with the derivative `derivative` with respect to `self`, in
+    /// place of the derivative of the expression computing it
+    ///
+    /// The dual part is that of `self` times `derivative`, while the real
+    /// part is exactly `value`. E.g. `x.with_derivative(x.real.round(), 1.)`
+    /// rounds with the derivative of the identity, a straight-through
+    /// estimator, and `x.with_derivative(f(x.real), df(x.real))` applies a
+    /// black-box function `f` with the known derivative `df`.
+    pub fn with_derivative(self, value: F, derivative: F) -> Self {
+        self.chain(value, derivative)
+    }
+
+    /// `value` with the derivatives of a function of several inputs, given as
+    /// pairs of an input and the partial derivative with respect to it
+    ///
+    /// The dual part is the sum of those of the inputs times the partial
+    /// derivatives, as for [`with_derivative`](Dual::with_derivative) with a
+    /// single input.
+    pub fn custom_grad(value: F, partials: &[(Self, F)]) -> Self {
+        let mut eps = [F::ZERO; N];
+        for &(input, partial) in partials {
+            for (e, i) in eps.iter_mut().zip(input.eps) {
+                *e += i * partial;
+            }
+        }
+        Dual { real: value, eps }
+    }
+
+    /// Report `self` as the result of `op` on `inputs` if it is the first
+    /// non-finite value, with the `tracing` feature
+    fn checked(self, _op: &'static str, _inputs: &[Self]) -> Self {
+        crate::__check_finite!(_op, self, _inputs);
+        self
+    }
+
+    /// Apply a function with value `real` and derivative `deriv` at `self.real`
+    fn chain(self, real: F, deriv: F) -> Self {
+        Dual {
+            real,
+            eps: self.eps.map(|e| e * deriv),
+        }
+    }
+
+    /// Combine the dual parts of two numbers, as `a * self.eps + b * other.eps`
+    fn combine(mut self, other: Self, a: F, b: F, real: F) -> Self {
+        for (e, o) in self.eps.iter_mut().zip(other.eps) {
+            *e = a * *e + b * o;
+        }
+        self.real = real;
+        self
+    }
+
+    /// Raise `self` to `pow`
+    pub fn powf(self, pow: F) -> Self {
+        crate::__count_op!(Self, Transcendental);
+        // power rule: d/dx [x^p] = p x^(p-1)
+        let (r, dr) = (self.real.powf(pow), pow * self.real.powf(pow - F::ONE));
+        self.chain(r, dr).checked("powf", &[self])
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        crate::__count_op!(Self, Mul);
+        // d/dx [1/x] = -1/x^2
+        let (r, dr) = (F::ONE / self.real, -F::ONE / (self.real * self.real));
+        self.chain(r, dr).checked("invert", &[self])
+    }
+
+    pub fn exp(self) -> Self {
+        crate::__count_op!(Self, Transcendental);
+        let r = self.real.exp();
+        self.chain(r, r).checked("exp", &[self])
+    }
+
+    /// Natural logarithm
+    pub fn ln(self) -> Self {
+        crate::__count_op!(Self, Transcendental);
+        let (r, dr) = (self.real.ln(), F::ONE / self.real);
+        self.chain(r, dr).checked("ln", &[self])
+    }
+
+    pub fn sin(self) -> Self {
+        crate::__count_op!(Self, Transcendental);
+        let (r, dr) = (self.real.sin(), self.real.cos());
+        self.chain(r, dr).checked("sin", &[self])
+    }
+
+    pub fn cos(self) -> Self {
+        crate::__count_op!(Self, Transcendental);
+        let (r, dr) = (self.real.cos(), -self.real.sin());
+        self.chain(r, dr).checked("cos", &[self])
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+}
+
+/// `x` clipped to `[-limit, limit]`, `NaN` if `x` is
+#[doc(hidden)]
+pub fn clip<F: Scalar>(x: F, limit: F) -> F {
+    if x.to_f64() > limit.to_f64() {
+        limit
+    } else if x.to_f64() < -limit.to_f64() {
+        -limit
+    } else {
+        x
+    }
+}
+
+impl<const N: usize, F: Scalar> std::cmp::PartialOrd for Dual<N, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.real.compare(other.real)
+    }
+}
+
+impl<const N: usize, F: Scalar> std::fmt::Display for Dual<N, F> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.real)?;
+        for (i, &v) in self.eps.iter().enumerate() {
+            match v.compare(F::ZERO) {
+                Some(std::cmp::Ordering::Less) => write!(fmt, " - {} eps_{}", -v, i)?,
+                Some(std::cmp::Ordering::Equal) => {}
+                _ => write!(fmt, " + {} eps_{}", v, i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, F: Scalar> std::fmt::LowerExp for Dual<N, F> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let eps = self.eps.iter().map(|e| e.to_f64()).enumerate();
+        crate::text::fmt_exp(fmt, self.real.to_f64(), eps, false)
+    }
+}
+
+impl<const N: usize, F: Scalar> std::fmt::UpperExp for Dual<N, F> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let eps = self.eps.iter().map(|e| e.to_f64()).enumerate();
+        crate::text::fmt_exp(fmt, self.real.to_f64(), eps, true)
+    }
+}
+
+impl<const N: usize, F: Scalar> From<F> for Dual<N, F> {
+    fn from(real: F) -> Self {
+        Dual::constant(real)
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Add<F> for Dual<N, F> {
+    type Output = Self;
+
+    fn add(self, other: F) -> Self::Output {
+        crate::__count_op!(Self, Add);
+        let real = self.real + other;
+        Dual { real, ..self }.checked("add", &[self])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Add<Self> for Dual<N, F> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        crate::__count_op!(Self, Add);
+        let real = self.real + other.real;
+        self.combine(other, F::ONE, F::ONE, real)
+            .checked("add", &[self, other])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Mul<F> for Dual<N, F> {
+    type Output = Self;
+
+    fn mul(self, other: F) -> Self::Output {
+        crate::__count_op!(Self, Mul);
+        let real = self.real * other;
+        self.chain(real, other).checked("mul", &[self])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Mul<Self> for Dual<N, F> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        crate::__count_op!(Self, Mul);
+        let (a, b) = (other.real, self.real);
+        self.combine(other, a, b, a * b)
+            .checked("mul", &[self, other])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Neg for Dual<N, F> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -F::ONE
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Sub<F> for Dual<N, F> {
+    type Output = Self;
+
+    fn sub(self, other: F) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Sub<Self> for Dual<N, F> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Div<F> for Dual<N, F> {
+    type Output = Self;
+
+    fn div(self, other: F) -> Self::Output {
+        self * (F::ONE / other)
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Div<Self> for Dual<N, F> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self::Output {
+        crate::__count_op!(Self, Mul);
+        // quotient rule: d/dx [a/b] = (a' - (a/b) b') / b
+        let real = self.real / other.real;
+        let inv = F::ONE / other.real;
+        self.combine(other, inv, -real * inv, real)
+            .checked("div", &[self, other])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Rem<F> for Dual<N, F> {
+    type Output = Self;
+
+    fn rem(self, other: F) -> Self::Output {
+        crate::__count_op!(Self, Add);
+        let real = self.real % other;
+        Dual { real, ..self }.checked("rem", &[self])
+    }
+}
+
+impl<const N: usize, F: Scalar> std::ops::Rem<Self> for Dual<N, F> {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self::Output {
+        crate::__count_op!(Self, Mul);
+        crate::__count_op!(Self, Add);
+        // a % b = a - b * trunc(a / b), where trunc has zero derivative
+        let quot = (self.real / other.real).trunc();
+        let real = self.real % other.real;
+        self.combine(other, F::ONE, -quot, real)
+            .checked("rem", &[self, other])
+    }
+}
+
+impl<const N: usize, F: Scalar> Scalar for Dual<N, F> {
+    const ZERO: Self = Dual {
+        real: F::ZERO,
+        eps: [F::ZERO; N],
+    };
+    const ONE: Self = Dual {
+        real: F::ONE,
+        eps: [F::ZERO; N],
+    };
+
+    fn from_f64(value: f64) -> Self {
+        Dual::constant(F::from_f64(value))
+    }
+
+    fn to_f64(self) -> f64 {
+        self.real.to_f64()
+    }
+
+    fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+        self.real.compare(other.real)
+    }
+
+    fn powf(self, pow: Self) -> Self {
+        if pow.eps.iter().all(|&e| e == F::ZERO) {
+            Dual::powf(self, pow.real)
+        } else {
+            // x^y = exp(y ln(x)), only defined for positive x
+            (pow * self.ln()).exp()
+        }
+    }
+
+    fn exp(self) -> Self {
+        Dual::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        Dual::ln(self)
+    }
+
+    fn sin(self) -> Self {
+        Dual::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Dual::cos(self)
+    }
+
+    fn trunc(self) -> Self {
+        Dual::constant(self.real.trunc())
+    }
+}
+
+macro_rules! impl_reverse {
+    ($f:ty, $op:ident, $fn:ident) => {
+        impl<const N: usize> std::ops::$op<Dual<N, $f>> for $f {
+            type Output = Dual<N, $f>;
+
+            fn $fn(self, other: Dual<N, $f>) -> Self::Output {
+                <Dual<N, $f> as std::ops::$op>::$fn(Dual::from(self), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl<const N: usize, F: Scalar> std::ops::$op_inplace<F> for Dual<N, F> {
+            fn $fn_inplace(&mut self, other: F) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+        impl<const N: usize, F: Scalar> std::ops::$op_inplace<Self> for Dual<N, F> {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_reverse! {f32, Add, add}
+impl_reverse! {f32, Sub, sub}
+impl_reverse! {f32, Mul, mul}
+impl_reverse! {f32, Div, div}
+impl_reverse! {f32, Rem, rem}
+impl_reverse! {f64, Add, add}
+impl_reverse! {f64, Sub, sub}
+impl_reverse! {f64, Mul, mul}
+impl_reverse! {f64, Div, div}
+impl_reverse! {f64, Rem, rem}
+// Operations with `f64` constants on nested duals
+macro_rules! impl_nested {
+    ($op:ident, $fn:ident) => {
+        impl<const N: usize, const M: usize> std::ops::$op<f64> for Dual<N, Dual<M>> {
+            type Output = Self;
+
+            fn $fn(self, other: f64) -> Self::Output {
+                std::ops::$op::$fn(self, Dual::<M>::constant(other))
+            }
+        }
+
+        impl<const N: usize, const M: usize> std::ops::$op<Dual<N, Dual<M>>> for f64 {
+            type Output = Dual<N, Dual<M>>;
+
+            fn $fn(self, other: Dual<N, Dual<M>>) -> Self::Output {
+                <Dual<N, Dual<M>> as std::ops::$op>::$fn(Dual::from(Dual::constant(self)), other)
+            }
+        }
+    };
+}
+
+impl_nested! {Add, add}
+impl_nested! {Sub, sub}
+impl_nested! {Mul, mul}
+impl_nested! {Div, div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+impl_inplace! {RemAssign, rem_assign, Rem, rem}
+
+impl<const N: usize, F: Scalar> crate::Numerical for Dual<N, F> {
+    fn constant(real: f64) -> Self {
+        Dual::constant(F::from_f64(real))
+    }
+
+    fn real(&self) -> f64 {
+        self.real.to_f64()
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        Dual::powf(self, F::from_f64(pow))
+    }
+
+    fn invert(self) -> Self {
+        Dual::invert(self)
+    }
+
+    fn sin(self) -> Self {
+        Dual::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Dual::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        Dual::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dual, Dual1};
+
+    #[test]
+    fn test_ops() {
+        let [x, y] = Dual::<2>::variables([2., 3.]);
+        assert_eq!(x * y, Dual::new(6., [3., 2.]));
+        assert_eq!(1. / x, Dual::new(0.5, [-0.25, 0.]));
+        assert_eq!(y % x, Dual::new(1., [-1., 1.]));
+        assert_eq!(format!("{}", 1. - y), "-2 - 1 eps_1");
+        let [p] = Dual::<3>::variables_at([4.], 2);
+        assert_eq!(p, Dual::new(4., [0., 0., 1.]));
+    }
+
+    #[test]
+    fn test_gradient() {
+        let z = Dual::new(1., [3., 4.]);
+        assert_eq!(z.grad_norm(), 5.);
+        assert_eq!(z.normalize_grad(), Dual::new(1., [0.6, 0.8]));
+        assert_eq!(z.directional_derivative([1., -1.]), -1.);
+        assert_eq!(z.scale_eps_by([2., 0.5]), Dual::new(1., [6., 2.]));
+        assert_eq!(Dual::new(2., [4.]).d_d_rel(0, 3.), 6.);
+        assert_eq!(z.clamp_grad(2.5), Dual::new(1., [1.5, 2.]));
+        assert_eq!(z.clamp_grad(10.), z);
+        assert_eq!(z.clamp_grad_per_component(3.5), Dual::new(1., [3., 3.5]));
+        let clipped = Dual::new(0., [-9., f64::NAN]).clamp_grad_per_component(1.);
+        assert!(clipped.eps[0] == -1. && clipped.eps[1].is_nan());
+    }
+
+    #[test]
+    fn test_nested() {
+        let x = Dual::<1, Dual1>::new(Dual::variable(2., 0), [Dual::constant(1.)]);
+        // d^2/dx^2 x^3 = 6x
+        let y = x.powf(Dual::constant(3.));
+        assert_eq!(y.eps[0].eps[0], 12.);
+        let y = 2. * x.exp() - 1.;
+        assert_eq!(y.eps[0].eps[0], 2. * 2f64.exp());
+    }
+
+    #[test]
+    fn test_single() {
+        let x = Dual1::variable(0., 0);
+        assert_eq!(x.sin(), Dual::new(0., [1.]));
+        assert_eq!(Dual::<1, f32>::variable(2., 0).powf(3.).d_d(0), 12.);
+    }
+
+    #[test]
+    fn test_custom_derivatives() {
+        let [x, y] = Dual::<2>::variables([2.6, -1.]);
+        // Straight-through rounding
+        let r = (x * 2.).with_derivative((x.real * 2.).round(), 1.);
+        assert_eq!(r, Dual::new(5., [2., 0.]));
+        // Black-box x^2 y with its partial derivatives
+        let f = Dual::custom_grad(-6.76, &[(x, -5.2), (y, 6.76)]);
+        let expected = x * x * y;
+        assert!((f.real - expected.real).abs() < 1e-15);
+        assert!(f
+            .eps
+            .iter()
+            .zip(expected.eps)
+            .all(|(a, b)| (a - b).abs() < 1e-14));
+    }
+}
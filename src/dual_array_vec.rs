@@ -0,0 +1,344 @@
+//! # Dynamically sized dual numbers without allocation
+//!
+//! [`DualArrayVec`] combines the runtime component count of
+//! [`DualVec`](crate::DualVec) with inline storage: the dual part is stored in
+//! an array of capacity `MAX`, of which the first `len` components are in use.
+//! It never allocates, making it usable on targets without a heap.
+//!
+//! ```
+//! use epsilon::DualArrayVec;
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7, with room for 4 variables
+//! let x = DualArrayVec::<f64, 4>::variable(5., 0, 2);
+//! let y = DualArrayVec::<f64, 4>::variable(7., 1, 2);
+//!
+//! let z = x.powf(2.) + y * y.sin();
+//!
+//! assert_eq!(z.len(), 2);
+//! assert_eq!(z.d_d(0), 10.);
+//! assert_eq!(z.d_d(1), 5.934302379121921);
+//! ```
+
+use crate::Scalar;
+
+/// Dual number with a runtime number of dual components, up to `MAX`
+///
+/// Like [`DualVec`](crate::DualVec), components past `len` are treated as
+/// zero, and binary operations return a result with as many components as the
+/// longest operand.
+#[derive(Copy, Clone, Debug)]
+pub struct DualArrayVec<F, const MAX: usize> {
+    /// The real value of the dual number
+    pub real: F,
+    len: usize,
+    // Invariant: all components from `len` onwards are zero
+    eps: [F; MAX],
+}
+
+impl<F: Scalar, const MAX: usize> DualArrayVec<F, MAX> {
+    /// Create instance with specified real and dual parts
+    /// Panics if `eps` has more than `MAX` components
+    pub fn new(real: F, eps: &[F]) -> Self {
+        assert!(
+            eps.len() <= MAX,
+            "{} dual components exceed the capacity of {MAX}",
+            eps.len()
+        );
+        let mut v = Self::constant(real);
+        v.eps[..eps.len()].copy_from_slice(eps);
+        v.len = eps.len();
+        v
+    }
+
+    /// Create instance with specified real part and an empty (zero) dual part
+    pub fn constant(real: F) -> Self {
+        DualArrayVec {
+            real,
+            len: 0,
+            eps: [F::ZERO; MAX],
+        }
+    }
+
+    /// Create the `index`th out of `n` variables, having the specified real
+    /// part and a unit dual part in component `index`
+    /// Panics if `n` exceeds `MAX`
+    pub fn variable(real: F, index: usize, n: usize) -> Self {
+        assert!(n <= MAX, "{n} dual components exceed the capacity of {MAX}");
+        assert!(
+            index < n,
+            "variable index {index} out of range for {n} components"
+        );
+        let mut v = Self::constant(real);
+        v.eps[index] = F::ONE;
+        v.len = n;
+        v
+    }
+
+    /// Number of dual components in use
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the dual part is empty, i.e. `self` is a constant
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The dual components in use
+    pub fn eps(&self) -> &[F] {
+        &self.eps[..self.len]
+    }
+
+    /// Derivative with respect to the `index`th component
+    /// Returns zero for components outside of the dual part
+    pub fn d_d(&self, index: usize) -> F {
+        self.eps().get(index).copied().unwrap_or(F::ZERO)
+    }
+
+    /// Apply a function with value `real` and derivative `deriv` at `self.real`
+    fn chain(mut self, real: F, deriv: F) -> Self {
+        self.real = real;
+        for e in &mut self.eps[..self.len] {
+            *e *= deriv;
+        }
+        self
+    }
+
+    /// Combine the dual parts of two numbers, as `a * self.eps + b * other.eps`
+    fn combine(mut self, other: Self, a: F, b: F, real: F) -> Self {
+        self.len = self.len.max(other.len);
+        for (e, &o) in self.eps[..self.len].iter_mut().zip(&other.eps) {
+            *e = a * *e + b * o;
+        }
+        self.real = real;
+        self
+    }
+
+    /// Raise `self` to `pow`
+    pub fn powf(self, pow: F) -> Self {
+        // power rule: d/dx [x^p] = p x^(p-1)
+        let (r, dr) = (self.real.powf(pow), pow * self.real.powf(pow - F::ONE));
+        self.chain(r, dr)
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        self.powf(-F::ONE)
+    }
+
+    pub fn sin(self) -> Self {
+        let (r, dr) = (self.real.sin(), self.real.cos());
+        self.chain(r, dr)
+    }
+
+    pub fn cos(self) -> Self {
+        let (r, dr) = (self.real.cos(), -self.real.sin());
+        self.chain(r, dr)
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::cmp::PartialEq for DualArrayVec<F, MAX> {
+    fn eq(&self, other: &Self) -> bool {
+        // Unused components are zero, so comparing the full arrays is correct
+        self.real == other.real && self.eps == other.eps
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::cmp::PartialOrd for DualArrayVec<F, MAX> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.real.compare(other.real)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::fmt::Display for DualArrayVec<F, MAX> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.real)?;
+        for (i, &v) in self.eps().iter().enumerate() {
+            match v.compare(F::ZERO) {
+                Some(std::cmp::Ordering::Less) => write!(fmt, " - {} eps_{}", -v, i)?,
+                Some(std::cmp::Ordering::Equal) => {}
+                _ => write!(fmt, " + {} eps_{}", v, i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Scalar, const MAX: usize> From<F> for DualArrayVec<F, MAX> {
+    fn from(real: F) -> Self {
+        DualArrayVec::constant(real)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Add<F> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn add(mut self, other: F) -> Self::Output {
+        self.real += other;
+        self
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Add<Self> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let real = self.real + other.real;
+        self.combine(other, F::ONE, F::ONE, real)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Mul<F> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn mul(self, other: F) -> Self::Output {
+        let real = self.real * other;
+        self.chain(real, other)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Mul<Self> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let (a, b) = (other.real, self.real);
+        self.combine(other, a, b, a * b)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Neg for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -F::ONE
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Sub<F> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn sub(self, other: F) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Sub<Self> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Div<F> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    fn div(self, other: F) -> Self::Output {
+        self * (F::ONE / other)
+    }
+}
+
+impl<F: Scalar, const MAX: usize> std::ops::Div<Self> for DualArrayVec<F, MAX> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Self) -> Self::Output {
+        self * other.invert()
+    }
+}
+
+macro_rules! impl_reverse {
+    ($f:ty, $op:ident, $fn:ident) => {
+        impl<const MAX: usize> std::ops::$op<DualArrayVec<$f, MAX>> for $f {
+            type Output = DualArrayVec<$f, MAX>;
+
+            fn $fn(self, other: DualArrayVec<$f, MAX>) -> Self::Output {
+                <DualArrayVec<$f, MAX> as std::ops::$op>::$fn(DualArrayVec::from(self), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl<F: Scalar, const MAX: usize> std::ops::$op_inplace<F> for DualArrayVec<F, MAX> {
+            fn $fn_inplace(&mut self, other: F) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+        impl<F: Scalar, const MAX: usize> std::ops::$op_inplace<Self> for DualArrayVec<F, MAX> {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_reverse! {f32, Add, add}
+impl_reverse! {f32, Sub, sub}
+impl_reverse! {f32, Mul, mul}
+impl_reverse! {f32, Div, div}
+impl_reverse! {f64, Add, add}
+impl_reverse! {f64, Sub, sub}
+impl_reverse! {f64, Mul, mul}
+impl_reverse! {f64, Div, div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+
+impl<F: Scalar, const MAX: usize> crate::Numerical for DualArrayVec<F, MAX> {
+    fn constant(real: f64) -> Self {
+        DualArrayVec::constant(F::from_f64(real))
+    }
+
+    fn real(&self) -> f64 {
+        self.real.to_f64()
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        DualArrayVec::powf(self, F::from_f64(pow))
+    }
+
+    fn invert(self) -> Self {
+        DualArrayVec::invert(self)
+    }
+
+    fn sin(self) -> Self {
+        DualArrayVec::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        DualArrayVec::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        DualArrayVec::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DualArrayVec;
+
+    type D = DualArrayVec<f64, 3>;
+
+    #[test]
+    fn test_mixed_lengths() {
+        let x = D::variable(2., 0, 1);
+        let y = D::variable(3., 1, 2);
+        assert_eq!(x * y, D::new(6., &[3., 2.]));
+        assert_eq!((x * y).len(), 2);
+        assert_eq!(D::constant(1.), D::new(1., &[0., 0.]));
+        assert_eq!(format!("{}", 1. - y), "-2 - 1 eps_1");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_capacity() {
+        D::variable(1., 0, 4);
+    }
+}
@@ -0,0 +1,299 @@
+//! # Dynamically sized dual numbers
+//!
+//! The types generated by [`make_dual`](crate::make_dual) have their
+//! components fixed at compile time. [`DualVec`] instead stores its dual part
+//! in a `Vec`, meaning the number of components can be chosen at runtime, for
+//! example when the variables come from user input.
+//!
+//! ```
+//! use epsilon::DualVec;
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7
+//! let x = DualVec::variable(5., 0, 2);
+//! let y = DualVec::variable(7., 1, 2);
+//!
+//! let z = x.powf(2.) + y.clone() * y.sin();
+//!
+//! assert_eq!(z.d_d(0), 10.);
+//! assert_eq!(z.d_d(1), 5.934302379121921);
+//! ```
+
+/// Dual number with a runtime number of dual components
+///
+/// Components missing from the dual part are treated as zero, meaning that
+/// constants can be created without knowing the number of components. Binary
+/// operations return a result with as many components as the longest operand.
+#[derive(Clone, Debug, Default)]
+pub struct DualVec {
+    /// The real value of the dual number
+    pub real: f64,
+    /// The dual components
+    pub eps: Vec<f64>,
+}
+
+impl DualVec {
+    /// Create instance with specified real and dual parts
+    pub fn new(real: f64, eps: Vec<f64>) -> Self {
+        DualVec { real, eps }
+    }
+
+    /// Create instance with specified real part and an empty (zero) dual part
+    pub fn constant(real: f64) -> Self {
+        DualVec {
+            real,
+            eps: Vec::new(),
+        }
+    }
+
+    /// Create the `index`th out of `n` variables, having the specified real
+    /// part and a unit dual part in component `index`
+    pub fn variable(real: f64, index: usize, n: usize) -> Self {
+        assert!(
+            index < n,
+            "variable index {index} out of range for {n} components"
+        );
+        let mut eps = vec![0.; n];
+        eps[index] = 1.;
+        DualVec { real, eps }
+    }
+
+    /// Derivative with respect to the `index`th component
+    /// Returns zero for components outside of the dual part
+    pub fn d_d(&self, index: usize) -> f64 {
+        self.eps.get(index).copied().unwrap_or(0.)
+    }
+
+    /// Apply a function with value `real` and derivative `deriv` at `self.real`
+    fn chain(mut self, real: f64, deriv: f64) -> Self {
+        self.real = real;
+        for e in &mut self.eps {
+            *e *= deriv;
+        }
+        self
+    }
+
+    /// Combine the dual parts of two numbers, as `a * self.eps + b * other.eps`
+    fn combine(mut self, other: DualVec, a: f64, b: f64, real: f64) -> Self {
+        if self.eps.len() < other.eps.len() {
+            self.eps.resize(other.eps.len(), 0.);
+        }
+        for (i, e) in self.eps.iter_mut().enumerate() {
+            *e = a * *e + b * other.d_d(i);
+        }
+        self.real = real;
+        self
+    }
+
+    /// Raise `self` to `pow`
+    pub fn powf(self, pow: f64) -> Self {
+        // power rule: d/dx [x^p] = p x^(p-1)
+        let (r, dr) = (self.real.powf(pow), pow * self.real.powf(pow - 1.));
+        self.chain(r, dr)
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        self.powf(-1.)
+    }
+
+    pub fn exp(self) -> Self {
+        let r = self.real.exp();
+        self.chain(r, r)
+    }
+
+    /// Natural logarithm
+    pub fn ln(self) -> Self {
+        let (r, dr) = (self.real.ln(), 1. / self.real);
+        self.chain(r, dr)
+    }
+
+    pub fn sin(self) -> Self {
+        let (r, dr) = (self.real.sin(), self.real.cos());
+        self.chain(r, dr)
+    }
+
+    pub fn cos(self) -> Self {
+        let (r, dr) = (self.real.cos(), -self.real.sin());
+        self.chain(r, dr)
+    }
+
+    pub fn tan(self) -> Self {
+        self.clone().sin() / self.cos()
+    }
+}
+
+impl std::cmp::PartialEq for DualVec {
+    fn eq(&self, other: &Self) -> bool {
+        let n = self.eps.len().max(other.eps.len());
+        self.real == other.real && (0..n).all(|i| self.d_d(i) == other.d_d(i))
+    }
+}
+
+impl std::cmp::PartialOrd for DualVec {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.real.partial_cmp(&other.real)
+    }
+}
+
+impl std::fmt::Display for DualVec {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.real)?;
+        for (i, &v) in self.eps.iter().enumerate() {
+            if v < 0. {
+                write!(fmt, " - {} eps_{}", -v, i)?;
+            } else if v > 0. {
+                write!(fmt, " + {} eps_{}", v, i)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<f64> for DualVec {
+    fn from(real: f64) -> Self {
+        DualVec::constant(real)
+    }
+}
+
+impl std::ops::Add<f64> for DualVec {
+    type Output = Self;
+
+    fn add(mut self, other: f64) -> Self::Output {
+        self.real += other;
+        self
+    }
+}
+
+impl std::ops::Add<DualVec> for DualVec {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let real = self.real + other.real;
+        self.combine(other, 1., 1., real)
+    }
+}
+
+impl std::ops::Mul<f64> for DualVec {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self::Output {
+        let real = self.real * other;
+        self.chain(real, other)
+    }
+}
+
+impl std::ops::Mul<DualVec> for DualVec {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let (a, b) = (other.real, self.real);
+        self.combine(other, a, b, a * b)
+    }
+}
+
+impl std::ops::Neg for DualVec {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -1.
+    }
+}
+
+impl std::ops::Sub<f64> for DualVec {
+    type Output = Self;
+
+    fn sub(self, other: f64) -> Self::Output {
+        self + -other
+    }
+}
+
+impl std::ops::Sub<DualVec> for DualVec {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl std::ops::Div<f64> for DualVec {
+    type Output = Self;
+
+    fn div(self, other: f64) -> Self::Output {
+        self * (1. / other)
+    }
+}
+
+impl std::ops::Div<DualVec> for DualVec {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Self) -> Self::Output {
+        self * other.invert()
+    }
+}
+
+macro_rules! impl_reverse {
+    ($op:ident, $fn:ident) => {
+        impl std::ops::$op<DualVec> for f64 {
+            type Output = DualVec;
+
+            fn $fn(self, other: DualVec) -> Self::Output {
+                <DualVec as std::ops::$op>::$fn(DualVec::from(self), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl std::ops::$op_inplace<f64> for DualVec {
+            fn $fn_inplace(&mut self, other: f64) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(std::mem::take(self), other);
+            }
+        }
+        impl std::ops::$op_inplace<DualVec> for DualVec {
+            fn $fn_inplace(&mut self, other: DualVec) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(std::mem::take(self), other);
+            }
+        }
+    };
+}
+
+impl_reverse! {Add, add}
+impl_reverse! {Sub, sub}
+impl_reverse! {Mul, mul}
+impl_reverse! {Div, div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+
+#[cfg(test)]
+mod tests {
+    use super::DualVec;
+
+    #[test]
+    fn test_mixed_lengths() {
+        let x = DualVec::variable(2., 0, 1);
+        let y = DualVec::variable(3., 1, 2);
+        assert_eq!(x.clone() * y.clone(), DualVec::new(6., vec![3., 2.]));
+        assert_eq!(y * x + 1., DualVec::new(7., vec![3., 2.]));
+        assert_eq!(
+            DualVec::constant(1.) - DualVec::variable(1., 0, 1),
+            DualVec::new(0., vec![-1.])
+        );
+        assert_eq!(DualVec::constant(1.), DualVec::new(1., vec![0., 0.]));
+    }
+
+    #[test]
+    fn test_ops() {
+        let x = DualVec::variable(10., 0, 2);
+        assert_eq!(x.clone().invert(), DualVec::new(0.1, vec![-0.01, 0.]));
+        assert_eq!(format!("{}", x.clone().invert()), "0.1 - 0.01 eps_0");
+        assert_eq!(3. - x.clone(), DualVec::new(-7., vec![-1., 0.]));
+
+        let mut v = x;
+        v *= 2.;
+        v += 1.;
+        assert_eq!(v, DualVec::new(21., vec![2., 0.]));
+    }
+}
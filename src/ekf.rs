@@ -0,0 +1,110 @@
+//! # Extended Kalman filter linearization
+//!
+//! An extended Kalman filter propagates the state covariance through the
+//! Jacobians `F` of the state transition and `H` of the measurement model.
+//! [`linearize`] computes either from the model written against [`Dual`], so
+//! they never have to be derived by hand. With the `nalgebra` feature, the
+//! results can be converted to `nalgebra` matrices for the filter update.
+//!
+//! ```
+//! use epsilon::ekf;
+//! // Pendulum with angle and angular velocity, observed through sin(angle)
+//! let dt = 0.01;
+//! let x = [0.5, 0.];
+//! let predict = ekf::linearize(|[a, w]| [a + dt * w, w - dt * 9.81 * a.sin()], x);
+//! let measure = ekf::linearize(|[a, _]| [a.sin()], predict.value);
+//!
+//! assert_eq!(predict.jacobian, [[1., dt], [-dt * 9.81 * 0.5f64.cos(), 1.]]);
+//! assert_eq!(measure.jacobian, [[0.5f64.cos(), 0.]]);
+//! ```
+
+use crate::Dual;
+
+/// Value and Jacobian of a model at a state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Linearization<const N: usize, const M: usize> {
+    /// Output of the model, i.e. the predicted state or measurement
+    pub value: [f64; M],
+    /// Jacobian of the output with respect to the state, `F` or `H`
+    pub jacobian: [[f64; N]; M],
+}
+
+/// Evaluate the state transition or measurement model `f` at the state `x`,
+/// along with its Jacobian
+///
+/// Inputs other than the state, such as controls or the time step, can be
+/// captured by the closure.
+pub fn linearize<const N: usize, const M: usize>(
+    f: impl FnOnce([Dual<N>; N]) -> [Dual<N>; M],
+    x: [f64; N],
+) -> Linearization<N, M> {
+    let y = f(Dual::variables(x));
+    Linearization {
+        value: y.map(|y| y.real),
+        jacobian: y.map(|y| y.eps),
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize, const M: usize> Linearization<N, M> {
+    /// The output as a `nalgebra` column vector
+    pub fn value_vector(&self) -> nalgebra::SVector<f64, M> {
+        nalgebra::SVector::from(self.value)
+    }
+
+    /// The Jacobian as a `nalgebra` matrix with `M` rows and `N` columns
+    pub fn jacobian_matrix(&self) -> nalgebra::SMatrix<f64, M, N> {
+        nalgebra::SMatrix::from_fn(|i, j| self.jacobian[i][j])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::linearize;
+
+    #[test]
+    fn test_linearize() {
+        // Unicycle moving at speed v with heading h, observed by squared range
+        let (v, dt) = (2., 0.1);
+        let x = [3., 4., 0.5];
+        let f =
+            |[px, py, h]: [crate::Dual<3>; 3]| [px + dt * v * h.cos(), py + dt * v * h.sin(), h];
+        let predict = linearize(f, x);
+        assert_eq!(predict.value[2], 0.5);
+        assert_eq!(
+            predict.jacobian,
+            [
+                [1., 0., -dt * v * 0.5f64.sin()],
+                [0., 1., dt * v * 0.5f64.cos()],
+                [0., 0., 1.]
+            ]
+        );
+
+        let range = linearize(|[px, py, _]| [px * px + py * py], [3., 4., 0.]);
+        assert_eq!(range.value, [25.]);
+        assert_eq!(range.jacobian, [[6., 8., 0.]]);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_nalgebra_update() {
+        use nalgebra::{Matrix1, Matrix2, Vector1};
+
+        // Scalar random walk observed through z = x0^2, with a velocity state
+        let x = [1., 0.5];
+        let p = Matrix2::identity();
+        let predict = linearize(|[a, b]| [a + b, b], x);
+        let f = predict.jacobian_matrix();
+        let p = f * p * f.transpose() + Matrix2::identity() * 0.01;
+
+        let measure = linearize(|[a, _]| [a * a], predict.value);
+        let h = measure.jacobian_matrix();
+        assert_eq!(h, nalgebra::Matrix1x2::new(3., 0.));
+        let s = h * p * h.transpose() + Matrix1::new(0.1);
+        let k = p * h.transpose() * s.try_inverse().unwrap();
+        let z = Vector1::new(2.); // measured
+        let x = predict.value_vector() + k * (z - measure.value_vector());
+        // The measurement pulls the position down towards sqrt(2)
+        assert!(x[0] < 1.5 && x[0] > 2f64.sqrt() - 0.1);
+    }
+}
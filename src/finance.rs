@@ -0,0 +1,156 @@
+//! # Option pricing
+//!
+//! The Black-Scholes price of European options, generic over [`Scalar`].
+//! Evaluated on dual numbers seeded in the spot price, volatility, rate and
+//! time to expiry, the derivatives of the price are the Greeks, which
+//! [`greeks`] computes including the second derivative gamma.
+//!
+//! ```
+//! use epsilon::finance::{greeks, Kind};
+//! let g = greeks(Kind::Call, 100., 100., 0.2, 0.05, 1.);
+//!
+//! assert!((g.price - 10.450583572185565).abs() < 1e-12);
+//! assert!((g.delta - 0.6368306511756191).abs() < 1e-14);
+//! ```
+
+use crate::special::normal_cdf;
+use crate::{Dual, HyperDual, Scalar};
+
+/// Right given by an option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// The right to buy at the strike price
+    Call,
+    /// The right to sell at the strike price
+    Put,
+}
+
+/// Black-Scholes price of a European option on an asset without dividends
+///
+/// `vol` is the annualized volatility, `rate` the continuously compounded
+/// risk-free rate and `time` the time to expiry in years.
+pub fn black_scholes<T: Scalar>(kind: Kind, spot: T, strike: T, vol: T, rate: T, time: T) -> T {
+    let vol_time = vol * time.powf(T::from_f64(0.5));
+    let d1 = ((spot / strike).ln() + (rate + vol * vol * T::from_f64(0.5)) * time) / vol_time;
+    let d2 = d1 - vol_time;
+    let discounted = strike * (-rate * time).exp();
+    match kind {
+        Kind::Call => spot * normal_cdf(d1) - discounted * normal_cdf(d2),
+        Kind::Put => discounted * normal_cdf(-d2) - spot * normal_cdf(-d1),
+    }
+}
+
+/// Price of an option and its sensitivities, see [`greeks`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Greeks {
+    pub price: f64,
+    /// Derivative with respect to the spot price
+    pub delta: f64,
+    /// Second derivative with respect to the spot price
+    pub gamma: f64,
+    /// Derivative with respect to the volatility
+    pub vega: f64,
+    /// Derivative with respect to the rate
+    pub rho: f64,
+    /// Derivative with respect to the passage of time, i.e. the negative
+    /// derivative with respect to the time to expiry
+    pub theta: f64,
+}
+
+/// [`black_scholes`] price and Greeks of an option, from a single evaluation
+/// on nested dual numbers
+pub fn greeks(kind: Kind, spot: f64, strike: f64, vol: f64, rate: f64, time: f64) -> Greeks {
+    // Seeded as in `hessian`, with the first derivatives in the inner part
+    let mut i = 0;
+    let [spot, vol, rate, time]: [HyperDual<4>; 4] =
+        Dual::variables([spot, vol, rate, time]).map(|inner| {
+            i += 1;
+            Dual::variable(inner, i - 1)
+        });
+    let price = black_scholes(kind, spot, Dual::from_f64(strike), vol, rate, time);
+    Greeks {
+        price: price.real.real,
+        delta: price.real.eps[0],
+        gamma: price.eps[0].eps[0],
+        vega: price.real.eps[1],
+        rho: price.real.eps[2],
+        theta: -price.real.eps[3],
+    }
+}
+
+/// An option in a chain, see [`chain`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contract {
+    pub kind: Kind,
+    pub strike: f64,
+    /// Time to expiry in years
+    pub time: f64,
+    /// Implied volatility of the contract
+    pub vol: f64,
+}
+
+/// [`greeks`] of each option in a chain on the same underlying
+pub fn chain(contracts: &[Contract], spot: f64, rate: f64) -> Vec<Greeks> {
+    contracts
+        .iter()
+        .map(|c| greeks(c.kind, spot, c.strike, c.vol, rate, c.time))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price() {
+        let call: f64 = black_scholes(Kind::Call, 100., 100., 0.2, 0.05, 1.);
+        let put: f64 = black_scholes(Kind::Put, 100., 100., 0.2, 0.05, 1.);
+        assert!((call - 10.450583572185565).abs() < 1e-12);
+        assert!((put - 5.573526022256971).abs() < 1e-12);
+        // Put-call parity, C - P = S - K exp(-r T)
+        let (call, put) = (
+            black_scholes(Kind::Call, 90., 120., 0.35, 0.02, 0.25),
+            black_scholes(Kind::Put, 90., 120., 0.35, 0.02, 0.25),
+        );
+        assert!((call - put - (90. - 120. * (-0.005f64).exp())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_greeks() {
+        // Closed forms with d1 = 0.35, d2 = 0.15
+        let phi = |x: f64| (-x * x / 2.).exp() / (2. * std::f64::consts::PI).sqrt();
+        let cdf = |x: f64| 0.5 * crate::special::erfc(-x / 2f64.sqrt());
+        let g = greeks(Kind::Call, 100., 100., 0.2, 0.05, 1.);
+        let discount = (-0.05f64).exp();
+        let expected = [
+            (g.delta, cdf(0.35)),
+            (g.gamma, phi(0.35) / 20.),
+            (g.vega, 100. * phi(0.35)),
+            (g.rho, 100. * discount * cdf(0.15)),
+            (g.theta, -100. * phi(0.35) * 0.1 - 5. * discount * cdf(0.15)),
+        ];
+        for (value, e) in expected {
+            assert!((value - e).abs() < 1e-12);
+        }
+
+        // Put delta is call delta minus one
+        let contracts = [
+            Contract {
+                kind: Kind::Call,
+                strike: 100.,
+                time: 1.,
+                vol: 0.2,
+            },
+            Contract {
+                kind: Kind::Put,
+                strike: 100.,
+                time: 1.,
+                vol: 0.2,
+            },
+        ];
+        let greeks = chain(&contracts, 100., 0.05);
+        assert_eq!(greeks[0], g);
+        assert!((greeks[1].delta - (g.delta - 1.)).abs() < 1e-14);
+        assert!((greeks[1].gamma - g.gamma).abs() < 1e-14);
+    }
+}
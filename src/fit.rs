@@ -0,0 +1,304 @@
+//! # Nonlinear least squares
+//!
+//! Fitting the parameters of a model `y = f(x, params)` to data by
+//! minimizing the sum of squared residuals. The model is written against
+//! [`Dual`], seeded with one component per parameter, so the Jacobian of the
+//! residuals is computed alongside their values. Before trusting a fit,
+//! [`conditioning`] reveals parameters which the data cannot determine.
+//!
+//! ```
+//! use epsilon::fit;
+//! // Exponential decay y = a exp(-k x)
+//! let xs = [0., 1., 2., 3., 4.];
+//! let ys = xs.map(|x: f64| 2. * (-0.5 * x).exp());
+//! let result = fit::curve_fit(|x, [a, k]| a * (-k * x).exp(), &xs, &ys, [1., 1.], 1e-12, 100);
+//!
+//! assert!(result.converged);
+//! assert!((result.params[0] - 2.).abs() < 1e-10);
+//! assert!((result.params[1] - 0.5).abs() < 1e-10);
+//! ```
+
+use crate::matrix::Matrix;
+use crate::solve::{LinearSolver, Lu};
+use crate::Dual;
+
+/// Result of a fit, along with diagnostics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fit<const P: usize> {
+    /// The fitted parameters
+    pub params: [f64; P],
+    /// Estimated covariance of the parameters, `s^2 (J^T J)^-1` with the
+    /// residual variance `s^2 = sum_squares / (n - P)`
+    ///
+    /// Infinite if there are no more data points than parameters, and `NaN`
+    /// if `J^T J` is singular at the solution.
+    pub covariance: [[f64; P]; P],
+    /// Sum of squared residuals at the solution
+    pub sum_squares: f64,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// Whether the gradient or the step became negligible
+    pub converged: bool,
+}
+
+/// Residuals, `J^T J` and `J^T r` at `params`
+fn normal_equations<X: Copy, const P: usize>(
+    model: &impl Fn(X, [Dual<P>; P]) -> Dual<P>,
+    xs: &[X],
+    ys: &[f64],
+    params: [f64; P],
+) -> (f64, [[f64; P]; P], [f64; P]) {
+    let vars = Dual::variables(params);
+    let mut jtj = [[0.; P]; P];
+    let mut jtr = [0.; P];
+    let mut sum_squares = 0.;
+    for (&x, &y) in xs.iter().zip(ys) {
+        let fx = model(x, vars);
+        let r = fx.real - y;
+        sum_squares += r * r;
+        for ((row, g), &ei) in jtj.iter_mut().zip(&mut jtr).zip(&fx.eps) {
+            *g += ei * r;
+            for (a, &ej) in row.iter_mut().zip(&fx.eps) {
+                *a += ei * ej;
+            }
+        }
+    }
+    (sum_squares, jtj, jtr)
+}
+
+/// Sum of squared residuals at `params`, without derivatives
+fn sum_squares<X: Copy, const P: usize>(
+    model: &impl Fn(X, [Dual<P>; P]) -> Dual<P>,
+    xs: &[X],
+    ys: &[f64],
+    params: [f64; P],
+) -> f64 {
+    let consts = params.map(Dual::constant);
+    xs.iter()
+        .zip(ys)
+        .map(|(&x, &y)| (model(x, consts).real - y).powi(2))
+        .sum()
+}
+
+/// Fit `model` to the data points `(xs[i], ys[i])` using the
+/// Levenberg-Marquardt method, starting at the parameters `p0`
+///
+/// Each step solves `(J^T J + lambda diag(J^T J)) step = -J^T r`, where the
+/// damping `lambda` is decreased after steps reducing the sum of squares,
+/// approaching Gauss-Newton steps, and increased otherwise, approaching
+/// small gradient descent steps. Iterates until the largest component of the
+/// gradient `J^T r` is at most `tol`, or the step is smaller than `tol`
+/// relative to the parameters, for at most `max_iter` iterations.
+///
+/// # Panics
+///
+/// If `xs` and `ys` have different lengths.
+pub fn curve_fit<X: Copy, const P: usize>(
+    model: impl Fn(X, [Dual<P>; P]) -> Dual<P>,
+    xs: &[X],
+    ys: &[f64],
+    p0: [f64; P],
+    tol: f64,
+    max_iter: usize,
+) -> Fit<P> {
+    const MAX_LAMBDA: f64 = 1e16;
+    assert_eq!(xs.len(), ys.len(), "xs and ys must have the same length");
+
+    let mut params = p0;
+    let mut lambda = 1e-3;
+    let mut iterations = 0;
+    let (mut cost, mut jtj, mut jtr) = normal_equations(&model, xs, ys, params);
+    let converged = loop {
+        let grad = jtr.iter().fold(0., |m: f64, g| m.max(g.abs()));
+        if grad <= tol {
+            break true;
+        }
+        if iterations == max_iter {
+            break false;
+        }
+        iterations += 1;
+
+        // Increase the damping until the step reduces the sum of squares
+        let step = loop {
+            let mut a = jtj;
+            for (i, row) in a.iter_mut().enumerate() {
+                row[i] += lambda * jtj[i][i].max(f64::MIN_POSITIVE);
+            }
+            let step = Lu.solve(a, jtr.map(|g| -g));
+            let trial = step.map(|step| std::array::from_fn(|i| params[i] + step[i]));
+            match trial {
+                Some(trial) if sum_squares(&model, xs, ys, trial) < cost => {
+                    lambda = (lambda / 10.).max(1e-12);
+                    break Some((trial, step.unwrap()));
+                }
+                _ if lambda < MAX_LAMBDA => lambda *= 10.,
+                _ => break None,
+            }
+        };
+        // Even the smallest steps don't reduce the sum of squares, so the
+        // parameters are at a minimum up to rounding
+        let Some((trial, step)) = step else {
+            break cost.is_finite();
+        };
+        params = trial;
+        (cost, jtj, jtr) = normal_equations(&model, xs, ys, params);
+
+        let small = step
+            .iter()
+            .zip(&params)
+            .all(|(s, p)| s.abs() <= tol * (p.abs() + tol));
+        if small {
+            break true;
+        }
+    };
+
+    let dof = xs.len() as f64 - P as f64;
+    let variance = if dof > 0. { cost / dof } else { f64::INFINITY };
+    let mut covariance = [[f64::NAN; P]; P];
+    for j in 0..P {
+        let mut e = [0.; P];
+        e[j] = 1.;
+        if let Some(column) = Lu.solve(jtj, e) {
+            for (row, c) in covariance.iter_mut().zip(column) {
+                row[j] = variance * c;
+            }
+        }
+    }
+
+    Fit {
+        params,
+        covariance,
+        sum_squares: cost,
+        iterations,
+        converged,
+    }
+}
+
+/// Conditioning of a Jacobian, see [`conditioning`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conditioning<const P: usize> {
+    /// Singular values in decreasing order
+    pub singular_values: [f64; P],
+    /// Ratio of the largest to the smallest singular value, infinite if the
+    /// smallest is zero
+    pub condition_number: f64,
+    /// Number of singular values above the tolerance relative to the largest
+    pub rank: usize,
+    /// Euclidean norm of each column, the sensitivity of the residuals to
+    /// each parameter on its own
+    pub column_norms: [f64; P],
+    /// Parameters which are nearly unidentifiable, in increasing order
+    pub unidentifiable: Vec<usize>,
+}
+
+/// Conditioning diagnostics of the Jacobian of residuals with respect to `P`
+/// parameters, with one row per residual, e.g. as computed by
+/// [`jacobian`](crate::jacobian)
+///
+/// Singular values at most `tol` times the largest are treated as zero. A
+/// parameter is flagged as unidentifiable if more than a tenth of its squared
+/// unit vector lies in the span of the corresponding right singular vectors,
+/// i.e. if it can be changed, together with other parameters, without
+/// changing the residuals to first order. The singular values are computed
+/// from `J^T J`, so those below about `1e-8` times the largest are at the
+/// level of rounding.
+pub fn conditioning<const P: usize>(jacobian: &[[f64; P]], tol: f64) -> Conditioning<P> {
+    let mut jtj = [[0.; P]; P];
+    for row in jacobian {
+        for (a, &ei) in jtj.iter_mut().zip(row) {
+            for (a, &ej) in a.iter_mut().zip(row) {
+                *a += ei * ej;
+            }
+        }
+    }
+    let eigen = Matrix::new(jtj).symmetric_eigen();
+    let singular_values: [f64; P] = std::array::from_fn(|i| eigen.values[P - 1 - i].max(0.).sqrt());
+    let largest = singular_values.first().copied().unwrap_or(0.);
+    let smallest = singular_values.last().copied().unwrap_or(0.);
+    let rank = singular_values
+        .iter()
+        .filter(|&&s| s > tol * largest)
+        .count();
+
+    // The null space is spanned by the eigenvectors of the P - rank smallest
+    // eigenvalues, the first columns
+    let vectors = eigen.vectors.rows;
+    let unidentifiable = (0..P)
+        .filter(|&i| {
+            let weight: f64 = vectors[i][..P - rank].iter().map(|v| v * v).sum();
+            weight > 0.1
+        })
+        .collect();
+
+    Conditioning {
+        singular_values,
+        condition_number: largest / smallest,
+        rank,
+        column_norms: std::array::from_fn(|i| jtj[i][i].sqrt()),
+        unidentifiable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conditioning, curve_fit};
+
+    #[test]
+    fn test_linear() {
+        // Ordinary least squares for a line has a closed-form covariance
+        let xs = [0., 1., 2., 3.];
+        let ys = [1.1, 2.9, 5.2, 6.8];
+        let fit = curve_fit(|x, [a, b]| a + b * x, &xs, &ys, [0., 0.], 1e-12, 20);
+        assert!(fit.converged);
+        assert!((fit.params[0] - 1.09).abs() < 1e-9);
+        assert!((fit.params[1] - 1.94).abs() < 1e-9);
+        assert!((fit.sum_squares - 0.082).abs() < 1e-12);
+
+        // s^2 (X^T X)^-1 with X^T X = [[4, 6], [6, 14]]
+        let s2 = 0.082 / 2.;
+        let expected = [[14. / 20., -6. / 20.], [-6. / 20., 4. / 20.]];
+        for (row, expected) in fit.covariance.iter().zip(expected) {
+            for (c, e) in row.iter().zip(expected) {
+                assert!((c - s2 * e).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nonlinear() {
+        // Gaussian peak in two-dimensional data, starting far from the peak
+        let xs: Vec<[f64; 2]> = (0..25).map(|i| [(i % 5) as f64, (i / 5) as f64]).collect();
+        let peak = |[x, y]: [f64; 2]| 3. * (-((x - 1.5).powi(2) + (y - 2.5).powi(2)) / 2.).exp();
+        let ys: Vec<f64> = xs.iter().map(|&x| peak(x)).collect();
+        let model = |[x, y]: [f64; 2], [a, cx, cy]: [crate::Dual<3>; 3]| {
+            a * (((x - cx) * (x - cx) + (y - cy) * (y - cy)) * -0.5).exp()
+        };
+        let fit = curve_fit(model, &xs, &ys, [1., 0., 0.], 1e-12, 100);
+        assert!(fit.converged);
+        for (p, e) in fit.params.iter().zip([3., 1.5, 2.5]) {
+            assert!((p - e).abs() < 1e-8);
+        }
+        assert!(fit.sum_squares < 1e-20);
+    }
+
+    #[test]
+    fn test_conditioning() {
+        // The parameters b and c only enter as b + c
+        let xs = [0., 1., 2., 3.];
+        let jacobian: Vec<[f64; 3]> = xs.iter().map(|&x| [1., x, x]).collect();
+        let diagnostics = conditioning(&jacobian, 1e-10);
+        assert_eq!(diagnostics.rank, 2);
+        assert!(diagnostics.condition_number > 1e7);
+        assert_eq!(diagnostics.unidentifiable, [1, 2]);
+        assert!((diagnostics.column_norms[1] - 14f64.sqrt()).abs() < 1e-14);
+
+        // A well-posed line fit
+        let jacobian: Vec<[f64; 2]> = xs.iter().map(|&x| [1., x]).collect();
+        let diagnostics = conditioning(&jacobian, 1e-10);
+        assert_eq!(diagnostics.rank, 2);
+        assert!(diagnostics.unidentifiable.is_empty());
+        // Eigenvalues of [[4, 6], [6, 14]] are 9 +- sqrt(61)
+        let expected = ((9. + 61f64.sqrt()) / (9. - 61f64.sqrt())).sqrt();
+        assert!((diagnostics.condition_number - expected).abs() < 1e-12);
+    }
+}
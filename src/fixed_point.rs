@@ -0,0 +1,128 @@
+//! # Fixed-point inner types
+//!
+//! With the `fixed` feature, the signed fixed-point types of the [`fixed`]
+//! crate (such as `I32F32`) implement [`Scalar`], and can be used as the inner
+//! type of [`make_dual`](crate::make_dual), e.g. on targets without an FPU.
+//!
+//! The trigonometric functions are computed using the CORDIC algorithm from
+//! the [`cordic`] crate, and `invert` and division between duals only use
+//! fixed-point arithmetic. `powf`, `exp` and `ln` have no CORDIC
+//! implementation, and are evaluated in (possibly soft-float) `f64`. Values
+//! out of range saturate, and NaN (e.g. the logarithm of a negative number)
+//! becomes zero. To route the math functions through a
+//! different implementation, wrap the fixed-point type in a newtype and
+//! implement [`Scalar`] for it.
+//!
+//! ```
+//! use epsilon::make_dual;
+//! use fixed::types::I32F32;
+//!
+//! make_dual! { FixDual: I32F32, x }
+//!
+//! let x = FixDual::x(I32F32::from_num(3));
+//! let y = x * x + x * I32F32::from_num(2);
+//! assert_eq!(y.d_dx(), I32F32::from_num(8));
+//! ```
+
+use crate::Scalar;
+use fixed::types::extra::{LeEqU16, LeEqU32, LeEqU64};
+use fixed::{FixedI16, FixedI32, FixedI64};
+
+macro_rules! impl_scalar_fixed {
+    ($t:ident, $le_eq:ident) => {
+        impl<Frac: $le_eq> Scalar for $t<Frac>
+        where
+            $t<Frac>: cordic::CordicNumber,
+        {
+            const ZERO: Self = Self::ZERO;
+            const ONE: Self = match <Self as fixed::traits::Fixed>::TRY_ONE {
+                Some(one) => one,
+                None => panic!("fixed-point type cannot represent one"),
+            };
+
+            fn from_f64(value: f64) -> Self {
+                if value.is_nan() {
+                    return Self::ZERO;
+                }
+                Self::saturating_from_num(value)
+            }
+
+            fn to_f64(self) -> f64 {
+                self.to_num()
+            }
+
+            fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(&other))
+            }
+
+            fn powf(self, pow: Self) -> Self {
+                Self::from_f64(self.to_f64().powf(pow.to_f64()))
+            }
+
+            fn exp(self) -> Self {
+                Self::from_f64(self.to_f64().exp())
+            }
+
+            fn ln(self) -> Self {
+                Self::from_f64(self.to_f64().ln())
+            }
+
+            fn sin(self) -> Self {
+                cordic::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                cordic::cos(self)
+            }
+
+            fn trunc(self) -> Self {
+                self.round_to_zero()
+            }
+        }
+    };
+}
+
+impl_scalar_fixed! {FixedI16, LeEqU16}
+impl_scalar_fixed! {FixedI32, LeEqU32}
+impl_scalar_fixed! {FixedI64, LeEqU64}
+
+#[cfg(test)]
+mod tests {
+    use fixed::types::{I16F16, I32F32};
+
+    crate::make_dual! { FixXY: I32F32, x, y }
+    crate::make_dual! { SmallFix: I16F16, t }
+
+    #[test]
+    fn test_fixed_ops() {
+        let x = FixXY::x(I32F32::from_num(1.5));
+        let y = FixXY::y(I32F32::from_num(-2));
+        let z = x * y - y * I32F32::from_num(4);
+        assert_eq!(z.real, I32F32::from_num(5));
+        assert_eq!(z.d_dx(), I32F32::from_num(-2));
+        assert_eq!(z.d_dy(), I32F32::from_num(-2.5));
+        assert_eq!(format!("{}", z), "5 - 2 eps_x - 2.5 eps_y");
+
+        // x / y = -0.75, d/dx = 1 / y, d/dy = -x / y^2
+        let q = x / y;
+        assert_eq!(q.real, I32F32::from_num(-0.75));
+        assert_eq!(q.d_dx(), I32F32::from_num(-0.5));
+        assert_eq!(q.d_dy(), I32F32::from_num(-0.375));
+        assert_eq!(y.invert().d_dy(), I32F32::from_num(-0.25));
+    }
+
+    #[test]
+    fn test_fixed_nan() {
+        use crate::Scalar;
+        assert_eq!(I32F32::from_f64(f64::NAN), I32F32::ZERO);
+        assert_eq!(I32F32::from_f64(f64::INFINITY), I32F32::MAX);
+        assert_eq!(Scalar::ln(I32F32::from_num(-1)), I32F32::ZERO);
+    }
+
+    #[test]
+    fn test_fixed_trig() {
+        let t = SmallFix::t(I16F16::from_num(0.5)).sin();
+        assert!((t.real.to_num::<f64>() - 0.5f64.sin()).abs() < 1e-3);
+        assert!((t.d_dt().to_num::<f64>() - 0.5f64.cos()).abs() < 1e-3);
+    }
+}
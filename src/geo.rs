@@ -0,0 +1,120 @@
+//! # Great-circle distances
+//!
+//! Distances between points on a sphere given by their latitude and
+//! longitude in radians, generic over [`Scalar`]. With dual numbers as the
+//! coordinates, the derivatives of the distances with respect to them are
+//! propagated, e.g. to calibrate positions against measured distances.
+//!
+//! ```
+//! use epsilon::geo::{vincenty, EARTH_RADIUS};
+//! use epsilon::Dual;
+//! // Paris to New York, with the derivative with respect to the latitude of Paris
+//! let [lat] = Dual::<1>::variables([48.8566f64.to_radians()]);
+//! let lon = Dual::constant(2.3522f64.to_radians());
+//! let ny = [40.7128f64.to_radians(), -74.006f64.to_radians()].map(Dual::constant);
+//! let d = vincenty(lat, lon, ny[0], ny[1], EARTH_RADIUS);
+//!
+//! assert!((d.real / 1000. - 5837.).abs() < 1.);
+//! // The route starts towards the northwest, so moving Paris north shortens it
+//! assert!(d.eps[0] < 0. && d.eps[0] > -EARTH_RADIUS);
+//! ```
+
+use crate::angle::atan2;
+use crate::Scalar;
+
+/// Mean radius of the Earth in meters
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+fn sqrt<T: Scalar>(x: T) -> T {
+    x.powf(T::from_f64(0.5))
+}
+
+/// Great-circle distance on a sphere of radius `radius` by the haversine
+/// formula
+///
+/// Accurate for nearby points, but loses precision close to antipodal
+/// points, where [`vincenty`] should be used instead. The derivatives are
+/// not finite where the points coincide or are antipodal, where the distance
+/// is not differentiable.
+pub fn haversine<T: Scalar>(lat1: T, lon1: T, lat2: T, lon2: T, radius: f64) -> T {
+    let half = T::from_f64(0.5);
+    let sin_lat = ((lat2 - lat1) * half).sin();
+    let sin_lon = ((lon2 - lon1) * half).sin();
+    let h = sin_lat * sin_lat + lat1.cos() * lat2.cos() * sin_lon * sin_lon;
+    // 2 asin(sqrt(h)), with h rounded above one clamped
+    let h = if h.to_f64() > 1. { T::ONE } else { h };
+    atan2(sqrt(h), sqrt(T::ONE - h)) * T::from_f64(2. * radius)
+}
+
+/// Angle at the center of the sphere between two points, by the special case
+/// of Vincenty's formula for a sphere
+///
+/// Well-conditioned for all pairs of points, including nearly antipodal
+/// ones.
+pub fn central_angle<T: Scalar>(lat1: T, lon1: T, lat2: T, lon2: T) -> T {
+    let (sin1, cos1) = (lat1.sin(), lat1.cos());
+    let (sin2, cos2) = (lat2.sin(), lat2.cos());
+    let dlon = lon2 - lon1;
+    let (sin_dlon, cos_dlon) = (dlon.sin(), dlon.cos());
+    let x = cos2 * sin_dlon;
+    let y = cos1 * sin2 - sin1 * cos2 * cos_dlon;
+    atan2(sqrt(x * x + y * y), sin1 * sin2 + cos1 * cos2 * cos_dlon)
+}
+
+/// Great-circle distance on a sphere of radius `radius`, by
+/// [`central_angle`]
+///
+/// The derivatives are not finite where the points coincide or are exactly
+/// antipodal, where the distance is not differentiable.
+pub fn vincenty<T: Scalar>(lat1: T, lon1: T, lat2: T, lon2: T, radius: f64) -> T {
+    central_angle(lat1, lon1, lat2, lon2) * T::from_f64(radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dual;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn test_distances() {
+        // A quarter of the equator, and from the equator to the pole
+        assert!((vincenty(0., 0., 0., FRAC_PI_2, 1.) - FRAC_PI_2).abs() < 1e-15);
+        assert!((haversine(0., 1., FRAC_PI_2, 0., 1.) - FRAC_PI_2).abs() < 1e-15);
+        assert_eq!(vincenty(0.3, 0.2, 0.3, 0.2, 1.), 0.);
+
+        // Nearly antipodal points, where the haversine formula is inaccurate
+        let d = vincenty(0.5, 0., -0.5, PI - 1e-9, 1.);
+        let naive: f64 = haversine(0.5, 0., -0.5, PI - 1e-9, 1.);
+        assert!((d - (PI - 1e-9 * 0.5f64.cos())).abs() < 1e-15);
+        assert!((naive - d).abs() > 1e-12);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let x = [0.3, -1.2, -0.4, 2.];
+        let at = |x: [f64; 4], f: fn(f64, f64, f64, f64, f64) -> f64| f(x[0], x[1], x[2], x[3], 1.);
+        for f in [haversine::<Dual<4>>, vincenty::<Dual<4>>] {
+            let [a, b, c, d] = Dual::<4>::variables(x);
+            let y = f(a, b, c, d, 1.);
+            for (i, e) in y.eps.iter().enumerate() {
+                let (mut lo, mut hi) = (x, x);
+                lo[i] -= 1e-6;
+                hi[i] += 1e-6;
+                let numerical = (at(hi, vincenty) - at(lo, vincenty)) / 2e-6;
+                assert!((e - numerical).abs() < 1e-8);
+            }
+        }
+
+        // Finite derivatives close to the antipode
+        let [lon] = Dual::<1>::variables([PI - 1e-6]);
+        let d = vincenty(
+            Dual::constant(0.5),
+            Dual::constant(0.),
+            Dual::constant(-0.5),
+            lon,
+            1.,
+        );
+        assert!(d.eps[0].is_finite() && d.eps[0] > 0.);
+    }
+}
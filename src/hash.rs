@@ -0,0 +1,125 @@
+//! # Hashing dual numbers
+//!
+//! Dual numbers only implement [`PartialEq`], comparing their real parts,
+//! like the floats they are built from. [`ByBits`] wraps a dual number to
+//! implement [`Eq`] and [`Hash`] by the bit patterns of all its components,
+//! so that it can key a memoization cache.
+//!
+//! ```
+//! use epsilon::hash::ByBits;
+//! use epsilon::Dual;
+//! use std::collections::HashMap;
+//!
+//! let mut cache = HashMap::new();
+//! let x = Dual::<1>::variable(2., 0);
+//! let y = *cache.entry(ByBits(x)).or_insert_with(|| x.exp());
+//! assert_eq!(cache[&ByBits(x)], y);
+//! // Same real part, different derivative
+//! assert!(!cache.contains_key(&ByBits(Dual::constant(2.))));
+//! ```
+//!
+//! Two values are equal exactly if all their components have the same bits,
+//! except that all `NaN`s are treated as the same canonical `NaN`, so that
+//! a `NaN` result can be cached. Positive and negative zero are different,
+//! since e.g. their reciprocals differ.
+
+use crate::{Dual, DualNumber};
+use std::hash::{Hash, Hasher};
+
+/// The bit patterns of the components of a number, with `NaN` canonicalized
+pub trait CanonicalBits {
+    /// Append the bit patterns of the real part and then of the dual
+    /// components to `out`
+    fn canonical_bits(&self, out: &mut Vec<u64>);
+}
+
+impl CanonicalBits for f64 {
+    fn canonical_bits(&self, out: &mut Vec<u64>) {
+        let x = if self.is_nan() { f64::NAN } else { *self };
+        out.push(x.to_bits());
+    }
+}
+
+impl CanonicalBits for f32 {
+    fn canonical_bits(&self, out: &mut Vec<u64>) {
+        let x = if self.is_nan() { f32::NAN } else { *self };
+        out.push(x.to_bits() as u64);
+    }
+}
+
+/// Nested dual numbers contribute the bits of all their components
+impl<const N: usize, F: CanonicalBits> CanonicalBits for Dual<N, F> {
+    fn canonical_bits(&self, out: &mut Vec<u64>) {
+        self.real.canonical_bits(out);
+        for e in &self.eps {
+            e.canonical_bits(out);
+        }
+    }
+}
+
+/// The components are converted to `f64`, which is exact for `f32` and `f64`
+/// inner types
+impl<T: DualNumber> CanonicalBits for T {
+    fn canonical_bits(&self, out: &mut Vec<u64>) {
+        self.real().canonical_bits(out);
+        for i in 0..T::COMPONENTS {
+            self.eps(i).canonical_bits(out);
+        }
+    }
+}
+
+/// Wrapper implementing [`Eq`] and [`Hash`] by the canonical bit patterns
+/// of all components
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ByBits<T>(pub T);
+
+impl<T: CanonicalBits> ByBits<T> {
+    fn bits(&self) -> Vec<u64> {
+        let mut out = Vec::new();
+        self.0.canonical_bits(&mut out);
+        out
+    }
+}
+
+impl<T: CanonicalBits> PartialEq for ByBits<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl<T: CanonicalBits> Eq for ByBits<T> {}
+
+impl<T: CanonicalBits> Hash for ByBits<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByBits;
+    use crate::{make_dual, Dual, HyperDual};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_equality() {
+        let nan = Dual::<2>::new(f64::NAN, [1., -0.]);
+        let other_nan = Dual::new(f64::from_bits(f64::NAN.to_bits() | 1), [1., -0.]);
+        assert_eq!(ByBits(nan), ByBits(other_nan));
+        assert_ne!(ByBits(nan), ByBits(Dual::new(f64::NAN, [1., 0.])));
+        assert_ne!(ByBits(0f32), ByBits(-0f32));
+
+        // Nested duals differ in the inner components
+        let a = HyperDual::<1>::variable(Dual::variable(1., 0), 0);
+        let b = HyperDual::<1>::variable(Dual::constant(1.), 0);
+        assert_ne!(ByBits(a), ByBits(b));
+    }
+
+    #[test]
+    fn test_hash() {
+        make_dual! { Pos, x, y }
+        let values = [Pos::x(1.), Pos::y(1.), Pos::x(1.), Pos::x(f64::NAN)];
+        let set: HashSet<_> = values.iter().chain(&values).map(|&v| ByBits(v)).collect();
+        assert_eq!(set.len(), 3);
+    }
+}
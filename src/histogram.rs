@@ -0,0 +1,105 @@
+//! # Soft histograms
+//!
+//! Counting samples into bins is piecewise constant, so the counts of a hard
+//! histogram have zero derivatives with respect to the samples and the bin
+//! edges. [`soft_histogram`] instead spreads each sample over the bins by a
+//! smooth kernel, so that losses matching histograms can be minimized by
+//! gradient methods. As the bandwidth goes to zero, the counts approach those
+//! of the hard histogram.
+//!
+//! ```
+//! use epsilon::histogram::{soft_histogram, Kernel};
+//! use epsilon::Dual;
+//! // Samples shifted by s, counted into the bins [0, 1) and [1, 2)
+//! let [s] = Dual::<1>::variables([0.]);
+//! let samples = [0.2, 0.5, 0.9, 1.4].map(|x| s + x);
+//! let edges = [0., 1., 2.].map(Dual::constant);
+//! let counts = soft_histogram(&samples, &edges, Dual::constant(0.05), Kernel::Gaussian);
+//!
+//! assert!((counts[0].real - 3.).abs() < 0.03 && (counts[1].real - 1.).abs() < 0.03);
+//! // Shifting the samples right moves mass from the first bin to the second
+//! assert!(counts[0].eps[0] < 0. && counts[1].eps[0] > 0.);
+//! ```
+
+use crate::special::normal_cdf;
+use crate::surrogate::Surrogate;
+use crate::Scalar;
+
+/// Kernel spreading a sample over the bins of [`soft_histogram`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kernel {
+    /// Normal distribution with the bandwidth as the standard deviation
+    Gaussian,
+    /// Triangular distribution on `[-bandwidth, bandwidth]`, which has
+    /// compact support so that a sample only contributes to nearby bins
+    Triangular,
+}
+
+impl Kernel {
+    /// Cumulative distribution function of the kernel with unit bandwidth
+    fn cdf<T: Scalar>(self, u: T) -> T {
+        match self {
+            Kernel::Gaussian => normal_cdf(u),
+            // The triangular surrogate of the step is the distribution
+            // function of the triangular kernel
+            Kernel::Triangular => Surrogate::Triangular { width: 1. }.eval(u),
+        }
+    }
+}
+
+/// Counts of `samples` in the bins between consecutive `edges`, with each
+/// sample spread over the bins by `kernel` scaled by `bandwidth`
+///
+/// The count of the bin `[a, b)` is the sum over the samples `x` of the mass
+/// of the kernel centered at `x` between `a` and `b`, `F((b - x) / h) -
+/// F((a - x) / h)` for the kernel distribution function `F` and the bandwidth
+/// `h`. The counts are differentiable with respect to the samples, the edges
+/// and the bandwidth. The mass outside the outermost edges is not counted.
+///
+/// The edges should be increasing. Returns one count less than the number of
+/// edges, or none for fewer than two edges.
+pub fn soft_histogram<T: Scalar>(
+    samples: &[T],
+    edges: &[T],
+    bandwidth: T,
+    kernel: Kernel,
+) -> Vec<T> {
+    let mut counts = vec![T::ZERO; edges.len().saturating_sub(1)];
+    for &x in samples {
+        let mut below = None;
+        for (i, &edge) in edges.iter().enumerate() {
+            let cdf = kernel.cdf((edge - x) / bandwidth);
+            if let Some(below) = below {
+                counts[i - 1] += cdf - below;
+            }
+            below = Some(cdf);
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{soft_histogram, Kernel};
+    use crate::Dual;
+
+    #[test]
+    fn test_soft_histogram() {
+        let edges = [-1., 0., 1., 2.];
+        let samples = [-0.5, 0.25, 0.5, 0.75, 1.5, 7.];
+        for kernel in [Kernel::Gaussian, Kernel::Triangular] {
+            let counts = soft_histogram(&samples, &edges, 1e-3, kernel);
+            assert_eq!(counts, [1., 3., 1.]);
+        }
+        assert!(soft_histogram(&samples, &[0.], 1., Kernel::Gaussian).is_empty());
+
+        // Triangular kernel of width 1 at x, with the edge 0 at x - 0.5
+        let [x, h, e] = Dual::<3>::variables([0.5, 1., 0.]);
+        let edges = [e - 1., e, e + 1.];
+        let counts = soft_histogram(&[x], &edges, h, Kernel::Triangular);
+        // Mass 1/8 left of 0, with the density 1/2 at u = -1/2
+        assert_eq!(counts[0], Dual::new(0.125, [-0.5, 0.25, 0.5]));
+        // Widening the kernel moves mass out of the bin on both sides
+        assert_eq!(counts[1], Dual::new(0.75, [0., -0.5, 0.]));
+    }
+}
@@ -0,0 +1,183 @@
+//! # Hamiltonian Monte Carlo
+//!
+//! With the `rand` feature, sampling from a distribution given by its
+//! unnormalized log-density, written against [`Dual`] so that the gradients
+//! driving the Hamiltonian dynamics come for free. For the low-dimensional
+//! models this is intended for, forward-mode gradients are cheap.
+//!
+//! ```
+//! use epsilon::hmc::{self, Hmc};
+//! use rand::SeedableRng;
+//! // Standard normal distribution in two dimensions
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+//! let chain = hmc::sample(|[x, y]| (x * x + y * y) * -0.5, [3., -3.], Hmc::default(), 2000, &mut rng);
+//!
+//! let mean = chain.samples.iter().map(|s| s[0]).sum::<f64>() / 2000.;
+//! assert!(mean.abs() < 0.1);
+//! assert!(chain.acceptance > 0.5);
+//! ```
+
+use crate::calculus::value_and_grad;
+use crate::reparam::standard_normal;
+use crate::Dual;
+
+/// Integrate Hamilton's equations for the potential `-log_density` with unit
+/// masses, starting at position `q` and momentum `p`, using `steps` leapfrog
+/// steps of size `step`
+///
+/// Returns the final position and momentum, and the log-density there. The
+/// integrator is symplectic and time-reversible, so the energy error stays
+/// bounded.
+pub fn leapfrog<const N: usize>(
+    log_density: impl Fn([Dual<N>; N]) -> Dual<N>,
+    q: [f64; N],
+    p: [f64; N],
+    step: f64,
+    steps: usize,
+) -> ([f64; N], [f64; N], f64) {
+    let (mut q, mut p) = (q, p);
+    let (mut value, mut grad) = value_and_grad(&log_density, q);
+    for _ in 0..steps {
+        for (p, g) in p.iter_mut().zip(grad) {
+            *p += 0.5 * step * g;
+        }
+        for (q, p) in q.iter_mut().zip(p) {
+            *q += step * p;
+        }
+        (value, grad) = value_and_grad(&log_density, q);
+        for (p, g) in p.iter_mut().zip(grad) {
+            *p += 0.5 * step * g;
+        }
+    }
+    (q, p, value)
+}
+
+/// Settings of [`sample`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hmc {
+    /// Initial leapfrog step size
+    pub step: f64,
+    /// Largest number of leapfrog steps per proposal, the number of steps is
+    /// drawn uniformly from `1..=max_steps` to avoid periodic trajectories
+    pub max_steps: usize,
+    /// Number of initial iterations adapting the step size, which are not
+    /// included in the samples
+    pub warmup: usize,
+    /// Acceptance probability targeted by the step size adaptation
+    pub target_acceptance: f64,
+}
+
+impl Default for Hmc {
+    fn default() -> Self {
+        Hmc {
+            step: 0.1,
+            max_steps: 20,
+            warmup: 500,
+            target_acceptance: 0.8,
+        }
+    }
+}
+
+/// Samples drawn by [`sample`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chain<const N: usize> {
+    pub samples: Vec<[f64; N]>,
+    /// Fraction of proposals accepted after the warmup
+    pub acceptance: f64,
+    /// Step size after the warmup
+    pub step: f64,
+}
+
+/// Draw `samples` samples from the distribution with the unnormalized
+/// `log_density`, starting at `q0`
+///
+/// Each iteration draws a standard normal momentum, integrates a trajectory
+/// with [`leapfrog`], and accepts its end point with the Metropolis
+/// probability `min(1, exp(-dH))`. During the warmup, the step size is
+/// scaled up after accepting with a probability above
+/// `options.target_acceptance`, and down otherwise.
+pub fn sample<const N: usize, R: rand::Rng + ?Sized>(
+    log_density: impl Fn([Dual<N>; N]) -> Dual<N>,
+    q0: [f64; N],
+    options: Hmc,
+    samples: usize,
+    rng: &mut R,
+) -> Chain<N> {
+    let kinetic = |p: &[f64; N]| 0.5 * p.iter().map(|p| p * p).sum::<f64>();
+    let mut q = q0;
+    let mut value = log_density(Dual::variables(q0)).real;
+    let mut step = options.step;
+    let mut chain = Chain {
+        samples: Vec::with_capacity(samples),
+        acceptance: 0.,
+        step,
+    };
+    let mut accepted = 0;
+
+    for iteration in 0..options.warmup + samples {
+        let p: [f64; N] = std::array::from_fn(|_| standard_normal(rng));
+        let steps = rng.random_range(1..=options.max_steps.max(1));
+        let (q_new, p_new, value_new) = leapfrog(&log_density, q, p, step, steps);
+
+        let log_ratio = (value_new - kinetic(&p_new)) - (value - kinetic(&p));
+        // NaN from diverging trajectories is never accepted
+        let probability = log_ratio.exp().min(1.);
+        let accept = rng.random::<f64>() < probability;
+        if accept {
+            (q, value) = (q_new, value_new);
+        }
+
+        if iteration < options.warmup {
+            step *= if probability >= options.target_acceptance {
+                1.1
+            } else {
+                0.9
+            };
+        } else {
+            accepted += accept as usize;
+            chain.samples.push(q);
+        }
+    }
+    chain.acceptance = accepted as f64 / samples.max(1) as f64;
+    chain.step = step;
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{leapfrog, sample, Hmc};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_leapfrog() {
+        // Harmonic oscillator, with the energy conserved up to O(step^2)
+        let log_density = |[x]: [crate::Dual<1>; 1]| x * x * -0.5;
+        let (q, p, value) = leapfrog(log_density, [1.], [0.], 0.01, 314);
+        assert!((q[0] + 1.).abs() < 1e-3 && p[0].abs() < 1e-2);
+        assert!((-value + 0.5 * p[0] * p[0] - 0.5).abs() < 1e-4);
+
+        // Reversing the momentum retraces the trajectory
+        let (q, p, _) = leapfrog(log_density, q, [-p[0]], 0.01, 314);
+        assert!((q[0] - 1.).abs() < 1e-12 && p[0].abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sample() {
+        // Correlated normal with unit variances and correlation 0.9
+        let rho: f64 = 0.9;
+        let log_density = |[x, y]: [crate::Dual<2>; 2]| {
+            (x * x - x * y * (2. * rho) + y * y) * (-0.5 / (1. - rho * rho))
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let n = 5000;
+        let chain = sample(log_density, [0., 0.], Hmc::default(), n, &mut rng);
+        assert_eq!(chain.samples.len(), n);
+        assert!((chain.acceptance - 0.8).abs() < 0.15);
+
+        let mean =
+            |f: &dyn Fn(&[f64; 2]) -> f64| chain.samples.iter().map(f).sum::<f64>() / n as f64;
+        assert!(mean(&|s| s[0]).abs() < 0.1);
+        assert!((mean(&|s| s[0] * s[0]) - 1.).abs() < 0.1);
+        assert!((mean(&|s| s[0] * s[1]) - rho).abs() < 0.1);
+    }
+}
@@ -0,0 +1,495 @@
+//! # Interval inner type
+//!
+//! [`Interval`] implements [`Scalar`] with interval arithmetic, so it can be
+//! used as the inner type of [`Dual`](crate::Dual) or
+//! [`make_dual`](crate::make_dual). Both the real and the dual parts are then
+//! intervals, enclosing the values and derivatives of a function over all
+//! inputs in the input intervals, as needed by interval Newton and Krawczyk
+//! methods.
+//!
+//! The endpoints of sums, differences, products and quotients are rounded
+//! outwards only when the floating-point operation is inexact, so that
+//! operations on degenerate intervals of exactly representable results, such
+//! as `2 - 1`, give degenerate intervals. Integer powers are computed by
+//! repeated multiplication. The endpoints of the other functions are rounded
+//! outwards by one unit in the last place, which makes the enclosures
+//! rigorous as long as the math functions of the platform are accurate to
+//! within one ulp.
+//!
+//! ```
+//! use epsilon::interval::Interval;
+//! use epsilon::Dual;
+//! // One interval Newton step for x^2 - 2 on [1, 2]
+//! let x = Interval::new(1., 2.);
+//! let f = |x: Dual<1, Interval>| x * x - Interval::point(2.);
+//!
+//! let slope = f(Dual::variable(x, 0)).eps[0];
+//! let mid = Interval::point(x.mid());
+//! let step = mid - f(Dual::constant(mid)).real / slope;
+//!
+//! let next = x.intersect(step).unwrap();
+//! assert!(next.contains(2f64.sqrt()));
+//! assert!(next.width() < 0.1);
+//! ```
+
+use crate::Scalar;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+/// Closed interval `[lo, hi]` of real numbers
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// The interval `[lo, hi]`, with `lo <= hi`
+    pub fn new(lo: f64, hi: f64) -> Self {
+        debug_assert!(lo <= hi, "interval bounds out of order");
+        Interval { lo, hi }
+    }
+
+    /// The degenerate interval `[x, x]`
+    pub fn point(x: f64) -> Self {
+        Interval { lo: x, hi: x }
+    }
+
+    /// The interval of all real numbers
+    pub fn entire() -> Self {
+        Interval {
+            lo: f64::NEG_INFINITY,
+            hi: f64::INFINITY,
+        }
+    }
+
+    pub fn mid(&self) -> f64 {
+        self.lo + (self.hi - self.lo) / 2.
+    }
+
+    pub fn width(&self) -> f64 {
+        self.hi - self.lo
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    /// The intersection of two intervals, or `None` if they are disjoint
+    pub fn intersect(self, other: Self) -> Option<Self> {
+        let (lo, hi) = (self.lo.max(other.lo), self.hi.min(other.hi));
+        (lo <= hi).then_some(Interval { lo, hi })
+    }
+
+    /// The smallest interval containing both intervals
+    pub fn hull(self, other: Self) -> Self {
+        Interval {
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+
+    /// Interval with the endpoints rounded outwards
+    fn outward(lo: f64, hi: f64) -> Self {
+        Interval {
+            lo: lo.next_down(),
+            hi: hi.next_up(),
+        }
+    }
+
+    /// Smallest interval containing the results of `op` on the pairs of
+    /// endpoints, with `op` rounding down or up as given by its last argument
+    fn enclose(pairs: [(f64, f64); 4], op: fn(f64, f64, bool) -> f64) -> Self {
+        let lo = pairs
+            .iter()
+            .map(|&(a, b)| op(a, b, false))
+            .fold(f64::INFINITY, f64::min);
+        let hi = pairs
+            .iter()
+            .map(|&(a, b)| op(a, b, true))
+            .fold(f64::NEG_INFINITY, f64::max);
+        Interval { lo, hi }
+    }
+
+    /// Clamp the lower bound to zero, for functions which are non-negative
+    fn max_zero(self) -> Self {
+        Interval {
+            lo: self.lo.max(0.),
+            hi: self.hi,
+        }
+    }
+
+    /// Range of a function like `sin` or `cos`, with maxima of 1 at
+    /// `peak + 2 k pi` and minima of -1 in between
+    fn periodic(self, f: fn(f64) -> f64, peak: f64) -> Self {
+        let attains = |at: f64| ((self.lo - at) / TAU).ceil() <= ((self.hi - at) / TAU).floor();
+        if self.width() >= TAU {
+            return Interval::new(-1., 1.);
+        }
+        let (a, b) = (f(self.lo), f(self.hi));
+        let range = Self::outward(a.min(b), a.max(b));
+        Interval {
+            lo: if attains(peak + PI) {
+                -1.
+            } else {
+                range.lo.max(-1.)
+            },
+            hi: if attains(peak) { 1. } else { range.hi.min(1.) },
+        }
+    }
+
+    /// Raise to a positive integer power by repeated squaring
+    fn powi(self, n: u64) -> Self {
+        let (mut base, mut n, mut result) = (self, n, Interval::point(1.));
+        while n > 0 {
+            if n % 2 == 1 {
+                result *= base;
+            }
+            base *= base;
+            n /= 2;
+        }
+        result
+    }
+
+    /// Raise to a constant power
+    fn powf_point(self, pow: f64) -> Self {
+        if pow == 0. {
+            Interval::point(1.)
+        } else if pow.fract() != 0. {
+            // Only defined for non-negative numbers, where it is monotonic
+            let (a, b) = (self.lo.max(0.).powf(pow), self.hi.powf(pow));
+            Self::outward(a.min(b), a.max(b))
+        } else if pow < 0. {
+            Interval::point(1.) / self.powf_point(-pow)
+        } else {
+            // Monotonic in each endpoint for odd powers, and in the distance
+            // from zero for even powers, so that multiplying the interval by
+            // itself would overestimate the range
+            let n = pow as u64;
+            if n % 2 == 1 {
+                Interval {
+                    lo: Interval::point(self.lo).powi(n).lo,
+                    hi: Interval::point(self.hi).powi(n).hi,
+                }
+            } else {
+                let near = if self.contains(0.) {
+                    0.
+                } else {
+                    self.lo.abs().min(self.hi.abs())
+                };
+                let far = self.lo.abs().max(self.hi.abs());
+                Interval::new(near, far).powi(n)
+            }
+        }
+    }
+}
+
+/// Lower bound of `a + b` if `!up`, and upper bound if `up`
+fn add_rounded(a: f64, b: f64, up: bool) -> f64 {
+    let s = a + b;
+    if !s.is_finite() {
+        // Overflow of finite operands is rounded towards the finite numbers
+        return match (a.is_finite() && b.is_finite(), up) {
+            (true, false) => s.next_down(),
+            (true, true) => s.next_up(),
+            (false, _) => s,
+        };
+    }
+    // The exact error of the sum, by Knuth's two-sum
+    let b_part = s - a;
+    let err = (a - (s - b_part)) + (b - b_part);
+    round_by(s, err, up)
+}
+
+/// Lower or upper bound of `a * b`, as for [`add_rounded`]
+fn mul_rounded(a: f64, b: f64, up: bool) -> f64 {
+    let p = a * b;
+    if a == 0. || b == 0. || p.is_nan() {
+        return p;
+    }
+    if p.abs() < TINY {
+        // The error may underflow, so it cannot be trusted to be exact
+        return if up { p.next_up() } else { p.next_down() };
+    }
+    // The exact error of the product, or infinite with the opposite sign of
+    // an overflowed product
+    round_by(p, a.mul_add(b, -p), up)
+}
+
+/// Lower or upper bound of `a / b`, as for [`add_rounded`]
+fn div_rounded(a: f64, b: f64, up: bool) -> f64 {
+    let q = a / b;
+    if a == 0. || q.is_nan() || b.is_infinite() && a.is_finite() {
+        return q;
+    }
+    if q.abs() < TINY || q.is_infinite() {
+        return if up { q.next_up() } else { q.next_down() };
+    }
+    // The exact quotient is q + r / b with the exact remainder r = a - q b
+    let r = (-q).mul_add(b, a);
+    round_by(q, r * b.signum(), up)
+}
+
+/// Round `x` down or up if the exact result `x + err` differs from it
+fn round_by(x: f64, err: f64, up: bool) -> f64 {
+    if up && err > 0. {
+        x.next_up()
+    } else if !up && err < 0. {
+        x.next_down()
+    } else {
+        x
+    }
+}
+
+/// Magnitude below which the error of a product or quotient may not be
+/// representable
+const TINY: f64 = 1e-280;
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.lo == self.hi {
+            write!(fmt, "{}", self.lo)
+        } else {
+            write!(fmt, "[{}, {}]", self.lo, self.hi)
+        }
+    }
+}
+
+impl From<f64> for Interval {
+    fn from(x: f64) -> Self {
+        Interval::point(x)
+    }
+}
+
+impl std::ops::Add for Interval {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Interval {
+            lo: add_rounded(self.lo, other.lo, false),
+            hi: add_rounded(self.hi, other.hi, true),
+        }
+    }
+}
+
+impl std::ops::Sub for Interval {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl std::ops::Mul for Interval {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let (a, b) = (self, other);
+        Self::enclose(
+            [(a.lo, b.lo), (a.lo, b.hi), (a.hi, b.lo), (a.hi, b.hi)],
+            mul_rounded,
+        )
+    }
+}
+
+impl std::ops::Div for Interval {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self::Output {
+        if other.contains(0.) {
+            return Interval::entire();
+        }
+        let (a, b) = (self, other);
+        Self::enclose(
+            [(a.lo, b.lo), (a.lo, b.hi), (a.hi, b.lo), (a.hi, b.hi)],
+            div_rounded,
+        )
+    }
+}
+
+impl std::ops::Rem for Interval {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self::Output {
+        // a % b = a - b * trunc(a / b)
+        self - other * (self / other).trunc()
+    }
+}
+
+impl std::ops::Neg for Interval {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl std::ops::$op_inplace for Interval {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+impl_inplace! {RemAssign, rem_assign, Rem, rem}
+
+impl Scalar for Interval {
+    const ZERO: Self = Interval { lo: 0., hi: 0. };
+    const ONE: Self = Interval { lo: 1., hi: 1. };
+
+    fn from_f64(value: f64) -> Self {
+        Interval::point(value)
+    }
+
+    /// The midpoint
+    fn to_f64(self) -> f64 {
+        self.mid()
+    }
+
+    /// Ordered if the intervals are disjoint, or equal as degenerate intervals
+    fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+        if self.hi < other.lo {
+            Some(std::cmp::Ordering::Less)
+        } else if self.lo > other.hi {
+            Some(std::cmp::Ordering::Greater)
+        } else if self.lo == self.hi && self == other {
+            Some(std::cmp::Ordering::Equal)
+        } else {
+            None
+        }
+    }
+
+    fn powf(self, pow: Self) -> Self {
+        if pow.lo == pow.hi {
+            self.powf_point(pow.lo)
+        } else {
+            // x^y = exp(y ln(x)), only defined for positive x
+            (pow * self.ln()).exp()
+        }
+    }
+
+    fn exp(self) -> Self {
+        Self::outward(self.lo.exp(), self.hi.exp()).max_zero()
+    }
+
+    fn ln(self) -> Self {
+        Self::outward(self.lo.max(0.).ln(), self.hi.ln())
+    }
+
+    fn sin(self) -> Self {
+        self.periodic(f64::sin, FRAC_PI_2)
+    }
+
+    fn cos(self) -> Self {
+        self.periodic(f64::cos, 0.)
+    }
+
+    fn trunc(self) -> Self {
+        Interval {
+            lo: self.lo.trunc(),
+            hi: self.hi.trunc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interval;
+    use crate::{make_dual, Scalar};
+
+    #[test]
+    fn test_functions() {
+        let x = Interval::new(-1., 2.);
+        let sq = x.powf(Interval::point(2.));
+        assert!(sq.lo == 0. && sq.contains(4.) && sq.hi < 4.0001);
+        let prod = x * x;
+        assert!(prod.contains(-2.) && prod.lo > -2.0001);
+        assert_eq!(x / x, Interval::entire());
+
+        let sin = Interval::new(1., 2.).sin();
+        assert!(sin.hi == 1. && sin.contains(1f64.sin()));
+        let cos = Interval::new(1., 2.).cos();
+        assert!(cos.contains(1f64.cos()) && cos.contains(2f64.cos()) && cos.hi < 0.541);
+        assert_eq!(Interval::new(0., 7.).sin(), Interval::new(-1., 1.));
+
+        assert_eq!(
+            Interval::new(1., 2.).compare(Interval::point(3.)),
+            Some(std::cmp::Ordering::Less)
+        );
+        assert_eq!(x.compare(Interval::point(0.)), None);
+    }
+
+    #[test]
+    fn test_rounding() {
+        // Exact operations on points stay points
+        let (two, one) = (Interval::point(2.), Interval::point(1.));
+        assert_eq!(two - one, one);
+        assert_eq!(two * two / Interval::point(4.), one);
+        // Inexact ones enclose the exact result
+        let third = one / Interval::point(3.);
+        assert!(third.lo < third.hi && third.lo * 3. <= 1. && third.hi * 3. >= 1.);
+        let sum = Interval::point(0.1) + Interval::point(0.2);
+        assert!(sum.lo < sum.hi && sum.contains(0.30000000000000004));
+        let big = Interval::point(f64::MAX) + Interval::point(f64::MAX);
+        assert_eq!((big.lo, big.hi), (f64::MAX, f64::INFINITY));
+        assert!(Interval::point(1e-300) * Interval::point(1e-300) != Interval::ZERO);
+    }
+
+    #[test]
+    fn test_powers() {
+        make_dual! { PowDual: Interval, x }
+
+        // Sign-straddling x in [-1, 2], x^2 in [0, 4] with derivative [-2, 4]
+        let x = PowDual::x(Interval::new(-1., 2.));
+        for y in [x.powf(Interval::point(2.)), x * x] {
+            assert!(y.d_dx().contains(-2.) && y.d_dx().contains(4.));
+            assert!(y.d_dx().lo > -2.0001 && y.d_dx().hi < 4.0001);
+        }
+        let y = x.powf(Interval::point(2.));
+        assert_eq!(y.real, Interval::new(0., 4.));
+
+        // Negative x in [-3, -2], x^3 in [-27, -8] with derivative [12, 27]
+        let x = PowDual::x(Interval::new(-3., -2.));
+        let y = x.powf(Interval::point(3.));
+        assert_eq!(
+            (y.real, y.d_dx()),
+            (Interval::new(-27., -8.), Interval::new(12., 27.))
+        );
+        let y = x.powf(Interval::point(-2.));
+        assert!(y.real.contains(1. / 9.) && y.real.contains(0.25) && y.real.hi < 0.2501);
+        // d/dx x^-2 = -2 x^-3 in [2/27, 1/4]
+        assert!(y.d_dx().contains(2. / 27.) && y.d_dx().contains(0.25) && y.d_dx().hi < 0.2501);
+
+        // 1 / x at -2, with the derivative -1/4
+        let y = PowDual::x(Interval::point(-2.)).invert();
+        assert_eq!(
+            (y.real, y.d_dx()),
+            (Interval::point(-0.5), Interval::point(-0.25))
+        );
+        let y = crate::Dual::<1, Interval>::variable(Interval::new(-4., -2.), 0).invert();
+        assert!(y.eps[0].contains(-0.25) && y.eps[0].contains(-1. / 16.));
+        assert!(y.eps[0].lo > -0.2501 && y.eps[0].hi < -0.0624);
+    }
+
+    #[test]
+    fn test_dual() {
+        make_dual! { IntervalDual: Interval, x }
+
+        // Derivative enclosure of x sin(x) on [0, 1]
+        let x = IntervalDual::x(Interval::new(0., 1.));
+        let y = x * x.sin();
+        assert!(y.real.contains(0.) && y.real.contains(1f64.sin()));
+        // d/dx = sin(x) + x cos(x), between 0 and sin(1) + cos(1)
+        assert!(y.d_dx().contains(0.) && y.d_dx().contains(1f64.sin() + 1f64.cos()));
+        assert!(y.d_dx().hi < 2.);
+    }
+}
@@ -0,0 +1,84 @@
+//! # Iterator adapters
+//!
+//! [`IteratorExt`] converts iterators of `f64`, such as input data, into
+//! iterators of dual numbers, either as constants or with each element
+//! seeded as its own variable.
+//!
+//! ```
+//! use epsilon::iter::IteratorExt;
+//! use epsilon::make_dual;
+//! make_dual! { Point, x, y }
+//!
+//! let data: Vec<Point> = [1., 2.].into_iter().as_constants().collect();
+//! assert_eq!(data[1], Point::from(2.));
+//!
+//! let seeded: Vec<Point> = [3., 4.].into_iter().seed_each().collect();
+//! assert_eq!(seeded, [Point::x(3.), Point::y(4.)]);
+//! ```
+
+use crate::{DualNumber, Numerical};
+
+/// Adapters for iterators of `f64`
+pub trait IteratorExt: Iterator<Item = f64> + Sized {
+    /// Each element as a constant, with a zero dual part
+    #[allow(clippy::wrong_self_convention)]
+    fn as_constants<T: Numerical>(self) -> std::iter::Map<Self, fn(f64) -> T> {
+        self.map(T::constant)
+    }
+
+    /// The `i`th element as a variable with a unit dual part in component
+    /// `i`, panicking past the last component of `T`
+    fn seed_each<T: DualNumber>(self) -> SeedEach<Self, T> {
+        SeedEach {
+            iter: self.enumerate(),
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator<Item = f64>> IteratorExt for I {}
+
+/// Iterator returned by [`IteratorExt::seed_each`]
+#[derive(Clone, Debug)]
+pub struct SeedEach<I, T> {
+    iter: std::iter::Enumerate<I>,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<I: Iterator<Item = f64>, T: DualNumber> Iterator for SeedEach<I, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(i, x)| T::variable(x, i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IteratorExt;
+    use crate::sample::SampleXYZ;
+    use crate::Dual;
+
+    #[test]
+    fn test_adapters() {
+        let v: Vec<Dual<2>> = (0..3).map(f64::from).as_constants().collect();
+        assert!(v
+            .iter()
+            .enumerate()
+            .all(|(i, x)| x.real == i as f64 && x.eps == [0.; 2]));
+
+        let v: Vec<SampleXYZ> = [1., 2., 3.].into_iter().seed_each().collect();
+        assert_eq!(v, [SampleXYZ::x(1.), SampleXYZ::y(2.), SampleXYZ::z(3.)]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "lenient-components"))]
+    #[should_panic(expected = "dual component index out of range")]
+    fn test_too_many() {
+        [1.; 4].into_iter().seed_each::<SampleXYZ>().for_each(drop);
+    }
+}
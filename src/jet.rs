@@ -0,0 +1,463 @@
+//! # Truncated Taylor series
+//!
+//! [`Jet<F, ORDER>`] carries the derivatives up to `ORDER` of an expression
+//! with respect to a single variable, as the coefficients of its Taylor
+//! series truncated after the term of degree `ORDER`. Arithmetic is truncated
+//! polynomial arithmetic, and the math functions use the recurrences for the
+//! Taylor coefficients of their compositions, so each operation costs
+//! `O(ORDER^2)`.
+//!
+//! ```
+//! use epsilon::Jet;
+//! // Derivatives of x^2 sin(x) at x = 0
+//! let x = Jet::<f64, 4>::variable(0.);
+//! let y = x * x * x.sin();
+//!
+//! assert_eq!(y.derivative(0), 0.);
+//! assert_eq!(y.derivative(3), 6.);
+//! assert_eq!(y.derivative(4), 0.);
+//! ```
+
+use crate::Scalar;
+
+/// Taylor series in one variable truncated after degree `ORDER`, backed by `F`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Jet<F, const ORDER: usize> {
+    /// The real value, i.e. the coefficient of degree 0
+    pub real: F,
+    /// The Taylor coefficients of degree `1..=ORDER`, i.e. `f^(k)(x) / k!`
+    pub coeffs: [F; ORDER],
+}
+
+impl<F: Scalar, const ORDER: usize> Jet<F, ORDER> {
+    /// Create instance with specified real part and Taylor coefficients
+    pub fn new(real: F, coeffs: [F; ORDER]) -> Self {
+        Jet { real, coeffs }
+    }
+
+    /// Create instance with specified real part and zero derivatives
+    pub fn constant(real: F) -> Self {
+        Jet {
+            real,
+            coeffs: [F::ZERO; ORDER],
+        }
+    }
+
+    /// Create the variable with specified real part, having a unit first
+    /// derivative
+    pub fn variable(real: F) -> Self {
+        let mut v = Self::constant(real);
+        if ORDER > 0 {
+            v.coeffs[0] = F::ONE;
+        }
+        v
+    }
+
+    /// Taylor coefficient of degree `k`, `f^(k)(x) / k!`
+    pub fn coeff(&self, k: usize) -> F {
+        if k == 0 {
+            self.real
+        } else {
+            self.coeffs[k - 1]
+        }
+    }
+
+    /// The `k`th derivative, `k <= ORDER`
+    pub fn derivative(&self, k: usize) -> F {
+        let factorial = (1..=k).fold(F::ONE, |f, i| f * F::from_f64(i as f64));
+        self.coeff(k) * factorial
+    }
+
+    /// Build a jet from its Taylor coefficients, computing coefficient `k`
+    /// from `k` and the previously computed coefficients
+    fn recurrence(mut coeff: impl FnMut(usize, &Self) -> F) -> Self {
+        let mut jet = Self::constant(F::ZERO);
+        jet.real = coeff(0, &jet);
+        for k in 1..=ORDER {
+            jet.coeffs[k - 1] = coeff(k, &jet);
+        }
+        jet
+    }
+
+    /// Raise `self` to `pow`
+    ///
+    /// At a zero base, with `x = t^m u(t)` for the first non-zero coefficient
+    /// of degree `m`, `x^p = t^(m p) u^p`. This is a Taylor series if `m p` is
+    /// a non-negative integer. Otherwise, the coefficients of degrees below
+    /// `m p` are zero and those above are infinite, as for the derivative of
+    /// [`Dual::powf`](crate::Dual::powf).
+    pub fn powf(self, pow: F) -> Self {
+        if self.real == F::ZERO {
+            return self.powf_zero(pow);
+        }
+        // y = x^p satisfies x y' = p x' y, giving
+        // y_k = sum_{j=1..k} ((p + 1) j - k) x_j y_{k-j} / (k x_0)
+        Self::recurrence(|k, y| {
+            if k == 0 {
+                return self.real.powf(pow);
+            }
+            let sum = (1..=k).fold(F::ZERO, |sum, j| {
+                let c = (pow + F::ONE) * F::from_f64(j as f64) - F::from_f64(k as f64);
+                sum + c * self.coeff(j) * y.coeff(k - j)
+            });
+            sum / (F::from_f64(k as f64) * self.real)
+        })
+    }
+
+    fn powf_zero(self, pow: F) -> Self {
+        let Some(m) = (1..=ORDER).find(|&j| self.coeff(j) != F::ZERO) else {
+            return Self::constant(self.real.powf(pow));
+        };
+        let u = Self::new(
+            self.coeff(m),
+            std::array::from_fn(|k| {
+                if k + m < ORDER {
+                    self.coeffs[k + m]
+                } else {
+                    F::ZERO
+                }
+            }),
+        );
+        let u_pow = u.powf(pow);
+        let q = m as f64 * pow.to_f64();
+        Self::recurrence(|k, _| {
+            let k_f = k as f64;
+            if q >= 0. && q.fract() == 0. {
+                if k_f < q {
+                    F::ZERO
+                } else {
+                    u_pow.coeff(k - q as usize)
+                }
+            } else if k_f < q {
+                F::ZERO
+            } else {
+                // The derivative of order k of t^q diverges at zero with the
+                // sign of q (q - 1) ... (q - k + 1)
+                let falling = (0..k).fold(1., |f, i| f * (q - i as f64));
+                u_pow.real * F::from_f64(falling * f64::INFINITY)
+            }
+        })
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        Self::constant(F::ONE) / self
+    }
+
+    pub fn exp(self) -> Self {
+        // y' = x' y
+        Self::recurrence(|k, y| {
+            if k == 0 {
+                return self.real.exp();
+            }
+            let sum = (1..=k).fold(F::ZERO, |sum, j| {
+                sum + F::from_f64(j as f64) * self.coeff(j) * y.coeff(k - j)
+            });
+            sum / F::from_f64(k as f64)
+        })
+    }
+
+    /// Natural logarithm
+    pub fn ln(self) -> Self {
+        // x y' = x'
+        Self::recurrence(|k, y| {
+            if k == 0 {
+                return self.real.ln();
+            }
+            let sum = (1..k).fold(F::ZERO, |sum, j| {
+                sum + F::from_f64(j as f64) * y.coeff(j) * self.coeff(k - j)
+            });
+            (self.coeff(k) - sum / F::from_f64(k as f64)) / self.real
+        })
+    }
+
+    /// Sine and cosine, computed together since their recurrences are coupled
+    pub fn sin_cos(self) -> (Self, Self) {
+        // s' = x' c, c' = -x' s
+        let (mut sin, mut cos) = (
+            Self::constant(self.real.sin()),
+            Self::constant(self.real.cos()),
+        );
+        for k in 1..=ORDER {
+            let (s, c) = (1..=k).fold((F::ZERO, F::ZERO), |(s, c), j| {
+                let a = F::from_f64(j as f64) * self.coeff(j);
+                (s + a * cos.coeff(k - j), c - a * sin.coeff(k - j))
+            });
+            let k_f = F::from_f64(k as f64);
+            sin.coeffs[k - 1] = s / k_f;
+            cos.coeffs[k - 1] = c / k_f;
+        }
+        (sin, cos)
+    }
+
+    pub fn sin(self) -> Self {
+        self.sin_cos().0
+    }
+
+    pub fn cos(self) -> Self {
+        self.sin_cos().1
+    }
+
+    pub fn tan(self) -> Self {
+        let (sin, cos) = self.sin_cos();
+        sin / cos
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::cmp::PartialOrd for Jet<F, ORDER> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.real.compare(other.real)
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::fmt::Display for Jet<F, ORDER> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.real)?;
+        for (i, &v) in self.coeffs.iter().enumerate() {
+            match v.compare(F::ZERO) {
+                Some(std::cmp::Ordering::Less) => write!(fmt, " - {} h^{}", -v, i + 1)?,
+                Some(std::cmp::Ordering::Equal) => {}
+                _ => write!(fmt, " + {} h^{}", v, i + 1)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> From<F> for Jet<F, ORDER> {
+    fn from(real: F) -> Self {
+        Jet::constant(real)
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Add<F> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn add(mut self, other: F) -> Self::Output {
+        self.real += other;
+        self
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Add<Self> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self::Output {
+        self.real += other.real;
+        for (c, o) in self.coeffs.iter_mut().zip(other.coeffs) {
+            *c += o;
+        }
+        self
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Mul<F> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn mul(self, other: F) -> Self::Output {
+        Jet {
+            real: self.real * other,
+            coeffs: self.coeffs.map(|c| c * other),
+        }
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Mul<Self> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        // Cauchy product, truncated after degree ORDER
+        Self::recurrence(|k, _| {
+            (0..=k).fold(F::ZERO, |sum, j| sum + self.coeff(j) * other.coeff(k - j))
+        })
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Neg for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -F::ONE
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Sub<F> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn sub(self, other: F) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Sub<Self> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Div<F> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn div(self, other: F) -> Self::Output {
+        self * (F::ONE / other)
+    }
+}
+
+impl<F: Scalar, const ORDER: usize> std::ops::Div<Self> for Jet<F, ORDER> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self::Output {
+        // Solve other * q = self for the coefficients of q in order
+        Self::recurrence(|k, q| {
+            let sum = (1..=k).fold(F::ZERO, |sum, j| sum + other.coeff(j) * q.coeff(k - j));
+            (self.coeff(k) - sum) / other.real
+        })
+    }
+}
+
+macro_rules! impl_reverse {
+    ($f:ty, $op:ident, $fn:ident) => {
+        impl<const ORDER: usize> std::ops::$op<Jet<$f, ORDER>> for $f {
+            type Output = Jet<$f, ORDER>;
+
+            fn $fn(self, other: Jet<$f, ORDER>) -> Self::Output {
+                <Jet<$f, ORDER> as std::ops::$op>::$fn(Jet::from(self), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl<F: Scalar, const ORDER: usize> std::ops::$op_inplace<F> for Jet<F, ORDER> {
+            fn $fn_inplace(&mut self, other: F) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+        impl<F: Scalar, const ORDER: usize> std::ops::$op_inplace<Self> for Jet<F, ORDER> {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_reverse! {f32, Add, add}
+impl_reverse! {f32, Sub, sub}
+impl_reverse! {f32, Mul, mul}
+impl_reverse! {f32, Div, div}
+impl_reverse! {f64, Add, add}
+impl_reverse! {f64, Sub, sub}
+impl_reverse! {f64, Mul, mul}
+impl_reverse! {f64, Div, div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+
+impl<F: Scalar, const ORDER: usize> crate::Numerical for Jet<F, ORDER> {
+    fn constant(real: f64) -> Self {
+        Jet::constant(F::from_f64(real))
+    }
+
+    fn real(&self) -> f64 {
+        self.real.to_f64()
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        Jet::powf(self, F::from_f64(pow))
+    }
+
+    fn invert(self) -> Self {
+        Jet::invert(self)
+    }
+
+    fn sin(self) -> Self {
+        Jet::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Jet::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        Jet::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Jet;
+
+    fn close<const ORDER: usize>(x: Jet<f64, ORDER>, derivatives: [f64; ORDER]) -> bool {
+        (1..=ORDER).all(|k| (x.derivative(k) - derivatives[k - 1]).abs() < 1e-12)
+    }
+
+    #[test]
+    fn test_functions() {
+        let x = Jet::<f64, 4>::variable(2.);
+        assert!(close(x * x * x, [12., 12., 6., 0.]));
+        assert!(close(x.powf(3.), [12., 12., 6., 0.]));
+        assert!(close(1. / x, [-0.25, 0.25, -0.375, 0.75]));
+        assert!(close(x.exp().ln(), [1., 0., 0., 0.]));
+        assert!(close((x * x).sin() / x.exp(), {
+            // Reference from the product of the series of sin(x^2) and
+            // exp(-x) at x = 2
+            let y = Jet::<f64, 4>::variable(2.);
+            let y = (y * y).sin() * (-y).exp();
+            [1, 2, 3, 4].map(|k| y.derivative(k))
+        }));
+        let x = Jet::<f64, 4>::variable(0.);
+        assert!(close(x.sin(), [1., 0., -1., 0.]));
+        assert!(close(x.tan(), [1., 0., 2., 0.]));
+        assert_eq!(
+            format!("{}", x.cos()),
+            "1 - 0.5 h^2 + 0.041666666666666664 h^4"
+        );
+    }
+
+    #[test]
+    fn test_powf_zero() {
+        let x = Jet::<f64, 3>::variable(0.);
+        assert_eq!(x.powf(2.), x * x);
+        assert_eq!(x.powf(0.), Jet::constant(1.));
+        assert_eq!((x * 2.).powf(3.), Jet::new(0., [0., 0., 8.]));
+        // sqrt(x^2 + x^3) = x sqrt(1 + x), with m p = 1
+        let y = (x * x + x * x * x).powf(0.5);
+        assert!(close(y, [1., 1., -0.75]));
+        // Not a Taylor series
+        let y = x.powf(1.5);
+        assert_eq!(y.coeffs[..1], [0.]);
+        assert_eq!(y.coeffs[1..], [f64::INFINITY, -f64::INFINITY]);
+        assert_eq!(x.powf(-1.).real, f64::INFINITY);
+        assert_eq!(Jet::<f64, 2>::constant(0.).powf(2.), Jet::constant(0.));
+        // Dual numbers agree on the first derivative
+        assert_eq!(
+            x.powf(0.5).coeffs[0],
+            crate::Dual::<1>::variable(0., 0).powf(0.5).eps[0]
+        );
+    }
+
+    #[test]
+    fn test_torsion() {
+        // Helix (cos t, sin t, t) has curvature and torsion 1/2
+        let t = Jet::<f64, 3>::variable(0.7);
+        let r = [t.cos(), t.sin(), t];
+        let d = |k: usize| r.map(|r| r.derivative(k));
+        let cross = |a: [f64; 3], b: [f64; 3]| {
+            [
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]
+        };
+        let dot = |a: [f64; 3], b: [f64; 3]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+        let n = cross(d(1), d(2));
+        let curvature = dot(n, n).sqrt() / dot(d(1), d(1)).powf(1.5);
+        let torsion = dot(n, d(3)) / dot(n, n);
+        assert!((curvature - 0.5).abs() < 1e-14);
+        assert!((torsion - 0.5).abs() < 1e-14);
+    }
+}
@@ -0,0 +1,242 @@
+//! # Matrix-free linear algebra
+//!
+//! Iterative solvers for linear systems `A x = b` that only need the products
+//! `A v`, provided by a [`LinearOperator`]. [`JacobianOperator`] computes the
+//! products with the Jacobian of a function by [`jvp`], so that Newton-Krylov
+//! methods for large systems never form the Jacobian.
+//!
+//! ```
+//! use epsilon::krylov::{gmres, JacobianOperator};
+//! // One Newton step for the residual of -u'' + u^3 = 1 on a grid
+//! let n = 50;
+//! let residual = |u: &[epsilon::Dual1]| {
+//!     (0..n)
+//!         .map(|i| {
+//!             let left = if i > 0 { u[i - 1] } else { u[i] * 0. };
+//!             let right = if i + 1 < n { u[i + 1] } else { u[i] * 0. };
+//!             u[i] * 2. - left - right + u[i] * u[i] * u[i] - 1.
+//!         })
+//!         .collect::<Vec<_>>()
+//! };
+//! let u = vec![0.5; n];
+//! let jacobian = JacobianOperator::new(residual, &u);
+//! let rhs: Vec<f64> = jacobian.value().iter().map(|r| -r).collect();
+//! let step = gmres(&jacobian, &rhs, 1e-10, n);
+//! assert!(step.converged);
+//! ```
+
+use crate::calculus::jvp;
+use crate::solve::Solution;
+use crate::Dual1;
+
+/// A linear map from `cols`-dimensional to `rows`-dimensional vectors, given
+/// by its action on vectors
+pub trait LinearOperator {
+    /// Dimension of the output
+    fn rows(&self) -> usize;
+    /// Dimension of the input
+    fn cols(&self) -> usize;
+    /// The product `A v`
+    fn apply(&self, v: &[f64]) -> Vec<f64>;
+}
+
+/// A dense matrix given by its rows
+impl LinearOperator for Vec<Vec<f64>> {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn cols(&self) -> usize {
+        self.first().map_or(0, Vec::len)
+    }
+
+    fn apply(&self, v: &[f64]) -> Vec<f64> {
+        self.iter().map(|row| dot(row, v)).collect()
+    }
+}
+
+/// The Jacobian of `f` at a point, applied by [`jvp`] without forming it
+pub struct JacobianOperator<F> {
+    f: F,
+    x: Vec<f64>,
+    value: Vec<f64>,
+}
+
+impl<F: Fn(&[Dual1]) -> Vec<Dual1>> JacobianOperator<F> {
+    /// The Jacobian of `f` at `x`, evaluating `f` once for its value
+    pub fn new(f: F, x: &[f64]) -> Self {
+        let constants: Vec<Dual1> = x.iter().map(|&x| Dual1::constant(x)).collect();
+        let value = f(&constants).iter().map(|y| y.real).collect();
+        JacobianOperator {
+            f,
+            x: x.to_vec(),
+            value,
+        }
+    }
+
+    /// The point the Jacobian is taken at
+    pub fn x(&self) -> &[f64] {
+        &self.x
+    }
+
+    /// The value of `f` at [`x`](Self::x)
+    pub fn value(&self) -> &[f64] {
+        &self.value
+    }
+}
+
+impl<F: Fn(&[Dual1]) -> Vec<Dual1>> LinearOperator for JacobianOperator<F> {
+    fn rows(&self) -> usize {
+        self.value.len()
+    }
+
+    fn cols(&self) -> usize {
+        self.x.len()
+    }
+
+    fn apply(&self, v: &[f64]) -> Vec<f64> {
+        jvp(&self.f, &self.x, v)
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Solve the square system `a x = b` using GMRES, starting at zero
+///
+/// Builds an orthonormal basis of the Krylov subspace spanned by `b, a b,
+/// a^2 b, ...` and minimizes the residual over it, using one product with
+/// `a` per iteration. Iterates until the Euclidean norm of the residual is at
+/// most `tol`, for at most `max_iter` iterations, without restarting, so the
+/// memory grows with the number of iterations. Converges in at most `n`
+/// iterations in exact arithmetic for nonsingular `a`. If `a` is singular on
+/// the Krylov subspace, stops early with the best solution found so far,
+/// which is then not converged.
+///
+/// # Panics
+///
+/// If `a` is not square or `b` has the wrong length.
+pub fn gmres(a: &impl LinearOperator, b: &[f64], tol: f64, max_iter: usize) -> Solution<Vec<f64>> {
+    let n = b.len();
+    assert!(
+        a.rows() == n && a.cols() == n,
+        "a must be square with the dimension of b"
+    );
+    let beta = norm(b);
+    let mut residual = beta;
+    // Orthonormal basis, Hessenberg matrix by columns, Givens rotations and
+    // the rotated right-hand side
+    let mut basis: Vec<Vec<f64>> = Vec::new();
+    let mut h: Vec<Vec<f64>> = Vec::new();
+    let mut rotations: Vec<(f64, f64)> = Vec::new();
+    let mut g = vec![beta];
+    if beta > 0. {
+        basis.push(b.iter().map(|b| b / beta).collect());
+    }
+
+    while residual > tol && h.len() < max_iter && !basis.is_empty() {
+        let k = h.len();
+        // Arnoldi step with modified Gram-Schmidt
+        let mut w = a.apply(&basis[k]);
+        let mut column = Vec::with_capacity(k + 2);
+        for v in &basis {
+            let c = dot(&w, v);
+            for (w, v) in w.iter_mut().zip(v) {
+                *w -= c * v;
+            }
+            column.push(c);
+        }
+        let w_norm = norm(&w);
+        column.push(w_norm);
+
+        // Apply the previous rotations, and eliminate the new subdiagonal
+        for (i, &(c, s)) in rotations.iter().enumerate() {
+            let (x, y) = (column[i], column[i + 1]);
+            column[i] = c * x + s * y;
+            column[i + 1] = c * y - s * x;
+        }
+        let r = column[k].hypot(column[k + 1]);
+        if r == 0. {
+            // Breakdown: a is singular on the Krylov subspace, and the new
+            // basis vector cannot reduce the residual, so the solution is the
+            // one in the previous basis vectors
+            break;
+        }
+        let (c, s) = (column[k] / r, column[k + 1] / r);
+        column[k] = r;
+        column[k + 1] = 0.;
+        rotations.push((c, s));
+        g.push(-s * g[k]);
+        g[k] *= c;
+        residual = g[k + 1].abs();
+        h.push(column);
+
+        if w_norm == 0. {
+            // The Krylov subspace is invariant, so the solution lies in it
+            break;
+        }
+        basis.push(w.into_iter().map(|w| w / w_norm).collect());
+    }
+
+    // Back substitution for the coefficients of the basis vectors
+    let m = h.len();
+    let mut y = vec![0.; m];
+    for i in (0..m).rev() {
+        let sum: f64 = (i + 1..m).map(|j| h[j][i] * y[j]).sum();
+        y[i] = (g[i] - sum) / h[i][i];
+    }
+    let mut x = vec![0.; n];
+    for (v, y) in basis.iter().zip(&y) {
+        for (x, v) in x.iter_mut().zip(v) {
+            *x += y * v;
+        }
+    }
+    Solution {
+        x,
+        residual,
+        iterations: m,
+        converged: residual <= tol,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gmres, JacobianOperator, LinearOperator};
+
+    #[test]
+    fn test_gmres() {
+        let a = vec![vec![4., 1., 0.], vec![2., 5., -1.], vec![0., 3., 6.]];
+        let b = [1., -2., 3.];
+        let sol = gmres(&a, &b, 1e-12, 10);
+        assert!(sol.converged && sol.iterations <= 3);
+        for (ax, b) in a.apply(&sol.x).iter().zip(b) {
+            assert!((ax - b).abs() < 1e-12);
+        }
+        assert_eq!(gmres(&a, &[0.; 3], 1e-12, 10).x, [0.; 3]);
+
+        // Singular on the Krylov subspace from the first step
+        let zero = vec![vec![0.; 2]; 2];
+        let sol = gmres(&zero, &[3., 4.], 1e-12, 10);
+        assert_eq!(
+            (sol.x, sol.residual, sol.converged),
+            (vec![0.; 2], 5., false)
+        );
+    }
+
+    #[test]
+    fn test_jacobian_operator() {
+        // f = (x^2 y, x + sin(y)), J = [[2 x y, x^2], [1, cos(y)]]
+        let f = |x: &[crate::Dual1]| vec![x[0] * x[0] * x[1], x[0] + x[1].sin()];
+        let op = JacobianOperator::new(f, &[2., 0.5]);
+        assert_eq!(op.value(), [2., 2. + 0.5f64.sin()]);
+        assert_eq!(op.apply(&[1., 0.]), [2., 1.]);
+        assert_eq!(op.apply(&[0., 1.]), [4., 0.5f64.cos()]);
+        let sol = gmres(&op, &[1., 1.], 1e-14, 2);
+        assert!(sol.converged);
+    }
+}
@@ -46,9 +46,180 @@
 #[doc(hidden)]
 pub use paste::paste;
 
+// Allows the code generated by derive macros to refer to `::epsilon`, also
+// within this crate
+extern crate self as epsilon;
+
+pub mod angle;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+pub mod calculus;
+pub use calculus::{curl, divergence, grad, hessian, jacobian, jvp, laplacian, value_and_grad};
+
+pub mod cast;
+
+pub mod check;
+
+#[cfg(feature = "complex")]
+pub mod complex;
+pub mod constraint;
+pub mod continuation;
+pub mod debug;
+
+#[cfg(feature = "defmt")]
+pub mod defmt_format;
+pub mod dgsm;
+pub mod distributions;
+
+pub mod dual;
+pub use dual::{Dual, Dual1, HyperDual};
+
+pub mod dual_array_vec;
+pub use dual_array_vec::DualArrayVec;
+
+pub mod dual_vec;
+pub use dual_vec::DualVec;
+
+pub mod ekf;
+pub mod finance;
+
+pub mod fit;
+
+#[cfg(feature = "fixed")]
+pub mod fixed_point;
+
+pub mod geo;
+
+pub mod hash;
+pub mod histogram;
+
+#[cfg(feature = "rand")]
+pub mod hmc;
+
+pub mod interval;
+pub mod iter;
+
+pub mod jet;
+pub use jet::Jet;
+
+pub mod krylov;
+pub mod line_search;
+pub mod losses;
+pub mod lut;
+pub mod lut_math;
+pub mod matrix;
+
+pub mod nn;
+
+#[cfg(feature = "tracing")]
+pub mod nonfinite;
+
+pub mod ode;
+
+#[cfg(feature = "op-count")]
+pub mod op_count;
+
+pub mod optim;
+
+pub mod params;
+
+#[cfg(feature = "parse")]
+pub mod parse;
+
+pub mod piecewise;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+pub mod polynomial;
+pub mod quad;
+pub mod quaternion;
+
+#[cfg(feature = "rand")]
+pub mod reparam;
+
+#[cfg(feature = "reverse")]
+pub mod reverse;
+
+#[cfg(feature = "rkyv")]
+pub mod rkyv_archive;
+
+pub mod rollout;
+pub mod sdf;
+pub mod sensitivity;
+pub mod signal;
+pub mod slice;
+
+pub mod soa;
+pub mod solve;
+pub mod sparse;
+pub mod special;
+pub mod spline;
+pub mod stats;
+pub mod surrogate;
+
+pub mod symbolic;
+
+#[cfg(feature = "candle")]
+pub mod tensor;
+pub mod text;
+pub mod transform;
+
+pub mod uncertainty;
+
+#[cfg(feature = "uom")]
+pub mod units;
+
+pub mod vector;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(not(feature = "uom"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_uom {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(not(feature = "op-count"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __count_op {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __check_finite {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(not(feature = "defmt"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_defmt {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(not(feature = "rkyv"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_rkyv {
+    ($($t:tt)*) => {};
+}
+
 /// Simple trait specifying the minimum functionality of a real-like number
 /// All dual types will implement this trait, making it useful to make code
 /// generic between dual and real numbers
+///
+/// The generic helpers of this crate create constants and read values with
+/// [`constant`](Numerical::constant) and [`real`](Numerical::real). These
+/// were added after the other methods, and implementations written before
+/// need to add them.
 pub trait Numerical:
     Copy
     + std::fmt::Debug
@@ -62,6 +233,10 @@ pub trait Numerical:
     + std::ops::MulAssign
     + std::ops::DivAssign
 {
+    /// Create a constant with the specified real value
+    fn constant(real: f64) -> Self;
+    /// The real part, or the value itself for real numbers
+    fn real(&self) -> f64;
     fn powf(self, pow: f64) -> Self;
     fn invert(self) -> Self;
     fn sin(self) -> Self;
@@ -69,9 +244,33 @@ pub trait Numerical:
     fn tan(self) -> Self;
 }
 
+/// Call the `std` method `$t::$method`, or with the `reproducible` feature
+/// the `libm` function `$libm`, whose results do not depend on the platform
+#[cfg(not(feature = "reproducible"))]
+macro_rules! float_fn {
+    ($t:ident::$method:ident / $libm:ident($($arg:expr),*)) => {
+        $t::$method($($arg),*)
+    };
+}
+
+#[cfg(feature = "reproducible")]
+macro_rules! float_fn {
+    ($t:ident::$method:ident / $libm:ident($($arg:expr),*)) => {
+        libm::$libm($($arg),*)
+    };
+}
+
 impl Numerical for f64 {
+    fn constant(real: f64) -> Self {
+        real
+    }
+
+    fn real(&self) -> f64 {
+        *self
+    }
+
     fn powf(self, pow: f64) -> Self {
-        f64::powf(self, pow)
+        float_fn!(f64::powf / pow(self, pow))
     }
 
     fn invert(self) -> Self {
@@ -79,24 +278,209 @@ impl Numerical for f64 {
     }
 
     fn sin(self) -> Self {
-        f64::sin(self)
+        float_fn!(f64::sin / sin(self))
     }
 
     fn cos(self) -> Self {
-        f64::cos(self)
+        float_fn!(f64::cos / cos(self))
     }
 
     fn tan(self) -> Self {
-        f64::tan(self)
+        float_fn!(f64::tan / tan(self))
+    }
+}
+
+/// Trait for the types backing the real and dual parts of a dual number
+/// Implemented for `f32` and `f64`, with the `fixed` feature for the
+/// fixed-point types of the `fixed` crate, and with the `complex` feature for
+/// `num_complex::Complex`. Implementing this trait for a custom number type
+/// allows it to be used as the inner type in [`make_dual`]
+///
+/// With the `reproducible` feature, the transcendental functions of `f32` and
+/// `f64`, here and in [`Numerical`], are computed by `libm` rather than the
+/// platform's math library, so that the results of dual number arithmetic are
+/// bit-for-bit identical on all platforms. Rust evaluates floating-point
+/// operations in program order and never fuses them, so optimization levels
+/// make no difference.
+pub trait Scalar:
+    Copy
+    + PartialEq
+    + std::fmt::Debug
+    + std::fmt::Display
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self>
+    + std::ops::Div<Output = Self>
+    + std::ops::Rem<Output = Self>
+    + std::ops::Neg<Output = Self>
+    + std::ops::AddAssign
+    + std::ops::SubAssign
+    + std::ops::MulAssign
+    + std::ops::DivAssign
+    + std::ops::RemAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_f64(value: f64) -> Self;
+    /// Convert to `f64`, keeping only the real part for complex numbers
+    fn to_f64(self) -> f64;
+    /// Ordering of two numbers, or `None` if they are unordered
+    fn compare(self, other: Self) -> Option<std::cmp::Ordering>;
+
+    fn powf(self, pow: Self) -> Self;
+    fn exp(self) -> Self;
+    /// Natural logarithm
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    /// Round towards zero
+    fn trunc(self) -> Self;
+}
+
+macro_rules! impl_scalar_float {
+    ($t:ident, $pow:ident, $exp:ident, $ln:ident, $sin:ident, $cos:ident) => {
+        impl Scalar for $t {
+            const ZERO: Self = 0.;
+            const ONE: Self = 1.;
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+                self.partial_cmp(&other)
+            }
+
+            fn powf(self, pow: Self) -> Self {
+                float_fn!($t::powf / $pow(self, pow))
+            }
+
+            fn exp(self) -> Self {
+                float_fn!($t::exp / $exp(self))
+            }
+
+            fn ln(self) -> Self {
+                float_fn!($t::ln / $ln(self))
+            }
+
+            fn sin(self) -> Self {
+                float_fn!($t::sin / $sin(self))
+            }
+
+            fn cos(self) -> Self {
+                float_fn!($t::cos / $cos(self))
+            }
+
+            fn trunc(self) -> Self {
+                $t::trunc(self)
+            }
+        }
+    };
+}
+
+impl_scalar_float! {f32, powf, expf, logf, sinf, cosf}
+impl_scalar_float! {f64, pow, exp, log, sin, cos}
+
+/// Trait giving uniform access to the components of a dual number
+/// All types generated by [`make_dual`] implement this trait, making it
+/// possible to write code generic over the specific dual type, such as code
+/// seeding variables or collecting gradients
+///
+/// The methods taking a component index or a number of components panic if
+/// it is out of range, unless the `lenient-components` feature is enabled.
+/// Then reading an out of range component gives NaN, writing one does
+/// nothing, and [`from_parts`](DualNumber::from_parts) with the wrong number
+/// of components gives NaN in every part. The feature only concerns these
+/// accessors, other methods of the generated types may still panic. The
+/// `try_` variants report an out of range index as `None` either way.
+pub trait DualNumber: Numerical {
+    /// Names of the dual components, in declaration order
+    const NAMES: &'static [&'static str];
+    /// Number of dual components
+    const COMPONENTS: usize = Self::NAMES.len();
+
+    /// The `i`th dual component, or `None` if there are not that many
+    fn try_eps(&self, i: usize) -> Option<f64>;
+
+    /// Set the `i`th dual component, or return `None` if there are not that
+    /// many
+    fn try_set_eps(&mut self, i: usize, value: f64) -> Option<()>;
+
+    /// The `i`th dual component
+    fn eps(&self, i: usize) -> f64 {
+        self.try_eps(i)
+            .unwrap_or_else(|| out_of_range("dual component index out of range", f64::NAN))
+    }
+
+    /// Set the `i`th dual component
+    fn set_eps(&mut self, i: usize, value: f64) {
+        if self.try_set_eps(i, value).is_none() {
+            out_of_range("dual component index out of range", ());
+        }
+    }
+
+    /// Create instance with specified real part and a unit dual part in
+    /// component `i`
+    fn variable(real: f64, i: usize) -> Self {
+        let mut v = Self::constant(real);
+        v.set_eps(i, 1.);
+        v
+    }
+
+    /// [`variable`](DualNumber::variable), or `None` if there are not `i`
+    /// components
+    fn try_variable(real: f64, i: usize) -> Option<Self> {
+        let mut v = Self::constant(real);
+        v.try_set_eps(i, 1.)?;
+        Some(v)
+    }
+
+    /// Create instance from a real part and all dual components
+    fn from_parts(real: f64, eps: &[f64]) -> Self {
+        Self::try_from_parts(real, eps).unwrap_or_else(|| {
+            let mut nan = Self::constant(f64::NAN);
+            for i in 0..Self::COMPONENTS {
+                nan.try_set_eps(i, f64::NAN);
+            }
+            out_of_range("wrong number of dual components", nan)
+        })
+    }
+
+    /// [`from_parts`](DualNumber::from_parts), or `None` if the number of
+    /// dual components is wrong
+    fn try_from_parts(real: f64, eps: &[f64]) -> Option<Self> {
+        if eps.len() != Self::COMPONENTS {
+            return None;
+        }
+        let mut v = Self::constant(real);
+        for (i, &e) in eps.iter().enumerate() {
+            v.try_set_eps(i, e)?;
+        }
+        Some(v)
     }
 }
 
+/// Panic with `message`, or return `fallback` with the `lenient-components` feature
+#[cfg(not(feature = "lenient-components"))]
+fn out_of_range<T>(message: &str, _fallback: T) -> T {
+    panic!("{message}")
+}
+
+#[cfg(feature = "lenient-components")]
+fn out_of_range<T>(_message: &str, fallback: T) -> T {
+    fallback
+}
 
 #[macro_export]
 /// # Create a dual number
 /// `$name` specifies the name of the type, $inner specifies the backing type
-/// (either `f32 `or `f64`, defaults to `f64`), and each `$comp` is a dual
-/// compoment of the type.
+/// (any type implementing [`Scalar`], such as `f32 `or `f64`, defaults to
+/// `f64`), and each `$comp` is a dual compoment of the type.
 ///
 /// For example, `make_dual! { SampleXYZ: f64, x, y, z, }` will generate a struct
 /// ```
@@ -118,11 +502,10 @@ impl Numerical for f64 {
 /// Functions with discontinuous intervals or points (such as `abs` at 0, or
 /// `sqrt` at negative numbers) may panic if applied at a discontinuous point.
 /// These methods all have a `try_`-prefix variant returning an `Option<Self>`.
-
 macro_rules! make_dual {
-    ($name:ident, $($comp:ident),+) => { make_dual!{ $name: f64, $($comp,)+ } };
-    ($name:ident, $($comp:ident,)+) => { make_dual!{ $name: f64, $($comp,)+ } };
-    ($name:ident: $inner:ty, $($comp:ident),+) => { make_dual!{ $name: $inner, $($comp,)+ } };
+    ($name:ident, $($comp:ident),+) => { $crate::make_dual!{ $name: f64, $($comp,)+ } };
+    ($name:ident, $($comp:ident,)+) => { $crate::make_dual!{ $name: f64, $($comp,)+ } };
+    ($name:ident: $inner:ty, $($comp:ident),+) => { $crate::make_dual!{ $name: $inner, $($comp,)+ } };
     ($name:ident: $inner:ty, $($comp:ident,)+) => { $crate::paste! {
         macro_rules! impl_reverse {
             ($t:ty, $op:ident, $fn:ident) => {
@@ -164,7 +547,7 @@ macro_rules! make_dual {
 
         impl std::cmp::PartialOrd for $name {
             fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                self.real.partial_cmp(&other.real)
+                $crate::Scalar::compare(self.real, other.real)
             }
         }
 
@@ -173,22 +556,37 @@ macro_rules! make_dual {
                 write!(fmt, "{}", self.real)?;
                 $(
                     let v = self.[< eps_ $comp >];
-                    if v < 0. {
-                        write!(fmt, " - {} eps_{}", -self.[< eps_ $comp >], stringify!($comp))?;
-                    } else if v > 0. {
-                        write!(fmt, " + {} eps_{}", self.[< eps_ $comp >], stringify!($comp))?;
+                    match $crate::Scalar::compare(v, <$inner as $crate::Scalar>::ZERO) {
+                        Some(std::cmp::Ordering::Less) => write!(fmt, " - {} eps_{}", -v, stringify!($comp))?,
+                        Some(std::cmp::Ordering::Equal) => {}
+                        // Positive, or unordered such as complex numbers
+                        _ => write!(fmt, " + {} eps_{}", v, stringify!($comp))?,
                     }
                 )+
                 Ok(())
             }
         }
 
+        impl std::fmt::LowerExp for $name {
+            fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+                let eps = [$((stringify!($comp), $crate::Scalar::to_f64(self.[<eps_ $comp>])),)+];
+                $crate::text::fmt_exp(fmt, $crate::Scalar::to_f64(self.real), eps, false)
+            }
+        }
+
+        impl std::fmt::UpperExp for $name {
+            fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+                let eps = [$((stringify!($comp), $crate::Scalar::to_f64(self.[<eps_ $comp>])),)+];
+                $crate::text::fmt_exp(fmt, $crate::Scalar::to_f64(self.real), eps, true)
+            }
+        }
+
         impl From<$inner> for $name {
             fn from(real: $inner) -> Self {
                 $name {
                     real,
                     $(
-                        [<eps_$comp>]: 0.,
+                        [<eps_$comp>]: <$inner as $crate::Scalar>::ZERO,
                     )+
                 }
             }
@@ -199,7 +597,7 @@ macro_rules! make_dual {
                 /// Create instance with specified real and dual part
                 pub fn [<eps_ $comp>](real: $inner, [<eps_ $comp>]: $inner) -> Self {
                     Self {
-                        [<eps_ $comp>]: [<eps_ $comp>],
+                        [<eps_ $comp>],
                         .. Self::from(real)
                     }
                 }
@@ -207,7 +605,7 @@ macro_rules! make_dual {
             $(
                 /// Create instance with specified real part and unit dual part
                 pub fn $comp(real: $inner) -> Self {
-                    Self::[<eps_ $comp>](real, 1.)
+                    Self::[<eps_ $comp>](real, <$inner as $crate::Scalar>::ONE)
                 }
             )+
 
@@ -219,44 +617,175 @@ macro_rules! make_dual {
                 }
             )+
 
+            $(
+                /// Elasticity `(x / z) dz/dx` with respect to the component,
+                /// given its value `x`, i.e. the relative change of `self` per
+                /// relative change of the variable
+                pub fn [<d_d $comp _rel>](self, x: $inner) -> $inner {
+                    x / self.real * self.[<eps_ $comp>]
+                }
+            )+
+
+            /// Multiply each dual component by the corresponding entry of
+            /// `scales`, e.g. for preconditioning or converting the units of
+            /// the variables
+            pub fn scale_eps_by(self, scales: [$inner; <Self as $crate::DualNumber>::COMPONENTS]) -> Self {
+                let [$( [<scale_ $comp>], )+] = scales;
+                Self {
+                    real: self.real,
+                    $(
+                        [<eps_ $comp>]: self.[<eps_ $comp>] * [<scale_ $comp>],
+                    )+
+                }
+            }
+
+            $(
+                /// Multiply the dual component by `scale`
+                pub fn [<scale_eps_ $comp>](mut self, scale: $inner) -> Self {
+                    self.[<eps_ $comp>] *= scale;
+                    self
+                }
+            )+
+
+            /// Euclidean norm of the dual part, i.e. of the gradient
+            pub fn grad_norm(self) -> $inner {
+                let squared = <$inner as $crate::Scalar>::ZERO $( + self.[<eps_ $comp>] * self.[<eps_ $comp>] )+;
+                $crate::Scalar::powf(squared, $crate::Scalar::from_f64(0.5))
+            }
+
+            /// `self` with the dual part scaled to unit norm, unchanged if the
+            /// dual part is zero
+            pub fn normalize_grad(self) -> Self {
+                let norm = self.grad_norm();
+                if norm == <$inner as $crate::Scalar>::ZERO {
+                    return self;
+                }
+                Self {
+                    real: self.real,
+                    $(
+                        [<eps_ $comp>]: self.[<eps_ $comp>] / norm,
+                    )+
+                }
+            }
+
+            /// `self` with the dual part scaled down to norm `max_norm` if its
+            /// norm is larger, keeping its direction
+            pub fn clamp_grad(self, max_norm: $inner) -> Self {
+                let norm = self.grad_norm();
+                if $crate::Scalar::to_f64(norm) <= $crate::Scalar::to_f64(max_norm) {
+                    return self;
+                }
+                Self {
+                    real: self.real,
+                    $(
+                        [<eps_ $comp>]: self.[<eps_ $comp>] * (max_norm / norm),
+                    )+
+                }
+            }
+
+            /// `self` with each dual component clipped to `[-limit, limit]`
+            pub fn clamp_grad_per_component(self, limit: $inner) -> Self {
+                Self {
+                    real: self.real,
+                    $(
+                        [<eps_ $comp>]: $crate::dual::clip(self.[<eps_ $comp>], limit),
+                    )+
+                }
+            }
+
+            /// Derivative in the direction `direction`, given with one entry
+            /// per component, i.e. the dot product with the gradient
+            pub fn directional_derivative(self, direction: [$inner; <Self as $crate::DualNumber>::COMPONENTS]) -> $inner {
+                let [$( [<dir_ $comp>], )+] = direction;
+                <$inner as $crate::Scalar>::ZERO $( + self.[<eps_ $comp>] * [<dir_ $comp>] )+
+            }
+
+            /// `value` with the derivative `derivative` with respect to
+            /// `self`, in place of the derivative of the expression computing
+            /// it
+            ///
+            /// The dual part is that of `self` times `derivative`, while the
+            /// real part is exactly `value`, e.g. for straight-through
+            /// estimators or black-box functions with known derivatives.
+            pub fn with_derivative(self, value: $inner, derivative: $inner) -> Self {
+                Self {
+                    real: value,
+                    $(
+                        [<eps_ $comp>]: self.[<eps_ $comp>] * derivative,
+                    )+
+                }
+            }
+
+            /// `value` with the derivatives of a function of several inputs,
+            /// given as pairs of an input and the partial derivative with
+            /// respect to it
+            pub fn custom_grad(value: $inner, partials: &[(Self, $inner)]) -> Self {
+                Self {
+                    real: value,
+                    $(
+                        [<eps_ $comp>]: partials.iter().fold(<$inner as $crate::Scalar>::ZERO, |sum, &(input, partial)| {
+                            sum + input.[<eps_ $comp>] * partial
+                        }),
+                    )+
+                }
+            }
+
+            /// Report `self` as the result of `op` on `inputs` if it is the
+            /// first non-finite value, with the `tracing` feature
+            fn checked(self, _op: &'static str, _inputs: &[Self]) -> Self {
+                $crate::__check_finite!(_op, self, _inputs);
+                self
+            }
+
             /// Raise `self` to `pow`
             pub fn powf(self, pow: $inner) -> Self {
+                $crate::__count_op!($name, Transcendental);
                 // power rule: d/dx [x^p] = p x^(p-1)
                 Self {
-                    real: self.real.powf(pow),
+                    real: $crate::Scalar::powf(self.real, pow),
                     $(
-                        [<eps_ $comp>]: self.[<eps_ $comp>] * pow * self.real.powf(pow - 1.),
+                        [<eps_ $comp>]: self.[<eps_ $comp>] * pow * $crate::Scalar::powf(self.real, pow - <$inner as $crate::Scalar>::ONE),
                     )+
-                }
+                }.checked("powf", &[self])
             }
 
             /// Invert `self` (`1./self`)
             pub fn invert(self) -> Self {
-                self.powf(-1.)
+                $crate::__count_op!($name, Mul);
+                // d/dx [1/x] = -1/x^2
+                let square = self.real * self.real;
+                Self {
+                    real: <$inner as $crate::Scalar>::ONE / self.real,
+                    $(
+                        [<eps_ $comp>]: -self.[<eps_ $comp>] / square,
+                    )+
+                }.checked("invert", &[self])
             }
 
             pub fn sin(self) -> Self {
-                let r = self.real.sin();
-                let dr = self.real.cos();
+                $crate::__count_op!($name, Transcendental);
+                let r = $crate::Scalar::sin(self.real);
+                let dr = $crate::Scalar::cos(self.real);
 
                 Self {
                     real: r,
                     $(
                         [<eps_ $comp>]: self.[<eps_ $comp>] * dr,
                     )+
-                }
+                }.checked("sin", &[self])
             }
 
             pub fn cos(self) -> Self {
-                let r = self.real.cos();
-                let dr = -self.real.sin();
+                $crate::__count_op!($name, Transcendental);
+                let r = $crate::Scalar::cos(self.real);
+                let dr = -$crate::Scalar::sin(self.real);
 
                 Self {
                     real: r,
                     $(
                         [<eps_ $comp>]: self.[<eps_ $comp>] * dr,
                     )+
-                }
+                }.checked("cos", &[self])
             }
 
             pub fn tan(self) -> Self {
@@ -268,8 +797,10 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn add(mut self, other: $inner) -> Self::Output {
+                $crate::__count_op!($name, Add);
+                let input = self;
                 self.real += other;
-                self
+                self.checked("add", &[input])
             }
         }
 
@@ -277,11 +808,13 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn add(mut self, other: Self) -> Self::Output {
+                $crate::__count_op!($name, Add);
+                let input = self;
                 self.real += other.real;
                 $(
                     self.[<eps_ $comp>] += other.[<eps_ $comp>];
                 )+
-                self
+                self.checked("add", &[input, other])
             }
         }
 
@@ -289,11 +822,13 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn mul(mut self, other: $inner) -> Self::Output {
+                $crate::__count_op!($name, Mul);
+                let input = self;
                 self.real *= other;
                 $(
                     self.[<eps_ $comp>] *= other;
                 )+
-                self
+                self.checked("mul", &[input])
             }
         }
 
@@ -301,7 +836,7 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn neg(self) -> Self::Output {
-                self * -1.
+                self * -<$inner as $crate::Scalar>::ONE
             }
         }
 
@@ -309,12 +844,13 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn mul(self, other: Self) -> $name {
+                $crate::__count_op!($name, Mul);
                 Self {
                     real: self.real * other.real,
                     $(
                         [<eps_ $comp>]: self.[<eps_ $comp>] * other.real + other.[<eps_ $comp>] * self.real,
                     )+
-                }
+                }.checked("mul", &[self, other])
             }
         }
 
@@ -322,7 +858,15 @@ macro_rules! make_dual {
             type Output = Self;
 
             fn div(self, other: Self) -> Self::Output {
-                self * other.invert()
+                $crate::__count_op!($name, Mul);
+                // quotient rule: d/dx [a/b] = (a' - (a/b) b') / b
+                let real = self.real / other.real;
+                Self {
+                    real,
+                    $(
+                        [<eps_ $comp>]: (self.[<eps_ $comp>] - real * other.[<eps_ $comp>]) / other.real,
+                    )+
+                }.checked("div", &[self, other])
             }
         }
 
@@ -342,11 +886,39 @@ macro_rules! make_dual {
             }
         }
 
-        impl std::ops::Div<f64> for $name {
+        impl std::ops::Rem<$name> for $name {
+            type Output = Self;
+
+            fn rem(self, other: Self) -> Self::Output {
+                $crate::__count_op!($name, Mul);
+                $crate::__count_op!($name, Add);
+                // a % b = a - b * trunc(a / b), where trunc has zero derivative
+                let quot = $crate::Scalar::trunc(self.real / other.real);
+                Self {
+                    real: self.real % other.real,
+                    $(
+                        [<eps_ $comp>]: self.[<eps_ $comp>] - other.[<eps_ $comp>] * quot,
+                    )+
+                }.checked("rem", &[self, other])
+            }
+        }
+
+        impl std::ops::Rem<$inner> for $name {
+            type Output = Self;
+
+            fn rem(mut self, other: $inner) -> Self::Output {
+                $crate::__count_op!($name, Add);
+                let input = self;
+                self.real %= other;
+                self.checked("rem", &[input])
+            }
+        }
+
+        impl std::ops::Div<$inner> for $name {
             type Output = Self;
 
             fn div(self, other: $inner) -> Self::Output {
-                self * (1./other)
+                self * (<$inner as $crate::Scalar>::ONE / other)
             }
         }
 
@@ -354,14 +926,24 @@ macro_rules! make_dual {
         impl_reverse!{$name, Sub, sub}
         impl_reverse!{$name, Mul, mul}
         impl_reverse!{$name, Div, div}
+        impl_reverse!{$name, Rem, rem}
         impl_inplace!{$name, AddAssign, add_assign, Add, add}
         impl_inplace!{$name, SubAssign, sub_assign, Sub, sub}
         impl_inplace!{$name, MulAssign, mul_assign, Mul, mul}
         impl_inplace!{$name, DivAssign, div_assign, Div, div}
+        impl_inplace!{$name, RemAssign, rem_assign, Rem, rem}
 
         impl $crate::Numerical for $name {
+            fn constant(real: f64) -> Self {
+                Self::from(<$inner as $crate::Scalar>::from_f64(real))
+            }
+
+            fn real(&self) -> f64 {
+                $crate::Scalar::to_f64(self.real)
+            }
+
             fn powf(self, pow: f64) -> Self {
-                $name::powf(self, pow)
+                $name::powf(self, $crate::Scalar::from_f64(pow))
             }
 
             fn invert(self) -> Self {
@@ -381,13 +963,30 @@ macro_rules! make_dual {
             }
         }
 
+        impl $crate::DualNumber for $name {
+            const NAMES: &'static [&'static str] = &[$(stringify!($comp),)+];
+
+            fn try_eps(&self, i: usize) -> Option<f64> {
+                [$(self.[<eps_ $comp>],)+].get(i).map(|&e| $crate::Scalar::to_f64(e))
+            }
+
+            fn try_set_eps(&mut self, i: usize, value: f64) -> Option<()> {
+                let eps = [$(&mut self.[<eps_ $comp>],)+].into_iter().nth(i)?;
+                *eps = $crate::Scalar::from_f64(value);
+                Some(())
+            }
+        }
+
+        $crate::__impl_uom!{ $name, $inner, $($comp,)+ }
+        $crate::__impl_defmt!{ $name, $inner, $($comp,)+ }
+        $crate::__impl_rkyv!{ $name, $inner, $($comp,)+ }
     } }
 }
 
 #[cfg(any(test, doc))]
-/// # Sample type
-///
 pub mod sample {
+    //! # Sample type
+    //!
     //! As all types are generated at compile time using [`make_dual`](crate::make_dual), this module serves to show an example generated dual type.
     //!
     //! The type is called `SampleXYZ` and has the fields (components) `x`, `y` and `z`. Function names such as `eps_x` are generated based on the names of the components.
@@ -454,6 +1053,99 @@ mod tests {
         let x = SampleXYZ::x(1.);
         let z = x * x;
         assert_eq!(z.d_dx(), 2.);
+
+        // Power laws have constant elasticity
+        let (x, y) = (SampleXYZ::x(3.), SampleXYZ::y(5.));
+        let z = x.powf(2.) * y.powf(-0.5);
+        assert!((z.d_dx_rel(3.) - 2.).abs() < 1e-15);
+        assert!((z.d_dy_rel(5.) + 0.5).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_rem() {
+        let x = SampleXYZ::x(7.);
+        let y = SampleXYZ::y(3.);
+        assert_eq!(x % 3., SampleXYZ::x(1.));
+        assert_eq!(x % y, SampleXYZ::eps_x(1., 1.) - SampleXYZ::eps_y(0., 2.));
+        assert_eq!(10. % x, SampleXYZ::eps_x(3., -1.));
+    }
+
+    #[test]
+    fn test_dual_number() {
+        use crate::{DualNumber, Numerical};
+
+        assert_eq!(SampleXYZ::NAMES, ["x", "y", "z"]);
+        assert_eq!(SampleXYZ::COMPONENTS, 3);
+        assert_eq!(SampleXYZ::variable(2., 1), SampleXYZ::y(2.));
+        let v = SampleXYZ::from_parts(1., &[2., 3., 4.]);
+        assert_eq!((v.real(), v.eps(0), v.eps(2)), (1., 2., 4.));
+
+        let mut v = SampleXYZ::try_variable(2., 2).unwrap();
+        assert_eq!((v.try_eps(2), v.try_eps(3)), (Some(1.), None));
+        assert_eq!(v.try_set_eps(3, 5.), None);
+        assert_eq!(v, SampleXYZ::z(2.));
+        assert_eq!(SampleXYZ::try_variable(2., 3), None);
+        assert_eq!(SampleXYZ::try_from_parts(1., &[2., 3.]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "lenient-components")]
+    fn test_lenient_components() {
+        use crate::DualNumber;
+
+        let v = std::panic::catch_unwind(|| {
+            let mut v = SampleXYZ::variable(2., 3);
+            v.set_eps(7, 1.);
+            (v, v.eps(3), SampleXYZ::from_parts(1., &[2.]))
+        })
+        .expect("no panics from the component accessors");
+        assert_eq!(v.0, SampleXYZ::from(2.));
+        assert!(v.1.is_nan());
+        assert!(v.2.real.is_nan() && v.2.eps_z.is_nan());
+    }
+
+    #[test]
+    #[cfg(feature = "reproducible")]
+    fn test_reproducible() {
+        let x = crate::Dual::<1>::variable(0.7, 0);
+        let y = x.sin() * x.exp() + x.powf(1.5);
+        let expected = libm::sin(0.7) * libm::exp(0.7) + libm::pow(0.7, 1.5);
+        assert_eq!(y.real.to_bits(), expected.to_bits());
+        let dy = (libm::cos(0.7) * 1.) * libm::exp(0.7)
+            + libm::sin(0.7) * (libm::exp(0.7) * 1.)
+            + 1. * 1.5 * libm::pow(0.7, 0.5);
+        assert_eq!(y.eps[0].to_bits(), dy.to_bits());
+    }
+
+    #[test]
+    fn test_grad_norm() {
+        let v = SampleXYZ::x(1.) * 3. + SampleXYZ::y(2.) * 4.;
+        assert_eq!(v.grad_norm(), 5.);
+        let n = v.normalize_grad();
+        assert_eq!((n.real, n.eps_x, n.eps_y, n.eps_z), (v.real, 0.6, 0.8, 0.));
+        assert_eq!(v.directional_derivative([1., -1., 7.]), -1.);
+        assert_eq!(SampleXYZ::from(2.).normalize_grad(), SampleXYZ::from(2.));
+
+        let c = v.clamp_grad(2.5);
+        assert_eq!((c.real, c.eps_x, c.eps_y, c.eps_z), (v.real, 1.5, 2., 0.));
+        let c = v.clamp_grad_per_component(3.5);
+        assert_eq!((c.eps_x, c.eps_y, c.eps_z), (3., 3.5, 0.));
+    }
+
+    #[test]
+    fn test_scale_eps() {
+        let v = SampleXYZ::x(1.) + SampleXYZ::y(2.) + SampleXYZ::z(3.);
+        let scaled = v.scale_eps_by([2., 0.5, 0.]);
+        assert_eq!((scaled.eps_x, scaled.eps_y, scaled.eps_z), (2., 0.5, 0.));
+        assert_eq!(v.scale_eps_y(10.), SampleXYZ { eps_y: 10., ..v });
+    }
+
+    #[test]
+    fn test_custom_derivatives() {
+        let (x, y) = (SampleXYZ::x(0.4), SampleXYZ::y(3.));
+        assert_eq!(x.with_derivative(0., 1.), SampleXYZ::eps_x(0., 1.));
+        let f = SampleXYZ::custom_grad(1.2, &[(x, 3.), (y, 0.4), (x, 1.)]);
+        assert_eq!((f.real, f.eps_x, f.eps_y, f.eps_z), (1.2, 4., 0.4, 0.));
     }
 
     #[test]
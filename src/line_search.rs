@@ -0,0 +1,227 @@
+//! # Line searches
+//!
+//! Step length selection along a search direction `dir` from `x`, i.e.
+//! approximate minimization of `phi(t) = f(x + t dir)`. The slope `phi'(t)` is
+//! computed by seeding a single dual component with `dir`, so it costs one
+//! dual evaluation regardless of the number of variables.
+//!
+//! ```
+//! use epsilon::line_search;
+//! // f = x^2 + 4 y^2, searching along the negative gradient at (1, 1)
+//! let f = |[x, y]: [epsilon::Dual1; 2]| x * x + 4. * y * y;
+//! let step = line_search::strong_wolfe(f, [1., 1.], [-2., -8.], 1., 1e-4, 0.9).unwrap();
+//! assert!(step.value < 5.);
+//! assert!(step.slope.abs() <= 0.9 * 68.);
+//! ```
+//!
+//! The `_1d` variants take `phi` directly, returning its value and slope,
+//! which allows reusing gradients computed by the caller.
+
+use crate::Dual1;
+
+/// Accepted step of a line search
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    /// Step length
+    pub t: f64,
+    /// `phi(t)`
+    pub value: f64,
+    /// `phi'(t)`
+    pub slope: f64,
+    /// Number of evaluations of `phi`
+    pub evaluations: usize,
+}
+
+/// Maximum number of evaluations of `phi` before a line search fails
+const MAX_EVALUATIONS: usize = 50;
+
+/// Restrict `f` to the line through `x` along `dir`
+fn restrict<const N: usize>(
+    f: impl Fn([Dual1; N]) -> Dual1,
+    x: [f64; N],
+    dir: [f64; N],
+) -> impl FnMut(f64) -> (f64, f64) {
+    move |t| {
+        let y = f(std::array::from_fn(|i| {
+            Dual1::new(x[i] + t * dir[i], [dir[i]])
+        }));
+        (y.real, y.eps[0])
+    }
+}
+
+/// Backtracking line search along `dir` from `x`, halving the step from `t0`
+/// until the Armijo (sufficient decrease) condition with parameter `c1` holds
+///
+/// Returns `None` if `dir` is not a descent direction or no step is found.
+pub fn backtracking<const N: usize>(
+    f: impl Fn([Dual1; N]) -> Dual1,
+    x: [f64; N],
+    dir: [f64; N],
+    t0: f64,
+    c1: f64,
+) -> Option<Step> {
+    let mut phi = restrict(f, x, dir);
+    let initial = phi(0.);
+    backtracking_1d(phi, initial, t0, c1).map(|step| Step {
+        evaluations: step.evaluations + 1,
+        ..step
+    })
+}
+
+/// [`backtracking`] on `phi`, where `initial` is the value and slope at `0`
+pub fn backtracking_1d(
+    mut phi: impl FnMut(f64) -> (f64, f64),
+    initial: (f64, f64),
+    t0: f64,
+    c1: f64,
+) -> Option<Step> {
+    let (value0, slope0) = initial;
+    if slope0 >= 0. {
+        return None;
+    }
+    let mut t = t0;
+    for evaluations in 1..=MAX_EVALUATIONS {
+        let (value, slope) = phi(t);
+        if value <= value0 + c1 * t * slope0 {
+            return Some(Step {
+                t,
+                value,
+                slope,
+                evaluations,
+            });
+        }
+        t /= 2.;
+    }
+    None
+}
+
+/// Line search along `dir` from `x` finding a step satisfying the strong Wolfe
+/// conditions, sufficient decrease with parameter `c1` and
+/// `|phi'(t)| <= c2 |phi'(0)|`, with `0 < c1 < c2 < 1`
+///
+/// The step is expanded from `t0` until the minimum is bracketed, and the
+/// bracket is then shrunk using safeguarded quadratic interpolation. Returns
+/// `None` if `dir` is not a descent direction or no step is found.
+pub fn strong_wolfe<const N: usize>(
+    f: impl Fn([Dual1; N]) -> Dual1,
+    x: [f64; N],
+    dir: [f64; N],
+    t0: f64,
+    c1: f64,
+    c2: f64,
+) -> Option<Step> {
+    let mut phi = restrict(f, x, dir);
+    let initial = phi(0.);
+    strong_wolfe_1d(phi, initial, t0, c1, c2).map(|step| Step {
+        evaluations: step.evaluations + 1,
+        ..step
+    })
+}
+
+/// [`strong_wolfe`] on `phi`, where `initial` is the value and slope at `0`
+///
+/// The accepted step is always the last one `phi` was evaluated at.
+pub fn strong_wolfe_1d(
+    mut phi: impl FnMut(f64) -> (f64, f64),
+    initial: (f64, f64),
+    t0: f64,
+    c1: f64,
+    c2: f64,
+) -> Option<Step> {
+    let (value0, slope0) = initial;
+    if slope0 >= 0. {
+        return None;
+    }
+    let sufficient_decrease = |t: f64, value: f64| value <= value0 + c1 * t * slope0;
+    let curvature = |slope: f64| slope.abs() <= -c2 * slope0;
+
+    // Points (t, phi(t), phi'(t)) bracketing an acceptable step
+    let mut prev = (0., value0, slope0);
+    let mut t = t0;
+    let mut evaluations = 0;
+    let (mut lo, mut hi) = loop {
+        if evaluations == MAX_EVALUATIONS {
+            return None;
+        }
+        let (value, slope) = phi(t);
+        evaluations += 1;
+        if !sufficient_decrease(t, value) || (evaluations > 1 && value >= prev.1) {
+            break (prev, (t, value, slope));
+        }
+        if curvature(slope) {
+            return Some(Step {
+                t,
+                value,
+                slope,
+                evaluations,
+            });
+        }
+        if slope >= 0. {
+            break ((t, value, slope), prev);
+        }
+        prev = (t, value, slope);
+        t *= 2.;
+    };
+
+    while evaluations < MAX_EVALUATIONS {
+        // Minimum of the quadratic matching phi(lo), phi'(lo) and phi(hi),
+        // kept away from the ends of the bracket
+        let d = hi.0 - lo.0;
+        let denom = 2. * (hi.1 - lo.1 - lo.2 * d);
+        let t_min = lo.0 - lo.2 * d * d / denom;
+        let (a, b) = (lo.0 + 0.1 * d, hi.0 - 0.1 * d);
+        let t = if t_min.is_finite() {
+            t_min.clamp(a.min(b), a.max(b))
+        } else {
+            lo.0 + d / 2.
+        };
+
+        let (value, slope) = phi(t);
+        evaluations += 1;
+        if !sufficient_decrease(t, value) || value >= lo.1 {
+            hi = (t, value, slope);
+        } else {
+            if curvature(slope) {
+                return Some(Step {
+                    t,
+                    value,
+                    slope,
+                    evaluations,
+                });
+            }
+            if slope * (hi.0 - lo.0) >= 0. {
+                hi = lo;
+            }
+            lo = (t, value, slope);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backtracking() {
+        let f = |[x]: [Dual1; 1]| x * x;
+        // The steps to -3 and -1 overshoot, the third gives the exact minimum
+        let step = backtracking(f, [1.], [-4.], 1., 1e-4).unwrap();
+        assert_eq!((step.t, step.value, step.evaluations), (0.25, 0., 4));
+        assert_eq!(backtracking(f, [1.], [1.], 1., 1e-4), None);
+    }
+
+    #[test]
+    fn test_strong_wolfe() {
+        let rosenbrock =
+            |[x, y]: [Dual1; 2]| (1. - x) * (1. - x) + 100. * (y - x * x) * (y - x * x);
+        let (c1, c2) = (1e-4, 0.1);
+        for t0 in [1e-4, 1., 100.] {
+            // Steepest descent from (-1.2, 1)
+            let step = strong_wolfe(rosenbrock, [-1.2, 1.], [215.6, 88.], t0, c1, c2).unwrap();
+            let initial = (24.2, -215.6 * 215.6 - 88. * 88.);
+            assert!(step.value <= initial.0 + c1 * step.t * initial.1);
+            assert!(step.slope.abs() <= -c2 * initial.1);
+        }
+    }
+}
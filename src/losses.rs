@@ -0,0 +1,176 @@
+//! # Loss functions
+//!
+//! Common losses between predictions and targets, generic over [`Scalar`] so
+//! that evaluating them on dual numbers gives their gradients with respect
+//! to the model parameters. The regression losses average over the elements,
+//! and the classification losses take unnormalized logits, using formulations
+//! which stay finite for large logits.
+//!
+//! ```
+//! use epsilon::losses::mse;
+//! use epsilon::Dual;
+//! // Gradient of the mean squared error of a line y = a x + b
+//! let [a, b] = Dual::<2>::variables([1., 0.]);
+//! let xs = [0., 1., 2.];
+//! let pred = xs.map(|x| a * Dual::constant(x) + b);
+//! let loss = mse(&pred, &[1., 2., 3.].map(Dual::constant));
+//!
+//! assert_eq!(loss.real, 1.);
+//! // d/db = 2 mean(pred - target)
+//! assert_eq!(loss.eps[1], -2.);
+//! ```
+
+use crate::special::softplus;
+use crate::Scalar;
+
+fn abs<T: Scalar>(x: T) -> T {
+    if x.to_f64() < 0. {
+        -x
+    } else {
+        x
+    }
+}
+
+/// Mean of `loss` applied to the pairs of elements
+fn mean<T: Scalar>(pred: &[T], target: &[T], loss: impl Fn(T, T) -> T) -> T {
+    assert_eq!(
+        pred.len(),
+        target.len(),
+        "predictions and targets differ in length"
+    );
+    let sum = pred
+        .iter()
+        .zip(target)
+        .fold(T::ZERO, |sum, (&p, &t)| sum + loss(p, t));
+    sum / T::from_f64(pred.len() as f64)
+}
+
+/// Mean squared error
+pub fn mse<T: Scalar>(pred: &[T], target: &[T]) -> T {
+    mean(pred, target, |p, t| (p - t) * (p - t))
+}
+
+/// Mean absolute error, with the derivative taken as zero where the error is
+/// zero
+pub fn mae<T: Scalar>(pred: &[T], target: &[T]) -> T {
+    mean(pred, target, |p, t| abs(p - t))
+}
+
+/// Huber loss, quadratic `0.5 d^2` for errors `d` up to `delta` and linear
+/// beyond, making it less sensitive to outliers than [`mse`]
+pub fn huber<T: Scalar>(pred: &[T], target: &[T], delta: f64) -> T {
+    let half = T::from_f64(0.5);
+    let delta = T::from_f64(delta);
+    mean(pred, target, |p, t| {
+        let d = abs(p - t);
+        if d.to_f64() <= delta.to_f64() {
+            half * d * d
+        } else {
+            delta * (d - half * delta)
+        }
+    })
+}
+
+/// Mean of `ln(cosh(d))` over the errors `d`, a smooth alternative to
+/// [`huber`]
+pub fn log_cosh<T: Scalar>(pred: &[T], target: &[T]) -> T {
+    // ln(cosh(d)) = |d| + ln(1 + exp(-2|d|)) - ln(2)
+    let ln_2 = T::from_f64(std::f64::consts::LN_2);
+    mean(pred, target, |p, t| {
+        let d = abs(p - t);
+        d + softplus(d * T::from_f64(-2.)) - ln_2
+    })
+}
+
+/// Logarithm of the softmax of `logits`, `x_i - ln(sum_j exp(x_j))`,
+/// shifted by the largest logit to avoid overflow
+pub fn log_softmax<T: Scalar>(logits: &[T]) -> Vec<T> {
+    let Some(&max) = logits
+        .iter()
+        .max_by(|a, b| a.to_f64().total_cmp(&b.to_f64()))
+    else {
+        return Vec::new();
+    };
+    let sum = logits.iter().fold(T::ZERO, |sum, &x| sum + (x - max).exp());
+    let ln_sum = max + sum.ln();
+    logits.iter().map(|&x| x - ln_sum).collect()
+}
+
+/// Mean binary cross-entropy of the probabilities `sigmoid(logits)` against
+/// the targets in `[0, 1]`
+pub fn binary_cross_entropy<T: Scalar>(logits: &[T], target: &[T]) -> T {
+    // -y ln(sigmoid(x)) - (1 - y) ln(1 - sigmoid(x)) = softplus(x) - x y
+    mean(logits, target, |x, y| softplus(x) - x * y)
+}
+
+/// Cross-entropy of the probabilities `softmax(logits)` against the target
+/// distribution `target`, e.g. one-hot labels
+pub fn categorical_cross_entropy<T: Scalar>(logits: &[T], target: &[T]) -> T {
+    assert_eq!(
+        logits.len(),
+        target.len(),
+        "logits and targets differ in length"
+    );
+    log_softmax(logits)
+        .into_iter()
+        .zip(target)
+        .fold(T::ZERO, |sum, (l, &t)| sum - t * l)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dual;
+
+    #[test]
+    fn test_regression() {
+        let pred = [1., 2., 6.];
+        let target = [1.5, 2., 3.];
+        assert_eq!(mse(&pred, &target), (0.25 + 9.) / 3.);
+        assert_eq!(mae(&pred, &target), 3.5 / 3.);
+        assert_eq!(huber(&pred, &target, 1.), (0.125 + 2.5) / 3.);
+        let expected = (0.5f64.cosh().ln() + 3f64.cosh().ln()) / 3.;
+        assert!((log_cosh(&pred, &target) - expected).abs() < 1e-15);
+        // Finite far beyond where cosh overflows
+        assert!((log_cosh(&[1000.], &[0.]) - (1000. - 2f64.ln())).abs() < 1e-12);
+
+        // Huber is linear with slope delta for large errors, log-cosh with
+        // slope tanh(d)
+        let [p] = Dual::<1>::variables([6.]);
+        let t = Dual::constant(3.);
+        assert_eq!(huber(&[p], &[t], 1.).eps[0], 1.);
+        assert!((log_cosh(&[p], &[t]).eps[0] - 3f64.tanh()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_classification() {
+        let logits = [1., 2., 3.];
+        let ln_sum = (1f64.exp() + 2f64.exp() + 3f64.exp()).ln();
+        for (l, x) in log_softmax(&logits).iter().zip(logits) {
+            assert!((l - (x - ln_sum)).abs() < 1e-15);
+        }
+        // Large logits neither overflow nor lose the small probabilities
+        let l = log_softmax(&[1000f64, 0.]);
+        assert_eq!(l[0], 0.);
+        assert!((l[1] + 1000.).abs() < 1e-12);
+        assert_eq!(categorical_cross_entropy(&[1000., 0.], &[0., 1.]), 1000.);
+
+        // The gradient of the cross-entropy is softmax(logits) - target
+        let x = Dual::<3>::variables(logits);
+        let loss = categorical_cross_entropy(&x, &[0., 1., 0.].map(Dual::constant));
+        for (i, &e) in loss.eps.iter().enumerate() {
+            let softmax = (logits[i] - ln_sum).exp();
+            assert!((e - (softmax - [0., 1., 0.][i])).abs() < 1e-15);
+        }
+
+        // Binary cross-entropy, with the gradient sigmoid(x) - y
+        let sigmoid = |x: f64| 1. / (1. + (-x).exp());
+        let bce = binary_cross_entropy(&[0.5, -2.], &[1., 0.]);
+        let expected = -(sigmoid(0.5).ln() + (1. - sigmoid(-2.)).ln()) / 2.;
+        assert!((bce - expected).abs() < 1e-15);
+        assert_eq!(binary_cross_entropy(&[-800.], &[1.]), 800.);
+        let [x] = Dual::<1>::variables([0.5]);
+        let bce = binary_cross_entropy(&[x], &[Dual::constant(1.)]);
+        assert!((bce.eps[0] - (sigmoid(0.5) - 1.)).abs() < 1e-15);
+    }
+}
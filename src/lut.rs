@@ -0,0 +1,208 @@
+//! # Lookup tables
+//!
+//! Tables of plain `f64` values on uniform grids in one and two dimensions,
+//! sampled at coordinates of any [`Numerical`] type by linear or cubic
+//! interpolation. With dual numbers as the coordinates, the derivatives of
+//! the interpolant with respect to them are propagated, so tabulated data
+//! such as material properties or textures can be used inside
+//! differentiated code.
+//!
+//! ```
+//! use epsilon::lut::Lut2;
+//! use epsilon::Dual;
+//! // f(x, y) = x + 2 y tabulated on [0, 3] x [0, 3]
+//! let values = (0..4).map(|i| (0..4).map(|j| (i + 2 * j) as f64).collect()).collect();
+//! let lut = Lut2::new([0., 0.], [1., 1.], values);
+//! let [x, y] = Dual::<2>::variables([1.25, 0.5]);
+//! let z = lut.bilinear(x, y);
+//!
+//! assert_eq!(z.real, 2.25);
+//! assert_eq!(z.eps, [1., 2.]);
+//! ```
+//!
+//! Outside the grid, the coordinates are clamped to it, so the value at the
+//! nearest edge is returned with a zero derivative.
+
+use crate::Numerical;
+
+/// Index of the cell containing `x` on the grid `start + i step` with `n`
+/// points, and the position `t` in `[0, 1]` within it
+fn locate<T: Numerical>(x: T, start: f64, step: f64, n: usize) -> (usize, T) {
+    let u = (x - T::constant(start)) / T::constant(step);
+    let (u0, last) = (u.real(), (n - 1) as f64);
+    if u0 <= 0. {
+        (0, T::constant(0.))
+    } else if u0 >= last {
+        (n - 2, T::constant(1.))
+    } else {
+        let i = (u0.floor() as usize).min(n - 2);
+        (i, u - T::constant(i as f64))
+    }
+}
+
+fn lerp<T: Numerical>(a: T, b: T, t: T) -> T {
+    a + (b - a) * t
+}
+
+/// Catmull-Rom interpolation between `p[1]` and `p[2]` at `t` in `[0, 1]`
+fn catmull_rom<T: Numerical>(p: [T; 4], t: T) -> T {
+    let half = T::constant(0.5);
+    let c1 = (p[2] - p[0]) * half;
+    let c2 = p[0] - p[1] * T::constant(2.5) + p[2] * T::constant(2.) - p[3] * half;
+    let c3 = (p[3] - p[0]) * half + (p[1] - p[2]) * T::constant(1.5);
+    p[1] + t * (c1 + t * (c2 + t * c3))
+}
+
+/// The four points around cell `i` for cubic interpolation, replicating the
+/// values at the edges
+fn stencil(i: usize, n: usize) -> [usize; 4] {
+    [i.saturating_sub(1), i, i + 1, (i + 2).min(n - 1)]
+}
+
+/// Table of the values at `start + i step`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut1 {
+    start: f64,
+    step: f64,
+    values: Vec<f64>,
+}
+
+impl Lut1 {
+    /// # Panics
+    ///
+    /// If there are fewer than two values, or `step` is not positive.
+    pub fn new(start: f64, step: f64, values: Vec<f64>) -> Self {
+        assert!(
+            values.len() >= 2,
+            "a lookup table needs at least two values"
+        );
+        assert!(step > 0., "step must be positive");
+        Lut1 {
+            start,
+            step,
+            values,
+        }
+    }
+
+    /// Piecewise linear interpolation at `x`
+    pub fn linear<T: Numerical>(&self, x: T) -> T {
+        let (i, t) = locate(x, self.start, self.step, self.values.len());
+        let v = |i: usize| T::constant(self.values[i]);
+        lerp(v(i), v(i + 1), t)
+    }
+
+    /// Cubic (Catmull-Rom) interpolation at `x`, with a continuous first
+    /// derivative
+    pub fn cubic<T: Numerical>(&self, x: T) -> T {
+        let n = self.values.len();
+        let (i, t) = locate(x, self.start, self.step, n);
+        catmull_rom(stencil(i, n).map(|i| T::constant(self.values[i])), t)
+    }
+}
+
+/// Table of the values `values[i][j]` at `(origin[0] + i step[0], origin[1]
+/// + j step[1])`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut2 {
+    origin: [f64; 2],
+    step: [f64; 2],
+    values: Vec<Vec<f64>>,
+}
+
+impl Lut2 {
+    /// # Panics
+    ///
+    /// If there are fewer than two values in either direction, the rows of
+    /// `values` differ in length, or the steps are not positive.
+    pub fn new(origin: [f64; 2], step: [f64; 2], values: Vec<Vec<f64>>) -> Self {
+        let ny = values.first().map_or(0, Vec::len);
+        assert!(
+            values.len() >= 2 && ny >= 2,
+            "a lookup table needs at least two values in each direction"
+        );
+        assert!(
+            values.iter().all(|row| row.len() == ny),
+            "rows must have the same length"
+        );
+        assert!(step[0] > 0. && step[1] > 0., "step must be positive");
+        Lut2 {
+            origin,
+            step,
+            values,
+        }
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        (self.values.len(), self.values[0].len())
+    }
+
+    /// Bilinear interpolation at `(x, y)`
+    pub fn bilinear<T: Numerical>(&self, x: T, y: T) -> T {
+        let (nx, ny) = self.shape();
+        let (i, tx) = locate(x, self.origin[0], self.step[0], nx);
+        let (j, ty) = locate(y, self.origin[1], self.step[1], ny);
+        let v = |i: usize, j: usize| T::constant(self.values[i][j]);
+        lerp(
+            lerp(v(i, j), v(i, j + 1), ty),
+            lerp(v(i + 1, j), v(i + 1, j + 1), ty),
+            tx,
+        )
+    }
+
+    /// Bicubic (Catmull-Rom) interpolation at `(x, y)`, with continuous
+    /// first derivatives
+    pub fn bicubic<T: Numerical>(&self, x: T, y: T) -> T {
+        let (nx, ny) = self.shape();
+        let (i, tx) = locate(x, self.origin[0], self.step[0], nx);
+        let (j, ty) = locate(y, self.origin[1], self.step[1], ny);
+        let columns = stencil(j, ny);
+        let rows = stencil(i, nx)
+            .map(|i| catmull_rom(columns.map(|j| T::constant(self.values[i][j])), ty));
+        catmull_rom(rows, tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lut1, Lut2};
+    use crate::Dual1;
+
+    #[test]
+    fn test_lut1() {
+        // Catmull-Rom reproduces quadratics in the interior
+        let lut = Lut1::new(
+            -1.,
+            0.5,
+            (0..9).map(|i| (-1. + 0.5 * i as f64).powi(2)).collect(),
+        );
+        let y = lut.cubic(Dual1::variable(0.3, 0));
+        assert!((y.real - 0.09).abs() < 1e-15 && (y.eps[0] - 0.6).abs() < 1e-14);
+        let y = lut.linear(Dual1::variable(0.25, 0));
+        assert_eq!((y.real, y.eps[0]), (0.125, 0.5));
+
+        // Clamped outside, with zero derivative
+        let y = lut.linear(Dual1::variable(5., 0));
+        assert_eq!((y.real, y.eps[0]), (9., 0.));
+        assert_eq!(lut.cubic(-2.), 1.);
+        assert_eq!(lut.cubic(0.5), 0.25);
+        assert!(lut.linear(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_lut2() {
+        // f = x y + y^2 is reproduced by bicubic interpolation in the interior
+        let f = |x: f64, y: f64| x * y + y * y;
+        let values = (0..6)
+            .map(|i| (0..5).map(|j| f(i as f64 * 0.5, j as f64)).collect())
+            .collect();
+        let lut = Lut2::new([0., 0.], [0.5, 1.], values);
+        let (x, y) = (1.2, 2.3);
+        let zx = lut.bicubic(Dual1::variable(x, 0), Dual1::constant(y));
+        let zy = lut.bicubic(Dual1::constant(x), Dual1::variable(y, 0));
+        assert!((zx.real - f(x, y)).abs() < 1e-14);
+        assert!((zx.eps[0] - y).abs() < 1e-14 && (zy.eps[0] - (x + 2. * y)).abs() < 1e-13);
+
+        // Bilinear interpolation matches at the grid points
+        assert_eq!(lut.bilinear(1.5, 3.), f(1.5, 3.));
+    }
+}
@@ -0,0 +1,290 @@
+//! # Table-based transcendental functions
+//!
+//! [`sin`], [`cos`] and [`exp`] evaluated by quintic Hermite interpolation of
+//! tables of 129 values computed at compile time, using the known first and
+//! second derivatives at the nodes. For moderate arguments they are accurate
+//! to a few units in the last place, and they take a fixed number of basic
+//! operations, which suits targets without a floating-point unit or with hard
+//! real-time deadlines.
+//!
+//! With the `lut-math` feature, `Lut` is an `f64` implementing
+//! [`Scalar`](crate::Scalar) with these functions. Dual numbers with it as the inner type
+//! compute `sin`, `cos` and `exp`, and their derivatives, from the tables,
+//! while all other numbers are unaffected.
+//!
+//! ```
+//! use epsilon::lut_math;
+//!
+//! assert!((lut_math::sin(1.) - 1f64.sin()).abs() < 1e-15);
+//! assert!((lut_math::exp(3.) / 3f64.exp() - 1.).abs() < 1e-15);
+//!
+//! # #[cfg(feature = "lut-math")] {
+//! use epsilon::lut_math::Lut;
+//! use epsilon::Dual;
+//! let x = Dual::<1, Lut>::variable(Lut(0.5), 0);
+//! let y = x.sin();
+//! assert_eq!((y.real, y.eps[0]), (Lut(lut_math::sin(0.5)), Lut(lut_math::cos(0.5))));
+//! # }
+//! ```
+
+use std::f64::consts::{FRAC_2_PI, FRAC_PI_2, LN_2};
+
+/// Number of intervals of the tables
+const SIZE: usize = 128;
+
+/// `sin(i / SIZE * pi / 2)`
+static SIN: [f64; SIZE + 1] = table(FRAC_PI_2, true);
+
+/// `2^(i / SIZE)`
+static EXP2: [f64; SIZE + 1] = table(LN_2, false);
+
+/// Values at `i / SIZE * range` of `sin` if `sin`, and of `exp` otherwise,
+/// by their Taylor series
+const fn table(range: f64, sin: bool) -> [f64; SIZE + 1] {
+    let mut values = [0.; SIZE + 1];
+    let mut i = 0;
+    while i <= SIZE {
+        let x = i as f64 / SIZE as f64 * range;
+        let (mut term, mut sum) = if sin { (x, x) } else { (1., 1.) };
+        let mut n = 1;
+        while n < 30 {
+            term *= if sin {
+                -x * x / ((2 * n * (2 * n + 1)) as f64)
+            } else {
+                x / n as f64
+            };
+            sum += term;
+            n += 1;
+        }
+        values[i] = sum;
+        i += 1;
+    }
+    // Exact at the end, where the series accumulates rounding errors
+    values[SIZE] = if sin { 1. } else { 2. };
+    values
+}
+
+/// Quintic Hermite interpolation between `y0` and `y1` with first
+/// derivatives `d0` and `d1` and second derivatives `s0` and `s1`, with
+/// respect to `t` in `[0, 1]`
+fn hermite([y0, y1]: [f64; 2], [d0, d1]: [f64; 2], [s0, s1]: [f64; 2], t: f64) -> f64 {
+    let dy = y1 - y0;
+    let c3 = 10. * dy - 6. * d0 - 4. * d1 - 0.5 * (3. * s0 - s1);
+    let c4 = -15. * dy + 8. * d0 + 7. * d1 + 0.5 * (3. * s0 - 2. * s1);
+    let c5 = 6. * dy - 3. * (d0 + d1) - 0.5 * (s0 - s1);
+    y0 + t * (d0 + t * (0.5 * s0 + t * (c3 + t * (c4 + t * c5))))
+}
+
+/// Index of the interval containing `p` in `[0, SIZE)`, and the position in
+/// it
+fn locate(p: f64) -> (usize, f64) {
+    let i = (p as usize).min(SIZE - 1);
+    (i, p - i as f64)
+}
+
+/// `sin` and `cos` of `r` in `[0, pi / 2]`
+fn quarter(r: f64) -> (f64, f64) {
+    let step = FRAC_PI_2 / SIZE as f64;
+    let (i, t) = locate(r / step);
+    let sin = [SIN[i], SIN[i + 1]];
+    let cos = [SIN[SIZE - i], SIN[SIZE - i - 1]];
+    let scaled = |v: [f64; 2], h: f64| v.map(|v| v * h);
+    (
+        hermite(sin, scaled(cos, step), scaled(sin, -step * step), t),
+        hermite(cos, scaled(sin, -step), scaled(cos, -step * step), t),
+    )
+}
+
+/// `pi / 2` split into a part with trailing zero bits, whose multiples by
+/// integers are exact, and the remainder
+const PIO2_HI: f64 = 1.5707963267341256;
+const PIO2_LO: f64 = 6.077100506506192e-11;
+
+/// `sin(x + quadrant pi / 2)`
+fn sin_quadrant(x: f64, quadrant: f64) -> f64 {
+    if !x.is_finite() {
+        return f64::NAN;
+    }
+    // The nearest multiple, keeping the relative accuracy near the zeros
+    let whole = (x * FRAC_2_PI).round();
+    let r = x - whole * PIO2_HI - whole * PIO2_LO;
+    let (s, c) = quarter(r.abs());
+    let s = if r < 0. { -s } else { s };
+    match (whole + quadrant).rem_euclid(4.) as u8 {
+        0 => s,
+        1 => c,
+        2 => -s,
+        _ => -c,
+    }
+}
+
+/// Sine, with the argument reduced in `f64`, losing accuracy for large `x`
+pub fn sin(x: f64) -> f64 {
+    sin_quadrant(x, 0.)
+}
+
+/// Cosine, with the argument reduced in `f64`, losing accuracy for large `x`
+pub fn cos(x: f64) -> f64 {
+    sin_quadrant(x, 1.)
+}
+
+/// `ln(2)` split like [`PIO2_HI`] and [`PIO2_LO`]
+const LN2_HI: f64 = 6.931471803691238e-1;
+const LN2_LO: f64 = 1.9082149292705877e-10;
+
+/// Exponential function, `2^k 2^r` with `x / ln(2) = k + r`
+pub fn exp(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    } else if x > 709.8 {
+        return f64::INFINITY;
+    } else if x < -745.2 {
+        return 0.;
+    }
+    let k = (x / LN_2).floor();
+    let step = LN_2 / SIZE as f64;
+    let (i, t) = locate((x - k * LN2_HI - k * LN2_LO) / step);
+    let y = [EXP2[i], EXP2[i + 1]];
+    let r = hermite(y, y.map(|y| y * step), y.map(|y| y * step * step), t);
+    // In two factors, as 2^k alone overflows for the smallest results
+    let half = (k / 2.).floor();
+    r * 2f64.powi(half as i32) * 2f64.powi((k - half) as i32)
+}
+
+/// `f64` whose `sin`, `cos` and `exp` are evaluated by the functions of this
+/// module, for use as the inner type of dual numbers
+#[cfg(feature = "lut-math")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Lut(pub f64);
+
+#[cfg(feature = "lut-math")]
+mod lut_scalar {
+    use super::Lut;
+    use crate::Scalar;
+
+    impl std::fmt::Display for Lut {
+        fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+            self.0.fmt(fmt)
+        }
+    }
+
+    impl From<f64> for Lut {
+        fn from(x: f64) -> Self {
+            Lut(x)
+        }
+    }
+
+    macro_rules! impl_op {
+        ($op:ident, $fn:ident, $op_inplace:ident, $fn_inplace:ident) => {
+            impl std::ops::$op for Lut {
+                type Output = Self;
+
+                fn $fn(self, other: Self) -> Self {
+                    Lut(std::ops::$op::$fn(self.0, other.0))
+                }
+            }
+
+            impl std::ops::$op_inplace for Lut {
+                fn $fn_inplace(&mut self, other: Self) {
+                    std::ops::$op_inplace::$fn_inplace(&mut self.0, other.0);
+                }
+            }
+        };
+    }
+
+    impl_op! {Add, add, AddAssign, add_assign}
+    impl_op! {Sub, sub, SubAssign, sub_assign}
+    impl_op! {Mul, mul, MulAssign, mul_assign}
+    impl_op! {Div, div, DivAssign, div_assign}
+    impl_op! {Rem, rem, RemAssign, rem_assign}
+
+    impl std::ops::Neg for Lut {
+        type Output = Self;
+
+        fn neg(self) -> Self {
+            Lut(-self.0)
+        }
+    }
+
+    impl Scalar for Lut {
+        const ZERO: Self = Lut(0.);
+        const ONE: Self = Lut(1.);
+
+        fn from_f64(value: f64) -> Self {
+            Lut(value)
+        }
+
+        fn to_f64(self) -> f64 {
+            self.0
+        }
+
+        fn compare(self, other: Self) -> Option<std::cmp::Ordering> {
+            self.partial_cmp(&other)
+        }
+
+        fn powf(self, pow: Self) -> Self {
+            Lut(Scalar::powf(self.0, pow.0))
+        }
+
+        fn exp(self) -> Self {
+            Lut(super::exp(self.0))
+        }
+
+        fn ln(self) -> Self {
+            Lut(Scalar::ln(self.0))
+        }
+
+        fn sin(self) -> Self {
+            Lut(super::sin(self.0))
+        }
+
+        fn cos(self) -> Self {
+            Lut(super::cos(self.0))
+        }
+
+        fn trunc(self) -> Self {
+            Lut(self.0.trunc())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cos, exp, sin};
+
+    #[test]
+    fn test_trig() {
+        for i in -2000..2000 {
+            let x = i as f64 * 0.01;
+            assert!((sin(x) - x.sin()).abs() < 1e-15, "{x}");
+            assert!((cos(x) - x.cos()).abs() < 1e-15, "{x}");
+        }
+        assert_eq!((sin(0.), cos(0.)), (0., 1.));
+        assert!(sin(f64::INFINITY).is_nan() && cos(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_exp() {
+        for i in -7000..7000 {
+            let x = i as f64 * 0.1;
+            assert!((exp(x) / x.exp() - 1.).abs() < 1e-15, "{x}");
+        }
+        assert_eq!(exp(0.), 1.);
+        assert_eq!((exp(-1000.), exp(1000.)), (0., f64::INFINITY));
+        assert!(exp(-744.).abs() > 0. && exp(f64::NAN).is_nan());
+    }
+
+    #[cfg(feature = "lut-math")]
+    #[test]
+    fn test_lut_scalar() {
+        use super::Lut;
+        use crate::Dual;
+
+        let x = Dual::<1, Lut>::variable(Lut(1.5), 0);
+        let y = (x * Lut(2.)).exp() + x.cos();
+        assert_eq!(y.real, Lut(exp(3.) + cos(1.5)));
+        assert_eq!(y.eps[0], Lut(2. * exp(3.) - sin(1.5)));
+        // Other dual numbers keep using the standard library
+        assert_eq!(Dual::<1>::variable(1.5, 0).cos().real, 1.5f64.cos());
+    }
+}
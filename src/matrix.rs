@@ -0,0 +1,626 @@
+//! # Small square matrices
+//!
+//! Stack-allocated `N`x`N` matrices over any [`Numerical`] type, with the
+//! determinant, inverse and solutions of linear systems, for differentiable
+//! small linear algebra such as coordinate transforms without depending on a
+//! linear algebra crate. Pivots are chosen by the magnitude of the real parts.
+//! Symmetric eigendecompositions and singular value decompositions are
+//! computed from the real parts, with the first derivatives propagated by
+//! perturbation theory. The matrix exponential [`Matrix::expm`] gives the
+//! derivatives of linear ODE propagators `e^(A t)` with respect to parameters
+//! in `A`.
+//!
+//! ```
+//! use epsilon::matrix::Matrix;
+//! use epsilon::Dual;
+//! // Rotation by an angle, with derivatives with respect to the angle
+//! let [a] = Dual::<1>::variables([0.3]);
+//! let r = Matrix::new([[a.cos(), -a.sin()], [a.sin(), a.cos()]]);
+//!
+//! let det = r.det();
+//! assert!((det.real - 1.).abs() < 1e-15 && det.eps[0].abs() < 1e-15);
+//! let [x, y] = r * [Dual::constant(1.), Dual::constant(0.)];
+//! assert_eq!((x.real, y.real), (0.3f64.cos(), 0.3f64.sin()));
+//! assert_eq!(y.eps[0], 0.3f64.cos());
+//! ```
+
+use crate::Numerical;
+
+/// `N`x`N` matrix, stored row by row
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix<T, const N: usize> {
+    pub rows: [[T; N]; N],
+}
+
+/// Matrix of 2x2 elements
+pub type Matrix2<T> = Matrix<T, 2>;
+/// Matrix of 3x3 elements
+pub type Matrix3<T> = Matrix<T, 3>;
+/// Matrix of 4x4 elements
+pub type Matrix4<T> = Matrix<T, 4>;
+
+/// LU decomposition with partial pivoting, `P A = L U`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Lu<T, const N: usize> {
+    /// `U` on and above the diagonal, the multipliers of `L` below it
+    lu: [[T; N]; N],
+    /// Row `i` of `P A` is row `perm[i]` of `A`
+    perm: [usize; N],
+    /// Whether the permutation is odd
+    odd: bool,
+}
+
+impl<T: Numerical, const N: usize> Matrix<T, N> {
+    pub fn new(rows: [[T; N]; N]) -> Self {
+        Matrix { rows }
+    }
+
+    pub fn zero() -> Self {
+        Matrix::new([[T::constant(0.); N]; N])
+    }
+
+    pub fn identity() -> Self {
+        Matrix::new(std::array::from_fn(|i| {
+            std::array::from_fn(|j| T::constant(if i == j { 1. } else { 0. }))
+        }))
+    }
+
+    /// Matrix with the entries `f(row, column)`
+    pub fn from_fn(mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Matrix::new(std::array::from_fn(|i| std::array::from_fn(|j| f(i, j))))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Matrix::from_fn(|i, j| self.rows[j][i])
+    }
+
+    /// Sum of the diagonal entries
+    pub fn trace(&self) -> T {
+        (0..N).fold(T::constant(0.), |sum, i| sum + self.rows[i][i])
+    }
+
+    /// The real parts of the entries
+    pub fn real(&self) -> Matrix<f64, N> {
+        Matrix::from_fn(|i, j| self.rows[i][j].real())
+    }
+
+    fn lu(&self) -> Option<Lu<T, N>> {
+        let mut lu = self.rows;
+        let mut perm = std::array::from_fn(|i| i);
+        let mut odd = false;
+        for k in 0..N {
+            let pivot =
+                (k..N).max_by(|&i, &j| lu[i][k].real().abs().total_cmp(&lu[j][k].real().abs()))?;
+            if lu[pivot][k].real() == 0. {
+                return None;
+            }
+            if pivot != k {
+                lu.swap(k, pivot);
+                perm.swap(k, pivot);
+                odd = !odd;
+            }
+
+            let (top, bottom) = lu.split_at_mut(k + 1);
+            let row_k = &top[k];
+            for row in bottom {
+                let m = row[k] / row_k[k];
+                row[k] = m;
+                for (a_ij, &a_kj) in row[k + 1..].iter_mut().zip(&row_k[k + 1..]) {
+                    *a_ij -= m * a_kj;
+                }
+            }
+        }
+        Some(Lu { lu, perm, odd })
+    }
+
+    /// Determinant, zero if the matrix is singular
+    pub fn det(&self) -> T {
+        match self.lu() {
+            Some(Lu { lu, odd, .. }) => {
+                let det = (0..N).fold(T::constant(1.), |det, i| det * lu[i][i]);
+                if odd {
+                    T::constant(0.) - det
+                } else {
+                    det
+                }
+            }
+            None => T::constant(0.),
+        }
+    }
+
+    /// Solve `self x = b`, or return `None` if the matrix is singular
+    pub fn solve(&self, b: [T; N]) -> Option<[T; N]> {
+        Some(self.lu()?.solve(b))
+    }
+
+    /// Inverse, or `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let lu = self.lu()?;
+        let columns = std::array::from_fn(|j| {
+            lu.solve(std::array::from_fn(|i| {
+                T::constant(if i == j { 1. } else { 0. })
+            }))
+        });
+        Some(Matrix::<T, N>::new(columns).transpose())
+    }
+}
+
+/// Degree of the numerator and denominator of the Padé approximant in
+/// [`Matrix::expm`], accurate to about `3e-16` for norms up to `1/2`
+const EXPM_DEGREE: usize = 6;
+
+impl<T: Numerical, const N: usize> Matrix<T, N> {
+    /// Matrix exponential `e^A = I + A + A^2 / 2! + ...`
+    ///
+    /// Uses scaling and squaring: `A` is divided by a power of two `2^s` such
+    /// that the infinity norm of its real part is at most `1/2`, the
+    /// exponential of that is approximated by a diagonal Padé approximant, and
+    /// the result squared `s` times. The scaling depends only on the real
+    /// parts, so the derivatives are those of a fixed rational function of
+    /// `A`, accurate to about the same relative precision as the values. NaN
+    /// if any entry is not finite.
+    pub fn expm(&self) -> Self {
+        let norm = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|a| a.real().abs()).sum::<f64>())
+            .fold(0., f64::max);
+        if !norm.is_finite() {
+            return Matrix::from_fn(|_, _| T::constant(f64::NAN));
+        }
+        let squarings = (norm.log2().floor() as i32 + 2).max(0);
+        let a = *self * T::constant(0.5f64.powi(squarings));
+
+        // Numerator and denominator of the Padé approximant, sum c_k A^k and
+        // sum (-1)^k c_k A^k
+        let q = EXPM_DEGREE;
+        let mut c = 0.5;
+        let mut power = a;
+        let mut num = Matrix::identity() + a * T::constant(c);
+        let mut den = Matrix::identity() - a * T::constant(c);
+        for k in 2..=q {
+            c *= (q - k + 1) as f64 / (k * (2 * q - k + 1)) as f64;
+            power = a * power;
+            num = num + power * T::constant(c);
+            den = if k % 2 == 0 {
+                den + power * T::constant(c)
+            } else {
+                den - power * T::constant(c)
+            };
+        }
+
+        // The denominator is close to the identity for norms up to 1/2
+        let Some(lu) = den.lu() else {
+            return Matrix::from_fn(|_, _| T::constant(f64::NAN));
+        };
+        let columns = std::array::from_fn(|j| lu.solve(std::array::from_fn(|i| num.rows[i][j])));
+        let mut exp = Matrix::<T, N>::new(columns).transpose();
+        for _ in 0..squarings {
+            exp = exp * exp;
+        }
+        exp
+    }
+}
+
+impl<T: Numerical, const N: usize> Lu<T, N> {
+    fn solve(&self, b: [T; N]) -> [T; N] {
+        // Forward substitution with L, which has a unit diagonal
+        let mut y: [T; N] = std::array::from_fn(|i| b[self.perm[i]]);
+        for i in 0..N {
+            for j in 0..i {
+                let l = self.lu[i][j];
+                let y_j = y[j];
+                y[i] -= l * y_j;
+            }
+        }
+        // Back substitution with U
+        for i in (0..N).rev() {
+            for j in i + 1..N {
+                let u = self.lu[i][j];
+                let y_j = y[j];
+                y[i] -= u * y_j;
+            }
+            y[i] /= self.lu[i][i];
+        }
+        y
+    }
+}
+
+/// Eigendecomposition of a symmetric matrix, see [`Matrix::symmetric_eigen`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eigen<T, const N: usize> {
+    /// Eigenvalues in increasing order
+    pub values: [T; N],
+    /// Unit eigenvectors as the columns, in the order of the eigenvalues
+    pub vectors: Matrix<T, N>,
+}
+
+/// Singular value decomposition `A = U diag(s) V^T`, see [`Matrix::svd`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Svd<T, const N: usize> {
+    /// Left singular vectors as the columns
+    pub u: Matrix<T, N>,
+    /// Singular values in decreasing order
+    pub singular_values: [T; N],
+    /// Right singular vectors as the columns
+    pub v: Matrix<T, N>,
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a real symmetric matrix by
+/// cyclic Jacobi rotations, in increasing order of the eigenvalues
+fn jacobi<const N: usize>(mut a: [[f64; N]; N]) -> ([f64; N], [[f64; N]; N]) {
+    const MAX_SWEEPS: usize = 100;
+    let mut v: [[f64; N]; N] = Matrix::identity().rows;
+    let norm: f64 = a.iter().flatten().map(|a| a * a).sum();
+    for _ in 0..MAX_SWEEPS {
+        let off: f64 = (0..N)
+            .flat_map(|p| (0..N).filter(move |&q| q != p).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off <= f64::EPSILON * f64::EPSILON * norm {
+            break;
+        }
+        for p in 0..N {
+            for q in p + 1..N {
+                if a[p][q] == 0. {
+                    continue;
+                }
+                // Rotation in the (p, q) plane zeroing a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+                let t = theta.signum() / (theta.abs() + theta.hypot(1.));
+                let c = 1. / t.hypot(1.);
+                let s = t * c;
+                let rotate = |x: f64, y: f64| (c * x - s * y, s * x + c * y);
+                for row in a.iter_mut().chain(v.iter_mut()) {
+                    (row[p], row[q]) = rotate(row[p], row[q]);
+                }
+                let (top, bottom) = a.split_at_mut(q);
+                for (x, y) in top[p].iter_mut().zip(&mut bottom[0]) {
+                    (*x, *y) = rotate(*x, *y);
+                }
+            }
+        }
+    }
+
+    let mut order: [usize; N] = std::array::from_fn(|i| i);
+    order.sort_by(|&i, &j| a[i][i].total_cmp(&a[j][j]));
+    let values = order.map(|i| a[i][i]);
+    let vectors = std::array::from_fn(|k| order.map(|i| v[k][i]));
+    (values, vectors)
+}
+
+impl<T: Numerical, const N: usize> Matrix<T, N> {
+    /// `u^T self v` for constant vectors
+    fn bilinear(&self, u: &[f64; N], v: &[f64; N]) -> T {
+        let mut sum = T::constant(0.);
+        for (row, &u) in self.rows.iter().zip(u) {
+            for (&a, &v) in row.iter().zip(v) {
+                sum += a * T::constant(u * v);
+            }
+        }
+        sum
+    }
+
+    /// Eigenvalues and eigenvectors of a symmetric matrix
+    ///
+    /// The decomposition is computed from the real parts, and the derivatives
+    /// are propagated by first-order perturbation theory: the derivative of
+    /// the eigenvalue `l_i` is `v_i^T dA v_i`, and that of the eigenvector
+    /// `v_i` is `sum_j v_j (v_j^T dA v_i) / (l_i - l_j)` over `j != i`. The
+    /// eigenvector derivatives are not finite for repeated eigenvalues.
+    /// Higher derivatives, e.g. of nested duals, are not propagated.
+    pub fn symmetric_eigen(&self) -> Eigen<T, N> {
+        let (values, vectors) = jacobi(self.real().rows);
+        let columns: [[f64; N]; N] = std::array::from_fn(|i| vectors.map(|row| row[i]));
+
+        // Rayleigh quotients, whose first derivatives are v_i^T dA v_i
+        let dual_values = columns.each_ref().map(|v| self.bilinear(v, v));
+        let dual_vectors = Matrix::from_fn(|k, i| {
+            let mut x = T::constant(columns[i][k]);
+            for (j, v_j) in columns.iter().enumerate().filter(|&(j, _)| j != i) {
+                let coupling = self.bilinear(v_j, &columns[i]);
+                let d = coupling - T::constant(coupling.real());
+                x += d * T::constant(v_j[k] / (values[i] - values[j]));
+            }
+            x
+        });
+        Eigen {
+            values: dual_values,
+            vectors: dual_vectors,
+        }
+    }
+
+    /// Singular value decomposition
+    ///
+    /// The decomposition is computed from the real parts, with the right
+    /// singular vectors from the eigendecomposition of `A^T A`. With
+    /// `dP = U^T dA V`, the derivative of the singular value `s_i` is `dP_ii`,
+    /// and those of the singular vectors are `dU = U W_U` and `dV = V W_V`
+    /// with, for `i != j`,
+    ///
+    /// ```text
+    /// (W_U)_ij = (s_j dP_ij + s_i dP_ji) / (s_j^2 - s_i^2)
+    /// (W_V)_ij = (s_i dP_ij + s_j dP_ji) / (s_j^2 - s_i^2)
+    /// ```
+    ///
+    /// The singular vector derivatives are not finite for repeated singular
+    /// values, including several zero singular values. Higher derivatives are
+    /// not propagated.
+    pub fn svd(&self) -> Svd<T, N> {
+        let a = self.real();
+        let (values, vectors) = jacobi((a.transpose() * a).rows);
+        // Decreasing singular values
+        let s: [f64; N] = std::array::from_fn(|i| values[N - 1 - i].max(0.).sqrt());
+        let v: [[f64; N]; N] = std::array::from_fn(|i| vectors.map(|row| row[N - 1 - i]));
+
+        // u_i = A v_i / s_i, completed to an orthonormal basis by Gram-Schmidt
+        // for zero singular values
+        let mut u = [[0.; N]; N];
+        for i in 0..N {
+            let mut candidates = std::iter::once(a * v[i])
+                .chain((0..N).map(|k| std::array::from_fn(|l| if l == k { 1. } else { 0. })));
+            u[i] = loop {
+                let Some(mut x) = candidates.next() else {
+                    break [f64::NAN; N];
+                };
+                for u in &u[..i] {
+                    let dot: f64 = x.iter().zip(u).map(|(x, u)| x * u).sum();
+                    for (x, u) in x.iter_mut().zip(u) {
+                        *x -= dot * u;
+                    }
+                }
+                let norm = x.iter().map(|x| x * x).sum::<f64>().sqrt();
+                if norm > f64::EPSILON * s[0].max(1.) {
+                    break x.map(|x| x / norm);
+                }
+            };
+        }
+
+        let p: Self = Matrix::from_fn(|i, j| {
+            let p = self.bilinear(&u[i], &v[j]);
+            p - T::constant(p.real())
+        });
+        let rotated = |basis: &[[f64; N]; N], w: &dyn Fn(usize, usize) -> T| {
+            Matrix::from_fn(|k, j| {
+                let mut x = T::constant(basis[j][k]);
+                for (i, b) in basis.iter().enumerate().filter(|&(i, _)| i != j) {
+                    x += w(i, j) * T::constant(b[k] / (s[j] * s[j] - s[i] * s[i]));
+                }
+                x
+            })
+        };
+        let dual_u = rotated(&u, &|i, j| {
+            p[(i, j)] * T::constant(s[j]) + p[(j, i)] * T::constant(s[i])
+        });
+        let dual_v = rotated(&v, &|i, j| {
+            p[(i, j)] * T::constant(s[i]) + p[(j, i)] * T::constant(s[j])
+        });
+        Svd {
+            u: dual_u,
+            singular_values: std::array::from_fn(|i| T::constant(s[i]) + p[(i, i)]),
+            v: dual_v,
+        }
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Index<(usize, usize)> for Matrix<T, N> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.rows[i][j]
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::IndexMut<(usize, usize)> for Matrix<T, N> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.rows[i][j]
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Add for Matrix<T, N> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Matrix::from_fn(|i, j| self.rows[i][j] + other.rows[i][j])
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Sub for Matrix<T, N> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Matrix::from_fn(|i, j| self.rows[i][j] - other.rows[i][j])
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Mul<T> for Matrix<T, N> {
+    type Output = Self;
+
+    fn mul(self, other: T) -> Self {
+        Matrix::from_fn(|i, j| self.rows[i][j] * other)
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Mul<[T; N]> for Matrix<T, N> {
+    type Output = [T; N];
+
+    fn mul(self, v: [T; N]) -> [T; N] {
+        self.rows
+            .map(|row| (0..N).fold(T::constant(0.), |sum, j| sum + row[j] * v[j]))
+    }
+}
+
+impl<T: Numerical, const N: usize> std::ops::Mul for Matrix<T, N> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Matrix::from_fn(|i, j| {
+            (0..N).fold(T::constant(0.), |sum, k| {
+                sum + self.rows[i][k] * other.rows[k][j]
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Matrix, Matrix3};
+    use crate::Dual;
+
+    #[test]
+    fn test_empty() {
+        let m = Matrix::<f64, 0>::new([]);
+        assert_eq!((m.trace(), m.det()), (0., 1.));
+        assert_eq!(m * [], [0f64; 0]);
+        assert_eq!(m * m, m);
+        assert_eq!((m.inverse(), m.expm()), (Some(m), m));
+        assert!(m.symmetric_eigen().values.is_empty() && m.svd().singular_values.is_empty());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a = Matrix3::new([[0., 2., 1.], [1., 1., 0.], [3., 0., 1.]]);
+        assert_eq!(a.det(), -5.);
+        let inv = a.inverse().unwrap();
+        let product = (a * inv).rows;
+        for (i, row) in product.iter().enumerate() {
+            for (j, &p) in row.iter().enumerate() {
+                assert!((p - if i == j { 1. } else { 0. }).abs() < 1e-15);
+            }
+        }
+        assert_eq!(
+            a.solve([3., 2., 4.]).map(|x| x.map(f64::round)),
+            Some([1., 1., 1.])
+        );
+
+        let singular = Matrix::new([[1., 2.], [2., 4.]]);
+        assert_eq!(singular.det(), 0.);
+        assert_eq!(singular.inverse(), None);
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // d det(A) / dt = det(A) tr(A^-1 dA/dt) for A = A0 + t B
+        let t = Dual::<1>::variable(0.5, 0);
+        let a0 = [[2., 1., 0.], [1., 3., 1.], [0., 1., 4.]];
+        let b = [[1., 0., 2.], [0., -1., 0.], [1., 0., 1.]];
+        let a = Matrix::from_fn(|i, j| t * b[i][j] + a0[i][j]);
+        let det = a.det();
+        let inv = a.real().inverse().unwrap();
+        let jacobi = det.real * (inv * Matrix::new(b)).trace();
+        assert!((det.eps[0] - jacobi).abs() < 1e-12);
+
+        // d(A^-1)/dt = -A^-1 B A^-1, and the derivative of a solution
+        let d_inv = (inv * Matrix::new(b) * inv).rows;
+        let a_inv = a.inverse().unwrap();
+        for (row, expected) in a_inv.rows.iter().zip(d_inv) {
+            for (x, e) in row.iter().zip(expected) {
+                assert!((x.eps[0] + e).abs() < 1e-12);
+            }
+        }
+        let rhs = [1., 2., 3.].map(Dual::constant);
+        let x = a.solve(rhs).unwrap();
+        for (x, y) in x.iter().zip(a_inv * rhs) {
+            assert!((x.real - y.real).abs() < 1e-12 && (x.eps[0] - y.eps[0]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        // Inertia tensor of a box with side lengths depending on (w, h)
+        let [w, h] = Dual::<2>::variables([1., 2.]);
+        let d = 0.5;
+        let (xx, yy, zz) = (h * h + d * d, w * w + d * d, w * w + h * h);
+        let offset = w * h * 0.3;
+        let zero = Dual::constant(0.);
+        let a = Matrix3::new([[xx, offset, zero], [offset, yy, zero], [zero, zero, zz]]);
+        let eigen = a.symmetric_eigen();
+
+        // A v = l v and v^T v = 1 hold for the real and the dual parts
+        for i in 0..3 {
+            let l = eigen.values[i];
+            let v = eigen.vectors.rows.map(|row| row[i]);
+            for (av, v) in (a * v).iter().zip(v) {
+                let r = *av - l * v;
+                assert!(r.real.abs() < 1e-12 && r.eps.iter().all(|e| e.abs() < 1e-12));
+            }
+            let norm = v.iter().fold(zero, |sum, &v| sum + v * v);
+            assert!((norm.real - 1.).abs() < 1e-12 && norm.eps.iter().all(|e| e.abs() < 1e-12));
+        }
+        // zz = w^2 + h^2 = 5 is the largest eigenvalue
+        assert!(eigen.values[0].real < eigen.values[1].real);
+        assert!((eigen.values[2].real - 5.).abs() < 1e-12);
+        assert!((eigen.values[2].eps[0] - 2.).abs() < 1e-12);
+        assert!((eigen.values[2].eps[1] - 4.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_svd() {
+        let t = Dual::<1>::variable(0.2, 0);
+        let a0 = [[3., 1., 0.], [1., -2., 1.], [0.5, 0., 1.]];
+        let b = [[0., 1., 0.], [2., 0., 0.], [0., 0., -1.]];
+        let a = Matrix3::from_fn(|i, j| t * b[i][j] + a0[i][j]);
+        let svd = a.svd();
+        let s = svd.singular_values;
+        assert!(s[0].real > s[1].real && s[1].real > s[2].real && s[2].real > 0.);
+
+        // A = U diag(s) V^T for the real and the dual parts
+        let sigma = Matrix::from_fn(|i, j| if i == j { s[i] } else { Dual::constant(0.) });
+        let product = svd.u * sigma * svd.v.transpose();
+        for (row, expected) in product.rows.iter().zip(a.rows) {
+            for (x, e) in row.iter().zip(expected) {
+                assert!((x.real - e.real).abs() < 1e-12);
+                assert!((x.eps[0] - e.eps[0]).abs() < 1e-12);
+            }
+        }
+
+        // Singular values of a rank-deficient matrix
+        let svd = Matrix::new([[1., 2.], [2., 4.]]).svd();
+        assert!((svd.singular_values[0] - 5.).abs() < 1e-12);
+        assert!(svd.singular_values[1].abs() < 1e-7);
+        assert!(svd.u.rows.iter().flatten().all(|u| u.is_finite()));
+    }
+
+    #[test]
+    fn test_expm() {
+        // Rotation generator, e^(t J) = [[cos t, sin t], [-sin t, cos t]]
+        let t = Dual::<1>::variable(2.5, 0);
+        let zero = Dual::constant(0.);
+        let exp = Matrix::new([[zero, t], [-t, zero]]).expm();
+        let (c, s) = (2.5f64.cos(), 2.5f64.sin());
+        for (x, (value, slope)) in
+            exp.rows
+                .iter()
+                .flatten()
+                .zip([(c, -s), (s, c), (-s, -c), (c, -s)])
+        {
+            assert!((x.real - value).abs() < 1e-14 && (x.eps[0] - slope).abs() < 1e-14);
+        }
+
+        // d/da e^(a M) = M e^(a M), also with a large norm
+        let m = Matrix3::new([[-1., 2., 0.5], [0., -3., 1.], [1., 0.5, -2.]]);
+        for a in [0.1, 1.7, 12.] {
+            let a = Dual::<1>::variable(a, 0);
+            let exp = Matrix::from_fn(|i, j| a * m.rows[i][j]).expm();
+            let expected = m * exp.real();
+            let scale = expected
+                .rows
+                .iter()
+                .flatten()
+                .fold(0., |m: f64, e| m.max(e.abs()));
+            for (x, e) in exp
+                .rows
+                .iter()
+                .flatten()
+                .zip(expected.rows.iter().flatten())
+            {
+                assert!((x.eps[0] - e).abs() < 1e-13 * scale);
+            }
+        }
+
+        // Nilpotent matrix, e^N = I + N
+        let exp = Matrix::new([[0., 3.], [0., 0.]]).expm();
+        assert_eq!(exp.rows, [[1., 3.], [0., 1.]]);
+        assert!(Matrix::new([[f64::INFINITY]]).expm().rows[0][0].is_nan());
+    }
+}
@@ -0,0 +1,182 @@
+//! # Neural networks
+//!
+//! The forward pass of small fully connected networks with given weights,
+//! generic over [`Scalar`]. Evaluating a network on dual number inputs gives
+//! the exact derivatives of its outputs with respect to the inputs, e.g. for
+//! saliency maps or the residuals of physics-informed networks, without a
+//! tensor framework. Training is out of scope, the weights are plain `f64`.
+//!
+//! ```
+//! use epsilon::nn::{Activation, Dense, Mlp};
+//! use epsilon::Dual;
+//! // y = tanh(x0 - x1), and the gradient with respect to the inputs
+//! let network = Mlp::new(vec![Dense::new(vec![vec![1., -1.]], vec![0.], Activation::Tanh)]);
+//! let y = network.forward(&Dual::<2>::variables([0.5, 0.2]));
+//!
+//! let slope = 1. - 0.3f64.tanh().powi(2);
+//! assert!((y[0].eps[0] - slope).abs() < 1e-15);
+//! assert!((y[0].eps[1] + slope).abs() < 1e-15);
+//! ```
+
+use crate::special::{logistic, softplus};
+use crate::Scalar;
+
+/// Nonlinearity applied elementwise after a [`Dense`] layer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Activation {
+    Identity,
+    /// `max(0, x)`, with the derivative taken as zero at zero
+    Relu,
+    /// `1 / (1 + exp(-x))`
+    Sigmoid,
+    Tanh,
+    /// `ln(1 + exp(x))`
+    Softplus,
+}
+
+impl Activation {
+    pub fn apply<T: Scalar>(self, x: T) -> T {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => {
+                if x.to_f64() > 0. {
+                    x
+                } else {
+                    T::ZERO
+                }
+            }
+            Activation::Sigmoid => logistic(x),
+            Activation::Tanh => {
+                let (sign, x) = if x.to_f64() < 0. {
+                    (-T::ONE, -x)
+                } else {
+                    (T::ONE, x)
+                };
+                let e = (x * T::from_f64(-2.)).exp();
+                sign * (T::ONE - e) / (T::ONE + e)
+            }
+            Activation::Softplus => softplus(x),
+        }
+    }
+}
+
+/// Fully connected layer `activation(weights x + bias)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dense {
+    /// One row of input weights per output
+    pub weights: Vec<Vec<f64>>,
+    pub bias: Vec<f64>,
+    pub activation: Activation,
+}
+
+impl Dense {
+    /// Panics if the rows of `weights` differ in length, or their number
+    /// differs from the length of `bias`
+    pub fn new(weights: Vec<Vec<f64>>, bias: Vec<f64>, activation: Activation) -> Self {
+        assert_eq!(weights.len(), bias.len(), "one bias per output is needed");
+        assert!(
+            weights.windows(2).all(|w| w[0].len() == w[1].len()),
+            "weight rows differ in length"
+        );
+        Dense {
+            weights,
+            bias,
+            activation,
+        }
+    }
+
+    /// Number of inputs
+    pub fn inputs(&self) -> usize {
+        self.weights.first().map_or(0, Vec::len)
+    }
+
+    /// Number of outputs
+    pub fn outputs(&self) -> usize {
+        self.bias.len()
+    }
+
+    pub fn forward<T: Scalar>(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.inputs(), "wrong number of inputs");
+        self.weights
+            .iter()
+            .zip(&self.bias)
+            .map(|(row, &b)| {
+                let z = row
+                    .iter()
+                    .zip(x)
+                    .fold(T::from_f64(b), |z, (&w, &x)| z + T::from_f64(w) * x);
+                self.activation.apply(z)
+            })
+            .collect()
+    }
+}
+
+/// Multilayer perceptron, a sequence of [`Dense`] layers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mlp {
+    pub layers: Vec<Dense>,
+}
+
+impl Mlp {
+    /// Panics if the outputs of a layer do not match the inputs of the next
+    pub fn new(layers: Vec<Dense>) -> Self {
+        assert!(
+            layers.windows(2).all(|l| l[0].outputs() == l[1].inputs()),
+            "layer sizes do not match"
+        );
+        Mlp { layers }
+    }
+
+    pub fn forward<T: Scalar>(&self, x: &[T]) -> Vec<T> {
+        self.layers
+            .iter()
+            .fold(x.to_vec(), |x, layer| layer.forward(&x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Activation, Dense, Mlp};
+    use crate::Dual;
+
+    #[test]
+    fn test_activations() {
+        let sigmoid = |x: f64| 1. / (1. + (-x).exp());
+        for x in [-3., -0.5, 0., 2.] {
+            let [d] = Dual::<1>::variables([x]);
+            let y = Activation::Sigmoid.apply(d);
+            assert!((y.real - sigmoid(x)).abs() < 1e-15);
+            assert!((y.eps[0] - sigmoid(x) * (1. - sigmoid(x))).abs() < 1e-15);
+            let y = Activation::Tanh.apply(d);
+            assert!((y.real - x.tanh()).abs() < 1e-15);
+            let y = Activation::Softplus.apply(d);
+            assert!((y.eps[0] - sigmoid(x)).abs() < 1e-15);
+        }
+        // No overflow for large inputs
+        assert_eq!(Activation::Sigmoid.apply(-1000.), 0.);
+        assert_eq!(Activation::Tanh.apply(1000.), 1.);
+        assert_eq!(Activation::Softplus.apply(1000.), 1000.);
+        assert_eq!(Activation::Relu.apply(-2.), 0.);
+    }
+
+    #[test]
+    fn test_mlp() {
+        // Two inputs, a hidden ReLU layer of three and a linear output
+        let network = Mlp::new(vec![
+            Dense::new(
+                vec![vec![1., 0.], vec![0., 1.], vec![1., 1.]],
+                vec![0., 0., -1.],
+                Activation::Relu,
+            ),
+            Dense::new(vec![vec![2., 3., 4.]], vec![0.5], Activation::Identity),
+        ]);
+        // Hidden layer (1, 2, 2) at (1, 2), all active
+        let y = network.forward(&Dual::<2>::variables([1., 2.]));
+        assert_eq!(y[0].real, 2. + 6. + 8. + 0.5);
+        assert_eq!(y[0].eps, [6., 7.]);
+
+        // The third unit is inactive at (0.2, 0.3)
+        let y = network.forward(&Dual::<2>::variables([0.2, 0.3]));
+        assert_eq!(y[0].eps, [2., 3.]);
+    }
+}
@@ -0,0 +1,165 @@
+//! # Non-finite results
+//!
+//! With the `tracing` feature, operations on a [`Dual`](crate::Dual) or a type generated
+//! by [`make_dual`](crate::make_dual) emit a [`tracing`] event when they
+//! produce a NaN or infinite real part or dual component from finite inputs,
+//! i.e. where a non-finite value first appears rather than at every
+//! operation it propagates through. The events have the fields `op`, the
+//! name of the operation, `type`, the name of the dual type, `real`, the real
+//! part of the result, and `components`, the indices of the non-finite dual
+//! components. Whether and at which level events are emitted is set by
+//! [`set_policy`].
+//!
+//! ```
+//! use epsilon::nonfinite::{self, Policy};
+//! use epsilon::Dual;
+//!
+//! nonfinite::set_policy(Policy::Error);
+//! let [x] = Dual::<1>::variables([0.]);
+//! // Emits an error event for `ln`, but not for the multiplication
+//! let y = x.ln() * x;
+//! assert!(y.real.is_nan());
+//! ```
+
+use crate::check::Components;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How non-finite results are reported
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// No events
+    Ignore,
+    /// Events at the `WARN` level
+    #[default]
+    Warn,
+    /// Events at the `ERROR` level
+    Error,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(Policy::Warn as u8);
+
+/// Set the policy for all threads, [`Policy::Warn`] by default
+pub fn set_policy(policy: Policy) {
+    POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+pub fn policy() -> Policy {
+    match POLICY.load(Ordering::Relaxed) {
+        0 => Policy::Ignore,
+        1 => Policy::Warn,
+        _ => Policy::Error,
+    }
+}
+
+/// Whether the real part and all dual components are finite
+fn is_finite<T: Components>(value: &T) -> bool {
+    value.value().is_finite() && (0..value.components()).all(|i| value.component(i).is_finite())
+}
+
+/// Report `result` of `op` if it is not finite while all `inputs` are
+#[doc(hidden)]
+pub fn check<T: Components>(op: &'static str, result: &T, inputs: &[T]) {
+    let policy = policy();
+    if policy == Policy::Ignore || is_finite(result) || !inputs.iter().all(is_finite) {
+        return;
+    }
+    let r#type = std::any::type_name::<T>();
+    let real = result.value();
+    let components: Vec<usize> = (0..result.components())
+        .filter(|&i| !result.component(i).is_finite())
+        .collect();
+    if policy == Policy::Warn {
+        tracing::warn!(op, r#type, real, ?components, "non-finite result");
+    } else {
+        tracing::error!(op, r#type, real, ?components, "non-finite result");
+    }
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __check_finite {
+    ($op:expr, $result:expr, $inputs:expr) => {
+        $crate::nonfinite::check($op, &$result, $inputs)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_policy, Policy};
+    use crate::sample::SampleXYZ;
+    use crate::Dual;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    /// Collects the level and the `op` field of each event
+    #[derive(Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<(Level, String)>>>);
+
+    struct Op(String);
+
+    impl Visit for Op {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "op" {
+                self.0 = value.to_string();
+            }
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    impl Subscriber for Collect {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut op = Op(String::new());
+            event.record(&mut op);
+            self.0
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), op.0));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_events() {
+        let collect = Collect::default();
+        tracing::subscriber::with_default(collect.clone(), || {
+            // Only the origin of the NaN is reported
+            let [x, y] = Dual::<2>::variables([-1., 2.]);
+            let _ = (x.powf(0.5) + y) * y;
+            let _ = SampleXYZ::x(0.).invert() * 2.;
+
+            set_policy(Policy::Ignore);
+            let _ = x.ln();
+            set_policy(Policy::Error);
+            let _ = x.ln();
+            set_policy(Policy::Warn);
+        });
+        let events = collect.0.lock().unwrap().clone();
+        let expected = [
+            (Level::WARN, "powf"),
+            (Level::WARN, "invert"),
+            (Level::ERROR, "ln"),
+        ];
+        assert_eq!(events.len(), expected.len());
+        for ((level, op), (l, o)) in events.iter().zip(expected) {
+            assert_eq!((level, op.as_str()), (&l, o));
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! # Ordinary differential equations
+//!
+//! Explicit Runge-Kutta integrators for `y' = f(t, y)`, generic over the
+//! [`Numerical`] state type. Integrating with dual numbers as the state
+//! propagates the forward sensitivities of the trajectory, i.e. the
+//! derivatives with respect to the initial conditions and any parameters of
+//! `f` seeded as variables.
+//!
+//! ```
+//! use epsilon::{ode, Dual};
+//! // Exponential decay y' = -k y, with the sensitivities of y(1) to y(0) and k
+//! let [y0, k] = Dual::<2>::variables([2., 0.5]);
+//! let trajectory = ode::rk45(|_, [y]| [-k * y], 0., [y0], 1., 1e-10);
+//! let (t, [y]) = trajectory.last();
+//!
+//! assert_eq!(t, 1.);
+//! assert!((y.real - 2. * (-0.5f64).exp()).abs() < 1e-9);
+//! assert!((y.eps[0] - (-0.5f64).exp()).abs() < 1e-9); // dy/dy0 = exp(-k)
+//! assert!((y.eps[1] + 2. * (-0.5f64).exp()).abs() < 1e-9); // dy/dk = -y0 exp(-k)
+//! ```
+//!
+//! For long simulations of Hamiltonian systems `q'' = a(q)`, the symplectic
+//! integrators [`velocity_verlet`] and [`yoshida4`] keep the energy error
+//! bounded instead of letting it drift, and propagate the sensitivities in
+//! the same way.
+
+use crate::Numerical;
+
+/// Solution of an ODE at the time points chosen by the integrator
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trajectory<T, const N: usize> {
+    /// Time points, starting with the initial time
+    pub t: Vec<f64>,
+    /// State at each time point
+    pub y: Vec<[T; N]>,
+}
+
+impl<T: Numerical, const N: usize> Trajectory<T, N> {
+    /// The final time point and state
+    pub fn last(&self) -> (f64, [T; N]) {
+        let i = self.t.len() - 1;
+        (self.t[i], self.y[i])
+    }
+}
+
+/// `y + h * sum(a_j k_j)`
+fn combine<T: Numerical, const N: usize>(y: [T; N], h: f64, terms: &[(f64, [T; N])]) -> [T; N] {
+    let mut y = y;
+    for &(a, k) in terms {
+        if a != 0. {
+            let a = T::constant(h * a);
+            for (y, k) in y.iter_mut().zip(k) {
+                *y += a * k;
+            }
+        }
+    }
+    y
+}
+
+/// Integrate from `t0` to `t1` using `steps` steps of the classical
+/// fourth-order Runge-Kutta method
+pub fn rk4<T: Numerical, const N: usize>(
+    f: impl Fn(f64, [T; N]) -> [T; N],
+    t0: f64,
+    y0: [T; N],
+    t1: f64,
+    steps: usize,
+) -> Trajectory<T, N> {
+    let h = (t1 - t0) / steps as f64;
+    let mut trajectory = Trajectory {
+        t: vec![t0],
+        y: vec![y0],
+    };
+    let mut y = y0;
+    for i in 0..steps {
+        let t = t0 + i as f64 * h;
+        let k1 = f(t, y);
+        let k2 = f(t + h / 2., combine(y, h, &[(0.5, k1)]));
+        let k3 = f(t + h / 2., combine(y, h, &[(0.5, k2)]));
+        let k4 = f(t + h, combine(y, h, &[(1., k3)]));
+        y = combine(
+            y,
+            h,
+            &[(1. / 6., k1), (1. / 3., k2), (1. / 3., k3), (1. / 6., k4)],
+        );
+        trajectory.t.push(if i + 1 == steps { t1 } else { t + h });
+        trajectory.y.push(y);
+    }
+    trajectory
+}
+
+/// Integrate from `t0` to `t1` using the adaptive Dormand-Prince 5(4) method
+///
+/// The step size is chosen to keep the estimated local error of each state
+/// component below `tol * (1 + |y|)`. Only the real parts are used for the
+/// error estimate, the accuracy of the dual parts follows that of the real
+/// parts. Integration stops early if the step size becomes negligible
+/// compared to the interval, or if `f` gives a NaN or infinite value, in
+/// which case the last time point is before `t1`.
+pub fn rk45<T: Numerical, const N: usize>(
+    f: impl Fn(f64, [T; N]) -> [T; N],
+    t0: f64,
+    y0: [T; N],
+    t1: f64,
+    tol: f64,
+) -> Trajectory<T, N> {
+    const C: [f64; 6] = [1. / 5., 3. / 10., 4. / 5., 8. / 9., 1., 1.];
+    const A2: [f64; 1] = [1. / 5.];
+    const A3: [f64; 2] = [3. / 40., 9. / 40.];
+    const A4: [f64; 3] = [44. / 45., -56. / 15., 32. / 9.];
+    const A5: [f64; 4] = [
+        19372. / 6561.,
+        -25360. / 2187.,
+        64448. / 6561.,
+        -212. / 729.,
+    ];
+    const A6: [f64; 5] = [
+        9017. / 3168.,
+        -355. / 33.,
+        46732. / 5247.,
+        49. / 176.,
+        -5103. / 18656.,
+    ];
+    // Fifth-order weights, also used for the last stage (first same as last)
+    const B: [f64; 6] = [
+        35. / 384.,
+        0.,
+        500. / 1113.,
+        125. / 192.,
+        -2187. / 6784.,
+        11. / 84.,
+    ];
+    // Difference between the fifth- and fourth-order weights
+    const E: [f64; 7] = [
+        71. / 57600.,
+        0.,
+        -71. / 16695.,
+        71. / 1920.,
+        -17253. / 339200.,
+        22. / 525.,
+        -1. / 40.,
+    ];
+
+    let mut trajectory = Trajectory {
+        t: vec![t0],
+        y: vec![y0],
+    };
+    let span = t1 - t0;
+    let (mut t, mut y) = (t0, y0);
+    let mut h = span / 100.;
+    let mut k1 = f(t, y);
+    while (t1 - t) * span.signum() > 0. {
+        if h.abs() <= 1e-12 * span.abs() {
+            break;
+        }
+        let last = (t + h - t1) * span.signum() >= 0.;
+        if last {
+            h = t1 - t;
+        }
+
+        let stage = |c: f64, a: &[f64], ks: &[[T; N]]| {
+            let terms: Vec<_> = a.iter().copied().zip(ks.iter().copied()).collect();
+            f(t + c * h, combine(y, h, &terms))
+        };
+        let k2 = stage(C[0], &A2, &[k1]);
+        let k3 = stage(C[1], &A3, &[k1, k2]);
+        let k4 = stage(C[2], &A4, &[k1, k2, k3]);
+        let k5 = stage(C[3], &A5, &[k1, k2, k3, k4]);
+        let k6 = stage(C[4], &A6, &[k1, k2, k3, k4, k5]);
+        let ks = [k1, k2, k3, k4, k5, k6];
+        let terms: Vec<_> = B.iter().copied().zip(ks).collect();
+        let y_next = combine(y, h, &terms);
+        let k7 = f(t + C[5] * h, y_next);
+
+        // Root mean square of the scaled error estimate
+        let error = (0..N)
+            .map(|i| {
+                let e: f64 = E
+                    .iter()
+                    .zip(ks.iter().chain([&k7]))
+                    .map(|(e, k)| e * k[i].real())
+                    .sum();
+                let scale = tol * (1. + y[i].real().abs().max(y_next[i].real().abs()));
+                (h * e / scale).powi(2)
+            })
+            .sum::<f64>()
+            / N.max(1) as f64;
+        let error = error.sqrt();
+        if !error.is_finite() {
+            // No step size can be derived from the error, and the states
+            // computed from here on would not be finite either
+            break;
+        }
+
+        if error <= 1. {
+            t = if last { t1 } else { t + h };
+            y = y_next;
+            k1 = k7;
+            trajectory.t.push(t);
+            trajectory.y.push(y);
+        }
+        let factor = if error == 0. {
+            5.
+        } else {
+            (0.9 * error.powf(-0.2)).clamp(0.2, 5.)
+        };
+        h *= factor;
+    }
+    trajectory
+}
+
+/// Solution of a second-order ODE `q'' = a(q)` at equally spaced time points
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseTrajectory<T, const N: usize> {
+    /// Time points, starting with the initial time
+    pub t: Vec<f64>,
+    /// Position at each time point
+    pub q: Vec<[T; N]>,
+    /// Velocity at each time point
+    pub v: Vec<[T; N]>,
+}
+
+impl<T: Numerical, const N: usize> PhaseTrajectory<T, N> {
+    /// The final time point, position and velocity
+    pub fn last(&self) -> (f64, [T; N], [T; N]) {
+        let i = self.t.len() - 1;
+        (self.t[i], self.q[i], self.v[i])
+    }
+}
+
+/// Integrate `q'' = accel(q)` from `t0` to `t1` by a symplectic splitting,
+/// alternating kicks `v += d h accel(q)` and drifts `q += c h v` with the
+/// given coefficients, starting and ending with a kick
+fn splitting<T: Numerical, const N: usize>(
+    accel: impl Fn([T; N]) -> [T; N],
+    t0: f64,
+    (q0, v0): ([T; N], [T; N]),
+    t1: f64,
+    steps: usize,
+    kicks: &[f64],
+    drifts: &[f64],
+) -> PhaseTrajectory<T, N> {
+    let h = (t1 - t0) / steps as f64;
+    let mut trajectory = PhaseTrajectory {
+        t: vec![t0],
+        q: vec![q0],
+        v: vec![v0],
+    };
+    let (mut q, mut v) = (q0, v0);
+    // The acceleration at the end of a step is reused for the next one
+    let mut a = accel(q);
+    for i in 0..steps {
+        for (j, &d) in kicks.iter().enumerate() {
+            v = combine(v, h, &[(d, a)]);
+            if let Some(&c) = drifts.get(j) {
+                q = combine(q, h, &[(c, v)]);
+                a = accel(q);
+            }
+        }
+        trajectory.t.push(if i + 1 == steps {
+            t1
+        } else {
+            t0 + (i + 1) as f64 * h
+        });
+        trajectory.q.push(q);
+        trajectory.v.push(v);
+    }
+    trajectory
+}
+
+/// Integrate `q'' = accel(q)` from `t0` to `t1`, starting at position `q0`
+/// with velocity `v0`, using `steps` steps of the velocity Verlet method
+///
+/// The method is the second-order leapfrog in its kick-drift-kick form,
+/// evaluating `accel` once per step. It is symplectic and time-reversible, so
+/// for a conservative force the energy error stays bounded, of order `h^2`,
+/// over arbitrarily long times.
+///
+/// ```
+/// use epsilon::{ode, Dual};
+/// // Pendulum q'' = -(g / l) sin(q), with the sensitivity to g / l
+/// let [k] = Dual::<1>::variables([4.]);
+/// let (q0, v0) = ([Dual::constant(1.)], [Dual::constant(0.)]);
+/// let trajectory = ode::velocity_verlet(|[q]| [-k * q.sin()], 0., q0, v0, 100., 100_000);
+/// let (_, [q], [v]) = trajectory.last();
+///
+/// // The energy at the end is close to the initial energy
+/// let energy = 0.5 * v.real * v.real - 4. * q.real.cos();
+/// assert!((energy + 4. * 1f64.cos()).abs() < 1e-4);
+/// ```
+pub fn velocity_verlet<T: Numerical, const N: usize>(
+    accel: impl Fn([T; N]) -> [T; N],
+    t0: f64,
+    q0: [T; N],
+    v0: [T; N],
+    t1: f64,
+    steps: usize,
+) -> PhaseTrajectory<T, N> {
+    splitting(accel, t0, (q0, v0), t1, steps, &[0.5, 0.5], &[1.])
+}
+
+/// Integrate `q'' = accel(q)` like [`velocity_verlet`], using Yoshida's
+/// fourth-order composition of three velocity Verlet steps
+///
+/// Evaluates `accel` three times per step, but the error decreases with
+/// `h^4`, which is cheaper for high accuracy. It is also symplectic and
+/// time-reversible.
+pub fn yoshida4<T: Numerical, const N: usize>(
+    accel: impl Fn([T; N]) -> [T; N],
+    t0: f64,
+    q0: [T; N],
+    v0: [T; N],
+    t1: f64,
+    steps: usize,
+) -> PhaseTrajectory<T, N> {
+    let cbrt2 = 2f64.powf(1. / 3.);
+    let w1 = 1. / (2. - cbrt2);
+    let w0 = -cbrt2 * w1;
+    let kicks = [w1 / 2., (w0 + w1) / 2., (w0 + w1) / 2., w1 / 2.];
+    splitting(accel, t0, (q0, v0), t1, steps, &kicks, &[w1, w0, w1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dual;
+
+    #[test]
+    fn test_rk4() {
+        // Harmonic oscillator with frequency w, x(t) = cos(w t)
+        let [w] = Dual::<1>::variables([2.]);
+        let trajectory = rk4(
+            |_, [x, v]| [v, -w * w * x],
+            0.,
+            [1., 0.].map(Dual::constant),
+            1.,
+            1000,
+        );
+        let (t, [x, _]) = trajectory.last();
+        assert_eq!(t, 1.);
+        assert_eq!(trajectory.y.len(), 1001);
+        assert!((x.real - 2f64.cos()).abs() < 1e-10);
+        // dx/dw = -t sin(w t)
+        assert!((x.eps[0] + 2f64.sin()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_rk45() {
+        let trajectory = rk45(|t, [y]| [t * y], 0., [1.], 2., 1e-12);
+        let (t, [y]) = trajectory.last();
+        assert_eq!(t, 2.);
+        assert!((y - 2f64.exp()).abs() < 1e-9);
+        assert!(trajectory.t.len() < 200);
+
+        // Backwards in time
+        let (t, [y]) = rk45(|_, [y]| [y], 1., [1.], 0., 1e-10).last();
+        assert_eq!(t, 0.);
+        assert!((y - (-1f64).exp()).abs() < 1e-9);
+
+        // Leaves the domain of the square root, giving NaN
+        let trajectory = rk45(|_, [y]| [(1. - y).powf(0.5) * 10.], 0., [0.], 10., 1e-8);
+        let (t, [y]) = trajectory.last();
+        assert!(t < 10. && y.is_finite());
+    }
+
+    #[test]
+    fn test_symplectic() {
+        // Harmonic oscillator with frequency w, q(t) = cos(w t)
+        let [w] = Dual::<1>::variables([2.]);
+        let accel = |[q]: [Dual<1>; 1]| [-w * w * q];
+        let (q0, v0) = ([Dual::constant(1.)], [Dual::constant(0.)]);
+        let energy = |q: f64, v: f64| 0.5 * v * v + 2. * q * q;
+
+        let verlet = velocity_verlet(accel, 0., q0, v0, 1000., 200_000);
+        assert_eq!(verlet.t.len(), 200_001);
+        let max_error = verlet
+            .q
+            .iter()
+            .zip(&verlet.v)
+            .map(|(q, v)| (energy(q[0].real, v[0].real) - 2.).abs())
+            .fold(0., f64::max);
+        assert!(max_error < 2e-4);
+
+        let (t, [q], _) = yoshida4(accel, 0., q0, v0, 10., 1000).last();
+        assert_eq!(t, 10.);
+        assert!((q.real - 20f64.cos()).abs() < 1e-6);
+        // dq/dw = -t sin(w t)
+        assert!((q.eps[0] + 10. * 20f64.sin()).abs() < 1e-5);
+    }
+}
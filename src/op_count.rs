@@ -0,0 +1,148 @@
+//! # Operation counts
+//!
+//! With the `op-count` feature, every arithmetic operation and elementary
+//! function evaluated on a [`Dual`](crate::Dual) or a type generated by
+//! [`make_dual`](crate::make_dual) is counted, per type and per thread, to
+//! compare the cost of different formulations of a model. Operations are
+//! counted as they are implemented, e.g. a subtraction as a negation, which
+//! is a multiplication, and an addition. Operations within the inner type of
+//! nested dual numbers are counted separately for that type.
+//!
+//! ```
+//! use epsilon::op_count;
+//! use epsilon::Dual;
+//!
+//! op_count::reset();
+//! let [x, y] = Dual::<2>::variables([1., 2.]);
+//! let _ = x * y + x.exp();
+//!
+//! let counts = op_count::counts::<Dual<2>>();
+//! assert_eq!((counts.add, counts.mul, counts.transcendental), (1, 1, 1));
+//! ```
+
+use std::cell::RefCell;
+
+/// Kind of a counted operation
+///
+/// Division counts as a multiplication, and the remainder
+/// `a % b = a - b * trunc(a / b)` as a multiplication and an addition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Mul,
+    /// Powers and elementary functions such as `exp` and `sin`
+    Transcendental,
+}
+
+/// Number of operations of each kind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    pub add: u64,
+    pub mul: u64,
+    pub transcendental: u64,
+}
+
+impl OpCounts {
+    pub fn total(&self) -> u64 {
+        self.add + self.mul + self.transcendental
+    }
+}
+
+thread_local! {
+    static COUNTS: RefCell<Vec<(&'static str, OpCounts)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Count an operation on the type `T`
+pub fn record<T: ?Sized>(op: Op) {
+    let name = std::any::type_name::<T>();
+    COUNTS.with_borrow_mut(|counts| {
+        let index = match counts.iter().position(|&(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                counts.push((name, OpCounts::default()));
+                counts.len() - 1
+            }
+        };
+        let entry = &mut counts[index].1;
+        match op {
+            Op::Add => entry.add += 1,
+            Op::Mul => entry.mul += 1,
+            Op::Transcendental => entry.transcendental += 1,
+        }
+    });
+}
+
+/// Operations counted on the type `T` by the current thread
+pub fn counts<T: ?Sized>() -> OpCounts {
+    let name = std::any::type_name::<T>();
+    COUNTS.with_borrow(|counts| {
+        counts
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map_or_else(OpCounts::default, |&(_, c)| c)
+    })
+}
+
+/// Operations counted by the current thread on each type, by type name
+pub fn all() -> Vec<(&'static str, OpCounts)> {
+    COUNTS.with_borrow(Clone::clone)
+}
+
+/// Reset the counts of the current thread to zero
+pub fn reset() {
+    COUNTS.with_borrow_mut(Vec::clear);
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __count_op {
+    ($t:ty, $op:ident) => {
+        $crate::op_count::record::<$t>($crate::op_count::Op::$op)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all, counts, reset, OpCounts};
+    use crate::sample::SampleXYZ;
+    use crate::Dual;
+
+    #[test]
+    fn test_counts() {
+        reset();
+        let x = SampleXYZ::x(2.);
+        let y = (x - 1.) * x.sin() / x;
+        let _ = y.cos();
+        let c = counts::<SampleXYZ>();
+        // Division counts as a multiplication
+        assert_eq!((c.add, c.mul, c.transcendental), (1, 2, 2));
+        assert_eq!(counts::<Dual<1>>().total(), 0);
+
+        // Remainders count as a multiplication and a subtraction, or only
+        // the subtraction for a real divisor
+        let _ = (x % x.sin()) % 3.;
+        let c = counts::<SampleXYZ>();
+        assert_eq!((c.add, c.mul, c.transcendental), (3, 3, 3));
+        let [a, b] = Dual::<2>::variables([5., 2.]);
+        let _ = a % b % 2.;
+        assert_eq!(
+            counts::<Dual<2>>(),
+            OpCounts {
+                add: 2,
+                mul: 1,
+                transcendental: 0
+            }
+        );
+
+        // Nested duals count the operations on their components separately
+        let h = crate::hessian(|[x]| x * x, [3.]);
+        assert_eq!(h, [[2.]]);
+        let names: Vec<&str> = all().iter().map(|&(n, _)| n).collect();
+        assert!(names
+            .iter()
+            .any(|n| n.ends_with("Dual<1, epsilon::dual::Dual<1>>")));
+        assert!(counts::<Dual<1>>().mul > 0);
+        reset();
+        assert!(all().is_empty());
+    }
+}
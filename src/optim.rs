@@ -0,0 +1,721 @@
+//! # Optimization
+//!
+//! Optimizers minimizing an objective written against [`Dual`], with the
+//! gradient computed by dual numbers in each iteration. [`minimize`] runs a
+//! first-order [`Optimizer`] such as [`Adam`], while [`lbfgs`] implements the
+//! quasi-Newton L-BFGS method, [`newton`] a line search Newton method with
+//! exact Hessians, and [`trust_region`] a Newton trust-region method.
+//! Constrained problems are solved by [`augmented_lagrangian`].
+//!
+//! ```
+//! use epsilon::optim::{self, Adam};
+//! // Minimize (x - 3)^2 + (y + 1)^2
+//! let min = optim::minimize(
+//!     &mut Adam::new(0.1),
+//!     |[x, y]| (x - 3.) * (x - 3.) + (y + 1.) * (y + 1.),
+//!     [0., 0.],
+//!     1e-8,
+//!     10_000,
+//! );
+//! assert!(min.converged);
+//! assert!((min.x[0] - 3.).abs() < 1e-6 && (min.x[1] + 1.).abs() < 1e-6);
+//! ```
+
+use crate::calculus::value_grad_hessian;
+use crate::{line_search, value_and_grad, Dual, Dual1, HyperDual};
+use std::collections::VecDeque;
+
+/// Update rule of a gradient-based optimizer
+pub trait Optimizer {
+    /// Update `params` given the gradient of the objective at `params`
+    fn step(&mut self, params: &mut [f64], grad: &[f64]);
+}
+
+/// Gradient descent with momentum
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sgd {
+    pub learning_rate: f64,
+    /// Fraction of the previous update added to the current one, `0` for
+    /// plain gradient descent
+    pub momentum: f64,
+    velocity: Vec<f64>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64, momentum: f64) -> Self {
+        Sgd {
+            learning_rate,
+            momentum,
+            velocity: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, params: &mut [f64], grad: &[f64]) {
+        self.velocity.resize(params.len(), 0.);
+        for ((p, v), g) in params.iter_mut().zip(&mut self.velocity).zip(grad) {
+            *v = self.momentum * *v - self.learning_rate * g;
+            *p += *v;
+        }
+    }
+}
+
+/// The Adam optimizer, using bias-corrected moving averages of the gradient
+/// and its square
+#[derive(Clone, Debug, PartialEq)]
+pub struct Adam {
+    pub learning_rate: f64,
+    /// Decay rate of the first moment estimate
+    pub beta1: f64,
+    /// Decay rate of the second moment estimate
+    pub beta2: f64,
+    /// Added to the denominator for numerical stability
+    pub epsilon: f64,
+    steps: i32,
+    m: Vec<f64>,
+    v: Vec<f64>,
+}
+
+impl Adam {
+    /// Create instance with the default decay rates `beta1 = 0.9` and
+    /// `beta2 = 0.999`, and `epsilon = 1e-8`
+    pub fn new(learning_rate: f64) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            steps: 0,
+            m: Vec::new(),
+            v: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, params: &mut [f64], grad: &[f64]) {
+        self.m.resize(params.len(), 0.);
+        self.v.resize(params.len(), 0.);
+        self.steps = self.steps.saturating_add(1);
+        let m_scale = 1. / (1. - self.beta1.powi(self.steps));
+        let v_scale = 1. / (1. - self.beta2.powi(self.steps));
+
+        for (i, (p, g)) in params.iter_mut().zip(grad).enumerate() {
+            self.m[i] = self.beta1 * self.m[i] + (1. - self.beta1) * g;
+            self.v[i] = self.beta2 * self.v[i] + (1. - self.beta2) * g * g;
+            let (m, v) = (self.m[i] * m_scale, self.v[i] * v_scale);
+            *p -= self.learning_rate * m / (v.sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// Result of a minimization, along with diagnostics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Minimum<X = f64> {
+    /// The final iterate
+    pub x: X,
+    /// Value of the objective at `x`
+    pub value: f64,
+    /// Euclidean norm of the gradient at `x`
+    pub grad_norm: f64,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// Whether the gradient norm is within the tolerance
+    pub converged: bool,
+}
+
+/// Minimize `f` using `optimizer`, starting at `x0`
+///
+/// Iterates until the Euclidean norm of the gradient is at most `tol`, for at
+/// most `max_iter` iterations.
+pub fn minimize<const N: usize, O: Optimizer>(
+    optimizer: &mut O,
+    f: impl Fn([Dual<N>; N]) -> Dual<N>,
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> Minimum<[f64; N]> {
+    let mut x = x0;
+    let mut iterations = 0;
+    loop {
+        let (value, grad) = value_and_grad(&f, x);
+        let grad_norm = norm(&grad);
+        let converged = grad_norm <= tol;
+        if converged || iterations == max_iter {
+            return Minimum {
+                x,
+                value,
+                grad_norm,
+                iterations,
+                converged,
+            };
+        }
+        optimizer.step(&mut x, &grad);
+        iterations += 1;
+    }
+}
+
+/// Minimize `f` using L-BFGS, starting at `x0`
+///
+/// The inverse Hessian is approximated from the last `memory` steps and
+/// gradient changes. Steps are chosen by a line search satisfying the strong
+/// Wolfe conditions. Iterates until the Euclidean norm of the
+/// gradient is at most `tol`, for at most `max_iter` iterations, stopping
+/// early without converging if the line search fails.
+pub fn lbfgs<const N: usize>(
+    f: impl Fn([Dual<N>; N]) -> Dual<N>,
+    x0: [f64; N],
+    memory: usize,
+    tol: f64,
+    max_iter: usize,
+) -> Minimum<[f64; N]> {
+    const C1: f64 = 1e-4;
+    const C2: f64 = 0.9;
+
+    let mut x = x0;
+    let (mut value, mut grad) = value_and_grad(&f, x);
+    // Steps s, gradient changes y and 1 / (s . y), oldest first
+    let mut history: VecDeque<([f64; N], [f64; N], f64)> = VecDeque::with_capacity(memory);
+    let mut iterations = 0;
+    loop {
+        let grad_norm = norm(&grad);
+        let converged = grad_norm <= tol;
+        let done = Minimum {
+            x,
+            value,
+            grad_norm,
+            iterations,
+            converged,
+        };
+        if converged || iterations == max_iter {
+            return done;
+        }
+
+        // Two-loop recursion computing the direction -H grad
+        let mut q = grad;
+        let mut alphas = Vec::with_capacity(history.len());
+        for (s, y, rho) in history.iter().rev() {
+            let alpha = rho * dot(s, &q);
+            q.iter_mut().zip(y).for_each(|(q, y)| *q -= alpha * y);
+            alphas.push(alpha);
+        }
+        if let Some((s, y, _)) = history.back() {
+            let gamma = dot(s, y) / dot(y, y);
+            q.iter_mut().for_each(|q| *q *= gamma);
+        }
+        for ((s, y, rho), alpha) in history.iter().zip(alphas.into_iter().rev()) {
+            let beta = rho * dot(y, &q);
+            q.iter_mut()
+                .zip(s)
+                .for_each(|(q, s)| *q += (alpha - beta) * s);
+        }
+        let mut dir = q.map(|q| -q);
+        if dot(&dir, &grad) >= 0. {
+            // Not a descent direction, restart from steepest descent
+            history.clear();
+            dir = grad.map(|g| -g);
+        }
+
+        // The accepted step is the last one evaluated, so its gradient is kept
+        let mut last = (x, grad);
+        let phi = |t: f64| {
+            let trial: [f64; N] = std::array::from_fn(|i| x[i] + t * dir[i]);
+            let (trial_value, trial_grad) = value_and_grad(&f, trial);
+            last = (trial, trial_grad);
+            (trial_value, dot(&trial_grad, &dir))
+        };
+        let initial = (value, dot(&grad, &dir));
+        let Some(step) = line_search::strong_wolfe_1d(phi, initial, 1., C1, C2) else {
+            return done;
+        };
+        let (x_next, value_next, grad_next) = (last.0, step.value, last.1);
+
+        let s: [f64; N] = std::array::from_fn(|i| x_next[i] - x[i]);
+        let y: [f64; N] = std::array::from_fn(|i| grad_next[i] - grad[i]);
+        let sy = dot(&s, &y);
+        if sy <= f64::EPSILON * dot(&y, &y) {
+            // The curvature condition is violated, so the approximation is
+            // no longer positive definite and is reset
+            history.clear();
+        } else if memory > 0 {
+            if history.len() == memory {
+                history.pop_front();
+            }
+            history.push_back((s, y, 1. / sy));
+        }
+        (x, value, grad) = (x_next, value_next, grad_next);
+        iterations += 1;
+    }
+}
+
+/// Cholesky factor `L` with `L L^T = a`, or `None` if `a` is not positive
+/// definite
+fn cholesky<const N: usize>(a: &[[f64; N]; N]) -> Option<[[f64; N]; N]> {
+    let mut l = [[0.; N]; N];
+    for i in 0..N {
+        for j in 0..=i {
+            let sum = a[i][j] - dot(&l[i][..j], &l[j][..j]);
+            if i == j {
+                if sum <= 0. {
+                    return None;
+                }
+                l[i][i] = sum.sqrt();
+            } else {
+                l[i][j] = sum / l[j][j];
+            }
+        }
+    }
+    Some(l)
+}
+
+/// Solve `(h + tau I) p = -g` for the smallest `tau >= 0` in a doubling
+/// sequence making `h + tau I` positive definite, so that `p` is a descent
+/// direction even where `h` is indefinite
+fn regularized_newton_step<const N: usize>(h: &[[f64; N]; N], g: &[f64; N]) -> [f64; N] {
+    const BETA: f64 = 1e-3;
+    let min_diagonal = (0..N).map(|i| h[i][i]).fold(f64::INFINITY, f64::min);
+    let mut tau = if min_diagonal > 0. {
+        0.
+    } else {
+        BETA - min_diagonal
+    };
+    loop {
+        let shifted: [[f64; N]; N] = std::array::from_fn(|i| {
+            std::array::from_fn(|j| h[i][j] + if i == j { tau } else { 0. })
+        });
+        if let Some(l) = cholesky(&shifted) {
+            // Forward and back substitution with L and L^T
+            let mut p = [0.; N];
+            for i in 0..N {
+                p[i] = (-g[i] - dot(&l[i][..i], &p[..i])) / l[i][i];
+            }
+            for i in (0..N).rev() {
+                let sum: f64 = (i + 1..N).map(|j| l[j][i] * p[j]).sum();
+                p[i] = (p[i] - sum) / l[i][i];
+            }
+            return p;
+        }
+        tau = (2. * tau).max(BETA);
+    }
+}
+
+/// Minimize `f` using Newton's method with a backtracking line search,
+/// starting at `x0`
+///
+/// The gradient and Hessian are computed exactly by evaluating `f` once on
+/// nested dual numbers in each iteration. Where the Hessian is not positive
+/// definite, a multiple of the identity is added to it, so the step is
+/// always a descent direction, and near a minimum the full Newton step is
+/// taken, converging quadratically. The cost of each iteration grows with
+/// `N^2`, which suits small problems. Iterates until the Euclidean norm of
+/// the gradient is at most `tol`, for at most `max_iter` iterations,
+/// stopping early without converging if the line search fails.
+///
+/// ```
+/// use epsilon::optim;
+/// let rosenbrock = |[x, y]: [_; 2]| (1. - x) * (1. - x) + 100. * (y - x * x) * (y - x * x);
+/// let min = optim::newton(rosenbrock, [-1.2, 1.], 1e-10, 100);
+/// assert!(min.converged && min.iterations < 30);
+/// assert!((min.x[0] - 1.).abs() < 1e-8);
+/// ```
+pub fn newton<const N: usize>(
+    f: impl Fn([HyperDual<N>; N]) -> HyperDual<N>,
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> Minimum<[f64; N]> {
+    const C1: f64 = 1e-4;
+
+    let mut x = x0;
+    let (mut value, mut grad, mut hessian) = value_grad_hessian(&f, x);
+    let mut iterations = 0;
+    loop {
+        let grad_norm = norm(&grad);
+        let converged = grad_norm <= tol;
+        let done = Minimum {
+            x,
+            value,
+            grad_norm,
+            iterations,
+            converged,
+        };
+        if converged || iterations == max_iter {
+            return done;
+        }
+
+        let dir = regularized_newton_step(&hessian, &grad);
+        // The accepted step is the last one evaluated, so its derivatives are
+        // kept
+        let mut last = (x, grad, hessian);
+        let mut phi = |t: f64| {
+            let trial: [f64; N] = std::array::from_fn(|i| x[i] + t * dir[i]);
+            let (trial_value, trial_grad, trial_hessian) = value_grad_hessian(&f, trial);
+            last = (trial, trial_grad, trial_hessian);
+            (trial_value, dot(&trial_grad, &dir))
+        };
+        let initial = (value, dot(&grad, &dir));
+        if -initial.1 <= f64::EPSILON * (1. + value.abs()) {
+            // The decrease is lost in rounding, so the full step is taken
+            value = phi(1.).0;
+        } else {
+            let Some(step) = line_search::backtracking_1d(phi, initial, 1., C1) else {
+                return done;
+            };
+            value = step.value;
+        }
+        (x, grad, hessian) = last;
+        iterations += 1;
+    }
+}
+
+/// Value, gradient and Hessian-vector product `H v` of `f` at `x`
+///
+/// The outer dual part of the nested dual carries the gradient, and the
+/// inner part the derivative along `v`, so the dual parts of the gradient are
+/// the Hessian-vector product.
+fn hessian_vector<const N: usize>(
+    f: impl Fn([Dual<N, Dual1>; N]) -> Dual<N, Dual1>,
+    x: [f64; N],
+    v: [f64; N],
+) -> (f64, [f64; N], [f64; N]) {
+    let vars = std::array::from_fn(|i| Dual::variable(Dual1::new(x[i], [v[i]]), i));
+    let y = f(vars);
+    (y.real.real, y.eps.map(|e| e.real), y.eps.map(|e| e.eps[0]))
+}
+
+/// Approximately minimize the quadratic model `g p + p H p / 2` within
+/// `||p|| <= radius` using the Steihaug conjugate gradient method
+fn steihaug<const N: usize>(
+    grad: [f64; N],
+    hvp: impl Fn([f64; N]) -> [f64; N],
+    radius: f64,
+) -> [f64; N] {
+    let tol = grad_tolerance(norm(&grad));
+    // Step from `z` along `d` to the trust-region boundary
+    let to_boundary = |z: [f64; N], d: [f64; N]| -> [f64; N] {
+        let (a, b, c) = (dot(&d, &d), 2. * dot(&z, &d), dot(&z, &z) - radius * radius);
+        let tau = (-b + (b * b - 4. * a * c).sqrt()) / (2. * a);
+        std::array::from_fn(|i| z[i] + tau * d[i])
+    };
+
+    let mut z = [0.; N];
+    let mut r = grad;
+    let mut d = grad.map(|g| -g);
+    for _ in 0..2 * N {
+        if norm(&r) <= tol {
+            break;
+        }
+        let hd = hvp(d);
+        let curvature = dot(&d, &hd);
+        if curvature <= 0. {
+            return to_boundary(z, d);
+        }
+        let alpha = dot(&r, &r) / curvature;
+        let z_next: [f64; N] = std::array::from_fn(|i| z[i] + alpha * d[i]);
+        if norm(&z_next) >= radius {
+            return to_boundary(z, d);
+        }
+        let r_next: [f64; N] = std::array::from_fn(|i| r[i] + alpha * hd[i]);
+        let beta = dot(&r_next, &r_next) / dot(&r, &r);
+        d = std::array::from_fn(|i| -r_next[i] + beta * d[i]);
+        (z, r) = (z_next, r_next);
+    }
+    z
+}
+
+/// Residual tolerance of the inner conjugate gradient iterations, giving
+/// superlinear convergence
+fn grad_tolerance(grad_norm: f64) -> f64 {
+    grad_norm.sqrt().min(0.5) * grad_norm
+}
+
+/// Minimize `f` using a Newton trust-region method, starting at `x0`
+///
+/// `f` is evaluated on nested dual numbers, providing exact Hessian-vector
+/// products to the Steihaug conjugate gradient method solving the
+/// trust-region subproblems. This handles indefinite Hessians, such as far
+/// from the minimum of non-convex functions. Iterates until the Euclidean
+/// norm of the gradient is at most `tol`, for at most `max_iter` iterations.
+///
+/// ```
+/// use epsilon::optim;
+/// let rosenbrock = |[x, y]: [_; 2]| (1. - x) * (1. - x) + 100. * (y - x * x) * (y - x * x);
+/// let min = optim::trust_region(rosenbrock, [-1.2, 1.], 1e-10, 100);
+/// assert!(min.converged);
+/// assert!((min.x[0] - 1.).abs() < 1e-8);
+/// ```
+pub fn trust_region<const N: usize>(
+    f: impl Fn([Dual<N, Dual1>; N]) -> Dual<N, Dual1>,
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> Minimum<[f64; N]> {
+    // Minimum ratio of actual to predicted reduction to accept a step
+    const ETA: f64 = 1e-4;
+    const MAX_RADIUS: f64 = 1e6;
+
+    let mut x = x0;
+    let mut radius = 1.;
+    let (mut value, mut grad, _) = hessian_vector(&f, x, [0.; N]);
+    let mut iterations = 0;
+    loop {
+        let grad_norm = norm(&grad);
+        let converged = grad_norm <= tol;
+        if converged || iterations == max_iter || radius == 0. {
+            return Minimum {
+                x,
+                value,
+                grad_norm,
+                iterations,
+                converged,
+            };
+        }
+
+        let hvp = |v| hessian_vector(&f, x, v).2;
+        let step = steihaug(grad, hvp, radius);
+        let predicted = -(dot(&grad, &step) + dot(&step, &hvp(step)) / 2.);
+        let trial: [f64; N] = std::array::from_fn(|i| x[i] + step[i]);
+        let (trial_value, trial_grad, _) = hessian_vector(&f, trial, [0.; N]);
+        let rho = if predicted <= f64::EPSILON * value.abs() {
+            // The reduction is lost in rounding, so the model is trusted
+            1.
+        } else {
+            (value - trial_value) / predicted
+        };
+
+        let step_norm = norm(&step);
+        if rho < 0.25 || rho.is_nan() {
+            radius = 0.25 * step_norm;
+        } else if rho > 0.75 && step_norm >= 0.99 * radius {
+            radius = (2. * radius).min(MAX_RADIUS);
+        }
+        if rho > ETA {
+            (x, value, grad) = (trial, trial_value, trial_grad);
+        }
+        iterations += 1;
+    }
+}
+
+/// Result of a constrained minimization, along with diagnostics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstrainedMinimum<const N: usize, const E: usize, const I: usize> {
+    /// The final iterate
+    pub x: [f64; N],
+    /// Value of the objective at `x`
+    pub value: f64,
+    /// Largest violation of a constraint at `x`
+    pub violation: f64,
+    /// Lagrange multiplier estimates of the equality constraints
+    pub eq_multipliers: [f64; E],
+    /// Lagrange multiplier estimates of the inequality constraints, which are
+    /// zero for inactive constraints
+    pub ineq_multipliers: [f64; I],
+    /// Number of outer iterations performed, i.e. multiplier updates
+    pub iterations: usize,
+    /// Whether the violation is within the tolerance, and the last
+    /// unconstrained minimization converged
+    pub converged: bool,
+}
+
+/// Minimize `f` subject to `eq(x) = 0` and `ineq(x) <= 0` component-wise,
+/// starting at `x0`, using the augmented Lagrangian method
+///
+/// Each outer iteration minimizes the augmented Lagrangian using [`lbfgs`]
+/// with at most `max_iter` iterations, and updates the multipliers. The
+/// penalty parameter is increased whenever the constraint violation does not
+/// decrease sufficiently. Iterates until the violation and the gradient norm
+/// of the augmented Lagrangian are at most `tol`, for at most `max_iter` outer
+/// iterations.
+///
+/// ```
+/// use epsilon::optim;
+/// // Minimize x + y on the unit circle, with x >= -0.5
+/// let min = optim::augmented_lagrangian(
+///     |[x, y]| x + y,
+///     |[x, y]| [x * x + y * y - 1.],
+///     |[x, _]| [-0.5 - x],
+///     [0., 0.],
+///     1e-8,
+///     100,
+/// );
+/// assert!(min.converged);
+/// assert!((min.x[0] + 0.5).abs() < 1e-6 && (min.x[1] + 0.75f64.sqrt()).abs() < 1e-6);
+/// ```
+pub fn augmented_lagrangian<const N: usize, const E: usize, const I: usize>(
+    f: impl Fn([Dual<N>; N]) -> Dual<N>,
+    eq: impl Fn([Dual<N>; N]) -> [Dual<N>; E],
+    ineq: impl Fn([Dual<N>; N]) -> [Dual<N>; I],
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> ConstrainedMinimum<N, E, I> {
+    const MEMORY: usize = 10;
+    const GROWTH: f64 = 10.;
+
+    let violation = |x: [f64; N]| {
+        let x = x.map(Dual::constant);
+        let eq = eq(x).map(|h| h.real.abs());
+        let ineq = ineq(x).map(|g| g.real.max(0.));
+        eq.into_iter().chain(ineq).fold(0., f64::max)
+    };
+
+    let mut x = x0;
+    let mut lambda = [0.; E];
+    let mut nu = [0.; I];
+    let mut mu = 10.;
+    let mut last_violation = violation(x);
+    let mut iterations = 0;
+    loop {
+        // Powell-Hestenes-Rockafellar augmented Lagrangian
+        let lagrangian = |x: [Dual<N>; N]| {
+            let mut l = f(x);
+            for (h, lambda) in eq(x).into_iter().zip(lambda) {
+                l += h * lambda + h * h * (mu / 2.);
+            }
+            for (g, nu) in ineq(x).into_iter().zip(nu) {
+                let shifted = g * mu + nu;
+                if shifted.real > 0. {
+                    l += shifted * shifted / (2. * mu);
+                }
+                l -= nu * nu / (2. * mu);
+            }
+            l
+        };
+        let inner = lbfgs(lagrangian, x, MEMORY, tol, max_iter);
+        x = inner.x;
+
+        let consts = x.map(Dual::constant);
+        for (lambda, h) in lambda.iter_mut().zip(eq(consts)) {
+            *lambda += mu * h.real;
+        }
+        for (nu, g) in nu.iter_mut().zip(ineq(consts)) {
+            *nu = (*nu + mu * g.real).max(0.);
+        }
+        iterations += 1;
+
+        let violation = violation(x);
+        let converged = violation <= tol && inner.converged;
+        if converged || iterations == max_iter {
+            return ConstrainedMinimum {
+                x,
+                value: f(consts).real,
+                violation,
+                eq_multipliers: lambda,
+                ineq_multipliers: nu,
+                iterations,
+                converged,
+            };
+        }
+        if violation > 0.25 * last_violation {
+            mu *= GROWTH;
+        }
+        last_violation = violation;
+    }
+}
+
+/// Euclidean norm
+fn norm(v: &[f64]) -> f64 {
+    dot(v, v).sqrt()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sgd() {
+        let f = |[x, y]: [Dual<2>; 2]| x * x + 10. * y * y;
+        let plain = minimize(&mut Sgd::new(0.05, 0.), f, [1., 1.], 1e-8, 10_000);
+        let momentum = minimize(&mut Sgd::new(0.05, 0.5), f, [1., 1.], 1e-8, 10_000);
+        assert!(plain.converged && momentum.converged);
+        assert!(momentum.iterations < plain.iterations);
+        assert!(norm(&momentum.x) < 1e-8);
+    }
+
+    #[test]
+    fn test_adam() {
+        let mut adam = Adam::new(0.5);
+        let mut x = [1.];
+        adam.step(&mut x, &[4.]);
+        // The first step has length `learning_rate` regardless of the gradient
+        assert!((x[0] - 0.5).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_lbfgs() {
+        let rosenbrock =
+            |[x, y]: [Dual<2>; 2]| (1. - x) * (1. - x) + 100. * (y - x * x) * (y - x * x);
+        let min = lbfgs(rosenbrock, [-1.2, 1.], 5, 1e-10, 200);
+        assert!(min.converged);
+        assert!((min.x[0] - 1.).abs() < 1e-8 && (min.x[1] - 1.).abs() < 1e-8);
+
+        // Without memory, L-BFGS degrades to steepest descent
+        let slow = lbfgs(rosenbrock, [-1.2, 1.], 0, 1e-10, 200);
+        assert!(!slow.converged);
+    }
+
+    #[test]
+    fn test_augmented_lagrangian() {
+        // Minimize x^2 + y^2 subject to x + y = 1, with the multiplier -1
+        let min = augmented_lagrangian(
+            |[x, y]| x * x + y * y,
+            |[x, y]| [x + y - 1.],
+            |_| [],
+            [0., 0.],
+            1e-10,
+            50,
+        );
+        assert!(min.converged);
+        assert!((min.x[0] - 0.5).abs() < 1e-8 && (min.x[1] - 0.5).abs() < 1e-8);
+        assert!((min.eq_multipliers[0] + 1.).abs() < 1e-6);
+
+        // An inactive inequality constraint has a zero multiplier
+        let min = augmented_lagrangian(
+            |[x]| (x - 1.) * (x - 1.),
+            |_| [],
+            |[x]| [x - 2.],
+            [0.],
+            1e-10,
+            50,
+        );
+        assert!(min.converged);
+        assert_eq!(min.ineq_multipliers, [0.]);
+    }
+
+    #[test]
+    fn test_newton() {
+        // Quadratic convergence from close to the minimum
+        let f = |[x, y]: [HyperDual<2>; 2]| (x - 1.).exp() - x + (y * y + 1.).ln() + y * y;
+        let min = newton(f, [1.5, 0.5], 1e-14, 100);
+        assert!(min.converged && min.iterations <= 6);
+        assert!((min.x[0] - 1.).abs() < 1e-14 && min.x[1].abs() < 1e-14);
+
+        // Regularized steps away from the saddle point at the origin
+        let f = |[x, y]: [HyperDual<2>; 2]| x * x * x * x - 2. * x * x + y * y;
+        let min = newton(f, [0.01, 1.], 1e-12, 100);
+        assert!(min.converged);
+        assert!((min.x[0].abs() - 1.).abs() < 1e-10 && (min.value + 1.).abs() < 1e-12);
+
+        let (_, grad, h) = value_grad_hessian(f, [2., 3.]);
+        assert_eq!((grad, h), ([24., 6.], [[44., 0.], [0., 2.]]));
+    }
+
+    #[test]
+    fn test_trust_region() {
+        // Starting close to the saddle point at the origin, where the Hessian
+        // is indefinite
+        let f = |[x, y]: [Dual<2, Dual1>; 2]| x * x * x * x - 2. * x * x + y * y;
+        let min = trust_region(f, [0.01, 1.], 1e-12, 100);
+        assert!(min.converged);
+        assert!((min.x[0].abs() - 1.).abs() < 1e-10 && min.x[1].abs() < 1e-10);
+        assert!((min.value + 1.).abs() < 1e-12);
+
+        let (_, grad, hv) = hessian_vector(f, [2., 3.], [1., -1.]);
+        assert_eq!((grad, hv), ([24., 6.], [44., -2.]));
+    }
+}
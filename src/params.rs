@@ -0,0 +1,84 @@
+//! # Parameter structs
+//!
+//! Models are often organized around a struct of parameters. With the
+//! `derive` feature, `#[derive(DualParams)]` on such a struct of `f64`
+//! fields generates a dual type with one component per field, and implements
+//! [`DualParams`] to convert between the struct and dual numbers, so that
+//! the gradient of a function of the parameters comes back as the same
+//! struct.
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! use epsilon::params::DualParams;
+//!
+//! #[derive(DualParams)]
+//! struct Oscillator {
+//!     stiffness: f64,
+//!     damping: f64,
+//! }
+//!
+//! // Generated: `OscillatorDual`, and `OscillatorDuals` with one per field
+//! fn energy(p: &OscillatorDuals) -> OscillatorDual {
+//!     p.stiffness * p.stiffness * 0.5 + p.damping * 2.
+//! }
+//!
+//! let p = Oscillator { stiffness: 3., damping: 0.1 };
+//! let grad = Oscillator::gradient(&energy(&p.seed()));
+//! assert_eq!((grad.stiffness, grad.damping), (3., 2.));
+//! # }
+//! ```
+
+#[cfg(feature = "derive")]
+pub use epsilon_derive::DualParams;
+
+use crate::DualNumber;
+
+/// Conversion between a struct of parameters and dual numbers with one
+/// component per parameter
+pub trait DualParams: Sized {
+    /// The dual type, with one component per parameter
+    type Dual: DualNumber;
+    /// The parameters as [`Dual`](DualParams::Dual) values
+    type Duals;
+
+    /// Each parameter as the variable of its own component
+    fn seed(&self) -> Self::Duals;
+
+    /// The parameters given by the real parts of `duals`
+    fn from_dual(duals: &Self::Duals) -> Self;
+
+    /// The derivatives of `value` with respect to each parameter
+    fn gradient(value: &Self::Dual) -> Self;
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::DualParams;
+
+    #[derive(Debug, PartialEq, DualParams)]
+    pub struct Line {
+        slope: f64,
+        intercept: f64,
+    }
+
+    #[test]
+    fn test_derive() {
+        let line = Line {
+            slope: 2.,
+            intercept: -1.,
+        };
+        let p = line.seed();
+        assert_eq!(Line::from_dual(&p), line);
+
+        // Squared residual of the point (3, 4)
+        let r = p.slope * 3. + p.intercept - 4.;
+        let grad = Line::gradient(&(r * r));
+        assert_eq!(
+            grad,
+            Line {
+                slope: 6.,
+                intercept: 2.
+            }
+        );
+    }
+}
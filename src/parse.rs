@@ -0,0 +1,402 @@
+//! # Expression strings
+//!
+//! With the `parse` feature, formulas given as strings at runtime, e.g. typed
+//! in by a user, can be evaluated along with their gradients. The variables
+//! are bound to values by name, and the result is a [`DualVec`] whose `i`th
+//! dual component is the derivative with respect to the `i`th binding.
+//!
+//! ```
+//! use epsilon::parse::Expression;
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7
+//! let expr = Expression::parse("x^2 + y*sin(y)").unwrap();
+//! let z = expr.eval(&[("x", 5.), ("y", 7.)]).unwrap();
+//!
+//! assert_eq!(z.real, 25. + 7. * 7f64.sin());
+//! assert_eq!(z.d_d(0), 10.);
+//! assert_eq!(z.d_d(1), 5.934302379121921);
+//! ```
+//!
+//! The syntax supports numbers, variables, `+`, `-`, `*`, `/`, `^` (right
+//! associative and binding tighter than unary minus), parentheses, the
+//! constants `pi` and `e`, and the functions `sin`, `cos`, `tan`, `exp`, `ln`
+//! and `sqrt`. Expressions nested more than 256 levels deep, counting
+//! parentheses, function calls and operators, are rejected as syntax errors,
+//! so that untrusted input cannot overflow the stack.
+
+use crate::DualVec;
+
+/// Error from parsing or evaluating an expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Invalid syntax, at the byte offset `position`
+    Syntax { position: usize, message: String },
+    /// A variable without a binding
+    Unbound(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Syntax { position, message } => write!(fmt, "{message} at position {position}"),
+            Error::Unbound(name) => write!(fmt, "unbound variable `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Sqrt,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Num(f64),
+    /// Index into the variable names of the expression
+    Var(usize),
+    Neg(Box<Node>),
+    Add(Box<Node>, Box<Node>),
+    Sub(Box<Node>, Box<Node>),
+    Mul(Box<Node>, Box<Node>),
+    Div(Box<Node>, Box<Node>),
+    Pow(Box<Node>, Box<Node>),
+    Call(Func, Box<Node>),
+}
+
+/// Parsed expression, which can be evaluated repeatedly
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    root: Node,
+    variables: Vec<String>,
+}
+
+impl Expression {
+    /// Parse an expression
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            src: src.as_bytes(),
+            pos: 0,
+            variables: Vec::new(),
+            nesting: 0,
+        };
+        let (root, _) = parser.sum()?;
+        parser.skip_whitespace();
+        if parser.pos < src.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Expression {
+            root,
+            variables: parser.variables,
+        })
+    }
+
+    /// Names of the variables, in order of first appearance
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Evaluate with the variables bound to the values in `bindings`, where
+    /// the `i`th dual component of the result is the derivative with respect
+    /// to the `i`th binding
+    pub fn eval(&self, bindings: &[(&str, f64)]) -> Result<DualVec, Error> {
+        let n = bindings.len();
+        let values = self
+            .variables
+            .iter()
+            .map(|name| {
+                let index = bindings
+                    .iter()
+                    .position(|(b, _)| b == name)
+                    .ok_or_else(|| Error::Unbound(name.clone()))?;
+                Ok(DualVec::variable(bindings[index].1, index, n))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(evaluate(&self.root, &values))
+    }
+}
+
+/// Parse `src` and evaluate it with `bindings`, see [`Expression::eval`]
+pub fn eval(src: &str, bindings: &[(&str, f64)]) -> Result<DualVec, Error> {
+    Expression::parse(src)?.eval(bindings)
+}
+
+fn evaluate(node: &Node, values: &[DualVec]) -> DualVec {
+    let sub = |node: &Node| evaluate(node, values);
+    match node {
+        Node::Num(x) => DualVec::constant(*x),
+        Node::Var(i) => values[*i].clone(),
+        Node::Neg(a) => -sub(a),
+        Node::Add(a, b) => sub(a) + sub(b),
+        Node::Sub(a, b) => sub(a) - sub(b),
+        Node::Mul(a, b) => sub(a) * sub(b),
+        Node::Div(a, b) => sub(a) / sub(b),
+        Node::Pow(a, b) => {
+            let (a, b) = (sub(a), sub(b));
+            if b.eps.iter().all(|&e| e == 0.) {
+                a.powf(b.real)
+            } else {
+                // a^b = exp(b ln(a)), only defined for positive a
+                (b * a.ln()).exp()
+            }
+        }
+        Node::Call(func, a) => {
+            let a = sub(a);
+            match func {
+                Func::Sin => a.sin(),
+                Func::Cos => a.cos(),
+                Func::Tan => a.tan(),
+                Func::Exp => a.exp(),
+                Func::Ln => a.ln(),
+                Func::Sqrt => a.powf(0.5),
+            }
+        }
+    }
+}
+
+/// Limit on the nesting of parentheses, function calls and unary operators,
+/// and on the height of the expression tree, so that parsing and evaluating
+/// untrusted input cannot overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// Recursive descent parser, with one method per precedence level
+///
+/// The methods return the parsed node along with the height of its tree.
+struct Parser<'s> {
+    src: &'s [u8],
+    pos: usize,
+    variables: Vec<String>,
+    /// Number of active calls of [`Parser::unary`], which every recursion
+    /// passes through
+    nesting: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Syntax {
+            position: self.pos,
+            message: message.to_owned(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` if it is the next non-whitespace character
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.src.get(self.pos) == Some(&c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// `node`, whose tree has height `height`, or an error if that is
+    /// beyond [`MAX_DEPTH`]
+    fn node(&self, node: Node, height: usize) -> Result<(Node, usize), Error> {
+        if height > MAX_DEPTH {
+            return Err(self.error("expression nested too deeply"));
+        }
+        Ok((node, height))
+    }
+
+    /// Consume bytes while `pred` holds, returning them
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &str {
+        let start = self.pos;
+        while self.src.get(self.pos).is_some_and(|&c| pred(c)) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.src[start..self.pos]).expect("ASCII")
+    }
+
+    /// `product (('+' | '-') product)*`
+    fn sum(&mut self) -> Result<(Node, usize), Error> {
+        let (mut node, mut height) = self.product()?;
+        loop {
+            let op = if self.eat(b'+') {
+                Node::Add
+            } else if self.eat(b'-') {
+                Node::Sub
+            } else {
+                return Ok((node, height));
+            };
+            let (rhs, h) = self.product()?;
+            (node, height) = self.node(op(Box::new(node), Box::new(rhs)), height.max(h) + 1)?;
+        }
+    }
+
+    /// `unary (('*' | '/') unary)*`
+    fn product(&mut self) -> Result<(Node, usize), Error> {
+        let (mut node, mut height) = self.unary()?;
+        loop {
+            let op = if self.eat(b'*') {
+                Node::Mul
+            } else if self.eat(b'/') {
+                Node::Div
+            } else {
+                return Ok((node, height));
+            };
+            let (rhs, h) = self.unary()?;
+            (node, height) = self.node(op(Box::new(node), Box::new(rhs)), height.max(h) + 1)?;
+        }
+    }
+
+    /// `'-' unary | atom ('^' unary)?`
+    fn unary(&mut self) -> Result<(Node, usize), Error> {
+        if self.nesting >= MAX_DEPTH {
+            return Err(self.error("expression nested too deeply"));
+        }
+        self.nesting += 1;
+        let node = self.unary_nested();
+        self.nesting -= 1;
+        node
+    }
+
+    fn unary_nested(&mut self) -> Result<(Node, usize), Error> {
+        if self.eat(b'-') {
+            let (node, height) = self.unary()?;
+            return self.node(Node::Neg(Box::new(node)), height + 1);
+        }
+        let (base, height) = self.atom()?;
+        if self.eat(b'^') {
+            let (exp, h) = self.unary()?;
+            self.node(Node::Pow(Box::new(base), Box::new(exp)), height.max(h) + 1)
+        } else {
+            Ok((base, height))
+        }
+    }
+
+    /// Number, variable, constant, function call or parenthesized expression
+    fn atom(&mut self) -> Result<(Node, usize), Error> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.src.get(self.pos) {
+            Some(b'(') => {
+                self.pos += 1;
+                let node = self.sum()?;
+                if !self.eat(b')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || *c == b'.' => {
+                self.take_while(|c| c.is_ascii_digit() || c == b'.');
+                // Exponent, only if followed by digits
+                let rest = &self.src[self.pos..];
+                let sign = matches!(rest.get(1), Some(b'+' | b'-')) as usize;
+                if matches!(rest.first(), Some(b'e' | b'E'))
+                    && rest.get(1 + sign).is_some_and(u8::is_ascii_digit)
+                {
+                    self.pos += 1 + sign;
+                    self.take_while(|c| c.is_ascii_digit());
+                }
+                let text = std::str::from_utf8(&self.src[start..self.pos]).expect("ASCII");
+                let num = text.parse().map_err(|_| Error::Syntax {
+                    position: start,
+                    message: "invalid number".to_owned(),
+                })?;
+                Ok((Node::Num(num), 1))
+            }
+            Some(c) if c.is_ascii_alphabetic() || *c == b'_' => {
+                let name = self
+                    .take_while(|c| c.is_ascii_alphanumeric() || c == b'_')
+                    .to_owned();
+                let func = match name.as_str() {
+                    "sin" => Some(Func::Sin),
+                    "cos" => Some(Func::Cos),
+                    "tan" => Some(Func::Tan),
+                    "exp" => Some(Func::Exp),
+                    "ln" => Some(Func::Ln),
+                    "sqrt" => Some(Func::Sqrt),
+                    _ => None,
+                };
+                if let Some(func) = func {
+                    if !self.eat(b'(') {
+                        return Err(self.error("expected `(` after function name"));
+                    }
+                    let (arg, height) = self.sum()?;
+                    if !self.eat(b')') {
+                        return Err(self.error("expected `)`"));
+                    }
+                    return self.node(Node::Call(func, Box::new(arg)), height + 1);
+                }
+                let node = match name.as_str() {
+                    "pi" => Node::Num(std::f64::consts::PI),
+                    "e" => Node::Num(std::f64::consts::E),
+                    _ => {
+                        let index = match self.variables.iter().position(|v| *v == name) {
+                            Some(index) => index,
+                            None => {
+                                self.variables.push(name);
+                                self.variables.len() - 1
+                            }
+                        };
+                        Node::Var(index)
+                    }
+                };
+                Ok((node, 1))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{eval, Error, Expression};
+
+    #[test]
+    fn test_eval() {
+        let z = eval("-x^2 + 2*x/y - 1.5e1", &[("y", 4.), ("x", 3.)]).unwrap();
+        assert_eq!(z.real, -9. + 1.5 - 15.);
+        // Components follow the order of the bindings
+        assert_eq!(z.eps, vec![-1.5 / 4., -6. + 0.5]);
+
+        let z = eval("2^x^2 * exp(ln(x)) + sqrt(x) * cos(pi)", &[("x", 1.)]).unwrap();
+        assert_eq!(z.real, 2. - 1.);
+        assert!((z.d_d(0) - (4. * 2f64.ln() + 2. - 0.5)).abs() < 1e-15);
+
+        let expr = Expression::parse("a * (b + a)").unwrap();
+        assert_eq!(expr.variables(), ["a", "b"]);
+        assert_eq!(expr.eval(&[("a", 1.)]), Err(Error::Unbound("b".into())));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |src: &str| match Expression::parse(src) {
+            Err(Error::Syntax { position, .. }) => position,
+            other => panic!("expected syntax error, got {other:?}"),
+        };
+        assert_eq!(error("x +"), 3);
+        assert_eq!(error("(x + 1"), 6);
+        assert_eq!(error("sin x"), 4);
+        assert_eq!(error("x $ y"), 2);
+        assert_eq!(error("1.2.3"), 0);
+        assert_eq!(
+            Expression::parse("x )").unwrap_err().to_string(),
+            "unexpected character at position 2"
+        );
+
+        // Deep nesting is an error rather than a stack overflow
+        let nested = |prefix: &str, suffix: &str, n: usize| {
+            Expression::parse(&(prefix.repeat(n) + "x" + &suffix.repeat(n)))
+        };
+        assert!(nested("-", "", 200).is_ok() && nested("(", ")", 200).is_ok());
+        for (prefix, suffix) in [("-", ""), ("(", ")"), ("sin(", ")"), ("x^", ""), ("x+", "")] {
+            let error = nested(prefix, suffix, 200_000).unwrap_err();
+            assert!(error
+                .to_string()
+                .starts_with("expression nested too deeply"));
+        }
+    }
+}
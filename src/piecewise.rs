@@ -0,0 +1,144 @@
+//! # Piecewise functions
+//!
+//! [`Piecewise`] combines functions defined on consecutive intervals. Inside
+//! an interval, the value and derivatives are those of its function, and
+//! exactly at a breakpoint, where the derivative is generally not defined,
+//! the [`AtBreakpoint`] policy makes the choice explicit.
+//!
+//! ```
+//! use epsilon::piecewise::{AtBreakpoint, Piecewise};
+//! use epsilon::Dual;
+//! // Absolute value, with the subgradient zero at the kink
+//! let abs = Piecewise::new(|x: Dual<1>| -x)
+//!     .then(0., |x| x)
+//!     .at_breakpoints(AtBreakpoint::Average);
+//!
+//! assert_eq!(abs.eval(Dual::variable(-2., 0)).eps[0], -1.);
+//! assert_eq!(abs.eval(Dual::variable(0., 0)).eps[0], 0.);
+//! ```
+
+use crate::Numerical;
+
+/// Which piece to evaluate exactly at a breakpoint
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AtBreakpoint {
+    /// Panic, for breakpoints which should never be hit
+    Panic,
+    /// The piece ending at the breakpoint
+    Left,
+    /// The piece starting at the breakpoint, i.e. intervals closed on the
+    /// left
+    #[default]
+    Right,
+    /// The mean of both pieces, in value and derivatives
+    Average,
+}
+
+type Piece<'a, T> = Box<dyn Fn(T) -> T + 'a>;
+
+/// Function given by a different closure on each interval between
+/// increasing breakpoints
+pub struct Piecewise<'a, T> {
+    first: Piece<'a, T>,
+    /// Breakpoints and the pieces starting at them
+    pieces: Vec<(f64, Piece<'a, T>)>,
+    policy: AtBreakpoint,
+}
+
+impl<'a, T: Numerical> Piecewise<'a, T> {
+    /// Function given by `f` everywhere, until pieces are added with
+    /// [`then`](Self::then)
+    pub fn new(f: impl Fn(T) -> T + 'a) -> Self {
+        Piecewise {
+            first: Box::new(f),
+            pieces: Vec::new(),
+            policy: AtBreakpoint::default(),
+        }
+    }
+
+    /// Use `f` from `breakpoint` on
+    ///
+    /// # Panics
+    ///
+    /// If `breakpoint` is not greater than the previous breakpoint.
+    pub fn then(mut self, breakpoint: f64, f: impl Fn(T) -> T + 'a) -> Self {
+        if let Some(&(last, _)) = self.pieces.last() {
+            assert!(breakpoint > last, "breakpoints must be increasing");
+        }
+        self.pieces.push((breakpoint, Box::new(f)));
+        self
+    }
+
+    /// Set the policy at the breakpoints, [`AtBreakpoint::Right`] by default
+    pub fn at_breakpoints(mut self, policy: AtBreakpoint) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = f64> + '_ {
+        self.pieces.iter().map(|&(b, _)| b)
+    }
+
+    /// The piece `i`, with `0` the piece before the first breakpoint
+    fn piece(&self, i: usize) -> &dyn Fn(T) -> T {
+        if i == 0 {
+            &self.first
+        } else {
+            &self.pieces[i - 1].1
+        }
+    }
+
+    pub fn eval(&self, x: T) -> T {
+        let x0 = x.real();
+        // Number of breakpoints at or before x
+        let i = self.pieces.partition_point(|&(b, _)| b <= x0);
+        if i == 0 || self.pieces[i - 1].0 != x0 {
+            return self.piece(i)(x);
+        }
+        match self.policy {
+            AtBreakpoint::Panic => panic!("piecewise function evaluated at the breakpoint {x0}"),
+            AtBreakpoint::Left => self.piece(i - 1)(x),
+            AtBreakpoint::Right => self.piece(i)(x),
+            AtBreakpoint::Average => (self.piece(i - 1)(x) + self.piece(i)(x)) * T::constant(0.5),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AtBreakpoint, Piecewise};
+    use crate::Dual;
+
+    #[test]
+    fn test_policies() {
+        // Ramp clamped to [0, 1]
+        let ramp = |policy| {
+            Piecewise::new(|_: Dual<1>| Dual::constant(0.))
+                .then(0., |x| x)
+                .then(1., |_| Dual::constant(1.))
+                .at_breakpoints(policy)
+        };
+        let d = |policy, x| ramp(policy).eval(Dual::variable(x, 0)).eps[0];
+        assert_eq!(d(AtBreakpoint::Right, 0.5), 1.);
+        assert_eq!(d(AtBreakpoint::Right, -3.), 0.);
+        assert_eq!(d(AtBreakpoint::Right, 0.), 1.);
+        assert_eq!(d(AtBreakpoint::Right, 1.), 0.);
+        assert_eq!(d(AtBreakpoint::Left, 0.), 0.);
+        assert_eq!(d(AtBreakpoint::Left, 1.), 1.);
+        assert_eq!(d(AtBreakpoint::Average, 1.), 0.5);
+        assert_eq!(
+            ramp(AtBreakpoint::Left).breakpoints().collect::<Vec<_>>(),
+            [0., 1.]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "breakpoint 2")]
+    fn test_panic() {
+        let f = Piecewise::new(|x: f64| x)
+            .then(2., |x| x * x)
+            .at_breakpoints(AtBreakpoint::Panic);
+        assert_eq!(f.eval(3.), 9.);
+        f.eval(2.);
+    }
+}
@@ -0,0 +1,385 @@
+//! # Polynomials
+//!
+//! [`Polynomial`] with coefficients of any [`Scalar`] type, supporting
+//! evaluation, differentiation and root finding. With dual numbers as the
+//! coefficients, the roots carry their derivatives with respect to whatever
+//! the coefficients depend on, e.g. how the poles of a characteristic
+//! polynomial move with the parameters of a plant.
+//!
+//! [`rational_eval`] evaluates rational functions such as Padé approximants,
+//! reporting points so close to a pole that the derivatives are inaccurate.
+//!
+//! ```
+//! use epsilon::polynomial::Polynomial;
+//! use epsilon::Dual;
+//! // s^2 + 2 zeta s + 1 with damping zeta, poles -zeta +- i sqrt(1 - zeta^2)
+//! let [zeta] = Dual::<1>::variables([0.6]);
+//! let p = Polynomial::new(vec![Dual::constant(1.), zeta * 2., Dual::constant(1.)]);
+//! let roots = p.roots();
+//!
+//! assert!((roots[1].re.real + 0.6).abs() < 1e-15 && (roots[1].im.real - 0.8).abs() < 1e-15);
+//! // d(im)/d(zeta) = -zeta / sqrt(1 - zeta^2)
+//! assert!((roots[1].re.eps[0] + 1.).abs() < 1e-14 && (roots[1].im.eps[0] + 0.75).abs() < 1e-14);
+//! ```
+
+use crate::Scalar;
+
+/// Polynomial `c[0] + c[1] x + c[2] x^2 + ...`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial<T> {
+    /// The coefficients, in order of increasing power
+    pub coefficients: Vec<T>,
+}
+
+/// A complex root `re + i im` of a [`Polynomial`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Root<T> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T: Scalar> Root<T> {
+    fn add(self, other: Self) -> Self {
+        Root {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Root {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Root {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn div(self, other: Self) -> Self {
+        let norm = other.re * other.re + other.im * other.im;
+        Root {
+            re: (self.re * other.re + self.im * other.im) / norm,
+            im: (self.im * other.re - self.re * other.im) / norm,
+        }
+    }
+
+    fn real(re: T) -> Self {
+        Root { re, im: T::ZERO }
+    }
+}
+
+impl Root<f64> {
+    fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+/// Largest number of Aberth iterations in [`Polynomial::roots`]
+const MAX_ITER: usize = 500;
+
+impl<T: Scalar> Polynomial<T> {
+    pub fn new(coefficients: Vec<T>) -> Self {
+        Polynomial { coefficients }
+    }
+
+    /// The highest power with a non-zero coefficient, or `None` for the zero
+    /// polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.iter().rposition(|c| c.to_f64() != 0.)
+    }
+
+    /// Value at `x`, by Horner's method
+    pub fn eval(&self, x: T) -> T {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(T::ZERO, |sum, &c| sum * x + c)
+    }
+
+    /// The derivative with respect to `x`
+    pub fn derivative(&self) -> Self {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| c * T::from_f64(i as f64))
+            .collect();
+        Polynomial::new(coefficients)
+    }
+
+    /// Value at the complex point `z`
+    fn eval_complex<S: Scalar>(coefficients: &[S], z: Root<S>) -> Root<S> {
+        coefficients
+            .iter()
+            .rev()
+            .fold(Root::real(S::ZERO), |sum, &c| sum.mul(z).add(Root::real(c)))
+    }
+
+    /// All complex roots, repeated by multiplicity and sorted by real and
+    /// then imaginary part
+    ///
+    /// The roots of the real parts of the coefficients are found by the
+    /// Aberth method. Roots whose imaginary part is within rounding of zero
+    /// are taken to be real. A final Newton step on `T` then gives the
+    /// derivatives of each root `r` by the implicit function theorem,
+    /// `dr/dc = -(dp/dc) / p'(r)`, which are not finite at multiple roots.
+    pub fn roots(&self) -> Vec<Root<T>> {
+        let Some(degree) = self.degree() else {
+            return Vec::new();
+        };
+        let coefficients = &self.coefficients[..=degree];
+        let real: Vec<f64> = coefficients.iter().map(|c| c.to_f64()).collect();
+        let derivative: Vec<f64> = Polynomial::new(real.clone()).derivative().coefficients;
+
+        // Initial guesses spread on a circle enclosing all roots
+        let radius = 1.
+            + real[..degree]
+                .iter()
+                .map(|c| (c / real[degree]).abs())
+                .fold(0., f64::max);
+        let mut z: Vec<Root<f64>> = (0..degree)
+            .map(|k| {
+                let angle = std::f64::consts::TAU * (k as f64 + 0.25) / degree as f64;
+                Root {
+                    re: radius * angle.cos(),
+                    im: radius * angle.sin(),
+                }
+            })
+            .collect();
+        for _ in 0..MAX_ITER {
+            let mut converged = true;
+            for k in 0..degree {
+                let ratio =
+                    Self::eval_complex(&real, z[k]).div(Self::eval_complex(&derivative, z[k]));
+                let repulsion = (0..degree)
+                    .filter(|&j| j != k)
+                    .fold(Root::real(0.), |sum, j| {
+                        sum.add(Root::real(1.).div(z[k].sub(z[j])))
+                    });
+                let step = ratio.div(Root::real(1.).sub(ratio.mul(repulsion)));
+                if step.re.is_finite() && step.im.is_finite() {
+                    z[k] = z[k].sub(step);
+                    converged &= step.abs() <= 4. * f64::EPSILON * z[k].abs().max(1.);
+                }
+            }
+            if converged {
+                break;
+            }
+        }
+
+        // The coefficients are real, so the complex roots come in conjugate
+        // pairs, which are made exact
+        for z in z.iter_mut() {
+            if z.im.abs() <= 1e-8 * z.abs().max(1.) {
+                z.im = 0.;
+            }
+        }
+        let mut paired = vec![false; degree];
+        for k in 0..degree {
+            if z[k].im <= 0. {
+                continue;
+            }
+            let conjugate = (0..degree)
+                .filter(|&j| z[j].im < 0. && !paired[j])
+                .min_by(|&i, &j| {
+                    let distance = |j: usize| {
+                        z[j].sub(Root {
+                            re: z[k].re,
+                            im: -z[k].im,
+                        })
+                        .abs()
+                    };
+                    distance(i).total_cmp(&distance(j))
+                });
+            if let Some(j) = conjugate {
+                paired[j] = true;
+                z[j] = Root {
+                    re: z[k].re,
+                    im: -z[k].im,
+                };
+            }
+        }
+
+        let derivative = self.derivative();
+        let mut roots: Vec<Root<T>> = z
+            .into_iter()
+            .map(|z| {
+                let z = Root {
+                    re: T::from_f64(z.re),
+                    im: T::from_f64(z.im),
+                };
+                let value = Self::eval_complex(coefficients, z);
+                let slope = Self::eval_complex(&derivative.coefficients, z);
+                z.sub(value.div(slope))
+            })
+            .collect();
+        roots.sort_by(|a, b| {
+            let key = |r: &Root<T>| (r.re.to_f64(), r.im.to_f64());
+            key(a)
+                .partial_cmp(&key(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        roots
+    }
+}
+
+/// Smallest relative size of the denominator accepted by [`rational_eval`]
+///
+/// The derivatives of a rational function grow as the inverse square of the
+/// denominator, so that below this, more than half of the significant digits
+/// of the derivatives may be lost to rounding.
+pub const POLE_TOLERANCE: f64 = 1e-8;
+
+/// Error of [`rational_eval`] at a point too close to a pole
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NearPole {
+    /// Value of the denominator
+    pub denominator: f64,
+    /// Size of the denominator relative to the sum of the absolute values of
+    /// its terms
+    pub relative: f64,
+}
+
+impl std::fmt::Display for NearPole {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "denominator {:e} is within {:e} relative to its terms of a pole",
+            self.denominator, self.relative
+        )
+    }
+}
+
+impl std::error::Error for NearPole {}
+
+/// Horner's method with the coefficients given from the highest power
+fn horner<T: Scalar>(coefficients: impl Iterator<Item = f64>, z: T) -> T {
+    coefficients.fold(T::ZERO, |sum, c| sum * z + T::from_f64(c))
+}
+
+/// The rational function `p(x) / q(x)`, with the coefficients of `p` in `num`
+/// and those of `q` in `den`, in order of increasing power
+///
+/// For `|x| > 1`, both polynomials are evaluated in `1 / x` with their
+/// coefficients reversed, so that high powers of `x` cannot overflow. The
+/// derivatives follow from the quotient rule of the dual number division.
+/// Returns [`NearPole`] if `|q(x)|` is less than [`POLE_TOLERANCE`] times the
+/// sum of the absolute values of its terms, i.e. if it is dominated by
+/// cancellation.
+///
+/// Panics if `den` is empty.
+pub fn rational_eval<T: Scalar>(num: &[f64], den: &[f64], x: T) -> Result<T, NearPole> {
+    assert!(!den.is_empty(), "the denominator has no coefficients");
+    let x0 = x.to_f64();
+    let reversed = x0.abs() > 1.;
+    let (p, q, size) = if reversed {
+        let z = T::ONE / x;
+        (
+            horner(num.iter().copied(), z),
+            horner(den.iter().copied(), z),
+            horner(den.iter().map(|c| c.abs()), 1. / x0.abs()),
+        )
+    } else {
+        (
+            horner(num.iter().rev().copied(), x),
+            horner(den.iter().rev().copied(), x),
+            horner(den.iter().rev().map(|c| c.abs()), x0.abs()),
+        )
+    };
+
+    let denominator = q.to_f64();
+    let relative = denominator.abs() / size;
+    // Also rejects the zero polynomial, for which this is NaN
+    if relative.is_nan() || relative < POLE_TOLERANCE {
+        return Err(NearPole {
+            denominator,
+            relative,
+        });
+    }
+    let mut ratio = p / q;
+    if reversed {
+        // p(x) / q(x) = x^(n - m) p~(1 / x) / q~(1 / x) for degrees n and m
+        let shift = num.len() as isize - den.len() as isize;
+        for _ in 0..shift.unsigned_abs() {
+            ratio = if shift > 0 { ratio * x } else { ratio / x };
+        }
+    }
+    Ok(ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rational_eval, NearPole, Polynomial};
+    use crate::Dual;
+
+    #[test]
+    fn test_eval() {
+        let p = Polynomial::new(vec![2., -3., 0., 1.]);
+        assert_eq!(p.eval(2.), 4.);
+        assert_eq!(p.derivative().coefficients, [-3., 0., 3.]);
+        assert_eq!(p.degree(), Some(3));
+        assert_eq!(Polynomial::new(vec![0., 0.]).degree(), None);
+
+        let dual = Polynomial::new(p.coefficients.iter().map(|&c| Dual::constant(c)).collect());
+        let y = dual.eval(Dual::<1>::variable(2., 0));
+        assert_eq!(y.eps[0], p.derivative().eval(2.));
+    }
+
+    #[test]
+    fn test_roots() {
+        // (x - 1)(x - 2)(x + 3) = x^3 - 7x + 6, with dr/dc0 = -1 / p'(r)
+        let [c0] = Dual::<1>::variables([6.]);
+        let c = |x: f64| Dual::constant(x);
+        let p = Polynomial::new(vec![c0, c(-7.), c(0.), c(1.)]);
+        let roots = p.roots();
+        for (root, expected) in roots.iter().zip([-3., 1., 2.]) {
+            let slope = 3. * expected * expected - 7.;
+            assert!((root.re.real - expected).abs() < 1e-14);
+            assert!((root.re.eps[0] + 1. / slope).abs() < 1e-13);
+            assert_eq!(root.im.real, 0.);
+        }
+
+        // x^4 + 1, roots on the unit circle at odd multiples of pi / 4
+        let roots = Polynomial::new(vec![1., 0., 0., 0., 1.]).roots();
+        let h = 0.5f64.sqrt();
+        for (root, expected) in roots.iter().zip([[-h, -h], [-h, h], [h, -h], [h, h]]) {
+            assert!((root.re - expected[0]).abs() < 1e-15 && (root.im - expected[1]).abs() < 1e-15);
+        }
+        assert!(Polynomial::new(vec![5.]).roots().is_empty());
+    }
+
+    #[test]
+    fn test_rational_eval() {
+        // [2/2] Pade approximant of exp(x)
+        let num = [1., 0.5, 1. / 12.];
+        let den = [1., -0.5, 1. / 12.];
+        for x in [-3., -0.4, 0.7, 5., 1e200] {
+            let y = rational_eval(&num, &den, Dual::<1>::variable(x, 0)).unwrap();
+            let (p, q) = (Polynomial::new(num.to_vec()), Polynomial::new(den.to_vec()));
+            let value = if x < 1e100 { p.eval(x) / q.eval(x) } else { 1. };
+            let slope = if x < 1e100 {
+                (p.derivative().eval(x) * q.eval(x) - p.eval(x) * q.derivative().eval(x))
+                    / q.eval(x).powi(2)
+            } else {
+                0.
+            };
+            assert!((y.real - value).abs() <= 1e-15 * value.abs());
+            assert!((y.eps[0] - slope).abs() <= 1e-14 * slope.abs());
+        }
+        // Different degrees, x^3 / (1 + x) at large x
+        let y = rational_eval(&[0., 0., 0., 1.], &[1., 1.], 1e10f64).unwrap();
+        assert!((y - 1e30 / (1. + 1e10)).abs() <= 1e-15 * y);
+
+        // Pole of 1 / (1 - x) at x = 1
+        assert!(rational_eval(&[1.], &[1., -1.], 1. - 1e-6).is_ok());
+        let err = rational_eval(&[1.], &[1., -1.], Dual::<1>::variable(1. + 1e-12, 0));
+        assert!(matches!(err, Err(NearPole { relative, .. }) if relative < 1e-11));
+        assert!(rational_eval(&[1.], &[0.], 2.).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! # Python bindings
+//!
+//! With the `python` feature, [`DualVec`] is exposed to Python as the class
+//! `Dual`, supporting the arithmetic operators and the same math methods as
+//! in Rust. Register it in an extension module using [`register`]:
+//!
+//! ```ignore
+//! #[pyo3::pymodule]
+//! fn my_module(m: &pyo3::Bound<'_, pyo3::types::PyModule>) -> pyo3::PyResult<()> {
+//!     epsilon::python::register(m)
+//! }
+//! ```
+//!
+//! ```python
+//! from my_module import Dual
+//! x, y = Dual.variable(5., 0, 2), Dual.variable(7., 1, 2)
+//! z = x ** 2 + y * y.sin()
+//! print(z.value, z.grad) # 25 + 7 sin(7), [10.0, 5.934302379121921]
+//! ```
+
+use crate::DualVec;
+use pyo3::prelude::*;
+
+/// Python wrapper around [`DualVec`]
+#[pyclass(name = "Dual", module = "epsilon", from_py_object)]
+#[derive(Clone)]
+pub struct PyDual {
+    /// The wrapped dual number
+    pub inner: DualVec,
+}
+
+/// Right hand side of a binary operator, either a dual number or a constant
+#[derive(FromPyObject)]
+enum Operand {
+    Dual(PyDual),
+    Real(f64),
+}
+
+impl From<Operand> for DualVec {
+    fn from(op: Operand) -> Self {
+        match op {
+            Operand::Dual(d) => d.inner,
+            Operand::Real(r) => DualVec::constant(r),
+        }
+    }
+}
+
+impl From<DualVec> for PyDual {
+    fn from(inner: DualVec) -> Self {
+        PyDual { inner }
+    }
+}
+
+#[pymethods]
+impl PyDual {
+    #[new]
+    #[pyo3(signature = (value, grad = Vec::new()))]
+    fn new(value: f64, grad: Vec<f64>) -> Self {
+        DualVec::new(value, grad).into()
+    }
+
+    /// Create the `index`th out of `n` variables, with a unit dual part
+    #[staticmethod]
+    fn variable(value: f64, index: usize, n: usize) -> PyResult<Self> {
+        if index >= n {
+            return Err(pyo3::exceptions::PyIndexError::new_err(format!(
+                "variable index {index} out of range for {n} components"
+            )));
+        }
+        Ok(DualVec::variable(value, index, n).into())
+    }
+
+    /// The real value
+    #[getter]
+    fn value(&self) -> f64 {
+        self.inner.real
+    }
+
+    /// The dual part, i.e. the derivative with respect to each component
+    #[getter]
+    fn grad(&self) -> Vec<f64> {
+        self.inner.eps.clone()
+    }
+
+    /// Derivative with respect to the `index`th component
+    fn d_d(&self, index: usize) -> f64 {
+        self.inner.d_d(index)
+    }
+
+    fn powf(&self, pow: f64) -> Self {
+        self.inner.clone().powf(pow).into()
+    }
+
+    fn invert(&self) -> Self {
+        self.inner.clone().invert().into()
+    }
+
+    fn sin(&self) -> Self {
+        self.inner.clone().sin().into()
+    }
+
+    fn cos(&self) -> Self {
+        self.inner.clone().cos().into()
+    }
+
+    fn tan(&self) -> Self {
+        self.inner.clone().tan().into()
+    }
+
+    fn __add__(&self, other: Operand) -> Self {
+        (self.inner.clone() + DualVec::from(other)).into()
+    }
+
+    fn __radd__(&self, other: Operand) -> Self {
+        (DualVec::from(other) + self.inner.clone()).into()
+    }
+
+    fn __sub__(&self, other: Operand) -> Self {
+        (self.inner.clone() - DualVec::from(other)).into()
+    }
+
+    fn __rsub__(&self, other: Operand) -> Self {
+        (DualVec::from(other) - self.inner.clone()).into()
+    }
+
+    fn __mul__(&self, other: Operand) -> Self {
+        (self.inner.clone() * DualVec::from(other)).into()
+    }
+
+    fn __rmul__(&self, other: Operand) -> Self {
+        (DualVec::from(other) * self.inner.clone()).into()
+    }
+
+    fn __truediv__(&self, other: Operand) -> Self {
+        (self.inner.clone() / DualVec::from(other)).into()
+    }
+
+    fn __rtruediv__(&self, other: Operand) -> Self {
+        (DualVec::from(other) / self.inner.clone()).into()
+    }
+
+    fn __pow__(&self, pow: f64, _modulo: Option<Py<PyAny>>) -> Self {
+        self.powf(pow)
+    }
+
+    fn __neg__(&self) -> Self {
+        (-self.inner.clone()).into()
+    }
+
+    fn __float__(&self) -> f64 {
+        self.inner.real
+    }
+
+    fn __lt__(&self, other: Operand) -> bool {
+        self.inner < DualVec::from(other)
+    }
+
+    fn __gt__(&self, other: Operand) -> bool {
+        self.inner > DualVec::from(other)
+    }
+
+    fn __eq__(&self, other: Operand) -> bool {
+        self.inner == DualVec::from(other)
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Dual({}, grad={:?})", self.inner.real, self.inner.eps)
+    }
+}
+
+/// Add the `Dual` class to a Python module
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDual>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register, PyDual};
+    use crate::DualVec;
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyModule};
+
+    #[test]
+    fn test_round_trip() {
+        Python::initialize();
+        Python::attach(|py| {
+            let m = PyModule::new(py, "epsilon").unwrap();
+            register(&m).unwrap();
+            let globals = PyDict::new(py);
+            globals
+                .set_item("Dual", m.getattr("Dual").unwrap())
+                .unwrap();
+            let code = c"x, y = Dual.variable(5., 0, 2), Dual.variable(7., 1, 2)
+z = 2 - x ** 2 / y + y.sin() * 3";
+            py.run(code, Some(&globals), None).unwrap();
+            let z: PyDual = globals.get_item("z").unwrap().unwrap().extract().unwrap();
+
+            let (x, y) = (DualVec::variable(5., 0, 2), DualVec::variable(7., 1, 2));
+            let expected = 2. - x.powf(2.) / y.clone() + y.sin() * 3.;
+            assert_eq!(z.inner, expected);
+            // Back into Python, and out again unchanged
+            let z = Bound::new(py, z).unwrap();
+            assert_eq!(
+                z.getattr("grad").unwrap().extract::<Vec<f64>>().unwrap(),
+                expected.eps
+            );
+            assert_eq!(z.extract::<PyDual>().unwrap().inner, expected);
+        });
+    }
+}
@@ -0,0 +1,159 @@
+//! # Numerical integration
+//!
+//! Adaptive Gauss-Kronrod quadrature of integrands returning any
+//! [`Numerical`] type. With dual numbers depending on parameters, the dual
+//! part of the integral is the derivative of the integral with respect to the
+//! parameters (differentiation under the integral sign).
+//!
+//! ```
+//! use epsilon::{quad, Dual1};
+//! // I(p) = int_0^1 exp(-p x) dx = (1 - exp(-p)) / p
+//! let p = Dual1::variable(2., 0);
+//! let integral = quad::gauss_kronrod(|x| (p * -x).exp(), 0., 1., 1e-12, 50);
+//!
+//! let e = (-2f64).exp();
+//! assert!((integral.value.real - (1. - e) / 2.).abs() < 1e-12);
+//! // dI/dp = (e^-p (p + 1) - 1) / p^2
+//! assert!((integral.value.eps[0] - (3. * e - 1.) / 4.).abs() < 1e-12);
+//! ```
+
+use crate::Numerical;
+
+/// Result of a numerical integration, along with diagnostics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Integral<T> {
+    /// Estimate of the integral
+    pub value: T,
+    /// Estimated absolute error of the real part
+    pub error: f64,
+    /// Number of evaluations of the integrand
+    pub evaluations: usize,
+    /// Whether the estimated error is within the tolerance
+    pub converged: bool,
+}
+
+/// Positive nodes of the 15-point Kronrod rule, the odd ones shared with the
+/// 7-point Gauss rule
+const NODES: [f64; 8] = [
+    0.9914553711208126,
+    0.9491079123427585,
+    0.8648644233597691,
+    0.7415311855993945,
+    0.5860872354676911,
+    0.4058451513773972,
+    0.20778495500789848,
+    0.,
+];
+
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022935322010529224,
+    0.06309209262997856,
+    0.10479001032225019,
+    0.14065325971552592,
+    0.1690047266392679,
+    0.19035057806478542,
+    0.20443294007529889,
+    0.20948214108472782,
+];
+
+/// Weights of the 7-point Gauss rule at `NODES[1]`, `NODES[3]`, ...
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.1294849661688697,
+    0.27970539148927664,
+    0.3818300505051189,
+    0.4179591836734694,
+];
+
+/// Integrate `f` over `[a, b]` using the 15-point Kronrod rule, estimating the
+/// error from the embedded 7-point Gauss rule
+fn kronrod<T: Numerical>(f: &impl Fn(f64) -> T, a: f64, b: f64) -> (T, f64) {
+    let (center, half) = ((a + b) / 2., (b - a) / 2.);
+    let mut kronrod = T::constant(0.);
+    let mut gauss = T::constant(0.);
+    for (i, (&node, &weight)) in NODES.iter().zip(&KRONROD_WEIGHTS).enumerate() {
+        let values = if node == 0. {
+            f(center)
+        } else {
+            f(center - half * node) + f(center + half * node)
+        };
+        kronrod += values * T::constant(weight);
+        if i % 2 == 1 {
+            gauss += values * T::constant(GAUSS_WEIGHTS[i / 2]);
+        }
+    }
+    let (kronrod, gauss) = (kronrod * T::constant(half), gauss * T::constant(half));
+    (kronrod, (kronrod - gauss).real().abs())
+}
+
+/// Integrate `f` over `[a, b]` using adaptive Gauss-Kronrod (G7-K15)
+/// quadrature
+///
+/// The subinterval with the largest error estimate is bisected until the
+/// total estimated error of the real part is at most `tol * (1 + |I|)`, for
+/// at most `max_subdivisions` bisections.
+pub fn gauss_kronrod<T: Numerical>(
+    f: impl Fn(f64) -> T,
+    a: f64,
+    b: f64,
+    tol: f64,
+    max_subdivisions: usize,
+) -> Integral<T> {
+    const EVALUATIONS: usize = 15;
+
+    let (value, error) = kronrod(&f, a, b);
+    // Subintervals with their integral and error estimates
+    let mut intervals = vec![(a, b, value, error)];
+    let mut evaluations = EVALUATIONS;
+    loop {
+        let value = intervals
+            .iter()
+            .fold(T::constant(0.), |sum, &(_, _, value, _)| sum + value);
+        let error: f64 = intervals.iter().map(|&(_, _, _, error)| error).sum();
+        let converged = error <= tol * (1. + value.real().abs());
+        if converged || intervals.len() > max_subdivisions {
+            return Integral {
+                value,
+                error,
+                evaluations,
+                converged,
+            };
+        }
+
+        let worst = (0..intervals.len())
+            .max_by(|&i, &j| intervals[i].3.total_cmp(&intervals[j].3))
+            .expect("at least one interval");
+        let (a, b, _, _) = intervals.swap_remove(worst);
+        let mid = (a + b) / 2.;
+        let (left, left_error) = kronrod(&f, a, mid);
+        let (right, right_error) = kronrod(&f, mid, b);
+        intervals.push((a, mid, left, left_error));
+        intervals.push((mid, b, right, right_error));
+        evaluations += 2 * EVALUATIONS;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gauss_kronrod;
+    use crate::Dual1;
+
+    #[test]
+    fn test_gauss_kronrod() {
+        // Polynomials up to degree 22 are integrated exactly
+        let integral = gauss_kronrod(|x| x.powi(10), -1., 1., 1e-14, 0);
+        assert!(integral.converged);
+        assert!((integral.value - 2. / 11.).abs() < 1e-15);
+        assert_eq!(integral.evaluations, 15);
+
+        // d/dw int_0^pi sin(w x) dx = int_0^pi x cos(w x) dx = -2 at w = 1
+        let w = Dual1::variable(1., 0);
+        let integral = gauss_kronrod(|x| (w * x).sin(), 0., std::f64::consts::PI, 1e-12, 50);
+        assert!((integral.value.real - 2.).abs() < 1e-12);
+        assert!((integral.value.eps[0] + 2.).abs() < 1e-12);
+
+        // The singularity requires subdivision
+        let integral = gauss_kronrod(|x: f64| x.sqrt(), 0., 1., 1e-10, 100);
+        assert!(integral.converged && integral.evaluations > 15);
+        assert!((integral.value - 2. / 3.).abs() < 1e-10);
+    }
+}
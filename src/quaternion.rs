@@ -0,0 +1,206 @@
+//! # Quaternions
+//!
+//! [`Quaternion`] over any [`Numerical`] type, for representing and applying
+//! rotations. With dual numbers as the components, derivatives of rotated
+//! vectors and orientation errors with respect to e.g. joint angles are
+//! propagated.
+//!
+//! ```
+//! use epsilon::quaternion::Quaternion;
+//! use epsilon::vector::Vec3;
+//! use epsilon::Dual;
+//! // Rotate the x axis about z, with the derivative with respect to the angle
+//! let [angle] = Dual::<1>::variables([0.5]);
+//! let q = Quaternion::from_axis_angle(Vec3::constant([0., 0., 1.]), angle);
+//! let v = q.rotate(Vec3::constant([1., 0., 0.]));
+//!
+//! assert!((v.x.real - 0.5f64.cos()).abs() < 1e-15);
+//! assert!((v.y.eps[0] - 0.5f64.cos()).abs() < 1e-15);
+//! ```
+
+use crate::matrix::Matrix3;
+use crate::vector::Vec3;
+use crate::Numerical;
+
+/// Quaternion `w + x i + y j + z k`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion<T> {
+    pub w: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Numerical> Quaternion<T> {
+    pub fn new(w: T, x: T, y: T, z: T) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    /// The rotation by zero
+    pub fn identity() -> Self {
+        let zero = T::constant(0.);
+        Quaternion::new(T::constant(1.), zero, zero, zero)
+    }
+
+    /// Quaternion with the real part `w` and the imaginary part `v`
+    pub fn from_parts(w: T, v: Vec3<T>) -> Self {
+        Quaternion::new(w, v.x, v.y, v.z)
+    }
+
+    /// Rotation by `angle` radians about `axis`, which need not be normalized
+    pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+        let half = angle * T::constant(0.5);
+        Quaternion::from_parts(half.cos(), axis.normalize() * half.sin())
+    }
+
+    /// The imaginary part
+    pub fn vector(self) -> Vec3<T> {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn conjugate(self) -> Self {
+        Quaternion::from_parts(self.w, -self.vector())
+    }
+
+    pub fn norm_squared(self) -> T {
+        self.w * self.w + self.vector().norm_squared()
+    }
+
+    pub fn norm(self) -> T {
+        self.norm_squared().powf(0.5)
+    }
+
+    /// Unit quaternion in the direction of `self`
+    pub fn normalize(self) -> Self {
+        let norm = self.norm();
+        Quaternion::from_parts(self.w / norm, self.vector() / norm)
+    }
+
+    pub fn inverse(self) -> Self {
+        let n = self.norm_squared();
+        let c = self.conjugate();
+        Quaternion::from_parts(c.w / n, c.vector() / n)
+    }
+
+    /// Rotate `v` by the unit quaternion `self`, `q v q^*`
+    pub fn rotate(self, v: Vec3<T>) -> Vec3<T> {
+        // v + 2 u x (u x v + w v), with u the imaginary part
+        let u = self.vector();
+        let t = u.cross(v) + v * self.w;
+        v + u.cross(t) * T::constant(2.)
+    }
+
+    /// The rotation matrix of the unit quaternion `self`
+    pub fn to_matrix(self) -> Matrix3<T> {
+        let Quaternion { w, x, y, z } = self;
+        let one = T::constant(1.);
+        let two = T::constant(2.);
+        Matrix3::new([
+            [
+                one - two * (y * y + z * z),
+                two * (x * y - w * z),
+                two * (x * z + w * y),
+            ],
+            [
+                two * (x * y + w * z),
+                one - two * (x * x + z * z),
+                two * (y * z - w * x),
+            ],
+            [
+                two * (x * z - w * y),
+                two * (y * z + w * x),
+                one - two * (x * x + y * y),
+            ],
+        ])
+    }
+
+    /// The real parts of `(w, x, y, z)`
+    pub fn real(&self) -> [f64; 4] {
+        [self.w.real(), self.x.real(), self.y.real(), self.z.real()]
+    }
+}
+
+impl<T: Numerical> std::fmt::Display for Quaternion<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} + ({})i + ({})j + ({})k",
+            self.w, self.x, self.y, self.z
+        )
+    }
+}
+
+/// The Hamilton product, composing the rotations `other` and then `self`
+impl<T: Numerical> std::ops::Mul for Quaternion<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (u, v) = (self.vector(), other.vector());
+        Quaternion::from_parts(
+            self.w * other.w - u.dot(v),
+            v * self.w + u * other.w + u.cross(v),
+        )
+    }
+}
+
+impl<T: Numerical> std::ops::MulAssign for Quaternion<T> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quaternion;
+    use crate::vector::Vec3;
+    use crate::Dual;
+
+    #[test]
+    fn test_algebra() {
+        let q: Quaternion<f64> = Quaternion::new(1., 2., 3., 4.);
+        let i = Quaternion::new(0., 1., 0., 0.);
+        let j = Quaternion::new(0., 0., 1., 0.);
+        assert_eq!((i * j).real(), [0., 0., 0., 1.]);
+        assert_eq!((j * i).real(), [0., 0., 0., -1.]);
+        assert_eq!((q * q.conjugate()).real(), [30., 0., 0., 0.]);
+        let p = q * q.inverse();
+        for (x, e) in p.real().iter().zip([1., 0., 0., 0.]) {
+            assert!((x - e).abs() < 1e-15);
+        }
+
+        // Rotation and matrix agree
+        let q = q.normalize();
+        let v = Vec3::constant([0.3, -1., 2.]);
+        let rotated = q.rotate(v).real();
+        let by_matrix = q.to_matrix() * v.real();
+        for (a, b) in rotated.iter().zip(by_matrix) {
+            assert!((a - b).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // Two joints about z and x, with the derivatives of the end point
+        let [a, b] = Dual::<2>::variables([0.4, 1.1]);
+        let qa = Quaternion::from_axis_angle(Vec3::constant([0., 0., 2.]), a);
+        let qb = Quaternion::from_axis_angle(Vec3::constant([1., 0., 0.]), b);
+        let p = (qa * qb).rotate(Vec3::constant([0., 1., 0.]));
+
+        // Rz(a) Rx(b) e_y = Rz(a) (0, cos b, sin b)
+        let (ca, sa, cb, sb) = (0.4f64.cos(), 0.4f64.sin(), 1.1f64.cos(), 1.1f64.sin());
+        let expected = [[-ca * cb, sa * sb], [-sa * cb, -ca * sb], [0., cb]];
+        for (x, e) in [p.x, p.y, p.z].iter().zip(expected) {
+            assert!((x.eps[0] - e[0]).abs() < 1e-15 && (x.eps[1] - e[1]).abs() < 1e-15);
+        }
+
+        // Normalization removes the derivative along the quaternion itself
+        let [s] = Dual::<1>::variables([2.]);
+        let q = Quaternion::new(s, s, Dual::constant(0.), Dual::constant(0.)).normalize();
+        assert!(q
+            .real()
+            .iter()
+            .zip([0.5f64.sqrt(), 0.5f64.sqrt(), 0., 0.])
+            .all(|(x, e)| (x - e).abs() < 1e-15));
+        assert!([q.w, q.x].iter().all(|c| c.eps[0].abs() < 1e-15));
+    }
+}
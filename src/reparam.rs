@@ -0,0 +1,122 @@
+//! # Reparameterized sampling
+//!
+//! With the `rand` feature, samplers which draw from a fixed base
+//! distribution and transform the draw by the distribution parameters, the
+//! reparameterization trick. With the parameters given as dual numbers, the
+//! samples carry their derivatives with respect to them, giving unbiased
+//! gradient estimates of expectations as used in stochastic variational
+//! inference. All samplers take the random number generator by reference, so
+//! seeding it makes the draws and their gradients reproducible.
+//!
+//! ```
+//! use epsilon::reparam::normal_rsample;
+//! use epsilon::Dual;
+//! use rand::SeedableRng;
+//! // Gradient of E[x^2] = mu^2 + sigma^2 with x ~ N(mu, sigma)
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+//! let [mu, sigma] = Dual::<2>::variables([1., 0.5]);
+//! let n = 10000;
+//! let mut grad = [0.; 2];
+//! for _ in 0..n {
+//!     let x = normal_rsample(mu, sigma, &mut rng);
+//!     let y = x * x;
+//!     grad[0] += y.eps[0] / n as f64;
+//!     grad[1] += y.eps[1] / n as f64;
+//! }
+//!
+//! assert!((grad[0] - 2.).abs() < 0.05 && (grad[1] - 1.).abs() < 0.05);
+//! ```
+
+use crate::Scalar;
+use rand::Rng;
+
+/// Draw from the standard normal distribution by the Box-Muller transform
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // 1 - u is in (0, 1], avoiding ln(0)
+    let u = 1. - rng.random::<f64>();
+    let v = rng.random::<f64>();
+    (-2. * u.ln()).sqrt() * (2. * std::f64::consts::PI * v).cos()
+}
+
+/// Normal distribution with mean `mu` and standard deviation `sigma`, as
+/// `mu + sigma z` with `z` standard normal
+pub fn normal_rsample<T: Scalar, R: Rng + ?Sized>(mu: T, sigma: T, rng: &mut R) -> T {
+    mu + sigma * T::from_f64(standard_normal(rng))
+}
+
+/// Log-normal distribution, with `ln(x)` normally distributed with mean `mu`
+/// and standard deviation `sigma`
+pub fn lognormal_rsample<T: Scalar, R: Rng + ?Sized>(mu: T, sigma: T, rng: &mut R) -> T {
+    normal_rsample(mu, sigma, rng).exp()
+}
+
+/// Uniform distribution on `[low, high)`, as `low + (high - low) u`
+pub fn uniform_rsample<T: Scalar, R: Rng + ?Sized>(low: T, high: T, rng: &mut R) -> T {
+    low + (high - low) * T::from_f64(rng.random())
+}
+
+/// Exponential distribution with rate `rate`, as `-ln(u) / rate` by inverse
+/// transform sampling
+pub fn exponential_rsample<T: Scalar, R: Rng + ?Sized>(rate: T, rng: &mut R) -> T {
+    let u = 1. - rng.random::<f64>();
+    T::from_f64(-u.ln()) / rate
+}
+
+/// Gumbel distribution with location `mu` and scale `beta`, as
+/// `mu - beta ln(-ln(u))`
+pub fn gumbel_rsample<T: Scalar, R: Rng + ?Sized>(mu: T, beta: T, rng: &mut R) -> T {
+    let u = 1. - rng.random::<f64>();
+    // -ln(u) is zero for u = 1, keep it positive
+    let e = (-u.ln()).max(f64::MIN_POSITIVE);
+    mu - beta * T::from_f64(e.ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dual;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_moments() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let n = 20000;
+        let mean = |f: &mut dyn FnMut() -> f64| (0..n).map(|_| f()).sum::<f64>() / n as f64;
+
+        let z = mean(&mut || standard_normal(&mut rng).powi(2));
+        assert!((z - 1.).abs() < 0.05);
+        let x = mean(&mut || normal_rsample(2., 3., &mut rng));
+        assert!((x - 2.).abs() < 0.1);
+        let x = mean(&mut || exponential_rsample(4., &mut rng));
+        assert!((x - 0.25).abs() < 0.01);
+        let x = mean(&mut || uniform_rsample(-1., 3., &mut rng));
+        assert!((x - 1.).abs() < 0.05);
+        // Mean mu + beta gamma, with gamma the Euler-Mascheroni constant
+        let x = mean(&mut || gumbel_rsample(1., 2., &mut rng));
+        assert!((x - 1. - 2. * 0.5772156649015329).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+
+        // Each sample is a deterministic function of the parameters
+        let [mu, sigma] = Dual::<2>::variables([1., 2.]);
+        let x = normal_rsample(mu, sigma, &mut rng);
+        let z = (x.real - 1.) / 2.;
+        assert_eq!(x.eps, [1., z]);
+
+        let [mu, sigma] = Dual::<2>::variables([0.5, 0.1]);
+        let x = lognormal_rsample(mu, sigma, &mut rng);
+        assert!((x.eps[0] - x.real).abs() < 1e-15);
+
+        // d/drate E[x] = -1 / rate^2
+        let [rate] = Dual::<1>::variables([2.]);
+        let n = 20000;
+        let grad = (0..n)
+            .map(|_| exponential_rsample(rate, &mut rng).eps[0])
+            .sum::<f64>()
+            / n as f64;
+        assert!((grad + 0.25).abs() < 0.01);
+    }
+}
@@ -0,0 +1,409 @@
+//! # Reverse-mode differentiation
+//!
+//! Dual numbers propagate the derivatives with respect to every input through
+//! each operation, so the cost grows with the number of inputs. For functions
+//! of many inputs and a single output, reverse mode is cheaper: operations on
+//! [`Var`] are recorded on a [`Tape`], and the gradient with respect to all
+//! inputs is computed in a single backward pass.
+//!
+//! [`Var`] has the same math methods as the dual types and implements
+//! [`Numerical`], so code generic over [`Numerical`] can be differentiated in
+//! either mode.
+//!
+//! ```
+//! use epsilon::reverse::Tape;
+//! // dz/dx and dz/dy for z = x^2+y*sin(y) at x=5, y=7
+//! let tape = Tape::new();
+//! let (x, y) = (tape.var(5.), tape.var(7.));
+//!
+//! let z = x.powf(2.) + y * y.sin();
+//!
+//! let grad = z.grad();
+//! assert_eq!(grad.wrt(x), 10.);
+//! assert_eq!(grad.wrt(y), 5.934302379121921);
+//! ```
+//!
+//! Results can be converted to and from the forward-mode dual types using
+//! [`Var::to_dual`] and [`Tape::from_dual`].
+
+use crate::{DualNumber, Numerical};
+use std::cell::RefCell;
+
+/// Record of the operations performed on [`Var`]s
+///
+/// Each node stores the local partial derivatives with respect to the nodes
+/// it was computed from.
+#[derive(Debug, Default)]
+pub struct Tape {
+    nodes: RefCell<Nodes>,
+}
+
+#[derive(Debug, Default)]
+struct Nodes {
+    /// End of the edges of each node in `edges`
+    ends: Vec<usize>,
+    /// Parent node and local partial derivative
+    edges: Vec<(usize, f64)>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape::default()
+    }
+
+    /// Create an input variable with the specified value
+    pub fn var(&self, value: f64) -> Var<'_> {
+        self.push(value, [])
+    }
+
+    /// Number of recorded nodes
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().ends.len()
+    }
+
+    /// Whether no nodes have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record a value computed using dual numbers, where the `i`th dual
+    /// component of `value` is the derivative with respect to `inputs[i]`
+    ///
+    /// This allows computing parts of a function in forward mode, e.g. a
+    /// subexpression with few inputs.
+    pub fn from_dual<'t, D: DualNumber>(&'t self, value: &D, inputs: &[Var<'t>]) -> Var<'t> {
+        assert_eq!(
+            inputs.len(),
+            D::COMPONENTS,
+            "number of inputs must match the number of dual components"
+        );
+        let partials = inputs.iter().enumerate().map(|(i, &v)| (v, value.eps(i)));
+        self.push(value.real(), partials)
+    }
+
+    /// Record a node, ignoring partials with respect to constants
+    fn push<'t>(
+        &'t self,
+        value: f64,
+        partials: impl IntoIterator<Item = (Var<'t>, f64)>,
+    ) -> Var<'t> {
+        let mut nodes = self.nodes.borrow_mut();
+        for (var, partial) in partials {
+            if let Some((tape, index)) = var.node {
+                assert!(
+                    std::ptr::eq(tape, self),
+                    "variables are from different tapes"
+                );
+                nodes.edges.push((index, partial));
+            }
+        }
+        let end = nodes.edges.len();
+        nodes.ends.push(end);
+        Var {
+            value,
+            node: Some((self, nodes.ends.len() - 1)),
+        }
+    }
+}
+
+/// Variable recorded on a [`Tape`]
+///
+/// Constants, created by [`Var::constant`] or by mixing with `f64`, are not
+/// recorded.
+#[derive(Copy, Clone)]
+pub struct Var<'t> {
+    value: f64,
+    node: Option<(&'t Tape, usize)>,
+}
+
+impl<'t> Var<'t> {
+    /// Create a constant with the specified value
+    pub fn constant(value: f64) -> Self {
+        Var { value, node: None }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Compute the derivatives of `self` with respect to all variables it was
+    /// computed from, by backpropagating through the tape
+    pub fn grad(&self) -> Gradients {
+        let Some((tape, index)) = self.node else {
+            return Gradients {
+                adjoints: Vec::new(),
+            };
+        };
+        let nodes = tape.nodes.borrow();
+        let mut adjoints = vec![0.; index + 1];
+        adjoints[index] = 1.;
+        for i in (0..=index).rev() {
+            let start = if i == 0 { 0 } else { nodes.ends[i - 1] };
+            let adjoint = adjoints[i];
+            for &(parent, partial) in &nodes.edges[start..nodes.ends[i]] {
+                adjoints[parent] += adjoint * partial;
+            }
+        }
+        Gradients { adjoints }
+    }
+
+    /// Convert to a dual number, where the `i`th dual component is the
+    /// derivative with respect to `inputs[i]`
+    pub fn to_dual<D: DualNumber>(&self, inputs: &[Var<'t>]) -> D {
+        let grad = self.grad();
+        let eps: Vec<f64> = inputs.iter().map(|&v| grad.wrt(v)).collect();
+        D::from_parts(self.value, &eps)
+    }
+
+    /// Apply a function with value `value` and derivative `deriv` at
+    /// `self.value`
+    fn chain(self, value: f64, deriv: f64) -> Self {
+        match self.node {
+            Some((tape, _)) => tape.push(value, [(self, deriv)]),
+            None => Var::constant(value),
+        }
+    }
+
+    /// Combine two variables, with partial derivatives `da` and `db`
+    fn combine(self, other: Self, value: f64, da: f64, db: f64) -> Self {
+        match self.node.or(other.node) {
+            Some((tape, _)) => tape.push(value, [(self, da), (other, db)]),
+            None => Var::constant(value),
+        }
+    }
+
+    /// Raise `self` to `pow`
+    pub fn powf(self, pow: f64) -> Self {
+        // power rule: d/dx [x^p] = p x^(p-1)
+        self.chain(self.value.powf(pow), pow * self.value.powf(pow - 1.))
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        self.powf(-1.)
+    }
+
+    pub fn sin(self) -> Self {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    pub fn cos(self) -> Self {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+}
+
+impl std::fmt::Debug for Var<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Var")
+            .field("value", &self.value)
+            .field("index", &self.node.map(|(_, index)| index))
+            .finish()
+    }
+}
+
+impl std::fmt::Display for Var<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.value)
+    }
+}
+
+impl std::cmp::PartialEq for Var<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl std::cmp::PartialOrd for Var<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl From<f64> for Var<'_> {
+    fn from(value: f64) -> Self {
+        Var::constant(value)
+    }
+}
+
+/// Derivatives of a [`Var`] with respect to the variables it was computed from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradients {
+    adjoints: Vec<f64>,
+}
+
+impl Gradients {
+    /// Derivative with respect to `var`
+    /// Returns zero for constants and variables not involved in the result
+    pub fn wrt(&self, var: Var) -> f64 {
+        var.node
+            .and_then(|(_, index)| self.adjoints.get(index))
+            .copied()
+            .unwrap_or(0.)
+    }
+}
+
+impl<'t> std::ops::Add for Var<'t> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        self.combine(other, self.value + other.value, 1., 1.)
+    }
+}
+
+impl<'t> std::ops::Sub for Var<'t> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.combine(other, self.value - other.value, 1., -1.)
+    }
+}
+
+impl<'t> std::ops::Mul for Var<'t> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        self.combine(other, self.value * other.value, other.value, self.value)
+    }
+}
+
+impl<'t> std::ops::Div for Var<'t> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self::Output {
+        let value = self.value / other.value;
+        self.combine(other, value, 1. / other.value, -value / other.value)
+    }
+}
+
+impl std::ops::Neg for Var<'_> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.chain(-self.value, -1.)
+    }
+}
+
+macro_rules! impl_scalar_ops {
+    ($op:ident, $fn:ident) => {
+        impl std::ops::$op<f64> for Var<'_> {
+            type Output = Self;
+
+            fn $fn(self, other: f64) -> Self::Output {
+                std::ops::$op::$fn(self, Var::constant(other))
+            }
+        }
+
+        impl<'t> std::ops::$op<Var<'t>> for f64 {
+            type Output = Var<'t>;
+
+            fn $fn(self, other: Var<'t>) -> Self::Output {
+                std::ops::$op::$fn(Var::constant(self), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl std::ops::$op_inplace<f64> for Var<'_> {
+            fn $fn_inplace(&mut self, other: f64) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+        impl std::ops::$op_inplace<Self> for Var<'_> {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_scalar_ops! {Add, add}
+impl_scalar_ops! {Sub, sub}
+impl_scalar_ops! {Mul, mul}
+impl_scalar_ops! {Div, div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+
+impl Numerical for Var<'_> {
+    fn constant(real: f64) -> Self {
+        Var::constant(real)
+    }
+
+    fn real(&self) -> f64 {
+        self.value
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        Var::powf(self, pow)
+    }
+
+    fn invert(self) -> Self {
+        Var::invert(self)
+    }
+
+    fn sin(self) -> Self {
+        Var::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Var::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        Var::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tape, Var};
+    use crate::sample::SampleXYZ;
+    use crate::DualNumber;
+
+    #[test]
+    fn test_matches_forward() {
+        let tape = Tape::new();
+        let (x, y, z) = (tape.var(1.5), tape.var(-2.), tape.var(0.3));
+        let f = x * y * 3. + z.tan() / x - 1. / y;
+        let forward = {
+            let (x, y, z) = (SampleXYZ::x(1.5), SampleXYZ::y(-2.), SampleXYZ::z(0.3));
+            x * y * 3. + z.tan() / x - 1. / y
+        };
+
+        let dual: SampleXYZ = f.to_dual(&[x, y, z]);
+        for i in 0..3 {
+            assert!((dual.eps(i) - forward.eps(i)).abs() < 1e-12);
+        }
+        assert_eq!(f.value(), forward.real);
+    }
+
+    #[test]
+    fn test_constants() {
+        let tape = Tape::new();
+        let x = tape.var(2.);
+        let c = Var::constant(3.).sin();
+        let grad = (x * c).grad();
+        assert_eq!(grad.wrt(x), 3f64.sin());
+        assert_eq!(grad.wrt(c), 0.);
+        assert_eq!(tape.len(), 2);
+    }
+
+    #[test]
+    fn test_from_dual() {
+        let tape = Tape::new();
+        let (x, y) = (tape.var(2.), tape.var(5.));
+        // Compute x*y in forward mode and continue on the tape
+        let xy = SampleXYZ::from_parts(10., &[5., 2., 0.]);
+        let z = tape.var(1.);
+        let f = tape.from_dual(&xy, &[x, y, z]).powf(2.);
+        let grad = f.grad();
+        assert_eq!((grad.wrt(x), grad.wrt(y), grad.wrt(z)), (100., 40., 0.));
+    }
+}
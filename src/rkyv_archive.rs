@@ -0,0 +1,127 @@
+//! # Zero-copy archives
+//!
+//! With the `rkyv` feature, all types generated by
+//! [`make_dual`](crate::make_dual) implement `rkyv`'s `Archive`, `Serialize`
+//! and `Deserialize`, as long as their inner type does, as `f64` and `f32`
+//! do. The archived form is an [`ArchivedDual`] holding the real part
+//! followed by the dual components, so large snapshots can be memory-mapped
+//! and read in place without deserializing them.
+//!
+//! ```
+//! use epsilon::make_dual;
+//! use rkyv::rancor::Error;
+//!
+//! make_dual! { Pos, x, y }
+//!
+//! let snapshot = vec![Pos::x(1.5), Pos::eps_y(-2., 0.5)];
+//! let bytes = rkyv::to_bytes::<Error>(&snapshot).unwrap();
+//!
+//! let archived = rkyv::access::<rkyv::Archived<Vec<Pos>>, Error>(&bytes).unwrap();
+//! assert_eq!(archived[0].eps(0), 1.);
+//! assert_eq!(archived[1].real(), -2.);
+//! assert_eq!(rkyv::deserialize::<Vec<Pos>, Error>(archived).unwrap(), snapshot);
+//! ```
+
+#[doc(hidden)]
+pub use rkyv;
+
+/// Archived form of a dual number with `K - 1` dual components
+#[derive(Clone, Copy, Debug, PartialEq, rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(transparent)]
+pub struct ArchivedDual<A> {
+    #[doc(hidden)]
+    pub parts: A,
+}
+
+impl<T: Copy, const K: usize> ArchivedDual<[T; K]> {
+    /// The archived real part
+    pub fn real(&self) -> T {
+        self.parts[0]
+    }
+
+    /// The archived `i`th dual component
+    pub fn eps(&self, i: usize) -> T {
+        self.parts[1 + i]
+    }
+
+    /// The real part followed by the dual components
+    pub fn parts(&self) -> &[T; K] {
+        &self.parts
+    }
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_rkyv {
+    ($name:ident, $inner:ty, $($comp:ident,)+) => { $crate::paste! {
+        const _: () = {
+            use $crate::rkyv_archive::rkyv::{rancor::Fallible, Archive, Deserialize, Place, Serialize};
+            use $crate::rkyv_archive::ArchivedDual;
+
+            type Parts = [$inner; 1 + <$name as $crate::DualNumber>::COMPONENTS];
+
+            fn parts(v: &$name) -> Parts {
+                [v.real, $(v.[< eps_ $comp >],)+]
+            }
+
+            // The higher-ranked bounds are not checked eagerly, so that no
+            // error arises for inner types which can not be archived
+            impl Archive for $name where for<'a> Parts: Archive {
+                type Archived = ArchivedDual<<Parts as Archive>::Archived>;
+                type Resolver = <Parts as Archive>::Resolver;
+
+                fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+                    // SAFETY: `ArchivedDual` is a transparent wrapper
+                    parts(self).resolve(resolver, unsafe { out.cast_unchecked() })
+                }
+            }
+
+            impl<S: Fallible + ?Sized> Serialize<S> for $name where for<'a> Parts: Serialize<S> {
+                fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+                    parts(self).serialize(serializer)
+                }
+            }
+
+            impl<A, D: Fallible + ?Sized> Deserialize<$name, D> for ArchivedDual<A>
+            where
+                A: Deserialize<Parts, D>,
+            {
+                fn deserialize(&self, deserializer: &mut D) -> Result<$name, D::Error> {
+                    let [real, $([< eps_ $comp >],)+] = self.parts.deserialize(deserializer)?;
+                    Ok($name { real, $([< eps_ $comp >],)+ })
+                }
+            }
+        };
+    } };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample::SampleXYZ;
+    use rkyv::rancor::Error;
+
+    crate::make_dual! { SmallXY: f32, x, y }
+
+    #[test]
+    fn test_round_trip() {
+        let duals = [SampleXYZ::x(2.), SampleXYZ::z(-1.5) * 3.];
+        let bytes = rkyv::to_bytes::<Error>(&duals).unwrap();
+        let archived = rkyv::access::<rkyv::Archived<[SampleXYZ; 2]>, Error>(&bytes).unwrap();
+        assert_eq!(archived[1].parts(), &[-4.5, 0., 0., 3.]);
+        assert_eq!(
+            rkyv::deserialize::<[SampleXYZ; 2], Error>(archived).unwrap(),
+            duals
+        );
+
+        let small = SmallXY::y(0.25);
+        let bytes = rkyv::to_bytes::<Error>(&small).unwrap();
+        let archived = rkyv::access::<rkyv::Archived<SmallXY>, Error>(&bytes).unwrap();
+        assert_eq!(archived.real(), 0.25);
+        assert_eq!(archived.eps(1), 1.);
+        assert_eq!(
+            rkyv::deserialize::<SmallXY, Error>(archived).unwrap(),
+            small
+        );
+    }
+}
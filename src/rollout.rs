@@ -0,0 +1,148 @@
+//! # Rollouts
+//!
+//! Gradients of discrete-time simulations `x[t + 1] = f(x[t], params, t)`
+//! with respect to the parameters. The parameters are seeded as the
+//! variables of [`Dual<P>`] once, and the dual parts of the states then
+//! carry `dx[t]/dparams` through every step, with no seeding to manage
+//! across time steps.
+//!
+//! ```
+//! use epsilon::rollout::rollout;
+//! // A cart with velocity damping, pushed by a constant force
+//! let step = |[x, v]: [_; 2], [force, damping]: [_; 2], _t| {
+//!     let dt = 0.1;
+//!     [x + v * dt, v + (force - damping * v) * dt]
+//! };
+//! let result = rollout(step, [0., 0.], [1., 0.5], 100, 1);
+//! let (t, [x, _]) = result.last();
+//!
+//! assert_eq!(t, 100);
+//! // Pushing harder moves the cart further, more damping less far
+//! assert!(x.eps[0] > 0. && x.eps[1] < 0.);
+//! assert_eq!(result.sensitivity(100).unwrap()[0], x.eps);
+//! ```
+//!
+//! For long rollouts, only every `checkpoint`th state is stored, and the
+//! others are recomputed from the checkpoint before them when needed by
+//! [`Rollout::recompute`].
+
+use crate::Dual;
+
+/// States of a rollout at the checkpoints, with their derivatives with
+/// respect to the parameters as the dual parts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rollout<const S: usize, const P: usize> {
+    /// The parameters the rollout was computed with
+    pub params: [f64; P],
+    /// Steps at which the states are stored, `0, checkpoint, 2 checkpoint,
+    /// ...`, and the final step
+    pub t: Vec<usize>,
+    /// The state at each step in `t`
+    pub states: Vec<[Dual<P>; S]>,
+}
+
+/// Iterate `f` from `x0` for `steps` steps, storing every `checkpoint`th
+/// state and the final one
+///
+/// `f` receives the state, the parameters seeded as variables, and the index
+/// of the step. With `checkpoint` equal to one, every state is stored.
+///
+/// # Panics
+///
+/// If `checkpoint` is zero.
+pub fn rollout<const S: usize, const P: usize>(
+    f: impl Fn([Dual<P>; S], [Dual<P>; P], usize) -> [Dual<P>; S],
+    x0: [f64; S],
+    params: [f64; P],
+    steps: usize,
+    checkpoint: usize,
+) -> Rollout<S, P> {
+    assert!(checkpoint > 0, "checkpoint interval must be positive");
+    let seeded = Dual::variables(params);
+    let mut result = Rollout {
+        params,
+        t: vec![0],
+        states: vec![x0.map(Dual::constant)],
+    };
+    let mut x = result.states[0];
+    for t in 0..steps {
+        x = f(x, seeded, t);
+        if (t + 1) % checkpoint == 0 || t + 1 == steps {
+            result.t.push(t + 1);
+            result.states.push(x);
+        }
+    }
+    result
+}
+
+impl<const S: usize, const P: usize> Rollout<S, P> {
+    /// The final step and state
+    pub fn last(&self) -> (usize, [Dual<P>; S]) {
+        let i = self.t.len() - 1;
+        (self.t[i], self.states[i])
+    }
+
+    /// The state after `t` steps, recomputed with `f` from the last stored
+    /// state at or before it
+    ///
+    /// `f` must be the step function the rollout was computed with.
+    ///
+    /// # Panics
+    ///
+    /// If `t` is beyond the final step.
+    pub fn recompute(
+        &self,
+        f: impl Fn([Dual<P>; S], [Dual<P>; P], usize) -> [Dual<P>; S],
+        t: usize,
+    ) -> [Dual<P>; S] {
+        assert!(t <= self.last().0, "step beyond the end of the rollout");
+        let i = self.t.partition_point(|&s| s <= t) - 1;
+        let seeded = Dual::variables(self.params);
+        (self.t[i]..t).fold(self.states[i], |x, s| f(x, seeded, s))
+    }
+
+    /// The derivatives `dx[t]/dparams` of the stored state after `t` steps,
+    /// where row `i` is the gradient of the `i`th state component, or `None`
+    /// if the state was not stored
+    pub fn sensitivity(&self, t: usize) -> Option<[[f64; P]; S]> {
+        let i = self.t.binary_search(&t).ok()?;
+        Some(self.states[i].map(|x| x.eps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rollout;
+    use crate::Dual;
+
+    /// Logistic map with growth rate r, whose first step depends on t
+    fn step([x]: [Dual<1>; 1], [r]: [Dual<1>; 1], t: usize) -> [Dual<1>; 1] {
+        let x = if t == 0 { x * 0.5 } else { x };
+        [r * x * (1. - x)]
+    }
+
+    #[test]
+    fn test_rollout() {
+        let full = rollout(step, [0.4], [2.5], 10, 1);
+        assert_eq!(full.t, (0..=10).collect::<Vec<_>>());
+        let sparse = rollout(step, [0.4], [2.5], 10, 4);
+        assert_eq!(sparse.t, [0, 4, 8, 10]);
+        assert_eq!(sparse.last(), full.last());
+        assert_eq!(sparse.sensitivity(5), None);
+
+        for t in 0..=10 {
+            assert_eq!(sparse.recompute(step, t), full.states[t]);
+        }
+        // One step, r x0 / 2 (1 - x0 / 2)
+        assert_eq!(full.sensitivity(1), Some([[0.2 * 0.8]]));
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        // Against central differences of the final state
+        let final_state = |r: f64| rollout(step, [0.4], [r], 20, 5).last().1[0].real;
+        let dr = rollout(step, [0.4], [2.5], 20, 5).last().1[0].eps[0];
+        let numerical = (final_state(2.5 + 1e-7) - final_state(2.5 - 1e-7)) / 2e-7;
+        assert!((dr - numerical).abs() < 1e-6);
+    }
+}
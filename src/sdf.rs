@@ -0,0 +1,176 @@
+//! # Signed distance functions
+//!
+//! Primitives and combinators for signed distance functions, negative inside
+//! a shape and positive outside, generic over the [`Numerical`] type of the
+//! point. The surface normal is the gradient of the distance, so evaluating
+//! on dual numbers gives exact normals without the noise of finite
+//! differences, see [`normal`].
+//!
+//! ```
+//! use epsilon::sdf;
+//! use epsilon::vector::Vec3;
+//! // A sphere with a box carved out of it
+//! let scene = |p: Vec3<_>| {
+//!     let carve = sdf::cuboid(p - Vec3::constant([1., 0., 0.]), [0.5, 0.5, 0.5]);
+//!     sdf::difference(sdf::sphere(p, 1.), carve)
+//! };
+//! let (distance, normal) = sdf::normal(scene, [0., 0., 2.]);
+//!
+//! assert_eq!(distance, 1.);
+//! assert_eq!(normal, [0., 0., 1.]);
+//! ```
+
+use crate::vector::Vec3;
+use crate::{Dual, Numerical};
+
+fn min<T: Numerical>(a: T, b: T) -> T {
+    if a.real() <= b.real() {
+        a
+    } else {
+        b
+    }
+}
+
+fn max<T: Numerical>(a: T, b: T) -> T {
+    if a.real() >= b.real() {
+        a
+    } else {
+        b
+    }
+}
+
+fn abs<T: Numerical>(a: T) -> T {
+    if a.real() < 0. {
+        T::constant(0.) - a
+    } else {
+        a
+    }
+}
+
+/// Sphere of radius `r` at the origin
+pub fn sphere<T: Numerical>(p: Vec3<T>, r: f64) -> T {
+    p.norm() - T::constant(r)
+}
+
+/// Axis-aligned box at the origin with the given half side lengths
+pub fn cuboid<T: Numerical>(p: Vec3<T>, half: [f64; 3]) -> T {
+    let q = Vec3::new(abs(p.x), abs(p.y), abs(p.z)) - Vec3::constant(half);
+    let zero = T::constant(0.);
+    let outside = q.map(|c| max(c, zero)).norm();
+    let inside = min(max(q.x, max(q.y, q.z)), zero);
+    outside + inside
+}
+
+/// Torus around the z axis, with the distance `major` from the origin to the
+/// center of the tube and the tube radius `minor`
+pub fn torus<T: Numerical>(p: Vec3<T>, major: f64, minor: f64) -> T {
+    let ring = (p.x * p.x + p.y * p.y).powf(0.5) - T::constant(major);
+    (ring * ring + p.z * p.z).powf(0.5) - T::constant(minor)
+}
+
+/// Capsule of radius `r` around the segment from `a` to `b`
+pub fn capsule<T: Numerical>(p: Vec3<T>, a: [f64; 3], b: [f64; 3], r: f64) -> T {
+    let (a, b) = (Vec3::constant(a), Vec3::constant(b));
+    let (pa, ba) = (p - a, b - a);
+    let h = pa.dot(ba) / ba.norm_squared();
+    let h = min(max(h, T::constant(0.)), T::constant(1.));
+    (pa - ba * h).norm() - T::constant(r)
+}
+
+/// Union of two shapes
+pub fn union<T: Numerical>(a: T, b: T) -> T {
+    min(a, b)
+}
+
+/// Intersection of two shapes
+pub fn intersection<T: Numerical>(a: T, b: T) -> T {
+    max(a, b)
+}
+
+/// The shape `a` with the shape `b` removed
+pub fn difference<T: Numerical>(a: T, b: T) -> T {
+    max(a, T::constant(0.) - b)
+}
+
+/// Union blending the shapes over a distance of about `k`, by the polynomial
+/// smooth minimum
+///
+/// Unlike [`union`], the distance and thereby the normal are continuous
+/// where the shapes meet.
+pub fn smooth_union<T: Numerical>(a: T, b: T, k: f64) -> T {
+    // Branch once, keeping min(a, b) and |a - b| consistent where a = b
+    let (lo, hi) = if a.real() <= b.real() { (a, b) } else { (b, a) };
+    let h = max(T::constant(k) - (hi - lo), T::constant(0.)) / T::constant(k);
+    lo - h * h * T::constant(k / 4.)
+}
+
+/// Distance to the surface of `f` at `p` and the unit normal, the
+/// normalized gradient of `f`
+///
+/// The normal is not finite where the gradient vanishes.
+pub fn normal(f: impl FnOnce(Vec3<Dual<3>>) -> Dual<3>, p: [f64; 3]) -> (f64, [f64; 3]) {
+    let d = f(Vec3::from(Dual::variables(p)));
+    let norm = d.eps.iter().map(|e| e * e).sum::<f64>().sqrt();
+    (d.real, d.eps.map(|e| e / norm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitives() {
+        let at = |p: [f64; 3]| Vec3::<f64>::constant(p);
+        assert_eq!(sphere(at([0., 3., 4.]), 2.), 3.);
+        assert_eq!(cuboid(at([3., 0.5, 0.]), [1., 1., 1.]), 2.);
+        assert_eq!(cuboid(at([0., 0.5, 0.]), [1., 1., 1.]), -0.5);
+        assert_eq!(cuboid(at([4., 5., 0.]), [1., 1., 1.]), 5.);
+        assert_eq!(torus(at([2., 0., 0.]), 2., 0.5), -0.5);
+        assert_eq!(torus(at([0., 3., 0.]), 2., 0.5), 0.5);
+        assert_eq!(
+            capsule(at([1., 2., 0.]), [0., 0., 0.], [2., 0., 0.], 1.),
+            1.
+        );
+        assert_eq!(
+            capsule(at([-3., 0., 0.]), [0., 0., 0.], [2., 0., 0.], 1.),
+            2.
+        );
+
+        assert_eq!(union(1., -2.), -2.);
+        assert_eq!(intersection(1., -2.), 1.);
+        assert_eq!(difference(-1., -2.), 2.);
+        // Blending only within k of the seam
+        assert_eq!(smooth_union(1., 3., 1.), 1.);
+        assert_eq!(smooth_union(1., 1., 1.), 0.75);
+    }
+
+    #[test]
+    fn test_normals() {
+        let (d, n) = normal(|p| sphere(p, 1.), [2., 0., 0.]);
+        assert_eq!((d, n), (1., [1., 0., 0.]));
+
+        // Box corner region, pointing diagonally away
+        let (_, n) = normal(|p| cuboid(p, [1., 1., 1.]), [2., 2., 0.5]);
+        let r = 0.5f64.sqrt();
+        assert!((n[0] - r).abs() < 1e-15 && (n[1] - r).abs() < 1e-15 && n[2] == 0.);
+
+        // Torus, at the top of the tube
+        let (d, n) = normal(|p| torus(p, 2., 0.5), [0., 2., 1.]);
+        assert_eq!((d, n), (0.5, [0., 0., 1.]));
+
+        // The smooth union has a normal between those of the two spheres
+        let scene = |p: Vec3<_>| {
+            let a = sphere(p - Vec3::constant([-0.9, 0., 0.]), 1.);
+            let b = sphere(p - Vec3::constant([0.9, 0., 0.]), 1.);
+            smooth_union(a, b, 0.5)
+        };
+        let (_, n) = normal(scene, [0.2, 0.8, 0.]);
+        let (_, nb) = normal(
+            |p| sphere(p - Vec3::constant([0.9, 0., 0.]), 1.),
+            [0.2, 0.8, 0.],
+        );
+        assert!(n[0] < 0. && n[0] > nb[0]);
+        let (_, n) = normal(scene, [0., 1., 0.]);
+        assert!(n[0].abs() < 1e-15 && (n[1] - 1.).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,205 @@
+//! # Sensitivity rankings
+//!
+//! Post-processing of gradients answering "which inputs matter": the inputs
+//! of a result are ranked by the magnitude of its derivative with respect to
+//! them, optionally scaled to make inputs with different units comparable.
+//! With the `serde` feature, the rankings can be serialized.
+//!
+//! ```
+//! use epsilon::make_dual;
+//! use epsilon::sensitivity::{rank, Scale};
+//!
+//! make_dual! { Beam, load, length, width }
+//!
+//! // Deflection of a cantilever, w = 4 P L^3 / (E b h^3) with E h^3 = 1
+//! let (p, l, b) = (Beam::load(2.), Beam::length(3.), Beam::width(0.5));
+//! let w = 4. * p * l * l * l / b;
+//!
+//! // Elasticities: a 1% longer beam deflects 3% more
+//! let ranking = rank(&w, Scale::Elasticity(&[2., 3., 0.5]));
+//! let names: Vec<_> = ranking.inputs.iter().map(|s| s.name.as_str()).collect();
+//! assert_eq!(names, ["length", "load", "width"]);
+//! assert!((ranking.inputs[0].score - 3.).abs() < 1e-12);
+//! ```
+
+use crate::check::Components;
+
+/// Scaling applied to the derivatives before ranking
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scale<'a> {
+    /// `|dy/dx_i|`
+    Absolute,
+    /// `|dy/dx_i x_i / y|`, the relative change of the result per relative
+    /// change of the input, given the values of the inputs
+    Elasticity(&'a [f64]),
+    /// `|dy/dx_i| sigma_i`, the change of the result per typical change of
+    /// the input, given the standard deviations of the inputs
+    Uncertainty(&'a [f64]),
+}
+
+/// Sensitivity of a result to a single input
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sensitivity {
+    /// Name of the dual component of the input
+    pub name: String,
+    /// Index of the dual component of the input
+    pub index: usize,
+    /// `dy/dx_i`
+    pub derivative: f64,
+    /// The scaled magnitude of the derivative the inputs are ranked by
+    pub score: f64,
+}
+
+/// Inputs of a result, ordered from most to least influential
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ranking {
+    /// Value of the result
+    pub value: f64,
+    pub inputs: Vec<Sensitivity>,
+}
+
+fn sensitivities<D: Components>(output: &D, scale: Scale) -> Vec<Sensitivity> {
+    if let Scale::Elasticity(values) | Scale::Uncertainty(values) = scale {
+        assert_eq!(
+            values.len(),
+            output.components(),
+            "the scale needs a value for each dual component"
+        );
+    }
+    (0..output.components())
+        .map(|i| {
+            let derivative = output.component(i);
+            let score = match scale {
+                Scale::Absolute => derivative.abs(),
+                Scale::Elasticity(x) => (derivative * x[i] / output.value()).abs(),
+                Scale::Uncertainty(sigma) => derivative.abs() * sigma[i],
+            };
+            Sensitivity {
+                name: output.name(i),
+                index: i,
+                derivative,
+                score,
+            }
+        })
+        .collect()
+}
+
+/// Sort by decreasing score, with NaN scores last
+fn sort(inputs: &mut [Sensitivity]) {
+    inputs.sort_by(|a, b| {
+        (a.score.is_nan().cmp(&b.score.is_nan())).then(b.score.total_cmp(&a.score))
+    });
+}
+
+/// Rank the inputs of `output` by the scaled magnitude of its derivatives
+///
+/// # Panics
+///
+/// If the values of [`Scale::Elasticity`] or [`Scale::Uncertainty`] are not
+/// one per dual component of `output`.
+pub fn rank<D: Components>(output: &D, scale: Scale) -> Ranking {
+    let mut inputs = sensitivities(output, scale);
+    sort(&mut inputs);
+    Ranking {
+        value: output.value(),
+        inputs,
+    }
+}
+
+/// Rank the inputs by their largest score over several results
+///
+/// Each [`Sensitivity`] is that of the result where the input has its largest
+/// score. All outputs must have the same dual components.
+///
+/// # Panics
+///
+/// If the values of [`Scale::Elasticity`] or [`Scale::Uncertainty`] are not
+/// one per dual component of the outputs.
+pub fn rank_all<D: Components>(outputs: &[D], scale: Scale) -> Vec<Sensitivity> {
+    let mut inputs: Vec<Sensitivity> = Vec::new();
+    for output in outputs {
+        for s in sensitivities(output, scale) {
+            match inputs.get_mut(s.index) {
+                Some(best) => {
+                    if s.score > best.score {
+                        *best = s;
+                    }
+                }
+                None => inputs.push(s),
+            }
+        }
+    }
+    sort(&mut inputs);
+    inputs
+}
+
+impl std::fmt::Display for Ranking {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(fmt, "value: {}", self.value)?;
+        for (rank, s) in self.inputs.iter().enumerate() {
+            writeln!(
+                fmt,
+                "{}. {}: score {:e}, derivative {:e}",
+                rank + 1,
+                s.name,
+                s.score,
+                s.derivative
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Dual;
+
+    #[test]
+    fn test_rank() {
+        let [x, y, z] = Dual::<3>::variables([1., 10., 2.]);
+        let f = x * 3. - y + z * z;
+        let ranking = rank(&f, Scale::Absolute);
+        let order: Vec<_> = ranking.inputs.iter().map(|s| s.index).collect();
+        assert_eq!(order, [2, 0, 1]);
+        assert_eq!(ranking.inputs[2].derivative, -1.);
+        assert_eq!(
+            ranking.to_string(),
+            "value: -3\n1. eps_2: score 4e0, derivative 4e0\n\
+             2. eps_0: score 3e0, derivative 3e0\n3. eps_1: score 1e0, derivative -1e0\n"
+        );
+
+        // y varies the most
+        let ranking = rank(&f, Scale::Uncertainty(&[0.1, 5., 0.1]));
+        assert_eq!(ranking.inputs[0].name, "eps_1");
+
+        // x dominates the second result
+        let g = x * 100.;
+        let combined = rank_all(&[f, g], Scale::Absolute);
+        assert_eq!(combined[0].index, 0);
+        assert_eq!(combined[0].score, 100.);
+        assert_eq!(combined.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the scale needs a value for each dual component")]
+    fn test_scale_length() {
+        let [x, y] = Dual::<2>::variables([1., 2.]);
+        rank(&(x * y), Scale::Elasticity(&[1.]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let [x] = Dual::<1>::variables([2.]);
+        let ranking = rank(&(x * x), Scale::Absolute);
+        let json = serde_json::to_string(&ranking).unwrap();
+        assert_eq!(
+            json,
+            r#"{"value":4.0,"inputs":[{"name":"eps_0","index":0,"derivative":4.0,"score":4.0}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Ranking>(&json).unwrap(), ranking);
+    }
+}
@@ -0,0 +1,272 @@
+//! # Signal processing
+//!
+//! Discrete convolution and cross-correlation, generic over [`Scalar`] so
+//! that derivatives with respect to both the signal and the filter
+//! coefficients propagate, e.g. when calibrating the parameters of a
+//! smoothing filter against measurements. The discrete Fourier transform
+//! [`fft`] and its inverse [`ifft`] likewise give the derivatives of spectra,
+//! e.g. for fitting model parameters to measured spectra.
+//!
+//! ```
+//! use epsilon::signal::{convolve, Mode};
+//! use epsilon::Dual;
+//! // Moving average with a weight w on the center, and its derivative
+//! let [w] = Dual::<1>::variables([0.5]);
+//! let side = (Dual::constant(1.) - w) * 0.5;
+//! let signal = [1., 4., 2., 8.].map(Dual::constant);
+//! let smoothed = convolve(&signal, &[side, w, side], Mode::Same);
+//!
+//! assert_eq!(smoothed[1].real, 2.75);
+//! // d/dw of 0.5 (1 - w) (1 + 2) + 4 w
+//! assert_eq!(smoothed[1].eps[0], 2.5);
+//! ```
+
+use crate::Scalar;
+
+/// Which part of the result of [`convolve`] or [`correlate`] to keep, with
+/// `n` and `m` the lengths of the longer and shorter input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Every overlap of the inputs, `n + m - 1` elements
+    Full,
+    /// The middle `n` elements, centered with respect to [`Mode::Full`]
+    Same,
+    /// Only complete overlaps of the inputs, `n - m + 1` elements
+    Valid,
+}
+
+/// Discrete convolution `(a * b)[k] = sum_i a[i] b[k - i]`
+///
+/// Computes only the elements kept by `mode`, directly in `O(n m)`. Empty if
+/// either input is empty.
+pub fn convolve<T: Scalar>(a: &[T], b: &[T], mode: Mode) -> Vec<T> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let (n, m) = (a.len().max(b.len()), a.len().min(b.len()));
+    let (start, len) = match mode {
+        Mode::Full => (0, n + m - 1),
+        Mode::Same => ((m - 1) / 2, n),
+        Mode::Valid => (m - 1, n - m + 1),
+    };
+    (start..start + len)
+        .map(|k| {
+            // Indices with both i < a.len() and k - i < b.len()
+            let low = (k + 1).saturating_sub(b.len());
+            let high = k.min(a.len() - 1);
+            (low..=high).fold(T::ZERO, |sum, i| sum + a[i] * b[k - i])
+        })
+        .collect()
+}
+
+/// Discrete cross-correlation `sum_i a[i + k] b[i]`, the convolution of `a`
+/// with `b` reversed, with the lags ordered as for [`convolve`]
+pub fn correlate<T: Scalar>(a: &[T], b: &[T], mode: Mode) -> Vec<T> {
+    let reversed: Vec<T> = b.iter().rev().copied().collect();
+    convolve(a, &reversed, mode)
+}
+
+/// In-place radix-2 FFT of a power-of-two length, with the exponent
+/// `-2 pi i j k / n`, or `+2 pi i j k / n` if `inverse`, without normalization
+fn radix2<T: Scalar>(re: &mut [T], im: &mut [T], inverse: bool) {
+    let n = re.len();
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1. } else { -1. };
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        for k in 0..half {
+            let angle = sign * std::f64::consts::TAU * k as f64 / len as f64;
+            let (c, s) = (T::from_f64(angle.cos()), T::from_f64(angle.sin()));
+            for start in (0..n).step_by(len) {
+                let (a, b) = (start + k, start + k + half);
+                let (tr, ti) = (re[b] * c - im[b] * s, re[b] * s + im[b] * c);
+                (re[b], im[b]) = (re[a] - tr, im[a] - ti);
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// DFT of any length by Bluestein's algorithm, writing `j k = (j^2 + k^2 -
+/// (k - j)^2) / 2` to express it as a convolution with a chirp, which is
+/// computed by radix-2 FFTs of a power-of-two length of at least `2n - 1`
+fn bluestein<T: Scalar>(re: &[T], im: &[T], inverse: bool) -> (Vec<T>, Vec<T>) {
+    let n = re.len();
+    let m = (2 * n - 1).next_power_of_two();
+    let sign = if inverse { 1. } else { -1. };
+    // exp(sign pi i k^2 / n), with k^2 reduced modulo 2n for accuracy
+    let chirp: Vec<(f64, f64)> = (0..n as u128)
+        .map(|k| {
+            let angle = sign * std::f64::consts::PI * ((k * k) % (2 * n as u128)) as f64 / n as f64;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+
+    let (mut ar, mut ai) = (vec![T::ZERO; m], vec![T::ZERO; m]);
+    for (k, &(c, s)) in chirp.iter().enumerate() {
+        let (c, s) = (T::from_f64(c), T::from_f64(s));
+        (ar[k], ai[k]) = (re[k] * c - im[k] * s, re[k] * s + im[k] * c);
+    }
+    // The conjugate chirp, wrapped around for the negative lags
+    let (mut br, mut bi) = (vec![0.; m], vec![0.; m]);
+    for (k, &(c, s)) in chirp.iter().enumerate() {
+        (br[k], bi[k]) = (c, -s);
+        (br[(m - k) % m], bi[(m - k) % m]) = (c, -s);
+    }
+
+    radix2(&mut ar, &mut ai, false);
+    radix2(&mut br, &mut bi, false);
+    for ((ar, ai), (&br, &bi)) in ar.iter_mut().zip(&mut ai).zip(br.iter().zip(&bi)) {
+        let (c, s) = (T::from_f64(br / m as f64), T::from_f64(bi / m as f64));
+        (*ar, *ai) = (*ar * c - *ai * s, *ar * s + *ai * c);
+    }
+    radix2(&mut ar, &mut ai, true);
+
+    chirp
+        .iter()
+        .zip(ar.into_iter().zip(ai))
+        .map(|(&(c, s), (xr, xi))| {
+            let (c, s) = (T::from_f64(c), T::from_f64(s));
+            (xr * c - xi * s, xr * s + xi * c)
+        })
+        .unzip()
+}
+
+fn transform<T: Scalar>(re: &[T], im: &[T], inverse: bool) -> (Vec<T>, Vec<T>) {
+    assert_eq!(
+        re.len(),
+        im.len(),
+        "real and imaginary parts differ in length"
+    );
+    if re.len() <= 1 || re.len().is_power_of_two() {
+        let (mut re, mut im) = (re.to_vec(), im.to_vec());
+        radix2(&mut re, &mut im, inverse);
+        (re, im)
+    } else {
+        bluestein(re, im, inverse)
+    }
+}
+
+/// Discrete Fourier transform `X[k] = sum_j x[j] exp(-2 pi i j k / n)` of
+/// the complex signal with the real parts `re` and the imaginary parts `im`,
+/// returning the real and imaginary parts of the spectrum
+///
+/// Uses a radix-2 FFT for power-of-two lengths, and Bluestein's algorithm in
+/// `O(n log n)` for other lengths. The transform is linear, so the dual parts
+/// of the spectrum are the transforms of the dual parts of the signal.
+///
+/// Panics if `re` and `im` differ in length.
+///
+/// ```
+/// use epsilon::signal::fft;
+/// use epsilon::Dual;
+/// // Cosine of frequency 1 with amplitude a
+/// let [a] = Dual::<1>::variables([2.]);
+/// let signal: Vec<_> = (0..4).map(|j| a * (std::f64::consts::FRAC_PI_2 * j as f64).cos()).collect();
+/// let (re, _) = fft(&signal, &[Dual::constant(0.); 4]);
+///
+/// assert!((re[1].real - 4.).abs() < 1e-15 && (re[3].real - 4.).abs() < 1e-15);
+/// // d/da of n a / 2
+/// assert!((re[1].eps[0] - 2.).abs() < 1e-15);
+/// ```
+pub fn fft<T: Scalar>(re: &[T], im: &[T]) -> (Vec<T>, Vec<T>) {
+    transform(re, im, false)
+}
+
+/// Inverse of [`fft`], `x[j] = 1/n sum_k X[k] exp(2 pi i j k / n)`
+pub fn ifft<T: Scalar>(re: &[T], im: &[T]) -> (Vec<T>, Vec<T>) {
+    let (mut re, mut im) = transform(re, im, true);
+    let scale = T::from_f64(1. / re.len() as f64);
+    for x in re.iter_mut().chain(&mut im) {
+        *x *= scale;
+    }
+    (re, im)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convolve, correlate, fft, ifft, Mode};
+    use crate::Dual;
+
+    #[test]
+    fn test_modes() {
+        let a = [1., 2., 3.];
+        let b = [0., 1., 0.5];
+        assert_eq!(convolve(&a, &b, Mode::Full), [0., 1., 2.5, 4., 1.5]);
+        assert_eq!(convolve(&a, &b, Mode::Same), [1., 2.5, 4.]);
+        assert_eq!(convolve(&a, &b, Mode::Valid), [2.5]);
+        // Commutative, also for inputs of different lengths
+        let c = [1., -1.];
+        assert_eq!(convolve(&a, &c, Mode::Same), convolve(&c, &a, Mode::Same));
+        assert_eq!(convolve(&c, &a, Mode::Valid), [1., 1.]);
+        assert_eq!(correlate(&a, &b, Mode::Same), [2., 3.5, 3.]);
+        assert_eq!(correlate(&a, &b, Mode::Valid), [3.5]);
+        assert!(convolve(&a, &[], Mode::Full).is_empty());
+    }
+
+    #[test]
+    fn test_gradients() {
+        // Derivatives with respect to the kernel are the shifted signal
+        let a = [1., 2., 3., 4.].map(Dual::constant);
+        let kernel = Dual::<2>::variables([0.25, 0.75]);
+        let y = convolve(&a, &kernel, Mode::Valid);
+        let eps: Vec<[f64; 2]> = y.iter().map(|y| y.eps).collect();
+        assert_eq!(eps, [[2., 1.], [3., 2.], [4., 3.]]);
+        let y = correlate(&a, &kernel, Mode::Valid);
+        let eps: Vec<[f64; 2]> = y.iter().map(|y| y.eps).collect();
+        assert_eq!(eps, [[1., 2.], [2., 3.], [3., 4.]]);
+    }
+
+    #[test]
+    fn test_fft() {
+        for n in [1, 2, 8, 6, 7, 12] {
+            let [t] = Dual::<1>::variables([0.3]);
+            let re: Vec<_> = (0..n).map(|j| (t * j as f64).sin() + j as f64).collect();
+            let im: Vec<_> = (0..n).map(|j| t * t * (j % 3) as f64).collect();
+            let (xr, xi) = fft(&re, &im);
+
+            // Direct DFT of the real and the dual parts
+            let dft = |part: &dyn Fn(Dual<1>) -> f64| -> Vec<(f64, f64)> {
+                (0..n)
+                    .map(|k| {
+                        (0..n).fold((0., 0.), |(sr, si), j| {
+                            let angle = -std::f64::consts::TAU * ((j * k) % n) as f64 / n as f64;
+                            let (c, s, a, b) = (angle.cos(), angle.sin(), part(re[j]), part(im[j]));
+                            (sr + a * c - b * s, si + a * s + b * c)
+                        })
+                    })
+                    .collect()
+            };
+            let parts: [&dyn Fn(Dual<1>) -> f64; 2] = [&|x| x.real, &|x| x.eps[0]];
+            for part in parts {
+                for (k, (er, ei)) in dft(part).into_iter().enumerate() {
+                    assert!((part(xr[k]) - er).abs() < 1e-13, "n = {n}, k = {k}");
+                    assert!((part(xi[k]) - ei).abs() < 1e-13, "n = {n}, k = {k}");
+                }
+            }
+
+            let (yr, yi) = ifft(&xr, &xi);
+            for (y, x) in yr.iter().chain(&yi).zip(re.iter().chain(&im)) {
+                assert!((y.real - x.real).abs() < 1e-13 && (y.eps[0] - x.eps[0]).abs() < 1e-13);
+            }
+        }
+        assert_eq!(fft::<f64>(&[], &[]), (vec![], vec![]));
+    }
+}
@@ -0,0 +1,436 @@
+//! # Slice operations
+//!
+//! [`SliceExt`] adds elementwise arithmetic to slices of any [`Numerical`]
+//! type, such as `Vec<Dual<N>>` or vectors of [`make_dual`](crate::make_dual)
+//! types, replacing the explicit loops of array-style code. Operations
+//! between two slices require equal lengths, and operations with a single
+//! number broadcast it to every element.
+//!
+//! ```
+//! use epsilon::slice::SliceExt;
+//! use epsilon::Dual;
+//! // y = a x + b, elementwise, with the derivatives with respect to a
+//! let [a] = Dual::<1>::variables([2.]);
+//! let x = [1., 2., 3.].map(Dual::constant);
+//! let y = x.mul_scalar(a).add_scalar(Dual::constant(1.));
+//!
+//! assert_eq!(y.iter().map(|y| y.real).collect::<Vec<_>>(), [3., 5., 7.]);
+//! assert_eq!(y.iter().map(|y| y.eps[0]).collect::<Vec<_>>(), [1., 2., 3.]);
+//! ```
+//!
+//! Long sums of dual numbers, such as of the residuals of a large fit, are
+//! computed to full precision in every component by [`sum_compensated`].
+//!
+//! The index of the largest or smallest element, found by [`argmax_real`]
+//! and [`argmin_real`], has no derivative. Where the selection itself needs
+//! to be differentiated, [`soft_argmax`] gives a smooth approximation, and
+//! likewise [`soft_sort`] and [`soft_rank`] for sorting and ranking.
+
+use crate::{Dual, Numerical, Scalar};
+
+/// Elementwise arithmetic on slices, implemented for `[T]` with `T`
+/// [`Numerical`]
+///
+/// The methods returning a `Vec` leave `self` unchanged, the methods taking
+/// `&mut self` update it in place without allocating. Panics if two slices
+/// differ in length.
+pub trait SliceExt<T> {
+    /// `self[i] + other[i]`
+    fn add_slice(&self, other: &[T]) -> Vec<T>;
+    /// `self[i] - other[i]`
+    fn sub_slice(&self, other: &[T]) -> Vec<T>;
+    /// `self[i] * other[i]`
+    fn mul_slice(&self, other: &[T]) -> Vec<T>;
+    /// `self[i] + a`
+    fn add_scalar(&self, a: T) -> Vec<T>;
+    /// `self[i] * a`
+    fn mul_scalar(&self, a: T) -> Vec<T>;
+    /// Sum of `self[i] * other[i]`
+    fn dot(&self, other: &[T]) -> T;
+    /// Sum of the elements, zero for an empty slice
+    fn sum(&self) -> T;
+    /// `f(self[i])`
+    fn map_vec<U>(&self, f: impl FnMut(T) -> U) -> Vec<U>;
+    /// `f(self[i], other[i])`
+    fn zip_map<U, V>(&self, other: &[U], f: impl FnMut(T, U) -> V) -> Vec<V>
+    where
+        U: Copy;
+
+    /// `self[i] += other[i]`
+    fn add_assign_slice(&mut self, other: &[T]);
+    /// `self[i] *= other[i]`
+    fn mul_assign_slice(&mut self, other: &[T]);
+    /// `self[i] *= a`
+    fn scale(&mut self, a: T);
+    /// `self[i] += a * x[i]`, the BLAS `axpy`
+    fn axpy(&mut self, a: T, x: &[T]);
+}
+
+fn check_len(a: usize, b: usize) {
+    assert_eq!(a, b, "slices differ in length");
+}
+
+impl<T: Numerical> SliceExt<T> for [T] {
+    fn add_slice(&self, other: &[T]) -> Vec<T> {
+        self.zip_map(other, |a, b| a + b)
+    }
+
+    fn sub_slice(&self, other: &[T]) -> Vec<T> {
+        self.zip_map(other, |a, b| a - b)
+    }
+
+    fn mul_slice(&self, other: &[T]) -> Vec<T> {
+        self.zip_map(other, |a, b| a * b)
+    }
+
+    fn add_scalar(&self, a: T) -> Vec<T> {
+        self.map_vec(|x| x + a)
+    }
+
+    fn mul_scalar(&self, a: T) -> Vec<T> {
+        self.map_vec(|x| x * a)
+    }
+
+    fn dot(&self, other: &[T]) -> T {
+        check_len(self.len(), other.len());
+        self.iter()
+            .zip(other)
+            .fold(T::constant(0.), |sum, (&a, &b)| sum + a * b)
+    }
+
+    fn sum(&self) -> T {
+        self.iter().fold(T::constant(0.), |sum, &x| sum + x)
+    }
+
+    fn map_vec<U>(&self, f: impl FnMut(T) -> U) -> Vec<U> {
+        self.iter().copied().map(f).collect()
+    }
+
+    fn zip_map<U, V>(&self, other: &[U], mut f: impl FnMut(T, U) -> V) -> Vec<V>
+    where
+        U: Copy,
+    {
+        check_len(self.len(), other.len());
+        self.iter().zip(other).map(|(&a, &b)| f(a, b)).collect()
+    }
+
+    fn add_assign_slice(&mut self, other: &[T]) {
+        check_len(self.len(), other.len());
+        for (a, &b) in self.iter_mut().zip(other) {
+            *a += b;
+        }
+    }
+
+    fn mul_assign_slice(&mut self, other: &[T]) {
+        check_len(self.len(), other.len());
+        for (a, &b) in self.iter_mut().zip(other) {
+            *a *= b;
+        }
+    }
+
+    fn scale(&mut self, a: T) {
+        for x in self.iter_mut() {
+            *x *= a;
+        }
+    }
+
+    fn axpy(&mut self, a: T, x: &[T]) {
+        check_len(self.len(), x.len());
+        for (y, &x) in self.iter_mut().zip(x) {
+            *y += a * x;
+        }
+    }
+}
+
+/// Sum of `values` by Neumaier's compensated summation
+fn neumaier<F: Scalar>(values: impl Iterator<Item = F>) -> F {
+    let abs = |x: F| x.to_f64().abs();
+    let (mut sum, mut compensation) = (F::ZERO, F::ZERO);
+    for x in values {
+        let t = sum + x;
+        // The low-order bits lost from the smaller of the two terms
+        compensation += if abs(sum) >= abs(x) {
+            (sum - t) + x
+        } else {
+            (x - t) + sum
+        };
+        sum = t;
+    }
+    sum + compensation
+}
+
+/// Sum of `values` with the error of the real part and of each dual component
+/// compensated separately, so that it stays within a few rounding errors of
+/// the exact sum regardless of the number of terms
+///
+/// ```
+/// use epsilon::slice::{sum_compensated, SliceExt};
+/// use epsilon::Dual;
+/// let values = vec![Dual::<1>::new(0.1, [0.1]); 1_000_000];
+/// let sum = sum_compensated(&values);
+/// assert_eq!(sum.real, 100000.);
+/// assert_eq!(sum.eps, [100000.]);
+/// assert_ne!(values.sum().real, 100000.);
+/// ```
+pub fn sum_compensated<const N: usize, F: Scalar>(values: &[Dual<N, F>]) -> Dual<N, F> {
+    Dual::new(
+        neumaier(values.iter().map(|x| x.real)),
+        std::array::from_fn(|i| neumaier(values.iter().map(|x| x.eps[i]))),
+    )
+}
+
+/// Index of the element with the largest real part, the first one if there
+/// are several, or `None` if `values` is empty or all `NaN`
+pub fn argmax_real<T: Numerical>(values: &[T]) -> Option<usize> {
+    select(values, |a, b| a > b)
+}
+
+/// Index of the element with the smallest real part, the first one if there
+/// are several, or `None` if `values` is empty or all `NaN`
+pub fn argmin_real<T: Numerical>(values: &[T]) -> Option<usize> {
+    select(values, |a, b| a < b)
+}
+
+fn select<T: Numerical>(values: &[T], better: impl Fn(f64, f64) -> bool) -> Option<usize> {
+    let mut best: Option<(usize, f64)> = None;
+    for (i, x) in values.iter().enumerate() {
+        let x = x.real();
+        if !x.is_nan() && best.is_none_or(|(_, b)| better(x, b)) {
+            best = Some((i, x));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+/// Expected index under the softmax distribution of `values / temperature`,
+/// a differentiable approximation of [`argmax_real`]
+///
+/// Approaches the index of the largest element as `temperature` goes to
+/// zero, and the mean index as it grows. Unlike the index itself, it has
+/// derivatives with respect to all `values`. `NaN` for an empty slice.
+///
+/// ```
+/// use epsilon::slice::soft_argmax;
+/// use epsilon::Dual;
+/// let values = Dual::<3>::variables([1., 5., 2.]);
+/// let index = soft_argmax(&values, 0.1);
+/// assert!((index.real - 1.).abs() < 1e-12);
+///
+/// // A warmer softmax leaks weight to the neighbours, and gradient to them
+/// let index = soft_argmax(&values, 2.);
+/// assert!(index.real > 1. && index.eps[2] > 0. && index.eps[0] < 0.);
+/// ```
+pub fn soft_argmax<T: Scalar>(values: &[T], temperature: f64) -> T {
+    if values.is_empty() {
+        return T::from_f64(f64::NAN);
+    }
+    let scaled: Vec<T> = values
+        .iter()
+        .map(|&x| x / T::from_f64(temperature))
+        .collect();
+    crate::losses::log_softmax(&scaled)
+        .into_iter()
+        .enumerate()
+        .fold(T::ZERO, |sum, (i, p)| sum + T::from_f64(i as f64) * p.exp())
+}
+
+/// Differentiable approximation of `values` sorted in ascending order
+///
+/// The projection of the sorted values onto the permutahedron, the convex
+/// hull of all permutations of `values`, regularized by `temperature`
+/// (Blondel et al., "Fast Differentiable Sorting and Ranking", 2020). Equal
+/// to the sorted values while the gaps between them are smaller than
+/// `1 / temperature`. Values further apart are pulled together, to
+/// `1 / temperature` apart, so that the result approaches the mean as
+/// `temperature` grows, always with the same sum. The derivatives are exact
+/// for this piecewise linear function.
+///
+/// ```
+/// use epsilon::slice::soft_sort;
+/// use epsilon::Dual;
+/// let values = Dual::<3>::variables([3., 1., 2.]);
+/// let sorted = soft_sort(&values, 0.1);
+/// assert_eq!(sorted.iter().map(|x| x.real).collect::<Vec<_>>(), [1., 2., 3.]);
+/// assert_eq!(sorted[0].eps, [0., 1., 0.]);
+///
+/// // Warmer, the values are pulled together and share their derivatives
+/// let sorted = soft_sort(&values, 2.);
+/// assert_eq!(sorted.iter().map(|x| x.real).collect::<Vec<_>>(), [1.5, 2., 2.5]);
+/// assert_eq!(sorted[0].eps, [1. / 3.; 3]);
+/// ```
+pub fn soft_sort<T: Scalar>(values: &[T], temperature: f64) -> Vec<T> {
+    let n = values.len();
+    let rho: Vec<T> = (0..n)
+        .map(|i| T::from_f64((n - i) as f64 / temperature))
+        .collect();
+    let negated: Vec<T> = values.iter().map(|&x| -x).collect();
+    // The descending sort of the negated values, negated
+    let mut sorted = project_permutahedron(&rho, &negated);
+    sorted.sort_by(|a, b| b.to_f64().total_cmp(&a.to_f64()));
+    sorted.into_iter().map(|x| -x).collect()
+}
+
+/// Differentiable approximation of the ranks of `values`, from 1 for the
+/// smallest to `values.len()` for the largest
+///
+/// The projection of `values / temperature` onto the permutahedron of the
+/// ranks, like [`soft_sort`]. Equal to the ranks if `temperature` is small
+/// compared to the gaps between the values, with ties and near ties sharing
+/// their mean rank.
+///
+/// ```
+/// use epsilon::slice::soft_rank;
+/// use epsilon::Dual;
+/// let ranks = soft_rank(&[3., 1., 2.], 0.1);
+/// assert_eq!(ranks, [3., 1., 2.]);
+///
+/// // Near ties have a derivative with respect to the gap
+/// let [a, b] = Dual::<2>::variables([0., 1.]);
+/// let ranks = soft_rank(&[a, b], 10.);
+/// assert!((ranks[1].real - 1.55).abs() < 1e-15 && (ranks[1].eps[1] - 0.05).abs() < 1e-15);
+/// ```
+pub fn soft_rank<T: Scalar>(values: &[T], temperature: f64) -> Vec<T> {
+    let n = values.len();
+    let scaled: Vec<T> = values
+        .iter()
+        .map(|&x| x / T::from_f64(temperature))
+        .collect();
+    let ranks: Vec<T> = (1..=n).map(|rank| T::from_f64(rank as f64)).collect();
+    project_permutahedron(&scaled, &ranks)
+}
+
+/// Euclidean projection of `z` onto the permutahedron of `w`
+///
+/// With the indices sorting `z` in descending order, the projection is `z`
+/// minus the decreasing isotonic regression of `z` sorted minus `w` sorted,
+/// found by pooling adjacent violators on the real parts.
+fn project_permutahedron<T: Scalar>(z: &[T], w: &[T]) -> Vec<T> {
+    let descending = |a: &T, b: &T| b.to_f64().total_cmp(&a.to_f64());
+    let mut order: Vec<usize> = (0..z.len()).collect();
+    order.sort_by(|&i, &j| descending(&z[i], &z[j]));
+    let mut w = w.to_vec();
+    w.sort_by(descending);
+
+    // Blocks of the sums of z and w and the count, with the means of z - w
+    // decreasing. The fit is the block mean of z - w.
+    let mut blocks: Vec<(T, T, usize)> = Vec::with_capacity(z.len());
+    let mean = |&(z, w, count): &(T, T, usize)| (z.to_f64() - w.to_f64()) / count as f64;
+    for (&i, &w) in order.iter().zip(&w) {
+        let mut block = (z[i], w, 1);
+        while let Some(last) = blocks.last() {
+            if mean(last) > mean(&block) {
+                break;
+            }
+            block = (last.0 + block.0, last.1 + block.1, last.2 + block.2);
+            blocks.pop();
+        }
+        blocks.push(block);
+    }
+
+    // z - mean(z) + mean(w) rather than z - mean(z - w), which is exactly w
+    // for blocks of one element
+    let mut projection = z.to_vec();
+    let mut order = order.into_iter();
+    for (z_sum, w_sum, count) in blocks {
+        let n = T::from_f64(count as f64);
+        for i in order.by_ref().take(count) {
+            projection[i] = z[i] - z_sum / n + w_sum / n;
+        }
+    }
+    projection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        argmax_real, argmin_real, soft_argmax, soft_rank, soft_sort, sum_compensated, SliceExt,
+    };
+    use crate::Dual;
+
+    #[test]
+    fn test_elementwise() {
+        let a = [1., 2., 3.];
+        let b = [4., 5., 6.];
+        assert_eq!(a.add_slice(&b), [5., 7., 9.]);
+        assert_eq!(a.sub_slice(&b), [-3., -3., -3.]);
+        assert_eq!(a.mul_slice(&b), [4., 10., 18.]);
+        assert_eq!(a.add_scalar(1.), [2., 3., 4.]);
+        assert_eq!(a.dot(&b), 32.);
+        assert_eq!(b.sum(), 15.);
+        assert_eq!(
+            a.zip_map(&[true, false, true], |x, k| k as u8 as f64 * x),
+            [1., 0., 3.]
+        );
+
+        let mut y = vec![1., 1., 1.];
+        y.axpy(2., &a);
+        assert_eq!(y, [3., 5., 7.]);
+        y.mul_assign_slice(&a);
+        y.scale(0.5);
+        y.add_assign_slice(&b);
+        assert_eq!(y, [5.5, 10., 16.5]);
+    }
+
+    #[test]
+    fn test_sum_compensated() {
+        // Cancellation that naive summation gets entirely wrong
+        let values = [1., 1e100, 1., -1e100].map(|x| Dual::<2>::new(x, [-x, 0.5 * x]));
+        assert_eq!(values.sum().real, 0.);
+        let sum = sum_compensated(&values);
+        assert_eq!(sum, Dual::new(2., [-2., 1.]));
+        assert_eq!(sum_compensated::<1, f64>(&[]), Dual::constant(0.));
+    }
+
+    #[test]
+    fn test_argmax() {
+        let values = [3., f64::NAN, 7., -1., 7.].map(Dual::<1>::constant);
+        assert_eq!(argmax_real(&values), Some(2));
+        assert_eq!(argmin_real(&values), Some(3));
+        assert_eq!(argmax_real::<f64>(&[]), None);
+        assert_eq!(argmin_real(&[f64::NAN]), None);
+
+        // Two elements, soft_argmax = sigmoid((b - a) / t)
+        let [a, b] = Dual::<2>::variables([0.5, 1.]);
+        let index = soft_argmax(&[a, b], 0.5);
+        let p = 1. / (1. + (-1f64).exp());
+        assert!((index.real - p).abs() < 1e-15);
+        let slope = p * (1. - p) / 0.5;
+        assert!((index.eps[0] + slope).abs() < 1e-15 && (index.eps[1] - slope).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_soft_sort() {
+        let values = [0.3, -1.2, 2.5, 0.31];
+        // Small temperatures give the hard sort and ranks, large ones the mean
+        let sorted = soft_sort(&values, 1e-3);
+        assert_eq!(sorted, [-1.2, 0.3, 0.31, 2.5]);
+        assert_eq!(soft_rank(&values, 1e-3), [2., 1., 4., 3.]);
+        let mean = values.iter().sum::<f64>() / 4.;
+        assert!(soft_sort(&values, 1e6)
+            .iter()
+            .all(|x| (x - mean).abs() < 1e-5));
+        assert!(soft_rank(&values, 1e6)
+            .iter()
+            .all(|r| (r - 2.5).abs() < 1e-5));
+
+        // Pooling the two values furthest apart, which then share derivatives
+        let x = Dual::<4>::variables(values);
+        let sorted = soft_sort(&x, 0.5);
+        let expected = [-1.2, 0.3, 0.405, 2.405];
+        assert!((0..4).all(|i| (sorted[i].real - expected[i]).abs() < 1e-15));
+        assert_eq!(
+            (sorted[2].eps, sorted[3].eps),
+            ([0., 0., 0.5, 0.5], [0., 0., 0.5, 0.5])
+        );
+        let total = sorted.iter().fold(Dual::constant(0.), |sum, &x| sum + x);
+        assert!(total.eps.iter().all(|e| (e - 1.).abs() < 1e-15));
+        assert!(soft_rank::<f64>(&[], 1.).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "slices differ in length")]
+    fn test_length_mismatch() {
+        let x = Dual::<1>::variables([1.]);
+        x.add_slice(&[]);
+    }
+}
@@ -0,0 +1,193 @@
+//! # Structure-of-arrays conversion
+//!
+//! Conversion between slices of dual numbers and separate columns of the
+//! real parts and of each dual component, as expected by columnar formats
+//! and vectorized code. [`split_into`] and [`join_into`] reuse the buffers
+//! of previous conversions, so converting many batches does not allocate.
+//!
+//! ```
+//! use epsilon::soa::{join, split};
+//! use epsilon::Dual;
+//!
+//! let duals = [Dual::new(1., [2., 3.]), Dual::new(4., [5., 6.])];
+//! let columns = split(&duals);
+//! assert_eq!(columns.values, [1., 4.]);
+//! assert_eq!(columns.eps, [[2., 5.], [3., 6.]]);
+//! assert_eq!(join::<Dual<2>>(&columns), duals);
+//! ```
+
+use crate::{Dual, DualNumber, Scalar};
+
+/// Dual numbers that can be converted to and from columns, implemented for
+/// [`Dual`] and the types generated by [`make_dual`](crate::make_dual)
+pub trait Columnar: Copy {
+    /// Type of the real part and the dual components, `f64` for the
+    /// generated types
+    type Elem: Copy;
+
+    /// Number of dual components
+    const COMPONENTS: usize;
+
+    fn real_elem(&self) -> Self::Elem;
+    /// The `i`th dual component
+    fn eps_elem(&self, i: usize) -> Self::Elem;
+    /// Create instance from the real part and the dual components
+    fn from_elems(real: Self::Elem, eps: impl FnMut(usize) -> Self::Elem) -> Self;
+}
+
+impl<const N: usize, F: Scalar> Columnar for Dual<N, F> {
+    type Elem = F;
+    const COMPONENTS: usize = N;
+
+    fn real_elem(&self) -> F {
+        self.real
+    }
+
+    fn eps_elem(&self, i: usize) -> F {
+        self.eps[i]
+    }
+
+    fn from_elems(real: F, eps: impl FnMut(usize) -> F) -> Self {
+        Dual::new(real, std::array::from_fn(eps))
+    }
+}
+
+impl<T: DualNumber> Columnar for T {
+    type Elem = f64;
+    const COMPONENTS: usize = <T as DualNumber>::COMPONENTS;
+
+    fn real_elem(&self) -> f64 {
+        self.real()
+    }
+
+    fn eps_elem(&self, i: usize) -> f64 {
+        self.eps(i)
+    }
+
+    fn from_elems(real: f64, mut eps: impl FnMut(usize) -> f64) -> Self {
+        let mut v = T::constant(real);
+        for i in 0..<T as DualNumber>::COMPONENTS {
+            v.set_eps(i, eps(i));
+        }
+        v
+    }
+}
+
+/// The real parts and dual components of a sequence of dual numbers, as one
+/// column each
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Columns<E> {
+    /// The real parts
+    pub values: Vec<E>,
+    /// One column for each dual component
+    pub eps: Vec<Vec<E>>,
+}
+
+impl<E> Columns<E> {
+    /// Number of rows, i.e. dual numbers
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Split `duals` into columns
+pub fn split<D: Columnar>(duals: &[D]) -> Columns<D::Elem> {
+    let mut columns = Columns {
+        values: Vec::new(),
+        eps: Vec::new(),
+    };
+    split_into(duals, &mut columns);
+    columns
+}
+
+/// Split `duals` into `columns`, replacing their contents but reusing their
+/// allocations
+pub fn split_into<D: Columnar>(duals: &[D], columns: &mut Columns<D::Elem>) {
+    columns.eps.resize_with(D::COMPONENTS, Vec::new);
+    columns.values.clear();
+    columns.values.reserve(duals.len());
+    for column in &mut columns.eps {
+        column.clear();
+        column.reserve(duals.len());
+    }
+    for d in duals {
+        columns.values.push(d.real_elem());
+        for (i, column) in columns.eps.iter_mut().enumerate() {
+            column.push(d.eps_elem(i));
+        }
+    }
+}
+
+/// Join `columns` back into dual numbers
+///
+/// # Panics
+///
+/// If the number of dual columns differs from the number of components of
+/// `D`, or the columns differ in length.
+pub fn join<D: Columnar>(columns: &Columns<D::Elem>) -> Vec<D> {
+    let mut duals = Vec::new();
+    join_into(columns, &mut duals);
+    duals
+}
+
+/// Join `columns` into `duals`, replacing its contents but reusing its
+/// allocation
+///
+/// # Panics
+///
+/// Like [`join`].
+pub fn join_into<D: Columnar>(columns: &Columns<D::Elem>, duals: &mut Vec<D>) {
+    assert_eq!(
+        columns.eps.len(),
+        D::COMPONENTS,
+        "wrong number of dual columns"
+    );
+    let n = columns.values.len();
+    assert!(
+        columns.eps.iter().all(|column| column.len() == n),
+        "columns differ in length"
+    );
+    duals.clear();
+    duals.extend((0..n).map(|row| D::from_elems(columns.values[row], |i| columns.eps[i][row])));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join, join_into, split, split_into, Columns};
+    use crate::sample::SampleXYZ;
+    use crate::Dual;
+
+    #[test]
+    fn test_round_trip() {
+        let duals = [SampleXYZ::x(1.), SampleXYZ::eps_z(2., -3.)];
+        let columns = split(&duals);
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns.eps, [[1., 0.], [0., 0.], [0., -3.]]);
+        assert_eq!(join::<SampleXYZ>(&columns), duals);
+
+        // Reusing buffers with a different number of rows and components
+        let mut columns = Columns::default();
+        let mut out: Vec<Dual<1, f32>> = Vec::new();
+        for n in [5, 2] {
+            let duals: Vec<Dual<1, f32>> = (0..n).map(|i| Dual::variable(i as f32, 0)).collect();
+            split_into(&duals, &mut columns);
+            join_into(&columns, &mut out);
+            assert_eq!(out, duals);
+        }
+        assert!(split::<Dual<3>>(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "columns differ in length")]
+    fn test_ragged() {
+        let columns = Columns {
+            values: vec![1., 2.],
+            eps: vec![vec![0.]],
+        };
+        join::<Dual<1>>(&columns);
+    }
+}
@@ -0,0 +1,422 @@
+//! # Root finding
+//!
+//! Solvers for `f(x) = 0` which use the derivatives computed by dual numbers,
+//! so only `f` itself needs to be supplied.
+//!
+//! ```
+//! use epsilon::solve;
+//! // Solve x^3 = 2
+//! let sol = solve::newton(|x| x * x * x - 2., 1., 1e-12, 50);
+//! assert!(sol.converged);
+//! assert!((sol.x - 2f64.powf(1. / 3.)).abs() < 1e-12);
+//! ```
+//!
+//! Systems of equations are solved by [`newton_system`], which builds the
+//! Jacobian with dual numbers in each iteration. The linear systems are solved
+//! by a [`LinearSolver`], either the built-in [`Lu`], or with the `nalgebra`
+//! and `faer` features the decompositions of those crates.
+//!
+//! ```
+//! use epsilon::solve;
+//! // Intersect the unit circle with the line y = 2x
+//! let sol = solve::newton_system(|[x, y]| [x * x + y * y - 1., y - 2. * x], [1., 1.], 1e-12, 50);
+//! assert!(sol.converged);
+//! assert!((sol.x[0] - 0.2f64.sqrt()).abs() < 1e-12);
+//! ```
+
+use crate::{Dual, Dual1};
+
+/// Result of a solver, along with diagnostics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Solution<X = f64> {
+    /// The final iterate
+    pub x: X,
+    /// Absolute value of `f` at `x`, or its Euclidean norm for systems
+    pub residual: f64,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// Whether the residual is within the tolerance
+    pub converged: bool,
+}
+
+/// Find a root of `f` using the Newton-Raphson method, starting at `x0`
+///
+/// Iterates until `|f(x)| <= tol`, for at most `max_iter` iterations. Stops
+/// early without converging if the derivative vanishes or the iterate is no
+/// longer finite.
+pub fn newton(f: impl Fn(Dual1) -> Dual1, x0: f64, tol: f64, max_iter: usize) -> Solution {
+    let mut x = x0;
+    let mut iterations = 0;
+    loop {
+        let y = f(Dual1::variable(x, 0));
+        let residual = y.real.abs();
+        let converged = residual <= tol;
+        let step = y.real / y.d_d(0);
+        if converged || iterations == max_iter || !step.is_finite() {
+            return Solution {
+                x,
+                residual,
+                iterations,
+                converged,
+            };
+        }
+        x -= step;
+        iterations += 1;
+    }
+}
+
+/// Solver for dense linear systems `a x = b`, used by [`newton_system_with`]
+pub trait LinearSolver {
+    /// Solve `a x = b`, or return `None` if `a` is singular
+    fn solve<const N: usize>(&self, a: [[f64; N]; N], b: [f64; N]) -> Option<[f64; N]>;
+}
+
+/// Gaussian elimination with partial pivoting
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lu;
+
+impl LinearSolver for Lu {
+    fn solve<const N: usize>(&self, mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+        for k in 0..N {
+            let pivot = (k..N).max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs()))?;
+            if a[pivot][k] == 0. {
+                return None;
+            }
+            a.swap(k, pivot);
+            b.swap(k, pivot);
+
+            let (top, bottom) = a.split_at_mut(k + 1);
+            let (row_k, b_k) = (&top[k], b[k]);
+            for (row, b_i) in bottom.iter_mut().zip(&mut b[k + 1..]) {
+                let m = row[k] / row_k[k];
+                for (a_ij, a_kj) in row[k..].iter_mut().zip(&row_k[k..]) {
+                    *a_ij -= m * a_kj;
+                }
+                *b_i -= m * b_k;
+            }
+        }
+
+        let mut x = [0.; N];
+        for k in (0..N).rev() {
+            let sum: f64 = a[k][k + 1..]
+                .iter()
+                .zip(&x[k + 1..])
+                .map(|(a, x)| a * x)
+                .sum();
+            x[k] = (b[k] - sum) / a[k][k];
+        }
+        Some(x)
+    }
+}
+
+/// LU decomposition with partial pivoting from `nalgebra`
+#[cfg(feature = "nalgebra")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Nalgebra;
+
+#[cfg(feature = "nalgebra")]
+impl LinearSolver for Nalgebra {
+    fn solve<const N: usize>(&self, a: [[f64; N]; N], b: [f64; N]) -> Option<[f64; N]> {
+        let a = nalgebra::DMatrix::from_fn(N, N, |i, j| a[i][j]);
+        let x = a.lu().solve(&nalgebra::DVector::from_column_slice(&b))?;
+        Some(std::array::from_fn(|i| x[i]))
+    }
+}
+
+/// LU decomposition with partial pivoting from `faer`
+#[cfg(feature = "faer")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Faer;
+
+#[cfg(feature = "faer")]
+impl LinearSolver for Faer {
+    fn solve<const N: usize>(&self, a: [[f64; N]; N], b: [f64; N]) -> Option<[f64; N]> {
+        use faer::linalg::solvers::Solve;
+
+        let a = faer::Mat::from_fn(N, N, |i, j| a[i][j]);
+        let x = a.partial_piv_lu().solve(faer::Col::from_fn(N, |i| b[i]));
+        // Singular systems are not reported, but produce non-finite solutions
+        let x: [f64; N] = std::array::from_fn(|i| x[i]);
+        x.iter().all(|x| x.is_finite()).then_some(x)
+    }
+}
+
+/// Find a root of the system `f` using the damped Newton method, starting at
+/// `x0`
+///
+/// Iterates until the Euclidean norm of `f(x)` is at most `tol`, for at most
+/// `max_iter` iterations. Each Newton step is halved until it reduces the
+/// residual. Stops early without converging if the Jacobian is singular or no
+/// reduction is found.
+pub fn newton_system<const N: usize>(
+    f: impl Fn([Dual<N>; N]) -> [Dual<N>; N],
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> Solution<[f64; N]> {
+    newton_system_with(&Lu, f, x0, tol, max_iter)
+}
+
+/// [`newton_system`], solving the linear systems using `solver`
+pub fn newton_system_with<const N: usize, S: LinearSolver>(
+    solver: &S,
+    f: impl Fn([Dual<N>; N]) -> [Dual<N>; N],
+    x0: [f64; N],
+    tol: f64,
+    max_iter: usize,
+) -> Solution<[f64; N]> {
+    const MAX_HALVINGS: usize = 30;
+    let norm = |y: &[Dual<N>; N]| y.iter().map(|y| y.real * y.real).sum::<f64>().sqrt();
+
+    let mut x = x0;
+    let mut y = f(Dual::variables(x));
+    let mut iterations = 0;
+    loop {
+        let residual = norm(&y);
+        let converged = residual <= tol;
+        let done = Solution {
+            x,
+            residual,
+            iterations,
+            converged,
+        };
+        if converged || iterations == max_iter {
+            return done;
+        }
+        let Some(step) = solver.solve(y.map(|y| y.eps), y.map(|y| -y.real)) else {
+            return done;
+        };
+
+        let mut t = 1.;
+        let next = (0..MAX_HALVINGS).find_map(|_| {
+            let trial: [f64; N] = std::array::from_fn(|i| x[i] + t * step[i]);
+            let y_trial = f(Dual::variables(trial));
+            t /= 2.;
+            (norm(&y_trial) < residual).then_some((trial, y_trial))
+        });
+        let Some((x_next, y_next)) = next else {
+            return done;
+        };
+        (x, y) = (x_next, y_next);
+        iterations += 1;
+    }
+}
+
+/// Derivatives `dy/dtheta` of the solution `y` of `f(y, theta) = 0` with
+/// respect to the parameters `theta`, using the implicit function theorem
+///
+/// `y` must be a solution at `theta`, found by any method. Rather than
+/// differentiating through the iterations of the solver, this evaluates
+/// `dy/dtheta = -(df/dy)^-1 df/dtheta` at the solution. `f` is evaluated once
+/// on dual numbers with `M = N + P` components, the first `N` seeded for `y`
+/// and the remaining `P` for `theta`. Returns `None` if `df/dy` is singular.
+///
+/// ```
+/// use epsilon::{solve, Dual};
+/// // y^3 + theta y - 2 = 0, solved by y = 1 at theta = 1
+/// let f = |[y]: [Dual<2>; 1], [theta]: [Dual<2>; 1]| [y * y * y + theta * y - 2.];
+/// let dy = solve::implicit_derivative(f, [1.], [1.]).unwrap();
+/// // dy/dtheta = -y / (3 y^2 + theta)
+/// assert_eq!(dy, [[-0.25]]);
+/// ```
+pub fn implicit_derivative<const N: usize, const P: usize, const M: usize>(
+    f: impl FnOnce([Dual<M>; N], [Dual<M>; P]) -> [Dual<M>; N],
+    y: [f64; N],
+    theta: [f64; P],
+) -> Option<[[f64; P]; N]> {
+    const { assert!(M == N + P, "number of dual components must be N + P") };
+    let jac = f(Dual::variables_at(y, 0), Dual::variables_at(theta, N));
+    let df_dy: [[f64; N]; N] = jac.map(|row| std::array::from_fn(|j| row.eps[j]));
+
+    let mut dy = [[0.; P]; N];
+    for k in 0..P {
+        let column = Lu.solve(df_dy, jac.map(|row| -row.eps[N + k]))?;
+        for (row, c) in dy.iter_mut().zip(column) {
+            row[k] = c;
+        }
+    }
+    Some(dy)
+}
+
+/// Result of [`brent`], with the sensitivities of the root to the parameters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Root<const P: usize> {
+    /// The root and diagnostics of the solver
+    pub solution: Solution,
+    /// `dx/dtheta` at the root, or `None` if `df/dx` vanishes there
+    pub sensitivities: Option<[f64; P]>,
+}
+
+/// Find a root of `f(x, theta)` in `x` bracketed by `[a, b]` using Brent's
+/// method, along with its derivatives with respect to the parameters `theta`
+///
+/// Iterates until the bracket is narrower than about `tol`, for at most
+/// `max_iter` iterations, evaluating `f` on constants. The sensitivities
+/// `dx/dtheta = -(df/dtheta) / (df/dx)` are then computed by
+/// [`implicit_derivative`] from a single evaluation on dual numbers with
+/// `M = 1 + P` components. Returns `None` if `f(a)` and `f(b)` have the same
+/// sign.
+///
+/// ```
+/// use epsilon::{solve, Dual};
+/// // x^2 - theta = 0 on [0, 4], solved by x = sqrt(theta)
+/// let f = |x: Dual<2>, [theta]: [Dual<2>; 1]| x * x - theta;
+/// let root = solve::brent(f, [2.], 0., 4., 1e-14, 100).unwrap();
+/// assert!(root.solution.converged);
+/// assert!((root.solution.x - 2f64.sqrt()).abs() < 1e-14);
+/// // dx/dtheta = 1 / (2 sqrt(theta))
+/// assert!((root.sensitivities.unwrap()[0] - 0.5 / 2f64.sqrt()).abs() < 1e-14);
+/// ```
+pub fn brent<const P: usize, const M: usize>(
+    f: impl Fn(Dual<M>, [Dual<M>; P]) -> Dual<M>,
+    theta: [f64; P],
+    a: f64,
+    b: f64,
+    tol: f64,
+    max_iter: usize,
+) -> Option<Root<P>> {
+    let eval = |x: f64| f(Dual::constant(x), theta.map(Dual::constant)).real;
+
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (eval(a), eval(b));
+    if fa * fb > 0. {
+        return None;
+    }
+    // b is the best estimate, c the other end of the bracket, and a the
+    // previous value of b
+    let (mut c, mut fc) = (b, fb);
+    let (mut d, mut e) = (b - a, b - a);
+    let mut iterations = 0;
+    let solution = loop {
+        if fb.signum() == fc.signum() {
+            (c, fc) = (a, fa);
+            (d, e) = (b - a, b - a);
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+        let tol1 = 2. * f64::EPSILON * b.abs() + tol / 2.;
+        let mid = (c - b) / 2.;
+        let converged = mid.abs() <= tol1 || fb == 0.;
+        if converged || iterations == max_iter {
+            break Solution {
+                x: b,
+                residual: fb.abs(),
+                iterations,
+                converged,
+            };
+        }
+
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            // Secant step, or inverse quadratic interpolation through a, b
+            // and c when they are distinct
+            let s = fb / fa;
+            let (p, q) = if a == c {
+                (2. * mid * s, 1. - s)
+            } else {
+                let (q, r) = (fa / fc, fb / fc);
+                (
+                    s * (2. * mid * q * (q - r) - (b - a) * (r - 1.)),
+                    (q - 1.) * (r - 1.) * (s - 1.),
+                )
+            };
+            let (p, q) = if p > 0. { (p, -q) } else { (-p, q) };
+            // Accept the interpolation only if it stays in the bracket and
+            // the steps are shrinking fast enough, otherwise bisect
+            if 2. * p < (3. * mid * q - (tol1 * q).abs()).min((e * q).abs()) {
+                (e, d) = (d, p / q);
+            } else {
+                (d, e) = (mid, mid);
+            }
+        } else {
+            (d, e) = (mid, mid);
+        }
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol1 {
+            d
+        } else {
+            tol1.copysign(mid)
+        };
+        fb = eval(b);
+        iterations += 1;
+    };
+
+    let sensitivities =
+        implicit_derivative(|[x], theta| [f(x, theta)], [solution.x], theta).map(|[dx]| dx);
+    Some(Root {
+        solution,
+        sensitivities,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newton() {
+        let sol = newton(|x| x.cos() - x, 1., 1e-14, 20);
+        assert!(sol.converged);
+        assert!((sol.x - 0.7390851332151607).abs() < 1e-14);
+        assert!(sol.iterations < 10);
+
+        // x^2 + 1 has no real root, and the derivative vanishes at 0
+        let sol = newton(|x| x * x + 1., 0., 1e-12, 20);
+        assert!(!sol.converged);
+        assert_eq!(sol.iterations, 0);
+    }
+
+    #[test]
+    fn test_lu() {
+        let a = [[0., 2., 1.], [1., 1., 0.], [2., 0., 3.]];
+        let x = Lu.solve(a, [7., 3., 11.]).unwrap();
+        assert!(x
+            .iter()
+            .zip([1., 2., 3.])
+            .all(|(x, e)| (x - e).abs() < 1e-14));
+        assert_eq!(Lu.solve([[1., 2.], [2., 4.]], [1., 1.]), None);
+    }
+
+    #[test]
+    fn test_newton_system() {
+        // Rosenbrock-type system with root (1, 1)
+        let f = |[x, y]: [Dual<2>; 2]| [10. * (y - x * x), 1. - x];
+        let sol = newton_system(f, [-1.2, 1.], 1e-12, 50);
+        assert!(sol.converged);
+        assert_eq!(sol.x, [1., 1.]);
+
+        #[cfg(feature = "nalgebra")]
+        assert_eq!(newton_system_with(&Nalgebra, f, [-1.2, 1.], 1e-12, 50), sol);
+        #[cfg(feature = "faer")]
+        assert_eq!(newton_system_with(&Faer, f, [-1.2, 1.], 1e-12, 50), sol);
+    }
+
+    #[test]
+    fn test_implicit_derivative() {
+        // Circle of radius r centered at (c, 0), intersected with the x-axis
+        // x = c + r at c = 1, r = 2, along with y = 0
+        let f = |[x, y]: [Dual<4>; 2], [c, r]: [Dual<4>; 2]| [(x - c) * (x - c) + y * y - r * r, y];
+        let dy = implicit_derivative(f, [3., 0.], [1., 2.]).unwrap();
+        assert_eq!(dy, [[1., 1.], [0., 0.]]);
+
+        // The tangent point of a parabola has a singular Jacobian
+        let f = |[x]: [Dual<2>; 1], [a]: [Dual<2>; 1]| [x * x - a];
+        assert_eq!(implicit_derivative(f, [0.], [0.]), None);
+    }
+
+    #[test]
+    fn test_brent() {
+        // y^3 + theta y - 2 = 0, solved by y = 1 at theta = 1
+        let f = |y: Dual<2>, [theta]: [Dual<2>; 1]| y * y * y + theta * y - 2.;
+        let root = brent(f, [1.], -3., 5., 1e-15, 100).unwrap();
+        assert!(root.solution.converged);
+        assert!(root.solution.iterations < 20);
+        assert!((root.solution.x - 1.).abs() < 1e-15);
+        let [dy] = root.sensitivities.unwrap();
+        assert!((dy + 0.25).abs() < 1e-14);
+
+        assert_eq!(brent(f, [1.], 2., 5., 1e-15, 100), None);
+    }
+}
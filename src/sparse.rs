@@ -0,0 +1,305 @@
+//! # Sparse Jacobians
+//!
+//! Jacobians of functions where each output depends on few inputs, such as
+//! the residuals of discretized differential equations, computed with far
+//! fewer evaluations than one per input. [`sparsity`] detects the pattern of
+//! non-zero entries, and [`Pattern::coloring`] groups the columns so that no
+//! two columns in a group share a row. All columns of a group are then
+//! seeded in the same dual component, and [`sparse_jacobian`] recovers their
+//! entries from one evaluation on dual numbers with `C` components per `C`
+//! groups.
+//!
+//! ```
+//! use epsilon::sparse::{sparse_jacobian, sparsity};
+//! use epsilon::Dual;
+//! // Residual of a discretized u'' = exp(u), with a tridiagonal Jacobian
+//! let n = 100;
+//! let f = |u: &[Dual<4>]| {
+//!     (0..n)
+//!         .map(|i| {
+//!             let left = if i > 0 { u[i - 1] } else { u[i] * 0. };
+//!             let right = if i + 1 < n { u[i + 1] } else { u[i] * 0. };
+//!             left - u[i] * 2. + right - u[i].exp()
+//!         })
+//!         .collect::<Vec<_>>()
+//! };
+//! let u = vec![0.1; n];
+//! let pattern = sparsity(f, &u);
+//! let coloring = pattern.coloring();
+//! assert_eq!(coloring.count, 3);
+//!
+//! // A single evaluation, rather than 25 with four columns each
+//! let j = sparse_jacobian(f, &u, &pattern, &coloring);
+//! assert_eq!(j.get(5, 4), 1.);
+//! assert_eq!(j.get(5, 5), -2. - 0.1f64.exp());
+//! ```
+
+use crate::krylov::LinearOperator;
+use crate::Dual;
+
+/// Positions of the non-zero entries of a matrix
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub rows: usize,
+    pub cols: usize,
+    /// The columns of the non-zero entries of each row, in increasing order
+    pub nonzeros: Vec<Vec<usize>>,
+}
+
+/// Assignment of the columns of a [`Pattern`] to groups, so that no two
+/// columns in a group have a non-zero entry in the same row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coloring {
+    /// The group of each column
+    pub colors: Vec<usize>,
+    /// Number of groups
+    pub count: usize,
+}
+
+/// Sparse matrix given by its non-zero entries
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    /// The `(row, column, value)` of each entry, ordered by row and column
+    pub entries: Vec<(usize, usize, f64)>,
+}
+
+/// Evaluate `f` at `x`, with the column `j` seeded in the dual component
+/// `seed(j)`, if any
+fn eval_seeded<const C: usize>(
+    f: impl Fn(&[Dual<C>]) -> Vec<Dual<C>>,
+    x: &[f64],
+    seed: impl Fn(usize) -> Option<usize>,
+) -> Vec<Dual<C>> {
+    let vars: Vec<Dual<C>> = x
+        .iter()
+        .enumerate()
+        .map(|(j, &x)| match seed(j) {
+            Some(k) => Dual::variable(x, k),
+            None => Dual::constant(x),
+        })
+        .collect();
+    f(&vars)
+}
+
+/// The pattern of non-zero entries of the Jacobian of `f` at `x`
+///
+/// `f` is evaluated once for every `C` inputs. Entries that vanish at `x`
+/// only by coincidence are missed, so `x` should be a generic point rather
+/// than e.g. zero.
+pub fn sparsity<const C: usize>(f: impl Fn(&[Dual<C>]) -> Vec<Dual<C>>, x: &[f64]) -> Pattern {
+    let cols = x.len();
+    let mut nonzeros: Vec<Vec<usize>> = Vec::new();
+    for start in (0..cols).step_by(C.max(1)) {
+        let y = eval_seeded(&f, x, |j| {
+            (start..start + C).contains(&j).then(|| j - start)
+        });
+        nonzeros.resize(y.len(), Vec::new());
+        for (row, y) in nonzeros.iter_mut().zip(&y) {
+            row.extend((0..C).filter(|&k| y.eps[k] != 0.).map(|k| start + k));
+        }
+    }
+    Pattern {
+        rows: nonzeros.len(),
+        cols,
+        nonzeros,
+    }
+}
+
+impl Pattern {
+    /// Number of non-zero entries
+    pub fn nnz(&self) -> usize {
+        self.nonzeros.iter().map(Vec::len).sum()
+    }
+
+    /// Group the columns with a greedy coloring of the column intersection
+    /// graph, visiting the columns with the most non-zero entries first
+    ///
+    /// Uses at least as many groups as the largest number of non-zero entries
+    /// in a row, e.g. three for a tridiagonal matrix.
+    pub fn coloring(&self) -> Coloring {
+        let mut rows_of = vec![Vec::new(); self.cols];
+        for (i, row) in self.nonzeros.iter().enumerate() {
+            for &j in row {
+                rows_of[j].push(i);
+            }
+        }
+        let mut order: Vec<usize> = (0..self.cols).collect();
+        order.sort_by_key(|&j| std::cmp::Reverse(rows_of[j].len()));
+
+        let mut colors = vec![usize::MAX; self.cols];
+        let mut count = 0;
+        // The column last forbidding each color, avoiding clearing a set
+        let mut forbidden = vec![usize::MAX; self.cols];
+        for &j in &order {
+            for &i in &rows_of[j] {
+                for &other in &self.nonzeros[i] {
+                    if colors[other] != usize::MAX {
+                        forbidden[colors[other]] = j;
+                    }
+                }
+            }
+            let color = (0..).find(|&c| forbidden[c] != j).unwrap();
+            colors[j] = color;
+            count = count.max(color + 1);
+        }
+        Coloring { colors, count }
+    }
+}
+
+/// The Jacobian of `f` at `x`, with the non-zero entries of `pattern`
+///
+/// The columns of each group of `coloring` are seeded together, so `f` is
+/// evaluated once for every `C` groups, rather than once for every `C`
+/// columns. Entries outside `pattern` are assumed to be zero.
+///
+/// # Panics
+///
+/// If `coloring` is not a valid coloring of `pattern`, which would mix up
+/// the entries of columns sharing a row.
+pub fn sparse_jacobian<const C: usize>(
+    f: impl Fn(&[Dual<C>]) -> Vec<Dual<C>>,
+    x: &[f64],
+    pattern: &Pattern,
+    coloring: &Coloring,
+) -> SparseMatrix {
+    for row in &pattern.nonzeros {
+        let mut colors: Vec<usize> = row.iter().map(|&j| coloring.colors[j]).collect();
+        colors.sort_unstable();
+        assert!(
+            colors.windows(2).all(|c| c[0] != c[1]),
+            "columns sharing a row must have different colors"
+        );
+    }
+
+    let mut values: Vec<Vec<f64>> = pattern
+        .nonzeros
+        .iter()
+        .map(|row| vec![0.; row.len()])
+        .collect();
+    for start in (0..coloring.count).step_by(C.max(1)) {
+        let in_pass = |j: usize| {
+            let color = coloring.colors[j];
+            (start..start + C).contains(&color).then(|| color - start)
+        };
+        let y = eval_seeded(&f, x, in_pass);
+        for ((row, values), y) in pattern.nonzeros.iter().zip(&mut values).zip(&y) {
+            for (&j, value) in row.iter().zip(values) {
+                if let Some(k) = in_pass(j) {
+                    *value = y.eps[k];
+                }
+            }
+        }
+    }
+
+    let entries = pattern
+        .nonzeros
+        .iter()
+        .zip(values)
+        .enumerate()
+        .flat_map(|(i, (row, values))| row.iter().zip(values).map(move |(&j, v)| (i, j, v)))
+        .collect();
+    SparseMatrix {
+        rows: pattern.rows,
+        cols: pattern.cols,
+        entries,
+    }
+}
+
+impl SparseMatrix {
+    /// The entry at `(row, col)`, zero if it is not stored
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.entries
+            .binary_search_by(|&(i, j, _)| (i, j).cmp(&(row, col)))
+            .map_or(0., |k| self.entries[k].2)
+    }
+
+    /// The matrix as dense rows
+    pub fn to_dense(&self) -> Vec<Vec<f64>> {
+        let mut dense = vec![vec![0.; self.cols]; self.rows];
+        for &(i, j, v) in &self.entries {
+            dense[i][j] = v;
+        }
+        dense
+    }
+}
+
+impl LinearOperator for SparseMatrix {
+    fn rows(&self) -> usize {
+        self.rows
+    }
+
+    fn cols(&self) -> usize {
+        self.cols
+    }
+
+    fn apply(&self, v: &[f64]) -> Vec<f64> {
+        let mut y = vec![0.; self.rows];
+        for &(i, j, a) in &self.entries {
+            y[i] += a * v[j];
+        }
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sparse_jacobian, sparsity, Coloring};
+    use crate::krylov::LinearOperator;
+    use crate::Dual;
+
+    /// Outputs depending on inputs `i` and `i + 3`, and the last on all
+    fn f<const C: usize>(x: &[Dual<C>]) -> Vec<Dual<C>> {
+        let n = x.len();
+        let mut y: Vec<Dual<C>> = (0..n - 3).map(|i| x[i] * x[i + 3]).collect();
+        y.push(x.iter().fold(Dual::constant(0.), |sum, &x| sum + x.sin()));
+        y
+    }
+
+    #[test]
+    fn test_sparsity() {
+        let x: Vec<f64> = (0..10).map(|i| 1. + i as f64 / 10.).collect();
+        let pattern = sparsity(f::<3>, &x);
+        assert_eq!((pattern.rows, pattern.cols, pattern.nnz()), (8, 10, 24));
+        assert_eq!(pattern.nonzeros[2], [2, 5]);
+        // The dense last row forces a color for every column
+        assert_eq!(pattern.coloring().count, 10);
+
+        let pattern = sparsity(f::<4>, &x[..6]);
+        assert_eq!(pattern.nonzeros.len(), 4);
+    }
+
+    #[test]
+    fn test_sparse_jacobian() {
+        // Without the dense row, columns i and i + 3 only need to differ
+        let g = |x: &[Dual<2>]| {
+            let mut y = f(x);
+            y.pop();
+            y
+        };
+        let x: Vec<f64> = (0..12).map(|i| 0.5 + i as f64).collect();
+        let pattern = sparsity(g, &x);
+        let coloring = pattern.coloring();
+        assert_eq!(coloring.count, 2);
+        let j = sparse_jacobian(g, &x, &pattern, &coloring);
+        assert_eq!(j.entries.len(), 18);
+        assert_eq!(j.get(4, 4), x[7]);
+        assert_eq!(j.get(4, 7), x[4]);
+        assert_eq!(j.get(4, 5), 0.);
+        let v = vec![1.; 12];
+        assert_eq!(j.apply(&v), j.to_dense().apply(&v));
+    }
+
+    #[test]
+    #[should_panic(expected = "columns sharing a row must have different colors")]
+    fn test_invalid_coloring() {
+        let x = [1., 2., 3., 4.];
+        let pattern = sparsity(f::<1>, &x);
+        let coloring = Coloring {
+            colors: vec![0; 4],
+            count: 1,
+        };
+        sparse_jacobian(f::<1>, &x, &pattern, &coloring);
+    }
+}
@@ -0,0 +1,297 @@
+//! # Special functions
+//!
+//! Functions beyond the elementary ones, generic over [`Scalar`] so that
+//! their derivatives follow from evaluating them on dual numbers. The
+//! derivative of [`ln_gamma`] is [`digamma`], which is in turn computed by
+//! evaluating [`ln_gamma`] on a nested dual number. The incomplete gamma
+//! functions [`gamma_p`] and [`gamma_q`] give the error functions [`erf`] and
+//! [`erfc`]. [`logistic`] and [`softplus`] are written to stay finite for
+//! arguments of any size.
+//!
+//! ```
+//! use epsilon::special::{digamma, ln_gamma};
+//! use epsilon::Dual;
+//!
+//! let x = Dual::<1>::variable(5., 0);
+//! let y = ln_gamma(x);
+//! assert!((y.real - 24f64.ln()).abs() < 1e-14);
+//! assert_eq!(y.eps[0], digamma(5.));
+//! ```
+
+use crate::{Dual, Scalar};
+
+/// Coefficients of the Lanczos approximation with `g = 7`
+const LANCZOS: [f64; 9] = [
+    0.9999999999998099,
+    676.5203681218851,
+    -1259.1392167224028,
+    771.3234287776531,
+    -176.6150291621406,
+    12.507343278686905,
+    -0.13857109526572012,
+    9.984369578019572e-6,
+    1.5056327351493116e-7,
+];
+
+/// Natural logarithm of the absolute value of the gamma function
+///
+/// Uses the Lanczos approximation, accurate to about 15 significant digits,
+/// and the reflection formula for `x < 0.5`. Infinite at the non-positive
+/// integers.
+pub fn ln_gamma<T: Scalar>(x: T) -> T {
+    let half = T::from_f64(0.5);
+    let x0 = x.to_f64();
+    if x0 <= 0. && x0 == x0.floor() {
+        // At the poles, sin(pi x) in the reflection formula is not exactly
+        // zero, which would give a large finite value
+        return T::from_f64(f64::INFINITY);
+    }
+    if x0 < 0.5 {
+        // Gamma(x) Gamma(1 - x) = pi / sin(pi x)
+        let pi = T::from_f64(std::f64::consts::PI);
+        let s = (pi * x).sin();
+        let s = if s.to_f64() < 0. { -s } else { s };
+        return (pi / s).ln() - ln_gamma(T::ONE - x);
+    }
+    let x = x - T::ONE;
+    let mut a = T::from_f64(LANCZOS[0]);
+    for (i, &c) in LANCZOS.iter().enumerate().skip(1) {
+        a += T::from_f64(c) / (x + T::from_f64(i as f64));
+    }
+    let t = x + T::from_f64(7.5);
+    T::from_f64(0.5 * (2. * std::f64::consts::PI).ln()) + (x + half) * t.ln() - t + a.ln()
+}
+
+/// The digamma function, the derivative of [`ln_gamma`]
+pub fn digamma<T: Scalar>(x: T) -> T {
+    ln_gamma(Dual::<1, T>::variable(x, 0)).eps[0]
+}
+
+/// Natural logarithm of the beta function, `B(a, b) = Gamma(a) Gamma(b) /
+/// Gamma(a + b)`
+pub fn ln_beta<T: Scalar>(a: T, b: T) -> T {
+    ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+}
+
+/// Iterations of the series and continued fraction of the incomplete gamma
+/// function, which converge within a few hundred for moderate `a`
+const MAX_ITER: usize = 1000;
+
+/// `x^a exp(-x) / Gamma(a)`, the common factor of the series and continued
+/// fraction of the incomplete gamma function
+fn gamma_prefactor<T: Scalar>(a: T, x: T) -> T {
+    (a * x.ln() - x - ln_gamma(a)).exp()
+}
+
+/// `P(a, x)` by its power series, converging quickly for `x < a + 1`
+fn gamma_p_series<T: Scalar>(a: T, x: T) -> T {
+    let mut term = T::ONE / a;
+    let mut sum = term;
+    let mut ap = a;
+    for _ in 0..MAX_ITER {
+        ap += T::ONE;
+        term *= x / ap;
+        sum += term;
+        if term.to_f64().abs() < sum.to_f64().abs() * f64::EPSILON {
+            break;
+        }
+    }
+    sum * gamma_prefactor(a, x)
+}
+
+/// `Q(a, x)` by its continued fraction using the modified Lentz method,
+/// converging quickly for `x >= a + 1`
+fn gamma_q_fraction<T: Scalar>(a: T, x: T) -> T {
+    let tiny = T::from_f64(1e-300);
+    let not_tiny = |v: T| if v.to_f64().abs() < 1e-300 { tiny } else { v };
+    let mut b = x + T::ONE - a;
+    // A constant, as dividing by tiny would give NaN derivatives
+    let mut c = T::from_f64(1e300);
+    let mut d = T::ONE / not_tiny(b);
+    let mut h = d;
+    for i in 1..MAX_ITER {
+        let i = T::from_f64(i as f64);
+        let an = -i * (i - a);
+        b += T::from_f64(2.);
+        d = T::ONE / not_tiny(an * d + b);
+        c = not_tiny(b + an / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta.to_f64() - 1.).abs() < f64::EPSILON {
+            break;
+        }
+    }
+    h * gamma_prefactor(a, x)
+}
+
+/// Regularized lower incomplete gamma function `P(a, x)`, the integral of
+/// `t^(a - 1) exp(-t) / Gamma(a)` from `0` to `x`
+///
+/// `NaN` unless `a > 0` and `x >= 0`.
+pub fn gamma_p<T: Scalar>(a: T, x: T) -> T {
+    let (a0, x0) = (a.to_f64(), x.to_f64());
+    if !(a0 > 0. && x0 >= 0.) {
+        T::from_f64(f64::NAN)
+    } else if x0 < a0 + 1. {
+        gamma_p_series(a, x)
+    } else {
+        T::ONE - gamma_q_fraction(a, x)
+    }
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x) = 1 - P(a, x)`,
+/// accurate also where it is much smaller than one
+pub fn gamma_q<T: Scalar>(a: T, x: T) -> T {
+    let (a0, x0) = (a.to_f64(), x.to_f64());
+    if !(a0 > 0. && x0 >= 0.) {
+        T::from_f64(f64::NAN)
+    } else if x0 < a0 + 1. {
+        T::ONE - gamma_p_series(a, x)
+    } else {
+        gamma_q_fraction(a, x)
+    }
+}
+
+/// `erf(x)` for `|x| < 1` by its Taylor series
+fn erf_series<T: Scalar>(x: T) -> T {
+    let x2 = x * x;
+    let mut power = x;
+    let mut sum = x;
+    for n in 1..MAX_ITER {
+        power *= -x2 / T::from_f64(n as f64);
+        let term = power / T::from_f64((2 * n + 1) as f64);
+        sum += term;
+        if term.to_f64().abs() < sum.to_f64().abs() * f64::EPSILON {
+            break;
+        }
+    }
+    sum * T::from_f64(std::f64::consts::FRAC_2_SQRT_PI)
+}
+
+/// The error function
+pub fn erf<T: Scalar>(x: T) -> T {
+    let x0 = x.to_f64();
+    if x0.abs() < 1. {
+        return erf_series(x);
+    }
+    // erf(|x|) = P(1/2, x^2)
+    let q = gamma_q(T::from_f64(0.5), x * x);
+    if x0 > 0. {
+        T::ONE - q
+    } else {
+        q - T::ONE
+    }
+}
+
+/// The complementary error function `1 - erf(x)`, accurate also for large
+/// `x` where it is much smaller than one
+pub fn erfc<T: Scalar>(x: T) -> T {
+    let x0 = x.to_f64();
+    if x0.abs() < 1. {
+        return T::ONE - erf_series(x);
+    }
+    let q = gamma_q(T::from_f64(0.5), x * x);
+    if x0 > 0. {
+        q
+    } else {
+        T::from_f64(2.) - q
+    }
+}
+
+/// Cumulative distribution function of the standard normal distribution
+pub fn normal_cdf<T: Scalar>(x: T) -> T {
+    erfc(-x * T::from_f64(std::f64::consts::FRAC_1_SQRT_2)) * T::from_f64(0.5)
+}
+
+/// The logistic function `1 / (1 + exp(-x))`, without overflow for large
+/// `|x|`
+pub fn logistic<T: Scalar>(x: T) -> T {
+    // Only ever exponentiate non-positive numbers
+    if x.to_f64() >= 0. {
+        T::ONE / (T::ONE + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (T::ONE + e)
+    }
+}
+
+/// `ln(1 + exp(x))`, without overflow for large `x`
+pub fn softplus<T: Scalar>(x: T) -> T {
+    if x.to_f64() > 0. {
+        x + (T::ONE + (-x).exp()).ln()
+    } else {
+        (T::ONE + x.exp()).ln()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        digamma, erf, erfc, gamma_p, gamma_q, ln_beta, ln_gamma, logistic, normal_cdf, softplus,
+    };
+    use crate::Dual;
+
+    #[test]
+    fn test_ln_gamma() {
+        let pi = std::f64::consts::PI;
+        assert!(ln_gamma(1f64).abs() < 1e-15);
+        assert!(ln_gamma(2f64).abs() < 1e-15);
+        assert!((ln_gamma(0.5) - pi.sqrt().ln()).abs() < 1e-14);
+        assert!((ln_gamma(-0.5) - (2. * pi.sqrt()).ln()).abs() < 1e-14);
+        assert!((ln_gamma(101f64) - 363.73937555556347).abs() < 1e-11);
+        assert!((ln_beta(2., 3.) - (1f64 / 12.).ln()).abs() < 1e-14);
+        for x in [0f64, -1., -2., -100.] {
+            assert_eq!(ln_gamma(x), f64::INFINITY);
+        }
+    }
+
+    #[test]
+    fn test_digamma() {
+        let euler = 0.5772156649015329;
+        assert!((digamma(1f64) + euler).abs() < 1e-14);
+        assert!((digamma(0.5) + euler + 2. * 2f64.ln()).abs() < 1e-14);
+        // Recurrence psi(x + 1) = psi(x) + 1 / x, across the reflection
+        for x in [-2.5f64, 0.3, 7.] {
+            assert!((digamma(x + 1.) - digamma(x) - 1. / x).abs() < 1e-12);
+        }
+        // The derivative of digamma, the trigamma function, by nesting
+        let trigamma = digamma(Dual::<1>::variable(1., 0)).eps[0];
+        assert!((trigamma - std::f64::consts::PI.powi(2) / 6.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_erf() {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-15 * b.abs().max(1e-300) * 4.;
+        assert!(close(erf(0.5), 0.5204998778130465));
+        assert!(close(erf(1.), 0.8427007929497149));
+        assert!(close(erf(-2.), -0.9953222650189527));
+        assert!(close(erfc(3.), 2.209049699858544e-5));
+        assert!(close(erfc(5.), 1.537459794428035e-12));
+        assert!(close(erfc(-1.5), 1.9661051464753108));
+        assert!(close(normal_cdf(-1.), 0.15865525393145707));
+        // P(1, x) = 1 - exp(-x)
+        assert!(close(gamma_p(1., 0.5), 1. - (-0.5f64).exp()));
+        assert!(close(gamma_q(1., 20.), (-20f64).exp()));
+        assert!(gamma_p(-1f64, 1.).is_nan());
+
+        // d/dx erf(x) = 2 / sqrt(pi) exp(-x^2) on both sides of the branches
+        for x in [0., 0.9, 1.1, -3.] {
+            let y = erf(Dual::<1>::variable(x, 0));
+            let expected = std::f64::consts::FRAC_2_SQRT_PI * (-x * x).exp();
+            assert!((y.eps[0] - expected).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn test_logistic() {
+        for x in [-30., -0.5, 0., 2., 40.] {
+            let y = softplus(Dual::<1>::variable(x, 0));
+            assert!((y.real - (1. + f64::exp(x)).ln()).abs() < 1e-15);
+            // The derivative of softplus is the logistic function
+            assert!((y.eps[0] - logistic(x)).abs() < 1e-15);
+        }
+        assert_eq!(logistic(-1000.), 0.);
+        assert_eq!(logistic(1000.), 1.);
+        assert_eq!(softplus(1000.), 1000.);
+    }
+}
@@ -0,0 +1,350 @@
+//! # Cubic splines
+//!
+//! Interpolation of tabulated data by piecewise cubic polynomials with
+//! continuous first and second derivatives, generic over the [`Numerical`]
+//! type of the tabulated values and the query point. With dual numbers as the
+//! values, derivatives with respect to whatever the table was computed from
+//! flow through the interpolation, and with a dual query point, the
+//! derivative of the interpolant is propagated. Where the data is monotone
+//! and must not overshoot, [`Pchip`] gives a shape-preserving alternative.
+//!
+//! ```
+//! use epsilon::spline::{Boundary, Spline};
+//! use epsilon::Dual1;
+//! // Spline through y = x^2, clamped to the slopes of the parabola
+//! let xs = [0., 1., 2., 3.];
+//! let ys = xs.map(|x| Dual1::constant(x * x));
+//! let spline = Spline::new(&xs, &ys, Boundary::Clamped(Dual1::constant(0.), Dual1::constant(6.)));
+//! let y = spline.eval(Dual1::variable(1.5, 0));
+//!
+//! assert!((y.real - 2.25).abs() < 1e-12);
+//! assert!((y.eps[0] - 3.).abs() < 1e-12);
+//! ```
+
+use crate::Numerical;
+
+/// Condition at the ends of the spline
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary<T> {
+    /// Zero second derivative at both ends
+    Natural,
+    /// Given first derivatives at the first and last knot
+    Clamped(T, T),
+}
+
+/// Cubic spline through the knots `(xs[i], ys[i])`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spline<T> {
+    xs: Vec<f64>,
+    ys: Vec<T>,
+    /// Second derivative at each knot
+    second: Vec<T>,
+}
+
+impl<T: Numerical> Spline<T> {
+    /// Construct the spline with the `boundary` condition at both ends
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two knots, `xs` and `ys` have different
+    /// lengths, or `xs` is not strictly increasing.
+    pub fn new(xs: &[f64], ys: &[T], boundary: Boundary<T>) -> Self {
+        let n = xs.len();
+        assert_eq!(n, ys.len(), "xs and ys must have the same length");
+        assert!(n >= 2, "a spline needs at least two knots");
+        assert!(
+            xs.windows(2).all(|w| w[0] < w[1]),
+            "xs must be strictly increasing"
+        );
+
+        // Tridiagonal system for the second derivatives, with the
+        // coefficients (sub, diagonal, super) and the right-hand side
+        let h = |i: usize| xs[i + 1] - xs[i];
+        let slope = |i: usize| (ys[i + 1] - ys[i]) / T::constant(h(i));
+        let mut rows: Vec<(f64, f64, f64, T)> = Vec::with_capacity(n);
+        rows.push(match boundary {
+            Boundary::Natural => (0., 1., 0., T::constant(0.)),
+            Boundary::Clamped(start, _) => {
+                (0., 2. * h(0), h(0), T::constant(6.) * (slope(0) - start))
+            }
+        });
+        for i in 1..n - 1 {
+            let rhs = T::constant(6.) * (slope(i) - slope(i - 1));
+            rows.push((h(i - 1), 2. * (h(i - 1) + h(i)), h(i), rhs));
+        }
+        rows.push(match boundary {
+            Boundary::Natural => (0., 1., 0., T::constant(0.)),
+            Boundary::Clamped(_, end) => (
+                h(n - 2),
+                2. * h(n - 2),
+                0.,
+                T::constant(6.) * (end - slope(n - 2)),
+            ),
+        });
+
+        Spline {
+            xs: xs.to_vec(),
+            ys: ys.to_vec(),
+            second: thomas(&mut rows),
+        }
+    }
+
+    /// Interpolated value at `x`
+    ///
+    /// Outside the knots, the cubic of the first or last interval is
+    /// extrapolated.
+    pub fn eval(&self, x: T) -> T {
+        let i = interval(&self.xs, x.real());
+        let h = T::constant(self.xs[i + 1] - self.xs[i]);
+        let a = (T::constant(self.xs[i + 1]) - x) / h;
+        let b = (x - T::constant(self.xs[i])) / h;
+        let cubic = |t: T| t * t * t - t;
+        a * self.ys[i]
+            + b * self.ys[i + 1]
+            + (cubic(a) * self.second[i] + cubic(b) * self.second[i + 1]) * h * h / T::constant(6.)
+    }
+
+    /// First derivative of the interpolant at `x`
+    pub fn derivative(&self, x: T) -> T {
+        let i = interval(&self.xs, x.real());
+        let h = T::constant(self.xs[i + 1] - self.xs[i]);
+        let a = (T::constant(self.xs[i + 1]) - x) / h;
+        let b = (x - T::constant(self.xs[i])) / h;
+        let three = T::constant(3.);
+        let one = T::constant(1.);
+        (self.ys[i + 1] - self.ys[i]) / h
+            + ((three * b * b - one) * self.second[i + 1] - (three * a * a - one) * self.second[i])
+                * h
+                / T::constant(6.)
+    }
+
+    /// The knot positions
+    pub fn knots(&self) -> &[f64] {
+        &self.xs
+    }
+}
+
+/// Monotone piecewise cubic Hermite interpolant (PCHIP) through the knots
+/// `(xs[i], ys[i])`
+///
+/// The slopes at the knots are chosen by the method of Fritsch and Carlson,
+/// as in SciPy's `PchipInterpolator`, so that the interpolant is monotone
+/// wherever the data is, and has no overshoot at local extrema. It has a
+/// continuous first derivative, but unlike [`Spline`] not a continuous
+/// second derivative.
+///
+/// ```
+/// use epsilon::spline::Pchip;
+/// use epsilon::Dual;
+/// // A step in the data, which a cubic spline would overshoot
+/// let [level] = Dual::<1>::variables([1.]);
+/// let zero = Dual::constant(0.);
+/// let pchip = Pchip::new(&[0., 1., 2., 3.], &[zero, zero, level, level]);
+/// let y = pchip.eval(Dual::constant(2.5));
+///
+/// assert_eq!(y.real, 1.);
+/// assert_eq!(y.eps, [1.]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pchip<T> {
+    xs: Vec<f64>,
+    ys: Vec<T>,
+    /// First derivative at each knot
+    slopes: Vec<T>,
+}
+
+impl<T: Numerical> Pchip<T> {
+    /// Construct the interpolant
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two knots, `xs` and `ys` have different
+    /// lengths, or `xs` is not strictly increasing.
+    pub fn new(xs: &[f64], ys: &[T]) -> Self {
+        let n = xs.len();
+        assert_eq!(n, ys.len(), "xs and ys must have the same length");
+        assert!(n >= 2, "a spline needs at least two knots");
+        assert!(
+            xs.windows(2).all(|w| w[0] < w[1]),
+            "xs must be strictly increasing"
+        );
+
+        let zero = T::constant(0.);
+        let h: Vec<f64> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+        let delta: Vec<T> = (0..n - 1)
+            .map(|i| (ys[i + 1] - ys[i]) / T::constant(h[i]))
+            .collect();
+        let sign = |x: T| x.real().partial_cmp(&0.).map_or(0, |o| o as i8);
+
+        let mut slopes = vec![zero; n];
+        for k in 1..n - 1 {
+            // Weighted harmonic mean of the secants, or zero at extrema
+            let (s0, s1) = (sign(delta[k - 1]), sign(delta[k]));
+            if s0 != 0 && s0 == s1 {
+                let w1 = T::constant(2. * h[k] + h[k - 1]);
+                let w2 = T::constant(h[k] + 2. * h[k - 1]);
+                slopes[k] = (w1 + w2) / (w1 / delta[k - 1] + w2 / delta[k]);
+            }
+        }
+        // One-sided three-point estimates at the ends, limited to preserve
+        // monotonicity
+        let end = |h0: f64, h1: f64, d0: T, d1: T| {
+            let slope =
+                (T::constant(2. * h0 + h1) * d0 - T::constant(h0) * d1) / T::constant(h0 + h1);
+            if sign(slope) != sign(d0) {
+                zero
+            } else if sign(d0) != sign(d1) && slope.real().abs() > 3. * d0.real().abs() {
+                d0 * T::constant(3.)
+            } else {
+                slope
+            }
+        };
+        if n == 2 {
+            slopes = vec![delta[0]; 2];
+        } else {
+            slopes[0] = end(h[0], h[1], delta[0], delta[1]);
+            slopes[n - 1] = end(h[n - 2], h[n - 3], delta[n - 2], delta[n - 3]);
+        }
+
+        Pchip {
+            xs: xs.to_vec(),
+            ys: ys.to_vec(),
+            slopes,
+        }
+    }
+
+    /// Interpolated value at `x`
+    ///
+    /// Outside the knots, the cubic of the first or last interval is
+    /// extrapolated.
+    pub fn eval(&self, x: T) -> T {
+        let i = interval(&self.xs, x.real());
+        let h = T::constant(self.xs[i + 1] - self.xs[i]);
+        let t = (x - T::constant(self.xs[i])) / h;
+        let (one, two, three) = (T::constant(1.), T::constant(2.), T::constant(3.));
+        let t2 = t * t;
+        let t3 = t2 * t;
+        (two * t3 - three * t2 + one) * self.ys[i]
+            + (t3 - two * t2 + t) * h * self.slopes[i]
+            + (three * t2 - two * t3) * self.ys[i + 1]
+            + (t3 - t2) * h * self.slopes[i + 1]
+    }
+
+    /// First derivative of the interpolant at `x`
+    pub fn derivative(&self, x: T) -> T {
+        let i = interval(&self.xs, x.real());
+        let h = T::constant(self.xs[i + 1] - self.xs[i]);
+        let t = (x - T::constant(self.xs[i])) / h;
+        let (one, two, three, six) = (
+            T::constant(1.),
+            T::constant(2.),
+            T::constant(3.),
+            T::constant(6.),
+        );
+        let t2 = t * t;
+        (six * t2 - six * t) * (self.ys[i] - self.ys[i + 1]) / h
+            + (three * t2 - T::constant(4.) * t + one) * self.slopes[i]
+            + (three * t2 - two * t) * self.slopes[i + 1]
+    }
+
+    /// The knot positions
+    pub fn knots(&self) -> &[f64] {
+        &self.xs
+    }
+}
+
+/// Index of the interval of `xs` containing `x`, using the first or last
+/// interval outside the knots
+fn interval(xs: &[f64], x: f64) -> usize {
+    let i = xs.partition_point(|&k| k <= x);
+    i.clamp(1, xs.len() - 1) - 1
+}
+
+/// Solve a tridiagonal system given as rows `(sub, diagonal, super, rhs)`
+fn thomas<T: Numerical>(rows: &mut [(f64, f64, f64, T)]) -> Vec<T> {
+    for i in 1..rows.len() {
+        let (_, diagonal, sup, rhs) = rows[i - 1];
+        let m = rows[i].0 / diagonal;
+        rows[i].1 -= m * sup;
+        rows[i].3 -= T::constant(m) * rhs;
+    }
+    let mut x = vec![T::constant(0.); rows.len()];
+    for i in (0..rows.len()).rev() {
+        let (_, diagonal, sup, rhs) = rows[i];
+        let next = x.get(i + 1).copied().unwrap_or(T::constant(0.));
+        x[i] = (rhs - T::constant(sup) * next) / T::constant(diagonal);
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Boundary, Pchip, Spline};
+    use crate::{Dual, Dual1};
+
+    #[test]
+    fn test_clamped_cubic() {
+        // A clamped spline reproduces a cubic exactly
+        let f = |x: f64| x * x * x - 2. * x;
+        let xs = [-1., 0., 0.5, 2., 3.];
+        let spline = Spline::new(&xs, &xs.map(f), Boundary::Clamped(1., 25.));
+        for x in [-1., -0.3, 0.25, 1.7, 3., 3.5] {
+            assert!((spline.eval(x) - f(x)).abs() < 1e-12);
+            assert!((spline.derivative(x) - (3. * x * x - 2.)).abs() < 1e-12);
+        }
+
+        // Natural spline through two knots is a line
+        let line = Spline::new(&[1., 3.], &[2., 6.], Boundary::Natural);
+        assert_eq!(line.eval(2.5), 5.);
+    }
+
+    #[test]
+    fn test_dual_knots() {
+        // Table of y = exp(k x), with the sensitivities to k in the values
+        let xs = [0., 0.5, 1., 1.5, 2.];
+        let [k] = Dual::<1>::variables([0.3]);
+        let ys = xs.map(|x| (k * x).exp());
+        let spline = Spline::new(&xs, &ys, Boundary::Natural);
+        let y = spline.eval(Dual::constant(1.2));
+        assert!((y.real - 0.36f64.exp()).abs() < 1e-3);
+        assert!((y.eps[0] - 1.2 * 0.36f64.exp()).abs() < 1e-2);
+
+        // The sensitivity is the spline through the sensitivities of the table
+        let dk = Spline::new(&xs, &ys.map(|y| y.eps[0]), Boundary::Natural);
+        assert!((y.eps[0] - dk.eval(1.2)).abs() < 1e-15);
+
+        // Derivative with respect to the query point
+        let real = Spline::new(&xs, &ys.map(|y| Dual1::constant(y.real)), Boundary::Natural);
+        let y = real.eval(Dual1::variable(1.2, 0));
+        assert!((y.eps[0] - real.derivative(Dual::constant(1.2)).real).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_pchip() {
+        // Monotone data gives a monotone interpolant
+        let xs = [0., 1., 2., 4., 5.];
+        let ys = [0., 0.1, 3., 3.2, 10.];
+        let pchip = Pchip::new(&xs, &ys);
+        let samples: Vec<f64> = (0..=100).map(|i| pchip.eval(i as f64 * 0.05)).collect();
+        assert!(samples.windows(2).all(|w| w[0] <= w[1]));
+        for (x, y) in xs.iter().zip(ys) {
+            assert!((pchip.eval(*x) - y).abs() < 1e-14);
+        }
+        // Zero slope at a local extremum
+        let peak = Pchip::new(&[0., 1., 2.], &[0., 1., 0.]);
+        assert_eq!(peak.derivative(1.), 0.);
+
+        // Derivatives with respect to the data, against central differences
+        let [a] = Dual::<1>::variables([2.]);
+        let c = Dual1::constant;
+        let y = Pchip::new(&xs, &[c(0.), c(1.), a, c(4.), c(5.)]).eval(c(1.5));
+        let at = |a: f64| Pchip::new(&xs, &[0., 1., a, 4., 5.]).eval(1.5);
+        assert!((y.real - at(2.)).abs() < 1e-15);
+        assert!((y.eps[0] - (at(2. + 1e-6) - at(2. - 1e-6)) / 2e-6).abs() < 1e-8);
+
+        // and with respect to the query point
+        let pchip = Pchip::new(&xs, &[0., 1., 2., 4., 5.].map(c));
+        let y = pchip.eval(Dual1::variable(1.5, 0));
+        assert!((y.eps[0] - pchip.derivative(c(1.5)).real).abs() < 1e-14);
+    }
+}
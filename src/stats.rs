@@ -0,0 +1,153 @@
+//! # Streaming statistics
+//!
+//! [`Welford`] accumulates the mean and variance of a stream of values one at
+//! a time, without storing them, generic over the [`Numerical`] type. With
+//! dual numbers as the values, the derivatives of the mean and variance are
+//! accumulated along with them.
+//!
+//! ```
+//! use epsilon::stats::Welford;
+//! use epsilon::Dual;
+//! // Residuals a x - y of a model with parameter a, streamed from a source
+//! let [a] = Dual::<1>::variables([2.]);
+//! let mut stats = Welford::new();
+//! for (x, y) in [(1., 2.5), (2., 3.5), (3., 7.5)] {
+//!     stats.push(a * x - y);
+//! }
+//!
+//! assert_eq!(stats.mean().real, -0.5);
+//! // d/da of the mean residual is the mean of x
+//! assert_eq!(stats.mean().eps, [2.]);
+//! ```
+
+use crate::Numerical;
+
+/// Online accumulator of the mean and variance, using Welford's algorithm
+///
+/// The updates are numerically stable, unlike accumulating the sums of the
+/// values and their squares, which loses precision when the mean is large
+/// compared to the standard deviation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Welford<T> {
+    count: u64,
+    mean: T,
+    /// Sum of squared deviations from the mean
+    m2: T,
+}
+
+impl<T: Numerical> Default for Welford<T> {
+    fn default() -> Self {
+        Welford::new()
+    }
+}
+
+impl<T: Numerical> Welford<T> {
+    pub fn new() -> Self {
+        Welford {
+            count: 0,
+            mean: T::constant(0.),
+            m2: T::constant(0.),
+        }
+    }
+
+    /// Add the value `x`
+    pub fn push(&mut self, x: T) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / T::constant(self.count as f64);
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Combine with the statistics of another stream, as if all its values
+    /// were pushed, e.g. to accumulate chunks in parallel
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = T::constant(other.count as f64 / count as f64);
+        self.mean += delta * weight;
+        self.m2 += other.m2 + delta * delta * T::constant(self.count as f64) * weight;
+        self.count = count;
+    }
+
+    /// Number of values pushed
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean of the values, zero if there are none
+    pub fn mean(&self) -> T {
+        self.mean
+    }
+
+    /// Sample variance, with Bessel's correction, `NaN` for fewer than two
+    /// values
+    pub fn variance(&self) -> T {
+        if self.count < 2 {
+            return T::constant(f64::NAN);
+        }
+        self.m2 / T::constant(self.count as f64 - 1.)
+    }
+
+    /// Population variance, `NaN` if there are no values
+    pub fn population_variance(&self) -> T {
+        self.m2 / T::constant(self.count as f64)
+    }
+
+    /// Sample standard deviation
+    pub fn std_dev(&self) -> T {
+        self.variance().powf(0.5)
+    }
+}
+
+impl<T: Numerical> Extend<T> for Welford<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for x in values {
+            self.push(x);
+        }
+    }
+}
+
+impl<T: Numerical> FromIterator<T> for Welford<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut stats = Welford::new();
+        stats.extend(values);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Welford;
+    use crate::Dual;
+
+    #[test]
+    fn test_welford() {
+        // Large offset, where the naive sum of squares loses all precision
+        let values = [4., 7., 13., 16.].map(|x: f64| x + 1e9);
+        let stats: Welford<f64> = values.into_iter().collect();
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.mean(), 1e9 + 10.);
+        assert_eq!(stats.variance(), 30.);
+        assert_eq!(stats.population_variance(), 22.5);
+        assert!(Welford::<f64>::new().variance().is_nan());
+
+        let mut first: Welford<f64> = values[..1].iter().copied().collect();
+        first.merge(&values[1..].iter().copied().collect());
+        first.merge(&Welford::new());
+        assert_eq!(first, stats);
+    }
+
+    #[test]
+    fn test_gradients() {
+        // Values s x_i, with d/ds var = 2 s var(x) and d/ds std = std(x)
+        let [s] = Dual::<1>::variables([3.]);
+        let stats: Welford<_> = [1., 2., 4.].iter().map(|&x| s * x).collect();
+        let var_x = 7. / 3.;
+        assert!((stats.variance().real - 9. * var_x).abs() < 1e-13);
+        assert!((stats.variance().eps[0] - 6. * var_x).abs() < 1e-13);
+        assert!((stats.std_dev().eps[0] - var_x.sqrt()).abs() < 1e-14);
+    }
+}
@@ -0,0 +1,94 @@
+//! # Surrogate derivatives
+//!
+//! The derivative of a step function is zero almost everywhere, so gradients
+//! through thresholds vanish. [`step`] keeps the exact step as its value, but
+//! takes its derivatives from a smooth [`Surrogate`] of chosen width, the
+//! surrogate gradient pattern, making the choice explicit.
+//!
+//! ```
+//! use epsilon::surrogate::{step, Surrogate};
+//! use epsilon::Dual;
+//! let x = Dual::<1>::variable(0.1, 0);
+//!
+//! let exact = step(x, Surrogate::Zero);
+//! let smooth = step(x, Surrogate::Sigmoid { width: 1. });
+//! assert_eq!((exact.real, exact.eps[0]), (1., 0.));
+//! assert_eq!(smooth.real, 1.);
+//! assert!(smooth.eps[0] > 0.2);
+//! ```
+
+use crate::special::logistic;
+use crate::Scalar;
+
+/// Function whose derivatives stand in for those of the step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Surrogate {
+    /// The exact derivative, zero everywhere
+    Zero,
+    /// The logistic function `1 / (1 + exp(-x / width))`, with the
+    /// derivative peaking at `1 / (4 width)`
+    Sigmoid { width: f64 },
+    /// The piecewise quadratic function with the triangular derivative
+    /// `max(0, 1 - |x| / width) / width`
+    Triangular { width: f64 },
+}
+
+impl Surrogate {
+    /// The smooth function itself, going from `0` to `1` around `x = 0`
+    pub fn eval<T: Scalar>(self, x: T) -> T {
+        let half = T::from_f64(0.5);
+        match self {
+            Surrogate::Zero => T::from_f64(if x.to_f64() >= 0. { 1. } else { 0. }),
+            Surrogate::Sigmoid { width } => logistic(x / T::from_f64(width)),
+            Surrogate::Triangular { width } => {
+                let z = x / T::from_f64(width);
+                let z0 = z.to_f64();
+                if z0 <= -1. {
+                    T::ZERO
+                } else if z0 >= 1. {
+                    T::ONE
+                } else if z0 < 0. {
+                    half * (T::ONE + z) * (T::ONE + z)
+                } else {
+                    T::ONE - half * (T::ONE - z) * (T::ONE - z)
+                }
+            }
+        }
+    }
+}
+
+/// Heaviside step function, `1` for `x >= 0` and `0` otherwise, with the
+/// derivatives of `surrogate`
+pub fn step<T: Scalar>(x: T, surrogate: Surrogate) -> T {
+    let s = surrogate.eval(x);
+    let value = if x.to_f64() >= 0. { 1. } else { 0. };
+    // Replace the value of the surrogate, keeping its derivatives
+    s - T::from_f64(s.to_f64()) + T::from_f64(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{step, Surrogate};
+    use crate::Dual;
+
+    #[test]
+    fn test_step() {
+        let d = |x: f64, surrogate| {
+            let y = step(Dual::<1>::variable(x, 0), surrogate);
+            assert_eq!(y.real, if x >= 0. { 1. } else { 0. });
+            y.eps[0]
+        };
+        assert_eq!(d(0., Surrogate::Zero), 0.);
+        assert_eq!(d(0., Surrogate::Sigmoid { width: 0.5 }), 0.5);
+        let s = 1. / (1. + 1f64.exp());
+        assert!((d(-1., Surrogate::Sigmoid { width: 1. }) - s * (1. - s)).abs() < 1e-15);
+        assert_eq!(d(0., Surrogate::Triangular { width: 2. }), 0.5);
+        assert_eq!(d(-1., Surrogate::Triangular { width: 2. }), 0.25);
+        assert_eq!(d(0.5, Surrogate::Triangular { width: 2. }), 0.375);
+        assert_eq!(d(3., Surrogate::Triangular { width: 2. }), 0.);
+
+        // The triangular surrogate is continuous
+        let t = Surrogate::Triangular { width: 1. };
+        assert_eq!((t.eval(-1.), t.eval(0.), t.eval(1.)), (0., 0.5, 1.));
+    }
+}
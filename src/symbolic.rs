@@ -0,0 +1,564 @@
+//! # Expression recording
+//!
+//! [`Symbolic`] wraps any [`Numerical`] type, evaluating it as usual while
+//! recording the operations on a [`Recorder`]. The recorded formula can then
+//! be retrieved as an [`Expr`] and printed as text, LaTeX or a Graphviz graph,
+//! which shows exactly what a derivative was computed from.
+//!
+//! ```
+//! use epsilon::symbolic::Recorder;
+//! use epsilon::Dual;
+//! // z = x^2+y*sin(y) at x=5, y=7, recorded while computing its gradient
+//! let recorder = Recorder::new();
+//! let [x, y] = Dual::<2>::variables([5., 7.]);
+//! let (x, y) = (recorder.var("x", x), recorder.var("y", y));
+//!
+//! let z = x.powf(2.) + y * y.sin();
+//!
+//! assert_eq!(z.value().eps, [10., 5.934302379121921]);
+//! assert_eq!(z.expr().to_string(), "x^2 + y * sin(y)");
+//! assert_eq!(z.expr().to_latex(), r"x^{2} + y \cdot \sin\left(y\right)");
+//! ```
+
+use crate::Numerical;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Record of the operations performed on [`Symbolic`] values
+#[derive(Debug, Default)]
+pub struct Recorder {
+    nodes: RefCell<Vec<Node>>,
+}
+
+/// Recorded operation, referring to its operands by index
+#[derive(Clone, Debug)]
+enum Node {
+    Var(String),
+    Const(f64),
+    Neg(usize),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Div(usize, usize),
+    Powf(usize, f64),
+    Sin(usize),
+    Cos(usize),
+    Tan(usize),
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder::default()
+    }
+
+    /// Create a named input with the specified value
+    pub fn var<T: Numerical>(&self, name: &str, value: T) -> Symbolic<'_, T> {
+        self.push(value, Node::Var(name.to_owned()))
+    }
+
+    /// Number of recorded nodes
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    /// Whether no nodes have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push<T: Numerical>(&self, value: T, node: Node) -> Symbolic<'_, T> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(node);
+        Symbolic {
+            value,
+            node: Some((self, nodes.len() - 1)),
+        }
+    }
+
+    /// Index of the node of `value`, recording it as a constant if needed
+    fn index<T: Numerical>(&self, value: Symbolic<T>) -> usize {
+        match value.node {
+            Some((recorder, index)) => {
+                assert!(
+                    std::ptr::eq(recorder, self),
+                    "values are from different recorders"
+                );
+                index
+            }
+            None => self
+                .push(value.value, Node::Const(value.value.real()))
+                .index(),
+        }
+    }
+
+    /// The expression of the node at `root`, with the operations used more
+    /// than once bound by [`Expr::Let`]
+    fn expr(&self, root: usize) -> Expr {
+        let nodes = self.nodes.borrow();
+        // Operands are recorded before the operations using them, so a
+        // backward pass sees all uses of a node before the node itself
+        let mut uses = vec![0; root + 1];
+        uses[root] = 1;
+        for i in (0..=root).rev() {
+            if uses[i] > 0 {
+                for a in nodes[i].operands() {
+                    uses[a] += 1;
+                }
+            }
+        }
+
+        let mut exprs: Vec<Option<Expr>> = vec![None; root + 1];
+        let mut bindings = vec![None; root + 1];
+        let mut lets = Vec::new();
+        for i in (0..=root).filter(|&i| uses[i] > 0) {
+            let mut sub = |a: usize| {
+                Box::new(match (bindings[a], &nodes[a]) {
+                    (Some(t), _) => Expr::Ref(t),
+                    (None, Node::Var(_) | Node::Const(_)) => exprs[a].clone().expect("operand"),
+                    (None, _) => exprs[a].take().expect("operand"),
+                })
+            };
+            let expr = match nodes[i] {
+                Node::Var(ref name) => Expr::Var(name.clone()),
+                Node::Const(c) => Expr::Const(c),
+                Node::Neg(a) => Expr::Neg(sub(a)),
+                Node::Add(a, b) => Expr::Add(sub(a), sub(b)),
+                Node::Sub(a, b) => Expr::Sub(sub(a), sub(b)),
+                Node::Mul(a, b) => Expr::Mul(sub(a), sub(b)),
+                Node::Div(a, b) => Expr::Div(sub(a), sub(b)),
+                Node::Powf(a, p) => Expr::Powf(sub(a), p),
+                Node::Sin(a) => Expr::Sin(sub(a)),
+                Node::Cos(a) => Expr::Cos(sub(a)),
+                Node::Tan(a) => Expr::Tan(sub(a)),
+            };
+            if uses[i] > 1 && !nodes[i].operands().is_empty() {
+                bindings[i] = Some(lets.len());
+                lets.push(expr);
+            } else {
+                exprs[i] = Some(expr);
+            }
+        }
+        let body = exprs[root].take().expect("root");
+        (lets.into_iter().enumerate().rev()).fold(body, |body, (t, value)| {
+            Expr::Let(t, Box::new(value), Box::new(body))
+        })
+    }
+}
+
+impl Node {
+    fn operands(&self) -> Vec<usize> {
+        match *self {
+            Node::Var(_) | Node::Const(_) => vec![],
+            Node::Neg(a) | Node::Powf(a, _) | Node::Sin(a) | Node::Cos(a) | Node::Tan(a) => vec![a],
+            Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b) => vec![a, b],
+        }
+    }
+}
+
+/// Value of type `T` whose operations are recorded on a [`Recorder`]
+///
+/// Constants, created by [`Symbolic::constant`] or by mixing with `f64`, are
+/// only recorded once they are combined with a recorded value.
+#[derive(Copy, Clone)]
+pub struct Symbolic<'r, T> {
+    value: T,
+    node: Option<(&'r Recorder, usize)>,
+}
+
+impl<'r, T: Numerical> Symbolic<'r, T> {
+    /// Create a constant with the specified value
+    pub fn constant(value: T) -> Self {
+        Symbolic { value, node: None }
+    }
+
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    /// The expression `self` was computed by
+    pub fn expr(&self) -> Expr {
+        match self.node {
+            Some((recorder, index)) => recorder.expr(index),
+            None => Expr::Const(self.value.real()),
+        }
+    }
+
+    fn index(&self) -> usize {
+        self.node.expect("recorded value").1
+    }
+
+    /// Record a unary operation with result `value`
+    fn unary(self, value: T, node: fn(usize) -> Node) -> Self {
+        match self.node {
+            Some((recorder, index)) => recorder.push(value, node(index)),
+            None => Symbolic::constant(value),
+        }
+    }
+
+    /// Record a binary operation with result `value`
+    fn binary(self, other: Self, value: T, node: fn(usize, usize) -> Node) -> Self {
+        match self.node.or(other.node) {
+            Some((recorder, _)) => {
+                let (a, b) = (recorder.index(self), recorder.index(other));
+                recorder.push(value, node(a, b))
+            }
+            None => Symbolic::constant(value),
+        }
+    }
+
+    /// Raise `self` to `pow`
+    pub fn powf(self, pow: f64) -> Self {
+        match self.node {
+            Some((recorder, index)) => recorder.push(self.value.powf(pow), Node::Powf(index, pow)),
+            None => Symbolic::constant(self.value.powf(pow)),
+        }
+    }
+
+    /// Invert `self` (`1./self`)
+    pub fn invert(self) -> Self {
+        Symbolic::constant(T::constant(1.)) / self
+    }
+
+    pub fn sin(self) -> Self {
+        self.unary(self.value.sin(), Node::Sin)
+    }
+
+    pub fn cos(self) -> Self {
+        self.unary(self.value.cos(), Node::Cos)
+    }
+
+    pub fn tan(self) -> Self {
+        self.unary(self.value.tan(), Node::Tan)
+    }
+}
+
+impl<T: Numerical> std::fmt::Debug for Symbolic<'_, T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Symbolic")
+            .field("value", &self.value)
+            .field("index", &self.node.map(|(_, index)| index))
+            .finish()
+    }
+}
+
+impl<T: Numerical> std::fmt::Display for Symbolic<'_, T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.value)
+    }
+}
+
+/// Expression tree recorded by [`Symbolic`]
+///
+/// Operations whose result is used more than once are bound once by
+/// [`Expr::Let`] and referred to by [`Expr::Ref`], so that the size of the
+/// expression is linear in the number of recorded operations. Variables and
+/// constants are repeated at each use.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Var(String),
+    Const(f64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Powf(Box<Expr>, f64),
+    Sin(Box<Expr>),
+    Cos(Box<Expr>),
+    Tan(Box<Expr>),
+    /// The value bound by the [`Expr::Let`] with the same index, written
+    /// `t{index}`
+    Ref(usize),
+    /// The binding `let t{index} = value; body`
+    Let(usize, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Binding strength, operands binding weaker than required are
+    /// parenthesized
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Let(..) => 0,
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) | Expr::Div(..) => 2,
+            Expr::Neg(_) => 3,
+            Expr::Const(c) if *c < 0. => 3,
+            Expr::Powf(..) => 4,
+            _ => 5,
+        }
+    }
+
+    /// Format as LaTeX math
+    pub fn to_latex(&self) -> String {
+        let paren = |e: &Expr, min: u8| {
+            if e.precedence() < min {
+                format!(r"\left({}\right)", e.to_latex())
+            } else {
+                e.to_latex()
+            }
+        };
+        match self {
+            Expr::Var(name) => name.clone(),
+            Expr::Const(c) => c.to_string(),
+            Expr::Neg(a) => format!("-{}", paren(a, 4)),
+            Expr::Add(a, b) => format!("{} + {}", paren(a, 1), paren(b, 1)),
+            Expr::Sub(a, b) => format!("{} - {}", paren(a, 1), paren(b, 2)),
+            Expr::Mul(a, b) => format!(r"{} \cdot {}", paren(a, 2), paren(b, 2)),
+            Expr::Div(a, b) => format!(r"\frac{{{}}}{{{}}}", a.to_latex(), b.to_latex()),
+            Expr::Powf(a, p) => format!("{}^{{{}}}", paren(a, 5), p),
+            Expr::Sin(a) => format!(r"\sin\left({}\right)", a.to_latex()),
+            Expr::Cos(a) => format!(r"\cos\left({}\right)", a.to_latex()),
+            Expr::Tan(a) => format!(r"\tan\left({}\right)", a.to_latex()),
+            Expr::Ref(t) => format!("t_{{{t}}}"),
+            Expr::Let(t, value, body) => {
+                format!(
+                    r"t_{{{t}}} = {}, \quad {}",
+                    value.to_latex(),
+                    body.to_latex()
+                )
+            }
+        }
+    }
+
+    /// Format as a Graphviz `digraph`, with edges from each operation to its
+    /// operands
+    ///
+    /// Each variable and each value bound by [`Expr::Let`] is a single node,
+    /// with an edge from every operation using it.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        let mut next = 0;
+        let mut vars: HashMap<&str, usize> = HashMap::new();
+        let mut bound: HashMap<usize, usize> = HashMap::new();
+        // Expressions to visit, with the node using them and the binding
+        // they are the value of
+        let mut stack: Vec<(&Expr, Option<usize>, Option<usize>)> = vec![(self, None, None)];
+        while let Some((expr, parent, binding)) = stack.pop() {
+            let mut new = |label: String, out: &mut String| {
+                out.push_str(&format!("    n{next} [label={label:?}];\n"));
+                next += 1;
+                next - 1
+            };
+            let (id, operands): (usize, Vec<&Expr>) = match expr {
+                Expr::Let(t, value, body) => {
+                    // The value is visited before the body referring to it
+                    stack.push((body, parent, binding));
+                    stack.push((value, None, Some(*t)));
+                    continue;
+                }
+                Expr::Ref(t) => (bound[t], vec![]),
+                Expr::Var(name) => match vars.get(name.as_str()) {
+                    Some(&id) => (id, vec![]),
+                    None => {
+                        let id = new(name.clone(), &mut out);
+                        vars.insert(name, id);
+                        (id, vec![])
+                    }
+                },
+                Expr::Const(c) => (new(c.to_string(), &mut out), vec![]),
+                Expr::Neg(a) => (new("-".into(), &mut out), vec![a]),
+                Expr::Add(a, b) => (new("+".into(), &mut out), vec![a, b]),
+                Expr::Sub(a, b) => (new("-".into(), &mut out), vec![a, b]),
+                Expr::Mul(a, b) => (new("*".into(), &mut out), vec![a, b]),
+                Expr::Div(a, b) => (new("/".into(), &mut out), vec![a, b]),
+                Expr::Powf(a, p) => (new(format!("^{p}"), &mut out), vec![a]),
+                Expr::Sin(a) => (new("sin".into(), &mut out), vec![a]),
+                Expr::Cos(a) => (new("cos".into(), &mut out), vec![a]),
+                Expr::Tan(a) => (new("tan".into(), &mut out), vec![a]),
+            };
+            if let Some(parent) = parent {
+                out.push_str(&format!("    n{parent} -> n{id};\n"));
+            }
+            if let Some(t) = binding {
+                bound.insert(t, id);
+            }
+            stack.extend(operands.into_iter().rev().map(|a| (a, Some(id), None)));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let paren = |e: &Expr, min: u8| {
+            if e.precedence() < min {
+                format!("({e})")
+            } else {
+                e.to_string()
+            }
+        };
+        match self {
+            Expr::Var(name) => write!(fmt, "{name}"),
+            Expr::Const(c) => write!(fmt, "{c}"),
+            Expr::Neg(a) => write!(fmt, "-{}", paren(a, 4)),
+            Expr::Add(a, b) => write!(fmt, "{} + {}", paren(a, 1), paren(b, 1)),
+            Expr::Sub(a, b) => write!(fmt, "{} - {}", paren(a, 1), paren(b, 2)),
+            Expr::Mul(a, b) => write!(fmt, "{} * {}", paren(a, 2), paren(b, 2)),
+            Expr::Div(a, b) => write!(fmt, "{} / {}", paren(a, 2), paren(b, 3)),
+            Expr::Powf(a, p) => write!(fmt, "{}^{}", paren(a, 5), p),
+            Expr::Sin(a) => write!(fmt, "sin({a})"),
+            Expr::Cos(a) => write!(fmt, "cos({a})"),
+            Expr::Tan(a) => write!(fmt, "tan({a})"),
+            Expr::Ref(t) => write!(fmt, "t{t}"),
+            Expr::Let(t, value, body) => write!(fmt, "let t{t} = {value}; {body}"),
+        }
+    }
+}
+
+macro_rules! impl_ops {
+    ($op:ident, $fn:ident, $node:ident) => {
+        impl<T: Numerical> std::ops::$op for Symbolic<'_, T> {
+            type Output = Self;
+
+            fn $fn(self, other: Self) -> Self::Output {
+                let value = std::ops::$op::$fn(self.value, other.value);
+                self.binary(other, value, Node::$node)
+            }
+        }
+
+        impl<T: Numerical> std::ops::$op<f64> for Symbolic<'_, T> {
+            type Output = Self;
+
+            fn $fn(self, other: f64) -> Self::Output {
+                std::ops::$op::$fn(self, Symbolic::constant(T::constant(other)))
+            }
+        }
+
+        impl<'r, T: Numerical> std::ops::$op<Symbolic<'r, T>> for f64 {
+            type Output = Symbolic<'r, T>;
+
+            fn $fn(self, other: Symbolic<'r, T>) -> Self::Output {
+                std::ops::$op::$fn(Symbolic::constant(T::constant(self)), other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_inplace {
+    ($op_inplace:ident, $fn_inplace:ident, $op_outofplace:ident, $fn_outofplace:ident) => {
+        impl<T: Numerical> std::ops::$op_inplace<f64> for Symbolic<'_, T> {
+            fn $fn_inplace(&mut self, other: f64) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+        impl<T: Numerical> std::ops::$op_inplace<Self> for Symbolic<'_, T> {
+            fn $fn_inplace(&mut self, other: Self) {
+                *self = std::ops::$op_outofplace::$fn_outofplace(*self, other);
+            }
+        }
+    };
+}
+
+impl_ops! {Add, add, Add}
+impl_ops! {Sub, sub, Sub}
+impl_ops! {Mul, mul, Mul}
+impl_ops! {Div, div, Div}
+impl_inplace! {AddAssign, add_assign, Add, add}
+impl_inplace! {SubAssign, sub_assign, Sub, sub}
+impl_inplace! {MulAssign, mul_assign, Mul, mul}
+impl_inplace! {DivAssign, div_assign, Div, div}
+
+impl<T: Numerical> std::ops::Neg for Symbolic<'_, T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        let value = T::constant(0.) - self.value;
+        self.unary(value, Node::Neg)
+    }
+}
+
+impl<T: Numerical> Numerical for Symbolic<'_, T> {
+    fn constant(real: f64) -> Self {
+        Symbolic::constant(T::constant(real))
+    }
+
+    fn real(&self) -> f64 {
+        self.value.real()
+    }
+
+    fn powf(self, pow: f64) -> Self {
+        Symbolic::powf(self, pow)
+    }
+
+    fn invert(self) -> Self {
+        Symbolic::invert(self)
+    }
+
+    fn sin(self) -> Self {
+        Symbolic::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        Symbolic::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        Symbolic::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Expr, Recorder};
+
+    #[test]
+    fn test_format() {
+        let recorder = Recorder::new();
+        let (x, y) = (recorder.var("x", 2.), recorder.var("y", 3.));
+        let z = (x + 1.) / (y * y).powf(0.5) - -x.cos() * 2.;
+        assert_eq!(z.value(), 1. + 2. * 2f64.cos());
+        assert_eq!(z.expr().to_string(), "(x + 1) / (y * y)^0.5 - -cos(x) * 2");
+        assert_eq!(
+            z.expr().to_latex(),
+            r"\frac{x + 1}{\left(y \cdot y\right)^{0.5}} - -\cos\left(x\right) \cdot 2"
+        );
+        assert_eq!((x - (y - 1.)).expr().to_string(), "x - (y - 1)");
+        assert_eq!(x.invert().expr().to_string(), "1 / x");
+        // Constants only enter the recording when combined with a variable
+        assert_eq!(recorder.len(), 17);
+    }
+
+    #[test]
+    fn test_dot() {
+        let recorder = Recorder::new();
+        let x = recorder.var("x", 1.);
+        let y = x * x.sin();
+        assert_eq!(
+            y.expr(),
+            Expr::Mul(
+                Box::new(Expr::Var("x".into())),
+                Box::new(Expr::Sin(Box::new(Expr::Var("x".into()))))
+            )
+        );
+        assert_eq!(
+            y.expr().to_dot(),
+            "digraph {\n    n0 [label=\"*\"];\n    n1 [label=\"x\"];\n    n0 -> n1;\n    \
+             n2 [label=\"sin\"];\n    n0 -> n2;\n    n2 -> n1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_shared() {
+        let recorder = Recorder::new();
+        let x = recorder.var("x", 0.5);
+        let s = x.sin();
+        let y = s * s + x;
+        assert_eq!(y.expr().to_string(), "let t0 = sin(x); t0 * t0 + x");
+        assert_eq!(
+            y.expr().to_latex(),
+            r"t_{0} = \sin\left(x\right), \quad t_{0} \cdot t_{0} + x"
+        );
+
+        // Each doubling is written once rather than 2^100 times
+        let mut y = x;
+        for _ in 0..100 {
+            y = y + y;
+        }
+        let expr = y.expr();
+        assert_eq!(expr.to_string().matches("let").count(), 99);
+        assert!(expr.to_string().ends_with("let t98 = t97 + t97; t98 + t98"));
+        // A node for x and each addition, with two edges per addition
+        assert_eq!(expr.to_dot().lines().count(), 2 + 101 + 200);
+    }
+}
@@ -0,0 +1,79 @@
+//! # Tensor conversion
+//!
+//! With the `candle` feature, batches of dual numbers can be loaded into
+//! [`candle_core`] tensors and back. A batch of `n` duals with `k` components
+//! is stored as a tensor of shape `(1 + k, n)`, where the first row (plane)
+//! holds the real values and row `1 + i` holds the `i`th dual component.
+//!
+//! ```
+//! use epsilon::{make_dual, tensor};
+//! make_dual! { TDual, a, b }
+//!
+//! let batch = [TDual::a(1.), TDual::b(2.) * 3.];
+//! let t = tensor::to_tensor(&batch, &candle_core::Device::Cpu).unwrap();
+//! assert_eq!(t.to_vec2::<f64>().unwrap(), [[1., 6.], [1., 0.], [0., 3.]]);
+//!
+//! let back: Vec<TDual> = tensor::from_tensor(&t).unwrap();
+//! assert_eq!(back, batch);
+//! ```
+
+use crate::DualNumber;
+use candle_core::{DType, Device, Result, Tensor};
+
+/// Load a batch of duals into a tensor of shape `(1 + D::COMPONENTS, n)`
+pub fn to_tensor<D: DualNumber>(duals: &[D], device: &Device) -> Result<Tensor> {
+    let n = duals.len();
+    let mut data = Vec::with_capacity((1 + D::COMPONENTS) * n);
+    data.extend(duals.iter().map(|d| d.real()));
+    for i in 0..D::COMPONENTS {
+        data.extend(duals.iter().map(|d| d.eps(i)));
+    }
+    Tensor::from_vec(data, (1 + D::COMPONENTS, n), device)
+}
+
+/// Read a batch of duals from a tensor of shape `(1 + D::COMPONENTS, n)`
+/// The tensor may have any floating point dtype, and is converted to `f64`
+pub fn from_tensor<D: DualNumber>(tensor: &Tensor) -> Result<Vec<D>> {
+    let (planes, n) = tensor.dims2()?;
+    if planes != 1 + D::COMPONENTS {
+        candle_core::bail!(
+            "expected {} planes (value + one per component), got {planes}",
+            1 + D::COMPONENTS
+        );
+    }
+    let data = tensor
+        .to_dtype(DType::F64)?
+        .flatten_all()?
+        .to_vec1::<f64>()?;
+    let mut eps = vec![0.; D::COMPONENTS];
+    Ok((0..n)
+        .map(|j| {
+            for (i, e) in eps.iter_mut().enumerate() {
+                *e = data[(1 + i) * n + j];
+            }
+            D::from_parts(data[j], &eps)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample::SampleXYZ;
+    use candle_core::{DType, Device, Tensor};
+
+    #[test]
+    fn test_roundtrip() {
+        let batch = [SampleXYZ::x(1.), SampleXYZ::eps_z(-2., 0.5)];
+        let t = super::to_tensor(&batch, &Device::Cpu).unwrap();
+        assert_eq!(t.dims(), [4, 2]);
+
+        let t32 = t.to_dtype(DType::F32).unwrap();
+        assert_eq!(super::from_tensor::<SampleXYZ>(&t32).unwrap(), batch);
+    }
+
+    #[test]
+    fn test_wrong_shape() {
+        let t = Tensor::zeros((3, 2), DType::F64, &Device::Cpu).unwrap();
+        assert!(super::from_tensor::<SampleXYZ>(&t).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! # Text format
+//!
+//! A canonical text format for dual numbers, `real; eps_a=.., eps_b=..`, with
+//! every dual component written in declaration order. Numbers are written in
+//! the shortest form which parses back to the same `f64`, including `NaN`,
+//! `inf` and `-inf`, so writing and parsing round-trips exactly for `f32`
+//! and `f64` inner types, and dumps from different runs can be diffed line by
+//! line.
+//!
+//! For reading rather than parsing, the dual types also implement
+//! [`LowerExp`](std::fmt::LowerExp) and [`UpperExp`](std::fmt::UpperExp),
+//! formatting like `Display` but in scientific notation, e.g.
+//! `format!("{:.3e}", v)`.
+//!
+//! ```
+//! use epsilon::make_dual;
+//! use epsilon::text::Text;
+//! make_dual! { Pos, x, y }
+//!
+//! let v = Pos::x(0.1) * 3. + Pos::y(f64::INFINITY);
+//! assert_eq!(v.to_text(), "inf; eps_x=3.0, eps_y=1.0");
+//! assert_eq!(Pos::from_text("0.30000000000000004; eps_y=-2, eps_x=3"), Ok(Pos {
+//!     real: 0.30000000000000004,
+//!     eps_x: 3.,
+//!     eps_y: -2.,
+//! }));
+//! ```
+
+use crate::check::Components;
+use crate::{Dual, DualNumber, Scalar};
+
+/// Error from parsing the text format
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Text which is not a number
+    Number(String),
+    /// A component without the form `name=value`
+    Syntax(String),
+    /// A component name which the type does not have
+    Unknown(String),
+    /// A component given more than once
+    Duplicate(String),
+    /// A component which is not given
+    Missing(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Number(text) => write!(fmt, "invalid number `{text}`"),
+            Error::Syntax(text) => write!(fmt, "expected `name=value`, found `{text}`"),
+            Error::Unknown(name) => write!(fmt, "unknown component `{name}`"),
+            Error::Duplicate(name) => write!(fmt, "component `{name}` given more than once"),
+            Error::Missing(name) => write!(fmt, "missing component `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Writing and parsing the text format, implemented for [`Dual`] and the
+/// types generated by [`make_dual`](crate::make_dual)
+///
+/// When parsing, the components may be given in any order, but each exactly
+/// once.
+pub trait Text: Components + Sized {
+    /// Create an instance from the real part and the dual components, in the
+    /// order of [`Components::component`]
+    fn from_f64_parts(real: f64, eps: &[f64]) -> Self;
+
+    fn to_text(&self) -> String {
+        let components: Vec<String> = (0..self.components())
+            .map(|i| format!("{}={:?}", self.field(i), self.component(i)))
+            .collect();
+        format!("{:?}; {}", self.value(), components.join(", "))
+    }
+
+    fn from_text(text: &str) -> Result<Self, Error> {
+        let number = |text: &str| {
+            let text = text.trim();
+            text.parse::<f64>()
+                .map_err(|_| Error::Number(text.to_string()))
+        };
+        let (real, rest) = text.split_once(';').unwrap_or((text, ""));
+        let real = number(real)?;
+
+        let zero = zero::<Self>();
+        let names: Vec<String> = (0..zero.components()).map(|i| zero.field(i)).collect();
+        let mut eps = vec![None; names.len()];
+        for part in rest.split(',').filter(|part| !part.trim().is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| Error::Syntax(part.trim().to_string()))?;
+            let name = name.trim();
+            let index = (names.iter().position(|n| n == name))
+                .ok_or_else(|| Error::Unknown(name.to_string()))?;
+            if eps[index].replace(number(value)?).is_some() {
+                return Err(Error::Duplicate(name.to_string()));
+            }
+        }
+        let eps = (eps.into_iter().zip(&names))
+            .map(|(e, name)| e.ok_or_else(|| Error::Missing(name.clone())))
+            .collect::<Result<Vec<f64>, Error>>()?;
+        Ok(Self::from_f64_parts(real, &eps))
+    }
+}
+
+/// An instance with all components zero, to read the component names
+fn zero<T: Text>() -> T {
+    T::from_f64_parts(0., &[])
+}
+
+impl<T: DualNumber> Text for T {
+    fn from_f64_parts(real: f64, eps: &[f64]) -> Self {
+        let mut v = T::constant(real);
+        for (i, &e) in eps.iter().enumerate() {
+            v.set_eps(i, e);
+        }
+        v
+    }
+}
+
+impl<const N: usize, F: Scalar> Text for Dual<N, F> {
+    fn from_f64_parts(real: f64, eps: &[f64]) -> Self {
+        let mut v = Dual::constant(F::from_f64(real));
+        for (v, &e) in v.eps.iter_mut().zip(eps) {
+            *v = F::from_f64(e);
+        }
+        v
+    }
+}
+
+/// Write a dual number in scientific notation, in the form of `Display`
+/// with the components given by name and value, for the `LowerExp` and
+/// `UpperExp` implementations
+#[doc(hidden)]
+pub fn fmt_exp<N: std::fmt::Display>(
+    fmt: &mut std::fmt::Formatter,
+    real: f64,
+    components: impl IntoIterator<Item = (N, f64)>,
+    upper: bool,
+) -> std::fmt::Result {
+    // Passing on the formatter keeps the precision and flags
+    let exp = |fmt: &mut std::fmt::Formatter, v: f64| {
+        if upper {
+            std::fmt::UpperExp::fmt(&v, fmt)
+        } else {
+            std::fmt::LowerExp::fmt(&v, fmt)
+        }
+    };
+    exp(fmt, real)?;
+    for (name, v) in components {
+        if v < 0. {
+            write!(fmt, " - ")?;
+            exp(fmt, -v)?;
+        } else if v == 0. {
+            continue;
+        } else {
+            write!(fmt, " + ")?;
+            exp(fmt, v)?;
+        }
+        write!(fmt, " eps_{name}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Text};
+    use crate::sample::SampleXYZ;
+    use crate::Dual;
+
+    #[test]
+    fn test_round_trip() {
+        let values = [
+            0.1 + 0.2,
+            -0.,
+            1e-300,
+            f64::MAX,
+            f64::NEG_INFINITY,
+            f64::NAN,
+        ];
+        for &real in &values {
+            for &e in &values {
+                let v = Dual::<2>::new(real, [e, 1. / 3.]);
+                let text = v.to_text();
+                let parsed = Dual::<2>::from_text(&text).unwrap();
+                // Compare the bits, as NaN is not equal to itself
+                assert_eq!(parsed.real.to_bits(), v.real.to_bits(), "{text}");
+                assert_eq!(parsed.eps.map(f64::to_bits), v.eps.map(f64::to_bits));
+            }
+        }
+        let v = Dual::<1, f32>::new(0.1, [-7.5]);
+        assert_eq!(Dual::<1, f32>::from_text(&v.to_text()), Ok(v));
+        assert_eq!(
+            SampleXYZ::x(2.5).to_text(),
+            "2.5; eps_x=1.0, eps_y=0.0, eps_z=0.0"
+        );
+    }
+
+    #[test]
+    fn test_exp() {
+        let v = SampleXYZ::from_f64_parts(1234.5, &[-2e-9, 0., 3e6]);
+        assert_eq!(format!("{v:e}"), "1.2345e3 - 2e-9 eps_x + 3e6 eps_z");
+        assert_eq!(format!("{v:.2E}"), "1.23E3 - 2.00E-9 eps_x + 3.00E6 eps_z");
+        let v = Dual::new(0.5, [f64::NAN]);
+        assert_eq!(format!("{v:e}"), "5e-1 + NaN eps_0");
+    }
+
+    #[test]
+    fn test_errors() {
+        let parse = SampleXYZ::from_text;
+        assert_eq!(
+            parse("1; eps_x=1, eps_y=2, eps_z=3"),
+            Ok(SampleXYZ::from_f64_parts(1., &[1., 2., 3.]))
+        );
+        assert_eq!(parse("one; eps_x=1"), Err(Error::Number("one".into())));
+        assert_eq!(parse("1; eps_x"), Err(Error::Syntax("eps_x".into())));
+        assert_eq!(parse("1; eps_w=1"), Err(Error::Unknown("eps_w".into())));
+        assert_eq!(
+            parse("1; eps_x=1, eps_x=2"),
+            Err(Error::Duplicate("eps_x".into()))
+        );
+        assert_eq!(
+            parse("1; eps_x=1, eps_z=2"),
+            Err(Error::Missing("eps_y".into()))
+        );
+    }
+}
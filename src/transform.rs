@@ -0,0 +1,205 @@
+//! # Rigid transforms
+//!
+//! Rotations followed by translations in two and three dimensions, i.e.
+//! elements of SE(2) and SE(3), generic over the [`Numerical`] type. Chains
+//! of transforms parameterized by dual numbers, such as the forward
+//! kinematics of a robot arm, give the derivatives of the end effector pose
+//! with respect to the joint parameters.
+//!
+//! ```
+//! use epsilon::transform::Transform2;
+//! use epsilon::Dual;
+//! // Planar arm with two unit links and joint angles a, b
+//! let [a, b] = Dual::<2>::variables([0.3, 0.9]);
+//! let (zero, one) = (Dual::constant(0.), Dual::constant(1.));
+//! let link = |angle| Transform2::rotation(angle) * Transform2::translation([one, zero]);
+//! let [x, y] = (link(a) * link(b)).apply([zero, zero]);
+//!
+//! assert!((x.real - (0.3f64.cos() + 1.2f64.cos())).abs() < 1e-15);
+//! // dx/db = -sin(a + b)
+//! assert!((x.eps[1] + 1.2f64.sin()).abs() < 1e-15);
+//! ```
+
+use crate::quaternion::Quaternion;
+use crate::vector::Vec3;
+use crate::Numerical;
+
+/// Rotation by `angle` radians followed by a translation in the plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2<T> {
+    pub angle: T,
+    pub translation: [T; 2],
+}
+
+impl<T: Numerical> Transform2<T> {
+    pub fn new(angle: T, translation: [T; 2]) -> Self {
+        Transform2 { angle, translation }
+    }
+
+    pub fn identity() -> Self {
+        let zero = T::constant(0.);
+        Transform2::new(zero, [zero; 2])
+    }
+
+    /// Pure translation
+    pub fn translation(translation: [T; 2]) -> Self {
+        Transform2::new(T::constant(0.), translation)
+    }
+
+    /// Pure rotation about the origin
+    pub fn rotation(angle: T) -> Self {
+        Transform2::new(angle, [T::constant(0.); 2])
+    }
+
+    /// Rotate `v` without translating it, e.g. for directions
+    pub fn rotate(&self, [x, y]: [T; 2]) -> [T; 2] {
+        let (c, s) = (self.angle.cos(), self.angle.sin());
+        [c * x - s * y, s * x + c * y]
+    }
+
+    /// Transform the point `p`
+    pub fn apply(&self, p: [T; 2]) -> [T; 2] {
+        let [x, y] = self.rotate(p);
+        [x + self.translation[0], y + self.translation[1]]
+    }
+
+    pub fn inverse(&self) -> Self {
+        let zero = T::constant(0.);
+        let inverse = Transform2::rotation(zero - self.angle);
+        let [x, y] = inverse.rotate(self.translation);
+        Transform2::new(inverse.angle, [zero - x, zero - y])
+    }
+}
+
+/// Composition applying `other` first and then `self`
+impl<T: Numerical> std::ops::Mul for Transform2<T> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, other: Self) -> Self {
+        Transform2::new(self.angle + other.angle, self.apply(other.translation))
+    }
+}
+
+/// Rotation given by a unit quaternion followed by a translation in space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform3<T> {
+    pub rotation: Quaternion<T>,
+    pub translation: Vec3<T>,
+}
+
+impl<T: Numerical> Transform3<T> {
+    pub fn new(rotation: Quaternion<T>, translation: Vec3<T>) -> Self {
+        Transform3 {
+            rotation,
+            translation,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Transform3::new(Quaternion::identity(), Vec3::zero())
+    }
+
+    /// Pure translation
+    pub fn translation(translation: Vec3<T>) -> Self {
+        Transform3::new(Quaternion::identity(), translation)
+    }
+
+    /// Pure rotation about the origin
+    pub fn rotation(rotation: Quaternion<T>) -> Self {
+        Transform3::new(rotation, Vec3::zero())
+    }
+
+    /// Rotation by `angle` radians about `axis` through the origin
+    pub fn axis_angle(axis: Vec3<T>, angle: T) -> Self {
+        Transform3::rotation(Quaternion::from_axis_angle(axis, angle))
+    }
+
+    /// Rotate `v` without translating it, e.g. for directions
+    pub fn rotate(&self, v: Vec3<T>) -> Vec3<T> {
+        self.rotation.rotate(v)
+    }
+
+    /// Transform the point `p`
+    pub fn apply(&self, p: Vec3<T>) -> Vec3<T> {
+        self.rotate(p) + self.translation
+    }
+
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.conjugate();
+        Transform3::new(rotation, -rotation.rotate(self.translation))
+    }
+}
+
+/// Composition applying `other` first and then `self`
+impl<T: Numerical> std::ops::Mul for Transform3<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Transform3::new(
+            self.rotation * other.rotation,
+            self.apply(other.translation),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transform2, Transform3};
+    use crate::vector::Vec3;
+    use crate::Dual;
+
+    #[test]
+    fn test_transform2() {
+        let t = Transform2::new(0.7, [1., -2.]);
+        let p = [0.5, 3.];
+        let [x, y] = t.inverse().apply(t.apply(p));
+        assert!((x - p[0]).abs() < 1e-15 && (y - p[1]).abs() < 1e-15);
+
+        let u = Transform2::new(-1.9, [4., 0.25]);
+        let composed = (t * u).apply(p);
+        let sequential = t.apply(u.apply(p));
+        assert!((composed[0] - sequential[0]).abs() < 1e-14);
+        assert!((composed[1] - sequential[1]).abs() < 1e-14);
+        assert_eq!(
+            Transform2::rotation(std::f64::consts::FRAC_PI_2).rotate([1., 0.])[1],
+            1.
+        );
+    }
+
+    #[test]
+    fn test_kinematics() {
+        // Shoulder yaw about z, elbow pitch about y, links along x
+        let [yaw, pitch, length] = Dual::<3>::variables([0.5, 0.3, 2.]);
+        let zero = Dual::constant(0.);
+        let shoulder = Transform3::axis_angle(Vec3::constant([0., 0., 1.]), yaw);
+        let upper = Transform3::translation(Vec3::constant([1., 0., 0.]));
+        let elbow = Transform3::axis_angle(Vec3::constant([0., 1., 0.]), pitch);
+        let lower = Transform3::translation(Vec3::new(length, zero, zero));
+        let arm = shoulder * upper * elbow * lower;
+        let tip = arm.apply(Vec3::zero());
+
+        // tip = Rz(yaw) ((1, 0, 0) + length (cos pitch, 0, -sin pitch))
+        let (cy, sy, cp, sp) = (0.5f64.cos(), 0.5f64.sin(), 0.3f64.cos(), 0.3f64.sin());
+        let r = 1. + 2. * cp;
+        let expected = [
+            [cy * r, sy * r, -2. * sp],
+            [-sy * r, cy * r, 0.],
+            [-2. * cy * sp, -2. * sy * sp, -2. * cp],
+            [cy * cp, sy * cp, -sp],
+        ];
+        let tip = [tip.x, tip.y, tip.z];
+        for (k, e) in expected.iter().enumerate() {
+            for (c, e) in tip.iter().zip(e) {
+                let value = if k == 0 { c.real } else { c.eps[k - 1] };
+                assert!((value - e).abs() < 1e-14);
+            }
+        }
+
+        // The inverse maps the tip back to the origin, with zero derivatives
+        let origin = arm.inverse().apply(arm.apply(Vec3::zero()));
+        for c in [origin.x, origin.y, origin.z] {
+            assert!(c.real.abs() < 1e-14 && c.eps.iter().all(|e| e.abs() < 1e-14));
+        }
+    }
+}
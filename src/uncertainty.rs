@@ -0,0 +1,130 @@
+//! # Propagation of uncertainty
+//!
+//! First-order (linear) propagation of the uncertainties of measured
+//! quantities through a calculation. The measurements are seeded as the
+//! variables of [`Dual<N>`], so the gradient `g` of a result is its dual part,
+//! and its variance is `g Σ g^T` for the covariance `Σ` of the measurements.
+//!
+//! ```
+//! use epsilon::uncertainty::Measurements;
+//! // Area of a 2 ± 0.1 by 3 ± 0.2 rectangle
+//! let measurements = Measurements::independent([2., 3.], [0.1, 0.2]);
+//! let [w, h] = measurements.variables();
+//! let area = measurements.propagate(w * h);
+//!
+//! assert_eq!(area.value, 6.);
+//! assert!((area.sigma - 0.5).abs() < 1e-15);
+//! assert_eq!(format!("{:.2}", area), "6.00 ± 0.50");
+//! ```
+
+use crate::Dual;
+
+/// Measured values along with their covariance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurements<const N: usize> {
+    /// Best estimates of the measured quantities
+    pub values: [f64; N],
+    /// Covariance matrix of the measurements
+    pub covariance: [[f64; N]; N],
+}
+
+/// A value with its standard uncertainty
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uncertain {
+    pub value: f64,
+    /// Standard deviation
+    pub sigma: f64,
+}
+
+impl<const N: usize> Measurements<N> {
+    /// Create instance from the values and their covariance matrix
+    pub fn new(values: [f64; N], covariance: [[f64; N]; N]) -> Self {
+        Measurements { values, covariance }
+    }
+
+    /// Create instance from uncorrelated values with standard uncertainties
+    /// `sigmas`
+    pub fn independent(values: [f64; N], sigmas: [f64; N]) -> Self {
+        let mut covariance = [[0.; N]; N];
+        for (i, sigma) in sigmas.iter().enumerate() {
+            covariance[i][i] = sigma * sigma;
+        }
+        Measurements { values, covariance }
+    }
+
+    /// Create instance from values with standard uncertainties `sigmas` and
+    /// the matrix of correlation coefficients between them
+    pub fn correlated(values: [f64; N], sigmas: [f64; N], correlation: [[f64; N]; N]) -> Self {
+        let covariance = std::array::from_fn(|i| {
+            std::array::from_fn(|j| sigmas[i] * correlation[i][j] * sigmas[j])
+        });
+        Measurements { values, covariance }
+    }
+
+    /// The measurements as variables, to compute results from
+    pub fn variables(&self) -> [Dual<N>; N] {
+        Dual::variables(self.values)
+    }
+
+    /// Variance `g Σ g^T` of a result computed from [`Self::variables`]
+    pub fn variance(&self, y: &Dual<N>) -> f64 {
+        self.covariance_between(y, y)
+    }
+
+    /// Covariance `g Σ h^T` of two results computed from [`Self::variables`]
+    pub fn covariance_between(&self, y: &Dual<N>, z: &Dual<N>) -> f64 {
+        self.covariance
+            .iter()
+            .zip(y.eps)
+            .map(|(row, g)| g * row.iter().zip(z.eps).map(|(s, h)| s * h).sum::<f64>())
+            .sum()
+    }
+
+    /// Covariance matrix `J Σ J^T` of several results, which are correlated
+    /// when they depend on the same measurements
+    pub fn covariance_of<const M: usize>(&self, ys: &[Dual<N>; M]) -> [[f64; M]; M] {
+        std::array::from_fn(|i| std::array::from_fn(|j| self.covariance_between(&ys[i], &ys[j])))
+    }
+
+    /// The value and standard uncertainty of a result computed from
+    /// [`Self::variables`]
+    pub fn propagate(&self, y: Dual<N>) -> Uncertain {
+        Uncertain {
+            value: y.real,
+            // Rounding can make a vanishing variance slightly negative
+            sigma: self.variance(&y).max(0.).sqrt(),
+        }
+    }
+}
+
+impl std::fmt::Display for Uncertain {
+    /// Formats as `value ± sigma`, applying the precision to both
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match fmt.precision() {
+            Some(p) => write!(fmt, "{:.*} ± {:.*}", p, self.value, p, self.sigma),
+            None => write!(fmt, "{} ± {}", self.value, self.sigma),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlated() {
+        // Difference of two measurements with correlation r has variance
+        // s1^2 + s2^2 - 2 r s1 s2
+        let measurements = Measurements::correlated([5., 3.], [0.5, 0.25], [[1., 0.5], [0.5, 1.]]);
+        assert_eq!(measurements.covariance, [[0.25, 0.0625], [0.0625, 0.0625]]);
+        let [a, b] = measurements.variables();
+        let diff = measurements.propagate(a - b);
+        assert_eq!(diff.value, 2.);
+        assert_eq!(diff.sigma, 0.1875f64.sqrt());
+
+        // Fully correlated results
+        let cov = measurements.covariance_of(&[a, 2. * a]);
+        assert_eq!(cov, [[0.25, 0.5], [0.5, 1.]]);
+        assert_eq!(format!("{}", measurements.propagate(a - a)), "0 ± 0");
+    }
+}
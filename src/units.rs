@@ -0,0 +1,130 @@
+//! # Unit-aware dual numbers
+//!
+//! With the `uom` feature enabled, all types generated by
+//! [`make_dual`](crate::make_dual) implement the traits required to be used
+//! as the storage type of [`uom`] quantities. This means dimensional analysis
+//! is kept while derivatives are propagated through the computation.
+//!
+//! Units in `uom` only know how to convert the primitive storage types, so
+//! quantities of dual numbers are created from and read as values in the base
+//! units of the quantity (e.g. meters for a length). Use [`quantity`] to wrap
+//! a dual value, and [`lift`] to turn a plain `f64` quantity into a constant
+//! dual quantity.
+//!
+//! ```
+//! use epsilon::{make_dual, units};
+//! use uom::si::{f64::Length, length::kilometer, SI};
+//!
+//! make_dual! { LenDual, l }
+//!
+//! type DualLength = uom::si::length::Length<SI<LenDual>, LenDual>;
+//!
+//! let side: DualLength = units::quantity(LenDual::l(3.));
+//! let offset: DualLength = units::lift(Length::new::<kilometer>(1.));
+//! let area = (side + offset) * side;
+//!
+//! // d/dl [(l + 1000) * l] = 2 l + 1000
+//! assert_eq!(area.value.d_dl(), 1006.);
+//! ```
+
+use uom::si::{Dimension, Quantity, Units};
+
+#[doc(hidden)]
+pub use num_traits;
+#[doc(hidden)]
+pub use uom;
+
+/// Wrap a value, given in the base units of the quantity, into a quantity
+pub fn quantity<D, U, V>(value: V) -> Quantity<D, U, V>
+where
+    D: Dimension + ?Sized,
+    U: Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V>,
+{
+    Quantity {
+        dimension: std::marker::PhantomData,
+        units: std::marker::PhantomData,
+        value,
+    }
+}
+
+/// Convert a quantity with `f64` storage into a quantity of constant dual
+/// numbers
+pub fn lift<D, Uf, U, V>(q: Quantity<D, Uf, f64>) -> Quantity<D, U, V>
+where
+    D: Dimension + ?Sized,
+    Uf: Units<f64> + ?Sized,
+    U: Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V> + From<f64>,
+{
+    quantity(V::from(q.value))
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_uom {
+    // The SI base units all have a unit coefficient, so converting a dual
+    // value to them is the identity
+    (@base_units $name:ident, $inner:ty, $($quantity:ident::$unit:ident,)+) => {
+        $(
+            impl $crate::units::uom::Conversion<$name> for $crate::units::uom::si::$quantity::$unit {
+                type T = $name;
+            }
+        )+
+    };
+    ($name:ident, $inner:ty, $($comp:ident,)+) => { $crate::paste! {
+        impl $crate::units::num_traits::Zero for $name {
+            fn zero() -> Self {
+                Self::from(<$inner as $crate::Scalar>::ZERO)
+            }
+
+            fn is_zero(&self) -> bool {
+                self.real == <$inner as $crate::Scalar>::ZERO
+                    $( && self.[<eps_ $comp>] == <$inner as $crate::Scalar>::ZERO )+
+            }
+        }
+
+        impl $crate::units::num_traits::One for $name {
+            fn one() -> Self {
+                Self::from(<$inner as $crate::Scalar>::ONE)
+            }
+        }
+
+        impl $crate::units::num_traits::Num for $name {
+            type FromStrRadixErr = <f64 as $crate::units::num_traits::Num>::FromStrRadixErr;
+
+            fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+                <f64 as $crate::units::num_traits::Num>::from_str_radix(s, radix)
+                    .map(|real| Self::from(<$inner as $crate::Scalar>::from_f64(real)))
+            }
+        }
+
+        impl $crate::units::uom::Conversion<$name> for $name {
+            type T = $name;
+
+            fn conversion(&self) -> Self::T {
+                *self
+            }
+        }
+
+        $crate::__impl_uom!{ @base_units $name, $inner,
+            length::meter,
+            mass::kilogram,
+            time::second,
+            electric_current::ampere,
+            thermodynamic_temperature::kelvin,
+            amount_of_substance::mole,
+            luminous_intensity::candela,
+        }
+
+        impl $crate::units::uom::ConversionFactor<$name> for $name {
+            fn powi(self, e: i32) -> Self {
+                self.powf(<$inner as $crate::Scalar>::from_f64(e as f64))
+            }
+
+            fn value(self) -> $name {
+                self
+            }
+        }
+    } };
+}
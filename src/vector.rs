@@ -0,0 +1,224 @@
+//! # Three-dimensional vectors
+//!
+//! [`Vec3`] over any [`Numerical`] type, with the products, norms and angles
+//! used in geometry code. With dual numbers as the components, the
+//! derivatives of e.g. a normalized vector follow without deriving the chain
+//! rule by hand.
+//!
+//! ```
+//! use epsilon::vector::Vec3;
+//! use epsilon::Dual;
+//! // Unit direction from the origin to a point moving along x
+//! let [t] = Dual::<1>::variables([3.]);
+//! let p = Vec3::new(t, Dual::constant(4.), Dual::constant(0.));
+//! let n = p.normalize();
+//!
+//! assert!((n.x.real - 0.6).abs() < 1e-15 && (n.y.real - 0.8).abs() < 1e-15);
+//! // dn/dt = (e_x - n n_x) / |p|
+//! assert!((n.x.eps[0] - 0.64 / 5.).abs() < 1e-15);
+//! assert!((n.y.eps[0] + 0.48 / 5.).abs() < 1e-15);
+//! ```
+
+use crate::Numerical;
+
+/// Vector with three components
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Numerical> Vec3<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Vec3 { x, y, z }
+    }
+
+    /// Vector with constant components
+    pub fn constant([x, y, z]: [f64; 3]) -> Self {
+        Vec3::new(T::constant(x), T::constant(y), T::constant(z))
+    }
+
+    pub fn zero() -> Self {
+        Vec3::constant([0.; 3])
+    }
+
+    /// The real parts of the components
+    pub fn real(&self) -> [f64; 3] {
+        [self.x.real(), self.y.real(), self.z.real()]
+    }
+
+    /// Apply `f` to each component
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Vec3<U> {
+        Vec3 {
+            x: f(self.x),
+            y: f(self.y),
+            z: f(self.z),
+        }
+    }
+
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Self) -> Self {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn norm_squared(self) -> T {
+        self.dot(self)
+    }
+
+    /// Euclidean norm
+    pub fn norm(self) -> T {
+        self.norm_squared().powf(0.5)
+    }
+
+    /// Unit vector in the direction of `self`
+    ///
+    /// The derivative is `(I - n n^T) dv / |v|`, and is not finite for the
+    /// zero vector.
+    pub fn normalize(self) -> Self {
+        self / self.norm()
+    }
+
+    /// Euclidean distance to `other`
+    pub fn distance(self, other: Self) -> T {
+        (self - other).norm()
+    }
+
+    /// Angle between `self` and `other`, in `[0, pi]`
+    ///
+    /// Computed as `atan2(|a x b|, a . b)`, which is accurate for nearly
+    /// parallel vectors. As [`Numerical`] has no inverse trigonometric
+    /// functions, the angle is evaluated on the real parts and the first
+    /// derivatives are propagated by the chain rule, so higher derivatives
+    /// (e.g. of nested duals) are not. The derivative is not finite for
+    /// parallel vectors.
+    pub fn angle(self, other: Self) -> T {
+        let (s, c) = (self.cross(other).norm(), self.dot(other));
+        let (s0, c0) = (s.real(), c.real());
+        // d atan2(s, c) = (c ds - s dc) / (s^2 + c^2)
+        let ds = s - T::constant(s0);
+        let dc = c - T::constant(c0);
+        let d = (ds * T::constant(c0) - dc * T::constant(s0)) / T::constant(s0 * s0 + c0 * c0);
+        T::constant(s0.atan2(c0)) + d
+    }
+}
+
+impl<T> From<[T; 3]> for Vec3<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Vec3 { x, y, z }
+    }
+}
+
+impl<T> From<Vec3<T>> for [T; 3] {
+    fn from(v: Vec3<T>) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl<T: Numerical> std::fmt::Display for Vec3<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl<T: Numerical> std::ops::Add for Vec3<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Numerical> std::ops::Sub for Vec3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Numerical> std::ops::Neg for Vec3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Vec3::zero() - self
+    }
+}
+
+impl<T: Numerical> std::ops::Mul<T> for Vec3<T> {
+    type Output = Self;
+
+    fn mul(self, other: T) -> Self {
+        self.map(|c| c * other)
+    }
+}
+
+impl<T: Numerical> std::ops::Div<T> for Vec3<T> {
+    type Output = Self;
+
+    fn div(self, other: T) -> Self {
+        self.map(|c| c / other)
+    }
+}
+
+impl<T: Numerical> std::ops::AddAssign for Vec3<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Numerical> std::ops::SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vec3;
+    use crate::Dual;
+
+    #[test]
+    fn test_products() {
+        let a: Vec3<f64> = Vec3::constant([1., 2., 3.]);
+        let b = Vec3::constant([-2., 0., 1.]);
+        assert_eq!(a.dot(b), 1.);
+        assert_eq!(a.cross(b).real(), [2., -7., 4.]);
+        assert_eq!(a.cross(b).dot(a), 0.);
+        assert_eq!((a - b).norm_squared(), 9. + 4. + 4.);
+        assert_eq!((-a + b * 2.).to_string(), "(-5, -2, -1)");
+        let angle: f64 = Vec3::constant([1., 0., 0.]).angle(Vec3::constant([1., 1., 0.]));
+        assert!((angle - 0.25 * std::f64::consts::PI).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // Normalize: dn = (I - n n^T) dv / |v|, with a variable per component
+        let v = Vec3::from(Dual::<3>::variables([2., -1., 2.]));
+        let n = v.normalize();
+        let n0 = n.real();
+        for (i, row) in <[_; 3]>::from(n).iter().enumerate() {
+            for (j, &d) in row.eps.iter().enumerate() {
+                let expected = (if i == j { 1. } else { 0. } - n0[i] * n0[j]) / 3.;
+                assert!((d - expected).abs() < 1e-15);
+            }
+        }
+
+        // Angle between (1, 0, 0) and (cos t, sin t, 0) is t, for t near 0
+        for t in [1e-9, 0.5, 3.] {
+            let [t] = Dual::<1>::variables([t]);
+            let a = Vec3::constant([2., 0., 0.]);
+            let b = Vec3::new(t.cos(), t.sin(), Dual::constant(0.)) * Dual::constant(3.);
+            let angle = a.angle(b);
+            assert!((angle.real - t.real).abs() < 1e-15);
+            assert!((angle.eps[0] - 1.).abs() < 1e-12);
+        }
+    }
+}
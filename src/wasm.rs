@@ -0,0 +1,160 @@
+//! # WebAssembly bindings
+//!
+//! With the `wasm` feature, [`DualVec`] is exported over `wasm-bindgen` as the
+//! JavaScript class `Dual`. As JavaScript has no operator overloading, the
+//! arithmetic operations are exposed as methods, each returning a new `Dual`.
+//!
+//! ```js
+//! import { Dual } from "./pkg/my_crate.js";
+//! const x = Dual.variable(5, 0, 2), y = Dual.variable(7, 1, 2);
+//! const z = x.powf(2).add(y.mul(y.sin()));
+//! console.log(z.value, z.grad); // 29.59..., Float64Array [10, 5.93...]
+//! ```
+
+use crate::DualVec;
+use wasm_bindgen::prelude::*;
+
+/// JavaScript wrapper around [`DualVec`]
+#[wasm_bindgen(js_name = Dual)]
+#[derive(Clone)]
+pub struct WasmDual {
+    inner: DualVec,
+}
+
+impl From<DualVec> for WasmDual {
+    fn from(inner: DualVec) -> Self {
+        WasmDual { inner }
+    }
+}
+
+impl From<WasmDual> for DualVec {
+    fn from(d: WasmDual) -> Self {
+        d.inner
+    }
+}
+
+#[wasm_bindgen(js_class = Dual)]
+impl WasmDual {
+    /// Create instance with specified real and dual parts
+    #[wasm_bindgen(constructor)]
+    pub fn new(value: f64, grad: Option<Vec<f64>>) -> WasmDual {
+        DualVec::new(value, grad.unwrap_or_default()).into()
+    }
+
+    /// Create the `index`th out of `n` variables, with a unit dual part
+    pub fn variable(value: f64, index: usize, n: usize) -> Result<WasmDual, JsError> {
+        if index >= n {
+            return Err(JsError::new(&format!(
+                "variable index {index} out of range for {n} components"
+            )));
+        }
+        Ok(DualVec::variable(value, index, n).into())
+    }
+
+    /// The real value
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> f64 {
+        self.inner.real
+    }
+
+    /// The dual part, i.e. the derivative with respect to each component
+    #[wasm_bindgen(getter)]
+    pub fn grad(&self) -> Vec<f64> {
+        self.inner.eps.clone()
+    }
+
+    /// Derivative with respect to the `index`th component
+    #[wasm_bindgen(js_name = dD)]
+    pub fn d_d(&self, index: usize) -> f64 {
+        self.inner.d_d(index)
+    }
+
+    pub fn add(&self, other: &WasmDual) -> WasmDual {
+        (self.inner.clone() + other.inner.clone()).into()
+    }
+
+    pub fn sub(&self, other: &WasmDual) -> WasmDual {
+        (self.inner.clone() - other.inner.clone()).into()
+    }
+
+    pub fn mul(&self, other: &WasmDual) -> WasmDual {
+        (self.inner.clone() * other.inner.clone()).into()
+    }
+
+    pub fn div(&self, other: &WasmDual) -> WasmDual {
+        (self.inner.clone() / other.inner.clone()).into()
+    }
+
+    #[wasm_bindgen(js_name = addScalar)]
+    pub fn add_scalar(&self, other: f64) -> WasmDual {
+        (self.inner.clone() + other).into()
+    }
+
+    #[wasm_bindgen(js_name = subScalar)]
+    pub fn sub_scalar(&self, other: f64) -> WasmDual {
+        (self.inner.clone() - other).into()
+    }
+
+    #[wasm_bindgen(js_name = mulScalar)]
+    pub fn mul_scalar(&self, other: f64) -> WasmDual {
+        (self.inner.clone() * other).into()
+    }
+
+    #[wasm_bindgen(js_name = divScalar)]
+    pub fn div_scalar(&self, other: f64) -> WasmDual {
+        (self.inner.clone() / other).into()
+    }
+
+    pub fn neg(&self) -> WasmDual {
+        (-self.inner.clone()).into()
+    }
+
+    pub fn powf(&self, pow: f64) -> WasmDual {
+        self.inner.clone().powf(pow).into()
+    }
+
+    pub fn invert(&self) -> WasmDual {
+        self.inner.clone().invert().into()
+    }
+
+    pub fn sin(&self) -> WasmDual {
+        self.inner.clone().sin().into()
+    }
+
+    pub fn cos(&self) -> WasmDual {
+        self.inner.clone().cos().into()
+    }
+
+    pub fn tan(&self) -> WasmDual {
+        self.inner.clone().tan().into()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmDual;
+    use crate::DualVec;
+
+    #[test]
+    fn test_round_trip() {
+        let x = WasmDual::variable(5., 0, 2).ok().unwrap();
+        let y = WasmDual::new(7., Some(vec![0., 1.]));
+        let z = x
+            .powf(2.)
+            .div(&y)
+            .neg()
+            .add_scalar(2.)
+            .add(&y.sin().mul_scalar(3.));
+
+        let (x, y) = (DualVec::variable(5., 0, 2), DualVec::variable(7., 1, 2));
+        let expected = 2. - x.powf(2.) / y.clone() + y.sin() * 3.;
+        assert_eq!((z.value(), z.grad()), (expected.real, expected.eps.clone()));
+        assert_eq!(z.d_d(1), expected.d_d(1));
+        assert_eq!(DualVec::from(WasmDual::from(expected.clone())), expected);
+    }
+}